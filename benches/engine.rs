@@ -0,0 +1,82 @@
+// Engine hot paths, for checking that a refactor meant to speed things up
+// actually does. `cargo bench` runs them all; `cargo bench -- clear` just
+// the ones matching.
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use tstris::ai::heuristic::{best_placement, placements, Weights};
+use tstris::game::board::{board_from_rows, clear_full_rows, Board};
+use tstris::game::piece::{shuffled_bag, Piece, PieceType};
+use tstris::game::state::Game;
+
+// A ragged midgame stack with a few holes, so collision checks have
+// something to hit
+fn midgame() -> Board {
+    let rows = [
+        "..........",
+        "....T.....",
+        "...TTT...O",
+        "L.SS....OO",
+        "LSS.ZZ.JJJ",
+        "LL..IZZ..J",
+        "GGGG.GGGGG",
+        "GGGGGG.GGG",
+    ];
+    board_from_rows(&rows.map(String::from))
+}
+
+// Four full rows under part of the midgame stack: a tetris
+fn tetris_ready() -> Board {
+    let rows = [
+        "...TTT...O",
+        "L.SS....OO",
+        "LSS.ZZ.JJJ",
+        "IJLOSTZGGG",
+        "IJLOSTZGGG",
+        "IJLOSTZGGG",
+        "IJLOSTZGGG",
+    ];
+    board_from_rows(&rows.map(String::from))
+}
+
+fn is_valid_position(c: &mut Criterion) {
+    let mut game = Game::new();
+    game.board = midgame();
+    let pieces: Vec<Piece> = PieceType::ALL.iter().flat_map(|&piece_type| placements(&game.board, piece_type)).collect();
+    c.bench_function("is_valid_position", |b| {
+        b.iter(|| pieces.iter().filter(|piece| game.is_valid_position(black_box(piece))).count())
+    });
+}
+
+fn clear_lines(c: &mut Criterion) {
+    let board = tetris_ready();
+    c.bench_function("clear_lines/tetris", |b| {
+        b.iter_batched(|| board, |mut board| clear_full_rows(black_box(&mut board)), BatchSize::SmallInput)
+    });
+    let board = midgame();
+    c.bench_function("clear_lines/none", |b| {
+        b.iter_batched(|| board, |mut board| clear_full_rows(black_box(&mut board)), BatchSize::SmallInput)
+    });
+}
+
+fn bag_generation(c: &mut Criterion) {
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    c.bench_function("bag_generation", |b| b.iter(|| shuffled_bag(black_box(&mut rng))));
+}
+
+fn ai_placements(c: &mut Criterion) {
+    let board = midgame();
+    c.bench_function("ai/placements", |b| {
+        b.iter(|| PieceType::ALL.map(|piece_type| placements(black_box(&board), piece_type).len()))
+    });
+    let weights = Weights::BALANCED;
+    c.bench_function("ai/best_placement", |b| {
+        b.iter(|| best_placement(black_box(&board), PieceType::T, &weights).map(|placement| placement.score))
+    });
+}
+
+criterion_group!(benches, is_valid_position, clear_lines, bag_generation, ai_placements);
+criterion_main!(benches);
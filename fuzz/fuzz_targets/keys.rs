@@ -0,0 +1,9 @@
+// Raw terminal input from SSH clients, before it reaches the game
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tstris::input::ansi::parse_keys;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_keys(data);
+});
@@ -0,0 +1,15 @@
+// Puzzle files, which get shared around like replays
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tstris::game::puzzle::Puzzle;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(puzzle) = Puzzle::parse(text, "fuzz") {
+        let _ = puzzle.board();
+        let _ = puzzle.target();
+    }
+});
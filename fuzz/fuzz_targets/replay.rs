@@ -0,0 +1,16 @@
+// Replay files come from other players, so a malformed or hostile one has to
+// fail verification rather than panic or hang the game
+#![no_main]
+
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+use tstris::game::replay;
+use tstris::persistence::ReplayFile;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(file) = serde_json::from_slice::<ReplayFile>(data) else {
+        return;
+    };
+    let _ = replay::verify(&file.replay, Duration::from_nanos(file.time_nanos), file.board_hash);
+});
@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+use crate::ai::heuristic::{best_placement, Weights};
+use crate::constants::ATTRACT_PPS;
+use crate::game::mode::GameMode;
+use crate::game::state::GameState;
+use crate::game::Game;
+
+// Demo play behind the Ready screen once it's been left alone: the heuristic
+// playing Marathon on a steady clock, starting over whenever the run ends
+pub struct Attract {
+    pub game: Game,
+    placed_at: Instant, // When the last piece went down
+}
+
+impl Attract {
+    pub fn new(now: Instant) -> Self {
+        Self { game: Self::start(now), placed_at: now }
+    }
+
+    fn start(now: Instant) -> Game {
+        let mut game = Game::new_simulated(now);
+        game.mode = GameMode::Marathon;
+        game.prepare_run(rand::random());
+        game.start_game();
+        game
+    }
+
+    // Place whatever pieces are due by `now`
+    pub fn advance(&mut self, now: Instant) {
+        let piece_time = Duration::from_secs_f64(1.0 / ATTRACT_PPS);
+        while now.duration_since(self.placed_at) >= piece_time {
+            self.placed_at += piece_time;
+            self.game.set_clock(self.placed_at);
+            self.place();
+        }
+        self.game.set_clock(now);
+    }
+
+    fn place(&mut self) {
+        if self.game.game_state != GameState::Playing {
+            self.game = Self::start(self.placed_at);
+            return;
+        }
+        let Some(piece_type) = self.game.current_piece.as_ref().map(|piece| piece.piece_type) else {
+            return;
+        };
+        match best_placement(&self.game.board, piece_type, &Weights::BALANCED) {
+            Some(placement) => {
+                self.game.current_piece = Some(placement.piece);
+                self.game.lock_piece();
+            }
+            None => self.game.top_out(),
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::heuristic::{best_placement, lock, Weights};
+use crate::constants::{BOARD_HEIGHT, BOARD_WIDTH, BUFFER_HEIGHT};
+use crate::game::board::{clear_full_rows, empty_board, garbage_row, Board, Cell};
+use crate::game::piece::PieceType;
+
+// Games still going after this many pieces each are called a draw
+const MAX_PIECES: u32 = 2000;
+
+// Lines sent for each combo count after the first clear
+const COMBO_ATTACK: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+
+#[derive(Clone, Copy, Debug)]
+pub struct AiConfig {
+    pub name: &'static str,
+    pub weights: Weights,
+    pub pps: f64, // Placement speed, used to put both players on one clock
+}
+
+impl AiConfig {
+    pub const PRESETS: [AiConfig; 2] = [
+        AiConfig { name: "balanced", weights: Weights::BALANCED, pps: 2.0 },
+        AiConfig { name: "aggressive", weights: Weights::AGGRESSIVE, pps: 2.0 },
+    ];
+
+    pub fn preset(name: &str) -> Option<AiConfig> {
+        AiConfig::PRESETS.iter().copied().find(|config| config.name == name)
+    }
+}
+
+// Evens out a match between players of different strength. The AI places
+// pieces without them falling and never holds, so gravity and hold only
+// matter to people, and speed only to the AI.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handicap {
+    pub garbage_rows: u32, // Garbage on the board before the first piece
+    pub attack: f64,       // Multiplier on lines sent, rounded to whole lines
+    pub speed: f64,        // Multiplier on placement speed
+    pub gravity: f64,      // Multiplier on how fast pieces fall
+    pub no_hold: bool,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Self { garbage_rows: 0, attack: 1.0, speed: 1.0, gravity: 1.0, no_hold: false }
+    }
+}
+
+impl Handicap {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Comma-separated settings, e.g. "garbage=4,attack=0.5,speed=0.8"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut handicap = Handicap::default();
+        for setting in spec.split(',').filter(|setting| !setting.is_empty()) {
+            let (key, value) = setting.split_once('=').ok_or_else(|| format!("expected key=value, got '{}'", setting))?;
+            let invalid = || format!("invalid value '{}' for {}", value, key);
+            match key {
+                "garbage" => {
+                    handicap.garbage_rows = value.parse().map_err(|_| invalid())?;
+                    if handicap.garbage_rows as usize >= BOARD_HEIGHT - BUFFER_HEIGHT {
+                        return Err(invalid());
+                    }
+                }
+                "attack" => handicap.attack = value.parse().ok().filter(|&x: &f64| x >= 0.0).ok_or_else(invalid)?,
+                "speed" => handicap.speed = value.parse().ok().filter(|&x: &f64| x > 0.0).ok_or_else(invalid)?,
+                _ => return Err(format!("unknown handicap '{}' (available: garbage, attack, speed)", key)),
+            }
+        }
+        Ok(handicap)
+    }
+}
+
+// Lines sent by a clear under guideline versus rules
+pub fn attack(lines: u32, combo: u32, back_to_back: bool, perfect_clear: bool) -> u32 {
+    let base = match lines {
+        2 => 1,
+        3 => 2,
+        4 => 4,
+        _ => 0,
+    };
+    let combo_bonus = COMBO_ATTACK[(combo as usize).min(COMBO_ATTACK.len() - 1)];
+    let b2b_bonus = u32::from(back_to_back && lines == 4);
+    let pc_bonus = if perfect_clear { 10 } else { 0 };
+    base + combo_bonus + b2b_bonus + pc_bonus
+}
+
+pub struct Player {
+    pub config: AiConfig,
+    pub handicap: Handicap,
+    pub board: Board,
+    pub pieces: u32,
+    pub lines_sent: u32,
+    pub pending_garbage: Vec<u32>, // Incoming attacks, oldest first, waiting to be placed
+    pub topped_out: bool,
+    bag: Vec<PieceType>,
+    bag_rng: StdRng,
+    garbage_rng: StdRng,
+    combo: Option<u32>, // Consecutive clearing placements, None when the last one cleared nothing
+    back_to_back: bool,
+}
+
+impl Player {
+    // Both players are given the same seed so they see the same pieces
+    fn new(config: AiConfig, handicap: Handicap, seed: u64) -> Self {
+        let mut player = Self {
+            config,
+            handicap,
+            board: empty_board(),
+            pieces: 0,
+            lines_sent: 0,
+            pending_garbage: Vec::new(),
+            topped_out: false,
+            bag: Vec::new(),
+            bag_rng: StdRng::seed_from_u64(seed),
+            garbage_rng: StdRng::seed_from_u64(seed.rotate_left(32)),
+            combo: None,
+            back_to_back: false,
+        };
+        if handicap.garbage_rows > 0 {
+            player.pending_garbage.push(handicap.garbage_rows);
+            player.receive_garbage();
+        }
+        player
+    }
+
+    fn next_piece_type(&mut self) -> PieceType {
+        if self.bag.is_empty() {
+            self.bag = PieceType::ALL.to_vec();
+            self.bag.shuffle(&mut self.bag_rng);
+        }
+        self.bag.pop().unwrap_or(PieceType::I)
+    }
+
+    // Seconds into the game at which this player's next piece locks
+    fn next_lock_time(&self) -> f64 {
+        (self.pieces + 1) as f64 / (self.config.pps * self.handicap.speed)
+    }
+
+    // Place one piece and return the lines it sends
+    fn place(&mut self) -> u32 {
+        let piece_type = self.next_piece_type();
+        let Some(placement) = best_placement(&self.board, piece_type, &self.config.weights) else {
+            self.topped_out = true;
+            return 0;
+        };
+        lock(&mut self.board, &placement.piece);
+        self.pieces += 1;
+        
+        // Lock out: nothing made it into the visible field
+        if placement.piece.get_blocks().iter().all(|&(_, y)| y < BUFFER_HEIGHT as i32) {
+            self.topped_out = true;
+            return 0;
+        }
+        
+        let (lines, _) = clear_full_rows(&mut self.board);
+        if lines == 0 {
+            self.combo = None;
+            self.receive_garbage();
+            return 0;
+        }
+        
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(combo);
+        let perfect_clear = self.board.iter().flatten().all(|&cell| cell == Cell::Empty);
+        let mut sent = (attack(lines, combo, self.back_to_back, perfect_clear) as f64 * self.handicap.attack).round() as u32;
+        self.back_to_back = lines == 4;
+        
+        // Outgoing lines cancel incoming garbage first
+        while sent > 0 && !self.pending_garbage.is_empty() {
+            let cancelled = sent.min(self.pending_garbage[0]);
+            sent -= cancelled;
+            self.pending_garbage[0] -= cancelled;
+            if self.pending_garbage[0] == 0 {
+                self.pending_garbage.remove(0);
+            }
+        }
+        self.lines_sent += sent;
+        sent
+    }
+
+    // Push waiting garbage up from the bottom, one hole column per attack
+    fn receive_garbage(&mut self) {
+        for rows in std::mem::take(&mut self.pending_garbage) {
+            let hole = self.garbage_rng.gen_range(0..BOARD_WIDTH);
+            for _ in 0..rows {
+                // Anything pushed out of the top of the matrix tops the player out
+                if self.board[0].iter().any(|&cell| cell != Cell::Empty) {
+                    self.topped_out = true;
+                }
+                self.board.rotate_left(1);
+                if let Some(bottom) = self.board.last_mut() {
+                    *bottom = garbage_row(hole);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Win(usize), // Index of the winning side
+    Draw,
+}
+
+// Who on the other side an attack goes to, when there's more than one
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Targeting {
+    Even,   // Split across everyone still in
+    Random, // All of it to one opponent, picked at random
+    Focus,  // Back at whoever last attacked the sender
+}
+
+impl Targeting {
+    pub const ALL: [Targeting; 3] = [Targeting::Even, Targeting::Random, Targeting::Focus];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Targeting::Even => "even",
+            Targeting::Random => "random",
+            Targeting::Focus => "focus",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|targeting| targeting.name() == name)
+    }
+}
+
+// Two sides of `team_size` players each, every player on a side running the
+// same AI configuration and handicap
+#[derive(Clone, Copy, Debug)]
+pub struct Setup {
+    pub sides: [AiConfig; 2],
+    pub handicaps: [Handicap; 2],
+    pub team_size: usize,
+    pub targeting: Targeting,
+}
+
+// Shares out each attack among the opponents still in, under a targeting
+pub struct Targeter {
+    pub targeting: Targeting,
+    rng: StdRng,
+    even_turn: usize,                  // Rotates who gets the odd lines of an even split
+    last_attacker: Vec<Option<usize>>, // Per player, for focus targeting
+}
+
+impl Targeter {
+    pub fn new(targeting: Targeting, players: usize, seed: u64) -> Self {
+        Self { targeting, rng: StdRng::seed_from_u64(seed), even_turn: 0, last_attacker: vec![None; players] }
+    }
+
+    // Lines from player `from` for each of `opponents`, leaving out anyone
+    // who gets none
+    pub fn split(&mut self, from: usize, lines: u32, opponents: &[usize]) -> Vec<(usize, u32)> {
+        if opponents.is_empty() {
+            return Vec::new();
+        }
+        let mut shares = vec![0; opponents.len()];
+        match self.targeting {
+            Targeting::Even => {
+                for line in 0..lines as usize {
+                    shares[(self.even_turn + line) % opponents.len()] += 1;
+                }
+                self.even_turn = (self.even_turn + lines as usize) % opponents.len();
+            }
+            Targeting::Random => shares[self.rng.gen_range(0..opponents.len())] = lines,
+            Targeting::Focus => {
+                let target = self.last_attacker[from]
+                    .and_then(|attacker| opponents.iter().position(|&i| i == attacker))
+                    .unwrap_or_else(|| self.rng.gen_range(0..opponents.len()));
+                shares[target] = lines;
+            }
+        }
+        let hits: Vec<(usize, u32)> = opponents.iter().copied().zip(shares).filter(|&(_, share)| share > 0).collect();
+        for &(target, _) in &hits {
+            self.last_attacker[target] = Some(from);
+        }
+        hits
+    }
+}
+
+pub struct Versus {
+    pub players: Vec<Player>, // Side A's players first, then side B's
+    pub elapsed: f64,         // Seconds of simulated play
+    team_size: usize,
+    targeter: Targeter,
+}
+
+impl Versus {
+    pub fn new(setup: &Setup, seed: u64) -> Self {
+        let players: Vec<Player> = (0..2 * setup.team_size)
+            .map(|i| {
+                let side = i / setup.team_size;
+                Player::new(setup.sides[side], setup.handicaps[side], seed)
+            })
+            .collect();
+        Self {
+            targeter: Targeter::new(setup.targeting, players.len(), seed.rotate_left(16)),
+            players,
+            elapsed: 0.0,
+            team_size: setup.team_size,
+        }
+    }
+
+    pub fn side(&self, player: usize) -> usize {
+        player / self.team_size
+    }
+
+    // Place the next piece for whichever player still in is due first.
+    // Returns the outcome once the game is over.
+    pub fn step(&mut self) -> Option<Outcome> {
+        let mover = (0..self.players.len())
+            .filter(|&i| !self.players[i].topped_out)
+            .min_by(|&a, &b| self.players[a].next_lock_time().total_cmp(&self.players[b].next_lock_time()))?;
+        self.elapsed = self.players[mover].next_lock_time();
+        
+        let sent = self.players[mover].place();
+        if sent > 0 {
+            self.send(mover, sent);
+        }
+        
+        let standing: Vec<usize> = (0..self.players.len()).filter(|&i| !self.players[i].topped_out).collect();
+        let sides_standing: Vec<usize> = (0..2).filter(|&side| standing.iter().any(|&i| self.side(i) == side)).collect();
+        match sides_standing[..] {
+            [] => Some(Outcome::Draw),
+            [side] => Some(Outcome::Win(side)),
+            _ if standing.iter().all(|&i| self.players[i].pieces >= MAX_PIECES) => Some(Outcome::Draw),
+            _ => None,
+        }
+    }
+
+    fn send(&mut self, from: usize, lines: u32) {
+        let opponents: Vec<usize> = (0..self.players.len())
+            .filter(|&i| self.side(i) != self.side(from) && !self.players[i].topped_out)
+            .collect();
+        for (target, share) in self.targeter.split(from, lines, &opponents) {
+            self.players[target].pending_garbage.push(share);
+        }
+    }
+
+    pub fn run(&mut self) -> Outcome {
+        loop {
+            if let Some(outcome) = self.step() {
+                return outcome;
+            }
+        }
+    }
+
+    // Attack per minute for one side, all its players together
+    pub fn apm(&self, side: usize) -> f64 {
+        if self.elapsed <= 0.0 {
+            return 0.0;
+        }
+        let sent: u32 = (0..self.players.len())
+            .filter(|&i| self.side(i) == side)
+            .map(|i| self.players[i].lines_sent)
+            .sum();
+        sent as f64 * 60.0 / self.elapsed
+    }
+}
+
+#[derive(Default)]
+pub struct BattleResult {
+    pub games: u32,
+    pub wins: [u32; 2],
+    pub draws: u32,
+    pub apm_total: [f64; 2],
+}
+
+impl BattleResult {
+    pub fn record(&mut self, game: &Versus, outcome: Outcome) {
+        self.games += 1;
+        match outcome {
+            Outcome::Win(side) => self.wins[side] += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+        for side in 0..2 {
+            self.apm_total[side] += game.apm(side);
+        }
+    }
+
+    pub fn win_rate(&self, side: usize) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.wins[side] as f64 / self.games as f64
+        }
+    }
+
+    pub fn average_apm(&self, side: usize) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.apm_total[side] / self.games as f64
+        }
+    }
+}
+
+// Play a batch of games headlessly, one seed per game
+pub fn run_battle(setup: &Setup, games: u32, seed: u64) -> BattleResult {
+    let mut result = BattleResult::default();
+    for game in 0..games {
+        let mut versus = Versus::new(setup, seed.wrapping_add(game as u64));
+        let outcome = versus.run();
+        result.record(&versus, outcome);
+    }
+    result
+}
+
+// Rows of the visible field as text, for watching a game in the terminal
+pub fn visible_rows(player: &Player) -> Vec<String> {
+    player.board[BUFFER_HEIGHT..]
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&cell| match cell {
+                    Cell::Empty => "  ",
+                    Cell::Garbage => "▒▒",
+                    Cell::Filled(_) => "██",
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn status_line(player: &Player) -> String {
+    format!("sent {} incoming {}", player.lines_sent, player.pending_garbage.iter().sum::<u32>())
+}
@@ -0,0 +1,136 @@
+use crate::constants::{BOARD_HEIGHT, BOARD_WIDTH, GRADE_HALF_LOSS};
+use crate::game::board::{clear_full_rows, column_heights, covered_holes, fits, Board, Cell};
+use crate::game::piece::{Piece, PieceType};
+
+// Weights for scoring the board left behind by a placement; higher is better
+#[derive(Clone, Copy, Debug)]
+pub struct Weights {
+    pub aggregate_height: f64,
+    pub holes: f64,
+    pub bumpiness: f64,
+    pub lines: f64,      // Per line cleared
+    pub tetris: f64,     // Extra on top of `lines` for a four line clear
+    pub well: f64,       // Per row of depth of the deepest well, up to four
+}
+
+impl Weights {
+    // Keeps the stack low and takes whatever clears come
+    pub const BALANCED: Weights = Weights {
+        aggregate_height: -0.51,
+        holes: -0.36,
+        bumpiness: -0.18,
+        lines: 0.76,
+        tetris: 0.0,
+        well: 0.0,
+    };
+
+    // Builds around a well and holds out for tetrises
+    pub const AGGRESSIVE: Weights = Weights {
+        aggregate_height: -0.35,
+        holes: -0.5,
+        bumpiness: -0.2,
+        lines: -0.3,
+        tetris: 6.0,
+        well: 0.3,
+    };
+}
+
+pub struct Placement {
+    pub piece: Piece, // Where the piece comes to rest
+    pub score: f64,
+}
+
+// Every rotation and column the piece can be hard dropped from at spawn
+// height. Tucks and spins aren't considered.
+pub fn placements(board: &Board, piece_type: PieceType) -> Vec<Piece> {
+    let rotations = if piece_type == PieceType::O { 1 } else { 4 };
+    let mut rotated = Piece::new(piece_type);
+    let mut found = Vec::new();
+    
+    for _ in 0..rotations {
+        let size = rotated.shape.len() as i32;
+        for x in -size..BOARD_WIDTH as i32 {
+            let mut piece = rotated.clone();
+            piece.x = x;
+            if !fits(board, &piece) {
+                continue;
+            }
+            while fits(board, &piece) {
+                piece.y += 1;
+            }
+            piece.y -= 1;
+            found.push(piece);
+        }
+        rotated = rotated.rotate_clockwise();
+    }
+    found
+}
+
+// Highest scoring placement, or None when the piece can't be placed at all
+pub fn best_placement(board: &Board, piece_type: PieceType, weights: &Weights) -> Option<Placement> {
+    placements(board, piece_type)
+        .into_iter()
+        .map(|piece| Placement {
+            score: score(board, &piece, weights),
+            piece,
+        })
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+}
+
+// How a placement compares with the best hard drop of the same piece,
+// judged with the balanced weights: 1 for a match, halving for every
+// GRADE_HALF_LOSS it falls behind. Tucks and spins can beat every drop;
+// they still count as 1.
+pub fn grade(board: &Board, placed: &Piece) -> f64 {
+    let best = placements(board, placed.piece_type)
+        .iter()
+        .map(|piece| score(board, piece, &Weights::BALANCED))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let loss = (best - score(board, placed, &Weights::BALANCED)).max(0.0);
+    0.5f64.powf(loss / GRADE_HALF_LOSS)
+}
+
+// Average grade as a percentage, like a chess accuracy score
+pub fn accuracy(grades: &[f64]) -> Option<f64> {
+    (!grades.is_empty()).then(|| grades.iter().sum::<f64>() / grades.len() as f64 * 100.0)
+}
+
+fn score(board: &Board, piece: &Piece, weights: &Weights) -> f64 {
+    let mut after = *board;
+    lock(&mut after, piece);
+    let (lines, _) = clear_full_rows(&mut after);
+    evaluate(&after, lines, weights)
+}
+
+pub fn lock(board: &mut Board, piece: &Piece) {
+    for (x, y) in piece.get_blocks() {
+        if y >= 0 && y < BOARD_HEIGHT as i32 && x >= 0 && x < BOARD_WIDTH as i32 {
+            board[y as usize][x as usize] = Cell::Filled(piece.color);
+        }
+    }
+}
+
+fn evaluate(board: &Board, lines: u32, weights: &Weights) -> f64 {
+    let heights = column_heights(board);
+    let aggregate_height: usize = heights.iter().sum();
+    let bumpiness: usize = heights.windows(2).map(|pair| pair[0].abs_diff(pair[1])).sum();
+    let holes = covered_holes(board);
+    
+    // Deepest column that sits below both its neighbours (walls count as full)
+    let well = (0..BOARD_WIDTH)
+        .map(|x| {
+            let left = if x == 0 { BOARD_HEIGHT } else { heights[x - 1] };
+            let right = if x + 1 == BOARD_WIDTH { BOARD_HEIGHT } else { heights[x + 1] };
+            left.min(right).saturating_sub(heights[x])
+        })
+        .max()
+        .unwrap_or(0)
+        .min(4);
+    
+    weights.aggregate_height * aggregate_height as f64
+        + weights.holes * holes as f64
+        + weights.bumpiness * bumpiness as f64
+        + weights.lines * lines as f64
+        + if lines == 4 { weights.tetris } else { 0.0 }
+        + weights.well * well as f64
+}
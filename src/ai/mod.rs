@@ -0,0 +1,7 @@
+pub mod attract;
+pub mod battle;
+pub mod heuristic;
+pub mod race;
+pub mod tspin;
+
+pub use battle::AiConfig;
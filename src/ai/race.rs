@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+
+use crate::ai::heuristic::{best_placement, Weights};
+use crate::config::parse_time;
+use crate::constants::RACE_MAX_PIECES;
+use crate::game::mode::GameMode;
+use crate::game::piece::PieceType;
+use crate::game::state::{EndReason, GameState};
+use crate::game::Game;
+
+// How fast the CPU plays its sprint
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Pace {
+    Pps(f64),
+    Time(Duration), // Finish in this time, however many pieces that takes
+}
+
+impl Pace {
+    // "2.5pps" for a placement speed, otherwise a finish time like "1:05" or "58.5"
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.strip_suffix("pps") {
+            Some(pps) => pps.trim().parse().ok().filter(|&pps: &f64| pps > 0.0 && pps.is_finite()).map(Pace::Pps),
+            None => parse_time(text).filter(|time| !time.is_zero()).map(Pace::Time),
+        }
+    }
+}
+
+// The CPU's side of a sprint race: the same pieces as the player, placed by
+// the heuristic on a steady clock. It plays on its own engine so lines,
+// finishing and topping out work exactly as they do for the player.
+pub struct CpuRacer {
+    pub game: Game,
+    weights: Weights,
+    piece_time: Duration,          // Time between placements
+    finish_time: Option<Duration>, // None if the CPU tops out before 40 lines
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RaceResult {
+    Won(Option<Duration>),  // Margin over the CPU, if it would have finished at all
+    Lost(Option<Duration>), // Margin the CPU won by, if the player finished too
+}
+
+impl CpuRacer {
+    pub fn new(seed: u64, queue: &[PieceType], pace: Pace, weights: Weights) -> Self {
+        // The CPU never varies, so playing its whole race once up front tells
+        // how many pieces it takes and whether it gets there
+        let mut trial = Self::start(seed, GameMode::Sprint, queue, weights);
+        while trial.place() {}
+        let pieces = trial.game.pieces_placed.max(1);
+        let finished = trial.game.end_reason == Some(EndReason::Completed);
+
+        let mut racer = Self::start(seed, GameMode::Sprint, queue, weights);
+        let (piece_time, finish_time) = match pace {
+            Pace::Pps(pps) => (Duration::from_secs_f64(1.0 / pps), Duration::from_secs_f64(pieces as f64 / pps)),
+            Pace::Time(time) => (time / pieces, time),
+        };
+        racer.piece_time = piece_time;
+        racer.finish_time = finished.then_some(finish_time);
+        racer
+    }
+
+    // Team matches: a CPU playing at a steady `pps` in any mode, with no
+    // finish time worked out ahead since garbage will change it
+    pub fn steady(seed: u64, mode: GameMode, pps: f64, weights: Weights) -> Self {
+        let mut cpu = Self::start(seed, mode, &[], weights);
+        cpu.piece_time = Duration::from_secs_f64(1.0 / pps);
+        cpu
+    }
+
+    fn start(seed: u64, mode: GameMode, queue: &[PieceType], weights: Weights) -> Self {
+        let mut game = Game::new_simulated(Instant::now());
+        game.mode = mode;
+        game.queue = queue.to_vec();
+        game.prepare_run(seed);
+        game.start_game();
+        Self { game, weights, piece_time: Duration::ZERO, finish_time: None }
+    }
+
+    // Lock the current piece where the heuristic wants it. False once the
+    // race is over for the CPU.
+    fn place(&mut self) -> bool {
+        if self.game.game_state != GameState::Playing || self.game.pieces_placed >= RACE_MAX_PIECES {
+            return false;
+        }
+        let Some(piece_type) = self.game.current_piece.as_ref().map(|piece| piece.piece_type) else {
+            return false;
+        };
+        match best_placement(&self.game.board, piece_type, &self.weights) {
+            Some(placement) => {
+                self.game.current_piece = Some(placement.piece);
+                self.game.lock_piece();
+                true
+            }
+            None => {
+                self.game.top_out();
+                false
+            }
+        }
+    }
+
+    // Catch up to where the CPU is `elapsed` into the run
+    pub fn advance(&mut self, elapsed: Duration) {
+        let due = (elapsed.as_nanos() / self.piece_time.as_nanos().max(1)).min(RACE_MAX_PIECES as u128) as u32;
+        while self.game.pieces_placed < due && self.place() {}
+    }
+
+    pub fn finish_time(&self) -> Option<Duration> {
+        self.finish_time
+    }
+
+    pub fn finished(&self) -> bool {
+        self.game.end_reason == Some(EndReason::Completed)
+    }
+
+    // Who won, once the player's run is over. A player who tops out loses.
+    pub fn result(&self, player: &Game) -> Option<RaceResult> {
+        if player.game_state != GameState::Finished {
+            return None;
+        }
+        let time = player.final_time.filter(|_| player.end_reason == Some(EndReason::Completed));
+        Some(match (time, self.finish_time) {
+            (Some(time), Some(cpu)) if cpu < time => RaceResult::Lost(Some(time - cpu)),
+            (Some(time), cpu) => RaceResult::Won(cpu.map(|cpu| cpu - time)),
+            (None, _) => RaceResult::Lost(None),
+        })
+    }
+
+    pub fn pps(&self) -> f64 {
+        1.0 / self.piece_time.as_secs_f64()
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::ai::heuristic::{lock, placements};
+use crate::constants::{BOARD_HEIGHT, BOARD_WIDTH};
+use crate::game::board::{clear_full_rows, Board, Cell};
+use crate::game::piece::{Piece, PieceType};
+use crate::game::rotation::kicks;
+
+// Placements leading up to a T-spin, shown one at a time by the hint key.
+// The last step is the T spinning into its slot.
+#[derive(Clone, Debug)]
+pub struct Hint {
+    pub steps: Vec<Piece>,
+    pub lines: u32,  // Cleared by the spin
+    pub done: usize, // Steps the player has followed so far
+}
+
+impl Hint {
+    pub fn next_step(&self) -> Option<&Piece> {
+        self.steps.get(self.done)
+    }
+
+    // The player put the piece where the step asked, in whatever orientation
+    // covers the same cells
+    pub fn followed(step: &Piece, placed: &Piece) -> bool {
+        let mut wanted = step.get_blocks();
+        let mut got = placed.get_blocks();
+        wanted.sort_unstable();
+        got.sort_unstable();
+        step.piece_type == placed.piece_type && wanted == got
+    }
+}
+
+// The best T-spin playing `queue` in order can set up: most lines, then
+// fewest pieces. Setup pieces are hard dropped; the T may tuck and spin.
+pub fn find_setup(board: &Board, queue: &[PieceType]) -> Option<Hint> {
+    search(board, queue).map(|(steps, lines)| Hint { steps, lines, done: 0 })
+}
+
+fn search(board: &Board, queue: &[PieceType]) -> Option<(Vec<Piece>, u32)> {
+    let (&piece_type, rest) = queue.split_first()?;
+    let mut best: Option<(Vec<Piece>, u32)> = None;
+    if piece_type == PieceType::T {
+        best = best_spin(board).map(|(spin, lines)| (vec![spin], lines));
+    }
+    // Only worth placing this piece if a T is still to come
+    if !rest.contains(&PieceType::T) {
+        return best;
+    }
+    for piece in placements(board, piece_type) {
+        let mut after = *board;
+        lock(&mut after, &piece);
+        clear_full_rows(&mut after);
+        if let Some((mut steps, lines)) = search(&after, rest) {
+            steps.insert(0, piece);
+            let better = best.as_ref().is_none_or(|(best_steps, best_lines)| {
+                lines > *best_lines || (lines == *best_lines && steps.len() < best_steps.len())
+            });
+            if better {
+                best = Some((steps, lines));
+            }
+        }
+    }
+    best
+}
+
+// The T in its four orientations, clockwise from spawn, so the search can
+// try positions without building a piece for each
+struct Tee {
+    pieces: [Piece; 4],
+    blocks: [Vec<(i32, i32)>; 4],     // Cells relative to the piece's corner
+    kicks: [[Vec<(i32, i32)>; 4]; 4], // Kicks from one orientation to another
+}
+
+impl Tee {
+    fn new() -> Self {
+        let spawn = Piece::new(PieceType::T);
+        let pieces = [spawn.clone(), spawn.rotate_clockwise(), spawn.rotate_180(), spawn.rotate_counter_clockwise()];
+        let blocks = pieces.each_ref().map(|piece| {
+            let mut corner = piece.clone();
+            corner.x = 0;
+            corner.y = 0;
+            corner.get_blocks()
+        });
+        let kicks = pieces.each_ref().map(|from| pieces.each_ref().map(|to| kicks(PieceType::T, from.rotation, to.rotation)));
+        Self { pieces, blocks, kicks }
+    }
+
+    fn fits(&self, board: &Board, (x, y, turn): (i32, i32, usize)) -> bool {
+        self.blocks[turn].iter().all(|&(dx, dy)| {
+            let (x, y) = (x + dx, y + dy);
+            x >= 0 && x < BOARD_WIDTH as i32 && y < BOARD_HEIGHT as i32 && (y < 0 || board[y as usize][x as usize] == Cell::Empty)
+        })
+    }
+
+    fn piece(&self, (x, y, turn): (i32, i32, usize)) -> Piece {
+        let mut piece = self.pieces[turn].clone();
+        piece.x = x;
+        piece.y = y;
+        piece
+    }
+}
+
+type Spot = (i32, i32, usize); // x, y and orientation
+
+// Spots where the T would rest with three of the four corners around its
+// center filled, clearing at least a line. Cheap to find, and most boards
+// have none, which saves searching how the T could get there.
+fn spin_slots(board: &Board, tee: &Tee) -> Vec<(Spot, u32)> {
+    let mut slots = Vec::new();
+    for turn in 0..4 {
+        for x in -1..BOARD_WIDTH as i32 {
+            for y in 0..BOARD_HEIGHT as i32 {
+                if !tee.fits(board, (x, y, turn)) || tee.fits(board, (x, y + 1, turn)) || corners_filled(board, x, y) < 3 {
+                    continue;
+                }
+                let mut after = *board;
+                lock(&mut after, &tee.piece((x, y, turn)));
+                let (lines, _) = clear_full_rows(&mut after);
+                if lines > 0 {
+                    slots.push(((x, y, turn), lines));
+                }
+            }
+        }
+    }
+    slots
+}
+
+// Search every spot the T can reach, remembering whether the last move was
+// a rotation: a slot only counts if the T spins into it
+fn best_spin(board: &Board) -> Option<(Piece, u32)> {
+    let tee = Tee::new();
+    let slots = spin_slots(board, &tee);
+    let most_lines = slots.iter().map(|&(_, lines)| lines).max()?;
+
+    // Everything above the stack is open, so start just over it in every
+    // column and orientation rather than from spawn
+    let top = board.iter().position(|row| row.iter().any(|&cell| cell != Cell::Empty)).unwrap_or(BOARD_HEIGHT) as i32 - 3;
+    let mut queue: VecDeque<(Spot, bool)> = (0..4)
+        .flat_map(|turn| (-1..BOARD_WIDTH as i32).map(move |x| ((x, top, turn), false)))
+        .filter(|&(spot, _)| tee.fits(board, spot))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut best: Option<(Spot, u32)> = None;
+
+    while let Some((spot, rotated)) = queue.pop_front() {
+        if !seen.insert((spot, rotated)) {
+            continue;
+        }
+        if rotated {
+            if let Some(&(_, lines)) = slots.iter().find(|&&(slot, _)| slot == spot) {
+                if best.is_none_or(|(_, best_lines)| lines > best_lines) {
+                    best = Some((spot, lines));
+                    if lines == most_lines {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let (x, y, turn) = spot;
+        for moved in [(x - 1, y, turn), (x + 1, y, turn), (x, y + 1, turn)] {
+            if tee.fits(board, moved) {
+                queue.push_back((moved, false));
+            }
+        }
+        // Clockwise, 180 and counter-clockwise, trying kicks in the engine's order
+        for to in [(turn + 1) % 4, (turn + 2) % 4, (turn + 3) % 4] {
+            let kicked = tee.kicks[turn][to].iter().map(|&(kick_x, kick_y)| (x + kick_x, y + kick_y, to)).find(|&spun| tee.fits(board, spun));
+            if let Some(spun) = kicked {
+                queue.push_back((spun, true));
+            }
+        }
+    }
+    best.map(|(spot, lines)| (tee.piece(spot), lines))
+}
+
+// Walls and floor count as filled
+fn corners_filled(board: &Board, x: i32, y: i32) -> usize {
+    let filled = |x: i32, y: i32| {
+        x < 0 || x >= BOARD_WIDTH as i32 || y >= BOARD_HEIGHT as i32 || (y >= 0 && board[y as usize][x as usize] != Cell::Empty)
+    };
+    [(0, 0), (2, 0), (0, 2), (2, 2)]
+        .iter()
+        .filter(|&&(dx, dy)| filled(x + dx, y + dy))
+        .count()
+}
@@ -0,0 +1,1266 @@
+use std::collections::VecDeque;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyCode;
+
+use crate::ai::attract::Attract;
+use crate::ai::race::{CpuRacer, Pace};
+use crate::ai::tspin::{self, Hint};
+use crate::ai::AiConfig;
+use crate::calibration::Calibration;
+use crate::config::{self, Config, Notice, CONFIG_VERSION};
+use crate::error::Result;
+use crate::config::format_time;
+use crate::constants::{ATTRACT_IDLE_SECS, EVENT_LOG_LEN, FRAME_STEP_MS, HINT_PIECES, NAME_MAX_WIDTH, NETPLAY_CHAT_CHARS, PREVIEW_MS, TOAST_MS, ZEN_AUTOSAVE_SECS};
+use crate::game::board::{board_from_rows, board_to_rows};
+use crate::game::event::{GameEvent, TimedEvent};
+use crate::game::piece::PieceType;
+use crate::game::playback::Playback;
+use crate::game::puzzle::Puzzle;
+use crate::game::replay::{self, InputEvent, Replay};
+use crate::game::state::{EndReason, GameState};
+use crate::game::{Game, GameMode};
+use crate::i18n::Strings;
+use crate::input::action::{parse_key, Bindings, GameAction};
+use crate::input::diagnostics::InputDiagnostics;
+use crate::koth::Koth;
+use crate::netplay::{MatchEvent, Netplay, Outcome};
+use crate::profiler::Profiler;
+use crate::persistence::{self, PersonalBest, ReplayFile, RunRecord, ZenSave};
+use crate::prediction::Prediction;
+use crate::report;
+use crate::title::{MenuItem, TitleMenu};
+use crate::ui::backdrop::Backdrop;
+use crate::ui::graphics::Protocol;
+use crate::ui::layout::PanelLayout;
+use crate::ui::text;
+use crate::ui::toast::{ToastKind, Toasts};
+use crate::ui::popup::ScorePopup;
+use crate::ui::trail::Trail;
+use crate::versus::{side_key, LocalVersus, MatchSetup, Row};
+use crate::wizard::Wizard;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PbStatus {
+    New,
+    Rejected, // Beat the PB but the replay didn't re-simulate to the same result
+}
+
+pub struct App {
+    pub game: Game,
+    pub config: Config,
+    pub strings: Strings, // UI text in the configured language
+    pub history: Vec<RunRecord>, // Saved runs, oldest first
+    pub session_start: usize,    // Index into history where this session's runs begin
+    pub session_attempts: u32,   // Runs started this session, including abandoned ones
+    pub pbs: Vec<PersonalBest>,
+    pub pb_status: Option<PbStatus>, // Outcome of the PB check for the last finished run
+    pub show_stats: bool,
+    pub layout: PanelLayout,
+    pub show_session: bool,
+    pub show_analysis: bool, // Column heights and hole markers over the board
+    preview_until: Option<Instant>, // Placement preview stays up until then
+    pub hint: Option<Hint>,         // T-spin setup being walked through
+    hint_pieces: u32,               // pieces_placed when the hint's current step came up
+    pub show_event_log: bool,
+    pub event_log: VecDeque<TimedEvent>, // Recent events of the current run, newest last
+    trail: Option<Trail>,           // Streak left by the last hard drop, with [board] trail on
+    popups: Vec<ScorePopup>,        // Points from recent clears, with [board] score_popups on
+    pub playback: Option<Playback>, // Replay being watched in place of the live game
+    pub ghost: Option<Playback>,    // PB replay raced alongside the live sprint
+    pub race: Option<Pace>,         // Race the CPU in sprints at this pace
+    pub cpu: Option<CpuRacer>,      // The CPU's side of the current race
+    pub twin: Option<Game>,         // Two-board practice: the right hand's board, on the same pieces
+    pub versus: Option<LocalVersus>, // Local versus, played on the main board and the twin
+    pub match_setup: Option<MatchSetup>, // Handicap screen over the Ready screen in a versus match
+    pub attract: Option<Attract>,   // Demo playing behind an idle Ready screen
+    pub title: TitleMenu,           // Menu on the title screen, which is the Ready screen
+    last_key: Instant,              // Last key press, for when the demo should start
+    pub zen_lifetime_lines: u64,
+    pub name_entry: Option<String>, // Name being typed on the first-run screen
+    pub wizard: Option<Wizard>,     // First-run setup, shown before the name prompt
+    pub preset: Option<usize>,      // Handling preset in use, into HandlingConfig::presets; None for [handling]'s own
+    pub handling_menu: bool,        // Preset quick menu over the Ready screen
+    pub quit_prompt: Option<bool>,  // Quit confirmation over a run; true when it paused the game
+    pub bindings: Bindings,         // Built-in keys with the config's layered over them
+    pub toasts: Toasts,
+    pub backdrop: Option<Backdrop>, // Art from [theme] background, behind the panels
+    pub koth: Option<Koth>, // King of the hill session, if one is running
+    pub netplay: Option<Netplay>, // Online versus match, once connected
+    pub chat_entry: Option<String>, // Chat message being typed; keys go here and not to the game
+    puzzles: Vec<Puzzle>,   // Puzzle set from the command line, played in order
+    puzzle_index: usize,
+    persist: bool, // Read and write saves in the data directory
+    pub profiler: Profiler,
+    pub show_profiler: bool, // Frame and update timings over the top-left corner
+    pub input_diagnostics: InputDiagnostics,
+    pub calibration: Option<Calibration>, // Latency calibration shown in place of the game
+    pub show_input_diagnostics: bool, // Per-key press and release statistics
+    pub frame_step: bool,             // Debugging: the game only moves a tick per key press
+    pub graphics: Option<Protocol>,   // Image protocol the board is drawn with, if any
+    frame_steps: u32,                 // Steps asked for and not yet run
+    pub prediction: Option<Prediction>, // Next piece drill, which hides the Next panel
+    zen_save: Option<ZenSave>,     // Progress to resume the next time Zen is picked
+    zen_lines_seen: u32,           // Zen lines already added to the lifetime counter
+    zen_saved_at: Instant,
+    last_mode: GameMode,
+    countdown_cue: Option<u32>,    // Countdown number last announced, 0 for GO
+    pub bell: bool,                // Ring the terminal bell with the next frame
+    run_recorded: bool,
+    attempt_counted: bool,
+}
+
+pub struct SessionSummary {
+    pub attempts: u32,
+    pub finished: usize,
+    pub best: Option<Duration>,
+    pub average: Option<Duration>,
+    pub pieces: u32,
+    pub pps: f64,
+}
+
+impl App {
+    pub fn new() -> Self {
+        // Unreadable config falls back to defaults rather than refusing to start
+        let (config, notices, config_error) = match config::load() {
+            Ok((config, notices)) => (config, notices, None),
+            Err(e) => (Config::default(), Vec::new(), Some(e.to_string())),
+        };
+        let mut app = Self::with_saves(
+            config,
+            persistence::load_runs(),
+            persistence::load_pbs(),
+            persistence::load_zen(),
+            true,
+        );
+        if let Some(error) = config_error {
+            tracing::warn!(%error, "config ignored");
+            let first_line = error.lines().next().unwrap_or_default();
+            let text = app.strings.fmt("toast.config_invalid", &[&first_line]);
+            app.toasts.push_for(ToastKind::Warning, text, Duration::from_millis(TOAST_MS * 2));
+        }
+        if let Some(path) = app.config.theme.background.as_deref().map(config::config_relative) {
+            match Backdrop::load(&path) {
+                Ok(backdrop) => app.backdrop = Some(backdrop),
+                Err(error) => {
+                    tracing::warn!(%error, path = %path.display(), "background art unreadable");
+                    let text = app.strings.fmt("toast.background_unreadable", &[&error]);
+                    app.toasts.push(ToastKind::Warning, text);
+                }
+            }
+        }
+        if let Some(name) = app.config.handling.preset.clone().filter(|_| app.preset.is_none()) {
+            app.toasts.push(ToastKind::Warning, app.strings.fmt("toast.preset_unknown", &[&name]));
+        }
+        // No config file at all is a first launch
+        if config::config_path().is_some_and(|path| !path.exists()) {
+            app.wizard = Some(Wizard::new());
+        }
+        for notice in notices {
+            let (kind, text) = match notice {
+                Notice::Migrated { from, backup } => {
+                    tracing::info!(from, to = CONFIG_VERSION, backup = %backup.display(), "config migrated");
+                    let name = backup.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+                    (ToastKind::Info, app.strings.fmt("toast.config_migrated", &[&CONFIG_VERSION, &name]))
+                }
+                Notice::Newer(version) => (ToastKind::Warning, app.strings.fmt("toast.config_newer", &[&version])),
+                Notice::Unknown(keys) => {
+                    tracing::warn!(keys = %keys.join(", "), "unknown config settings");
+                    (ToastKind::Warning, app.strings.fmt("toast.config_unknown", &[&keys.join(", ")]))
+                }
+            };
+            app.toasts.push_for(kind, text, Duration::from_millis(TOAST_MS * 2));
+        }
+        app
+    }
+
+    // Someone playing on a shared server: default settings under their own
+    // name, with nothing read from or written to this machine's saves
+    #[cfg(feature = "ssh")]
+    pub fn guest(name: &str) -> Self {
+        let config = Config { name: Some(name.to_string()), ..Config::default() };
+        Self::with_saves(config, Vec::new(), Vec::new(), None, false)
+    }
+
+    fn with_saves(
+        config: Config,
+        history: Vec<RunRecord>,
+        pbs: Vec<PersonalBest>,
+        zen_save: Option<ZenSave>,
+        persist: bool,
+    ) -> Self {
+        let mut game = Game::new();
+        game.player = config.name.clone().unwrap_or_default();
+        game.curve = config.speed_curve().ok().flatten(); // Already checked when the config was read
+        game.set_garbage_rules(config.garbage.rules());
+        game.set_adaptive_gravity(config.zen.adaptive_gravity);
+        let presets = config.handling.presets();
+        let preset = config.handling.preset.as_ref().and_then(|name| presets.iter().position(|(preset, _)| preset == name));
+        game.timings = preset.map_or_else(|| config.handling.timings(), |i| presets[i].1);
+        Self {
+            game,
+            name_entry: config.name.is_none().then(String::new),
+            wizard: None,
+            preset,
+            handling_menu: false,
+            quit_prompt: None,
+            bindings: config.bindings().unwrap_or_default(), // Already checked when the config was read
+            layout: PanelLayout::new(config.layout.preset, config.layout.mirrored),
+            strings: Strings::new(config.language),
+            toasts: Toasts::default(),
+            backdrop: None,
+            koth: None,
+            netplay: None,
+            chat_entry: None,
+            puzzles: Vec::new(),
+            puzzle_index: 0,
+            persist,
+            profiler: Profiler::new(),
+            show_profiler: false,
+            input_diagnostics: InputDiagnostics::default(),
+            calibration: None,
+            show_input_diagnostics: false,
+            frame_step: false,
+            graphics: config.display.graphics.protocol(),
+            frame_steps: 0,
+            prediction: None,
+            config,
+            session_start: history.len(),
+            history,
+            session_attempts: 0,
+            pbs,
+            pb_status: None,
+            show_stats: false,
+            show_session: true,
+            show_analysis: false,
+            preview_until: None,
+            hint: None,
+            hint_pieces: 0,
+            show_event_log: false,
+            event_log: VecDeque::new(),
+            trail: None,
+            popups: Vec::new(),
+            playback: None,
+            ghost: None,
+            race: None,
+            cpu: None,
+            twin: None,
+            versus: None,
+            match_setup: None,
+            attract: None,
+            title: TitleMenu::new(),
+            last_key: Instant::now(),
+            zen_lifetime_lines: zen_save.as_ref().map_or(0, |save| save.lifetime_lines),
+            zen_save,
+            zen_lines_seen: 0,
+            zen_saved_at: Instant::now(),
+            last_mode: GameMode::Sprint,
+            countdown_cue: None,
+            bell: false,
+            run_recorded: false,
+            attempt_counted: false,
+        }
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        // A timed run can end under the quit prompt; there's nothing left to ask about
+        if self.quit_prompt.is_some() && matches!(self.game.game_state, GameState::Ready | GameState::Finished) {
+            self.quit_prompt = None;
+        }
+        // A restart key held long enough restarts; let go early, it's dropped
+        match self.game.input_state.restart_progress(Instant::now()) {
+            Some(progress) if progress >= 1.0 => self.game.press_action(GameAction::Restart),
+            Some(_) => {}
+            None => self.game.input_state.restart_key = None,
+        }
+        if self.frame_step {
+            self.run_frame_steps()?;
+        } else {
+            self.game.update()?;
+        }
+        self.toasts.update(Instant::now());
+        self.popups.retain(|popup| popup.place(Instant::now()).is_some());
+        let events = self.game.drain_events();
+        self.update_netplay(&events);
+        for &event in &events {
+            // Not worth a line in the log or a crash report, there are so many
+            if let GameEvent::HardDrop(rows) = event.event {
+                if let Some(piece) = self.game.last_locked.as_ref().filter(|_| self.config.board.trail) {
+                    self.trail = Some(Trail::new(piece, rows, Instant::now()));
+                }
+                continue;
+            }
+            // The clear itself is logged; this is only for the popup
+            if let GameEvent::Scored(points, row) = event.event {
+                if self.config.board.score_popups {
+                    self.popups.push(ScorePopup::new(points, row, Instant::now()));
+                }
+                continue;
+            }
+            if let GameEvent::Mystery(mystery) = event.event {
+                let name = self.strings.get(&format!("mystery.{}", mystery.key())).to_string();
+                let about = self.strings.get(&format!("mystery_about.{}", mystery.key())).to_string();
+                self.toasts.push(ToastKind::Warning, self.strings.fmt("toast.mystery", &[&name, &about]));
+            }
+            if self.event_log.len() == EVENT_LOG_LEN {
+                self.event_log.pop_front();
+            }
+            report::remember(&event);
+            self.event_log.push_back(event);
+        }
+        if let Some(playback) = self.playback.as_mut() {
+            playback.advance();
+        }
+
+        if self.game.mode != self.last_mode {
+            self.mode_changed();
+        }
+        if self.game.mode == GameMode::Zen {
+            self.update_zen();
+        }
+
+        match self.game.game_state {
+            GameState::Finished => {
+                self.hint = None;
+                if !self.run_recorded {
+                    self.record_run();
+                    if self.game.mode == GameMode::Puzzle && self.game.end_reason == Some(EndReason::Completed) {
+                        self.next_puzzle();
+                    }
+                }
+            }
+            GameState::Ready => {
+                self.run_recorded = false;
+                self.attempt_counted = false;
+                // The mode may change before the next run
+                self.ghost = None;
+                self.cpu = None;
+            }
+            GameState::Countdown(_) => {
+                if self.ghost.is_none() {
+                    self.ghost = self.load_ghost();
+                }
+                if self.cpu.is_none() {
+                    self.cpu = self.start_cpu();
+                }
+                self.run_recorded = false;
+                self.attempt_counted = false;
+                self.pb_status = None;
+                self.event_log.clear();
+                self.show_stats = false;
+                self.hint = None;
+                if self.prediction.is_some() {
+                    self.prediction = Some(Prediction::default());
+                }
+            }
+            GameState::Paused => {}
+            GameState::Playing => {
+                self.run_recorded = false;
+                self.show_stats = false;
+                if !self.attempt_counted {
+                    self.session_attempts += 1;
+                    self.attempt_counted = true;
+                }
+                if let Some(prediction) = self.prediction.as_mut() {
+                    prediction.follow(&self.game);
+                }
+            }
+        }
+
+        self.countdown_sound();
+
+        // Keep the ghost at the same point in its run as the live game
+        if matches!(self.game.game_state, GameState::Countdown(_) | GameState::Playing) {
+            if let Some(ghost) = self.ghost.as_mut() {
+                ghost.seek(self.game.replay_elapsed());
+            }
+        }
+        self.update_cpu();
+        let twin_events = self.update_twin()?;
+        self.update_versus(&events, &twin_events);
+        self.update_attract();
+        self.follow_hint();
+
+        Ok(())
+    }
+
+    // Ring the terminal bell on each countdown number and at GO
+    fn countdown_sound(&mut self) {
+        let cue = match self.game.game_state {
+            GameState::Countdown(count) => Some(count),
+            GameState::Playing => Some(0),
+            GameState::Paused => self.countdown_cue,
+            _ => None,
+        };
+        if cue != self.countdown_cue {
+            if cue.is_some() && self.config.theme.sound {
+                self.bell = true;
+            }
+            self.countdown_cue = cue;
+        }
+    }
+
+    fn load_ghost(&self) -> Option<Playback> {
+        if self.game.mode != GameMode::Sprint {
+            return None;
+        }
+        if !self.persist {
+            return None;
+        }
+        let path = persistence::pb_replay_path(self.game.mode)?;
+        let file = persistence::load_replay(&path).ok()?;
+        Some(Playback::new(file.replay))
+    }
+
+    // The CPU plays the same pieces as this run
+    fn start_cpu(&self) -> Option<CpuRacer> {
+        if self.game.mode != GameMode::Sprint {
+            return None;
+        }
+        let pace = self.race?;
+        let weights = AiConfig::PRESETS[0].weights;
+        Some(CpuRacer::new(self.game.seed, &self.game.queue, pace, weights))
+    }
+
+    // The CPU stops where it is once the player's run is over
+    fn update_cpu(&mut self) {
+        if self.game.game_state == GameState::Finished {
+            return;
+        }
+        let Some(cpu) = self.cpu.as_mut() else {
+            return;
+        };
+        let was_finished = cpu.finished();
+        cpu.advance(self.game.get_current_time().unwrap_or_default());
+        if let Some(time) = cpu.finish_time().filter(|_| cpu.finished() && !was_finished) {
+            self.toasts.push(ToastKind::Warning, self.strings.fmt("toast.race_cpu_finished", &[&format_time(time)]));
+        }
+    }
+
+    // The demo starts once the Ready screen has been left alone for a while,
+    // with nothing else open over it
+    fn update_attract(&mut self) {
+        let now = Instant::now();
+        let quiet = self.game.game_state == GameState::Ready
+            && self.playback.is_none()
+            && self.calibration.is_none()
+            && self.wizard.is_none()
+            && self.name_entry.is_none()
+            && self.twin.is_none()
+            && self.koth.is_none()
+            && !self.handling_menu
+            && self.match_setup.is_none()
+            && !self.show_stats;
+        if !quiet {
+            self.attract = None;
+            return;
+        }
+        match self.attract.as_mut() {
+            Some(attract) => attract.advance(now),
+            None if now.duration_since(self.last_key) >= Duration::from_secs(ATTRACT_IDLE_SECS) => {
+                tracing::debug!("attract mode started");
+                self.attract = Some(Attract::new(now));
+            }
+            None => {}
+        }
+    }
+
+    // Any key press ends the demo. True if one was showing, so the key
+    // goes no further.
+    pub fn wake(&mut self) -> bool {
+        self.last_key = Instant::now();
+        self.attract.take().is_some()
+    }
+
+    // Two-board practice: a second board for the right hand, beside this one
+    pub fn start_two_boards(&mut self) {
+        self.twin = Some(self.game.clone());
+    }
+
+    // Local versus: two people on the two boards, each with a CPU teammate
+    // in team play, and the handicap screen up to start with
+    pub fn start_versus(&mut self, teams: bool) {
+        self.twin = Some(self.game.clone());
+        self.versus = Some(LocalVersus::new(teams));
+        self.match_setup = Some(MatchSetup::new(2, teams));
+    }
+
+    // The right hand's board starts each run with the left's, on the same
+    // seed and mode, and pauses with it. From there the two play on their
+    // own. Gives the reports it took from the twin.
+    fn update_twin(&mut self) -> Result<Vec<TimedEvent>> {
+        let Some(twin) = self.twin.as_mut() else {
+            return Ok(Vec::new());
+        };
+        let game = &self.game;
+        let out_of_step = match game.game_state {
+            GameState::Ready => twin.game_state != GameState::Ready || twin.mode != game.mode || twin.seed != game.seed,
+            GameState::Countdown(_) => !matches!(twin.game_state, GameState::Countdown(_)) || twin.seed != game.seed,
+            GameState::Playing | GameState::Paused | GameState::Finished => false,
+        };
+        if out_of_step {
+            twin.mode = game.mode;
+            twin.fixed_seed = Some(game.seed);
+            twin.reset();
+            if game.game_state == GameState::Ready {
+                twin.game_state = GameState::Ready;
+            }
+        }
+        match (game.game_state, twin.game_state) {
+            (GameState::Paused, GameState::Playing) => twin.focus_lost(),
+            (GameState::Playing, GameState::Paused) => twin.focus_gained(),
+            _ => {}
+        }
+
+        let was_finished = twin.game_state == GameState::Finished;
+        twin.update()?;
+        let events = twin.drain_events();
+        if twin.game_state == GameState::Finished && !was_finished && self.versus.is_none() {
+            let time = format_time(twin.get_current_time().unwrap_or_default());
+            self.toasts.push(ToastKind::Info, self.strings.fmt("toast.twin_finished", &[&twin.lines_cleared, &time]));
+        }
+        Ok(events)
+    }
+
+    // Each board's clears go at the other side, garbage that's come due
+    // rises, and the match is settled once a side wins
+    fn update_versus(&mut self, events: &[TimedEvent], twin_events: &[TimedEvent]) {
+        let (Some(versus), Some(twin)) = (self.versus.as_mut(), self.twin.as_mut()) else {
+            return;
+        };
+        if let Some(winner) = versus.update(&mut self.game, twin, events, twin_events) {
+            let name = self.strings.get(side_key(versus.teams, winner)).to_string();
+            self.toasts.push(ToastKind::Info, self.strings.fmt("versus.won", &[&name]));
+        }
+    }
+
+    fn mode_changed(&mut self) {
+        if self.last_mode == GameMode::Zen {
+            self.save_zen();
+        }
+        self.last_mode = self.game.mode;
+
+        // Pick Zen back up where it was left
+        if self.game.mode == GameMode::Zen && self.game.game_state == GameState::Ready {
+            if let Some(save) = self.zen_save.take() {
+                self.game.restore_progress(board_from_rows(&save.board), save.lines, save.score, save.pieces);
+            }
+        }
+        self.zen_lines_seen = self.game.lines_cleared;
+    }
+
+    fn update_zen(&mut self) {
+        // Lines only ever go down when a restart wipes them; those were counted already
+        let lines = self.game.lines_cleared;
+        self.zen_lifetime_lines += lines.saturating_sub(self.zen_lines_seen) as u64;
+        self.zen_lines_seen = lines;
+
+        if self.game.game_state == GameState::Playing
+            && self.zen_saved_at.elapsed() >= Duration::from_secs(ZEN_AUTOSAVE_SECS)
+        {
+            self.save_zen();
+        }
+    }
+
+    // Also called on quit so nothing since the last autosave is lost
+    pub fn save_zen(&mut self) {
+        self.zen_saved_at = Instant::now();
+        if self.game.mode != GameMode::Zen {
+            return;
+        }
+
+        let save = ZenSave {
+            lines: self.game.lines_cleared,
+            score: self.game.scoring.points,
+            pieces: self.game.pieces_placed,
+            board: board_to_rows(&self.game.board),
+            lifetime_lines: self.zen_lifetime_lines,
+        };
+        let saved = if self.persist { persistence::save_zen(&save) } else { Ok(()) };
+        if let Err(e) = saved {
+            tracing::warn!(error = %e, "couldn't save zen progress");
+        }
+        self.zen_save = Some(save);
+    }
+
+    fn record_run(&mut self) {
+        let record = RunRecord::from_game(&self.game);
+
+        tracing::info!(
+            mode = record.mode.short_name(),
+            completed = record.completed,
+            time_ms = record.time_ms,
+            pieces = record.pieces,
+            misdrops = record.misdrops,
+            "run finished"
+        );
+        // Losing a history entry shouldn't end the session
+        let saved = if self.persist { persistence::append_run(&record) } else { Ok(()) };
+        if let Err(e) = saved {
+            tracing::warn!(error = %e, "couldn't save run history");
+        }
+
+        let completed = record.completed;
+        self.history.push(record);
+        self.run_recorded = true;
+
+        if let Some(koth) = self.koth.as_mut() {
+            if !koth.finish_turn(&self.game) {
+                let mode = koth.mode.map_or("", |mode| self.strings.mode_title(mode));
+                self.toasts.push(ToastKind::Warning, self.strings.fmt("toast.koth_wrong_mode", &[&mode]));
+            }
+            return; // Other people's runs aren't the owner's PBs
+        }
+
+        // PBs are best times, so only modes ranked by time have them. A run
+        // on a fixed queue is practice, one with big pieces is a novelty and
+        // a frame-stepped one isn't real time, so none is ever a PB. Custom
+        // modes finish differently from the mode they're based on, and a
+        // board played one-handed beside another is practice too. Garbage
+        // from an online opponent makes a run no measure of anything, and
+        // neither does one held under the quit prompt, with time to think
+        // off the clock. A run played blind is a different challenge, kept
+        // to its own tag.
+        if completed
+            && self.game.mode.is_time_ranked()
+            && self.game.queue.is_empty()
+            && !self.game.big_pieces
+            && self.game.blind.is_none()
+            && !self.frame_step
+            && self.game.goal.is_none()
+            && self.twin.is_none()
+            && self.netplay.is_none()
+            && !self.game.replay.paused()
+        {
+            self.check_pb();
+        }
+    }
+
+    // Play a custom mode from the config: its base mode, finished by its own
+    // win condition
+    pub fn start_custom(&mut self, name: &str) {
+        let Some(custom) = self.config.modes.get(name) else {
+            self.toasts.push(ToastKind::Warning, self.strings.fmt("toast.custom_unknown", &[&name]));
+            return;
+        };
+        let text = self.strings.fmt("toast.custom", &[&name, &self.strings.goal(&custom.win)]);
+        self.toasts.push(ToastKind::Info, text);
+        self.game.mode = custom.base;
+        self.game.goal = Some(custom.win.clone());
+        self.game.prepare_run(self.game.seed);
+    }
+
+    // Play through these puzzles, starting with the first
+    pub fn start_puzzles(&mut self, puzzles: Vec<Puzzle>) {
+        self.puzzles = puzzles;
+        self.puzzle_index = 0;
+        self.game.mode = GameMode::Puzzle;
+        self.load_puzzle();
+        self.game.prepare_run(self.game.seed);
+    }
+
+    // A solved puzzle's successor is set up by the next restart
+    fn next_puzzle(&mut self) {
+        if self.puzzle_index + 1 < self.puzzles.len() {
+            self.puzzle_index += 1;
+            self.load_puzzle();
+        } else if !self.puzzles.is_empty() {
+            self.toasts.push(ToastKind::Info, self.strings.get("toast.puzzles_done"));
+        }
+    }
+
+    fn load_puzzle(&mut self) {
+        let Some(puzzle) = self.puzzles.get(self.puzzle_index) else {
+            return;
+        };
+        let text = self.strings.fmt("toast.puzzle", &[&(self.puzzle_index + 1), &self.puzzles.len(), &puzzle.name]);
+        self.toasts.push(ToastKind::Info, text);
+        self.game.puzzle = Some(puzzle.clone());
+    }
+
+    pub fn start_koth(&mut self, players: Vec<String>) {
+        self.name_entry = None;
+        self.koth = Some(Koth::new(players));
+    }
+
+    // The next player has the keyboard: start their run on the match's
+    // mode and seed
+    pub fn take_turn(&mut self) {
+        let Some(koth) = self.koth.as_mut() else {
+            return;
+        };
+        koth.handoff = false;
+        self.game.player = koth.up_next().to_string();
+        self.game.fixed_seed = Some(koth.seed);
+        if let Some(mode) = koth.mode {
+            self.game.mode = mode;
+        }
+        self.show_stats = false;
+        self.game.reset();
+    }
+
+    pub fn pb(&self, mode: GameMode) -> Option<&PersonalBest> {
+        self.pbs.iter().find(|pb| pb.mode == mode)
+    }
+
+    // A better time only becomes the PB once its replay re-simulates to the
+    // same time and final board
+    fn check_pb(&mut self) {
+        let Some(time) = self.game.final_time else {
+            return;
+        };
+        let mode = self.game.mode;
+        if self.pb(mode).is_some_and(|pb| !mode.compare_times(time, pb.time()).is_gt()) {
+            return;
+        }
+
+        let board_hash = replay::board_hash(&self.game.board);
+        match replay::verify(&self.game.replay, time, board_hash) {
+            Ok(validation_hash) => {
+                let time_nanos = time.as_nanos() as u64;
+                self.pbs.retain(|pb| pb.mode != mode);
+                self.pbs.push(PersonalBest {
+                    mode,
+                    time_nanos,
+                    board_hash,
+                    validation_hash,
+                    timestamp: self.history.last().map_or(0, |run| run.timestamp),
+                });
+
+                let replay_file = ReplayFile {
+                    replay: self.game.replay.clone(),
+                    time_nanos,
+                    board_hash,
+                    validation_hash,
+                };
+                tracing::info!(mode = mode.short_name(), time_nanos, "new PB");
+                let saved = if self.persist { persistence::save_pb(&self.pbs, &replay_file) } else { Ok(()) };
+                if let Err(e) = saved {
+                    tracing::error!(error = %e, "couldn't save PB");
+                    self.toasts.push(ToastKind::Error, self.strings.fmt("toast.save_failed", &[&e]));
+                }
+                self.pb_status = Some(PbStatus::New);
+                self.toasts.push(ToastKind::Success, self.strings.fmt("toast.new_pb", &[&format_time(time)]));
+                // Race the new PB from now on
+                self.ghost = None;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "PB replay failed verification");
+                self.pb_status = Some(PbStatus::Rejected);
+                self.toasts.push(ToastKind::Warning, self.strings.get("toast.pb_rejected"));
+            }
+        }
+    }
+
+    pub fn session_runs(&self) -> &[RunRecord] {
+        &self.history[self.session_start..]
+    }
+
+    // Completed run times for a mode in this session, oldest first
+    fn session_times(&self, mode: GameMode) -> impl Iterator<Item = Duration> + '_ {
+        self.session_runs()
+            .iter()
+            .filter(move |run| run.mode == mode && run.completed)
+            .map(|run| run.time())
+    }
+
+    pub fn session_best(&self, mode: GameMode) -> Option<Duration> {
+        self.session_times(mode).max_by(|&time, &other| mode.compare_times(time, other))
+    }
+
+    pub fn session_average(&self, mode: GameMode) -> Option<Duration> {
+        let times: Vec<_> = self.session_times(mode).collect();
+        if times.is_empty() {
+            None
+        } else {
+            Some(times.iter().sum::<Duration>() / times.len() as u32)
+        }
+    }
+
+    pub fn session_summary(&self) -> SessionSummary {
+        let runs = self.session_runs();
+        let pieces = runs.iter().map(|run| run.pieces).sum();
+        let time: Duration = runs.iter().map(|run| run.time()).sum();
+        let pps = if time.is_zero() { 0.0 } else { pieces as f64 / time.as_secs_f64() };
+
+        SessionSummary {
+            attempts: self.session_attempts,
+            finished: runs.iter().filter(|run| run.completed).count(),
+            best: self.session_best(self.game.mode),
+            average: self.session_average(self.game.mode),
+            pieces,
+            pps,
+        }
+    }
+
+    // Start a fresh session; saved history and lifetime stats are untouched
+    pub fn reset_session(&mut self) {
+        if matches!(self.game.game_state, GameState::Playing | GameState::Paused) {
+            return;
+        }
+        self.session_start = self.history.len();
+        self.session_attempts = 0;
+    }
+
+    // Only ever the live game's
+    pub fn trail(&self) -> Option<&Trail> {
+        self.trail.as_ref().filter(|_| self.playback.is_none() && self.attract.is_none())
+    }
+
+    // Likewise
+    pub fn score_popups(&self) -> &[ScorePopup] {
+        if self.playback.is_none() && self.attract.is_none() {
+            &self.popups
+        } else {
+            &[]
+        }
+    }
+
+    // The game on screen: the replay being watched, otherwise the live one
+
+    pub fn shown_game(&self) -> &Game {
+        if let Some(attract) = &self.attract {
+            return &attract.game;
+        }
+        self.playback.as_ref().map_or(&self.game, |playback| &playback.game)
+    }
+
+    // Nothing on screen moves without input, so the loop can slow right down
+    pub fn idle(&self) -> bool {
+        if self.calibration.is_some() {
+            return false;
+        }
+        let game = self.shown_game();
+        let stopped = matches!(game.game_state, GameState::Ready | GameState::Finished | GameState::Paused);
+        match &self.playback {
+            Some(playback) => playback.paused || stopped,
+            None => stopped || self.frame_step,
+        }
+    }
+
+    // Online versus over an open connection. Pausing would stop only one
+    // side's clock, so the game never pauses.
+    pub fn start_netplay(&mut self, stream: TcpStream) -> std::io::Result<()> {
+        let input_delay = Duration::from_millis(self.config.netplay.input_delay_ms);
+        self.netplay = Some(Netplay::start(stream, &self.game.player, input_delay)?);
+        self.game.versus = true;
+        Ok(())
+    }
+
+    fn update_netplay(&mut self, events: &[TimedEvent]) {
+        let Some(netplay) = self.netplay.as_mut() else {
+            return;
+        };
+        for news in netplay.update(&mut self.game, events) {
+            let opponent = netplay.opponent.clone().unwrap_or_default();
+            let (kind, text) = match news {
+                MatchEvent::Joined(name) => (ToastKind::Info, self.strings.fmt("toast.netplay_joined", &[&name])),
+                MatchEvent::Left => (ToastKind::Warning, self.strings.fmt("toast.netplay_left", &[&opponent])),
+                MatchEvent::Decided(Outcome::Won) => (ToastKind::Info, self.strings.fmt("toast.netplay_won", &[&opponent])),
+                MatchEvent::Decided(Outcome::Lost) => (ToastKind::Warning, self.strings.fmt("toast.netplay_lost", &[&opponent])),
+            };
+            self.toasts.push(kind, text);
+        }
+    }
+
+    // T in a match. Keys held for the game are let go, since their releases
+    // will go to the chat.
+    pub fn open_chat(&mut self) {
+        if self.netplay.as_ref().is_some_and(Netplay::connected) {
+            self.game.apply_input(InputEvent::ReleaseAll);
+            self.chat_entry = Some(String::new());
+        }
+    }
+
+    pub fn type_chat_char(&mut self, c: char) {
+        if let Some(entry) = self.chat_entry.as_mut() {
+            if !c.is_control() && entry.chars().count() < NETPLAY_CHAT_CHARS {
+                entry.push(c);
+            }
+        }
+    }
+
+    pub fn erase_chat_char(&mut self) {
+        if let Some(entry) = self.chat_entry.as_mut() {
+            entry.pop();
+        }
+    }
+
+    // Enter: send it, unless there's nothing to send
+    pub fn send_chat(&mut self) {
+        let Some(entry) = self.chat_entry.take() else {
+            return;
+        };
+        if let (Some(netplay), false) = (self.netplay.as_mut(), entry.trim().is_empty()) {
+            netplay.say(entry.trim(), false);
+        }
+    }
+
+    // The emote `[netplay] emotes` gives a key, if any
+    pub fn emote(&self, key_code: KeyCode) -> Option<&str> {
+        let key_code = match key_code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            key_code => key_code,
+        };
+        let (_, text) = self.config.netplay.emotes.iter().find(|(key, _)| parse_key(key) == Some(key_code))?;
+        self.netplay.is_some().then_some(text.as_str())
+    }
+
+    pub fn send_emote(&mut self, key_code: KeyCode) {
+        let Some(text) = self.emote(key_code).map(str::to_string) else {
+            return;
+        };
+        if let Some(netplay) = self.netplay.as_mut() {
+            netplay.say(&text, true);
+        }
+    }
+
+    pub fn watch(&mut self, replay: Replay) {
+        self.show_stats = false;
+        self.playback = Some(Playback::new(replay));
+    }
+
+    // Watch the run that just finished
+    // The current mode's PB replay, for the title screen
+    pub fn watch_pb(&mut self) {
+        let path = persistence::pb_replay_path(self.game.mode).filter(|_| self.persist);
+        match path.and_then(|path| persistence::load_replay(&path).ok()) {
+            Some(file) => self.watch(file.replay),
+            None => self.toasts.push(ToastKind::Info, self.strings.fmt("toast.no_pb_replay", &[&self.strings.mode_title(self.game.mode)])),
+        }
+    }
+
+    // The title screen shows on the Ready screen with nothing else over it
+    pub fn on_title(&self) -> bool {
+        self.game.game_state == GameState::Ready
+            && self.attract.is_none()
+            && self.playback.is_none()
+            && self.calibration.is_none()
+            && !self.show_stats
+    }
+
+    // Enter on the title screen. False for Quit.
+    pub fn choose_title_item(&mut self) -> bool {
+        if let Some(mode) = self.title.picked_mode() {
+            self.title.picking_mode = None;
+            self.game.set_mode(mode);
+            return true;
+        }
+        match self.title.item() {
+            MenuItem::Play => self.game.start_countdown(),
+            MenuItem::Modes => self.title.open_modes(self.game.mode),
+            MenuItem::Settings => self.open_handling_menu(),
+            MenuItem::Stats => self.toggle_stats(),
+            MenuItem::Replays => self.watch_pb(),
+            MenuItem::Quit => return false,
+        }
+        true
+    }
+
+    pub fn watch_last_run(&mut self) {
+        if self.game.game_state == GameState::Finished {
+            self.watch(self.game.replay.clone());
+        }
+    }
+
+    pub fn type_name_char(&mut self, c: char) {
+        if let Some(name) = self.name_entry.as_mut() {
+            if !c.is_control() && text::width(name) + text::width(&c.to_string()) <= NAME_MAX_WIDTH {
+                name.push(c);
+            }
+        }
+    }
+
+    pub fn erase_name_char(&mut self) {
+        if let Some(name) = self.name_entry.as_mut() {
+            name.pop();
+        }
+    }
+
+    // Keep the typed name for this and future sessions; blank names are ignored
+    pub fn confirm_name(&mut self) {
+        let Some(name) = self.name_entry.as_ref().map(|name| name.trim().to_string()) else {
+            return;
+        };
+        if name.is_empty() {
+            return;
+        }
+        // Unwritable config just means asking again next launch
+        if let Err(e) = config::save_name(&name) {
+            tracing::warn!(error = %e, "couldn't save player name");
+            self.toasts.push(ToastKind::Warning, self.strings.fmt("toast.name_not_saved", &[&e]));
+        }
+        self.game.player = name.clone();
+        self.toasts.push(ToastKind::Info, self.strings.fmt("toast.welcome", &[&name]));
+        self.config.name = Some(name);
+        self.name_entry = None;
+    }
+
+    // Enter on a setup step: the next one, or save the choices after the last
+    pub fn advance_wizard(&mut self) {
+        let Some(wizard) = self.wizard.as_mut() else {
+            return;
+        };
+        if wizard.advance() {
+            return;
+        }
+        self.config.handling.das_ms = Some(wizard.timings().das_ms);
+        self.config.handling.arr_ms = Some(wizard.timings().arr_ms);
+        self.config.handling.soft_drop_ms = Some(wizard.timings().soft_drop_ms);
+        self.config.glyphs = wizard.glyphs();
+        self.game.timings = wizard.timings();
+        self.wizard = None;
+        // The choices still hold for this session if they can't be saved
+        match config::save_setup(&self.config.handling, &self.config.glyphs) {
+            Ok(path) => tracing::info!(path = %path.display(), "setup saved"),
+            Err(e) => {
+                tracing::warn!(error = %e, "couldn't save setup");
+                self.toasts.push(ToastKind::Warning, self.strings.fmt("toast.setup_not_saved", &[&e]));
+            }
+        }
+    }
+
+    // Q quits straight away except mid-run, where it asks first. The run
+    // waits paused meanwhile, timed or not, with the pause in the replay;
+    // only a versus match carries on, as the other player's clock does.
+    // False to quit now.
+    pub fn ask_quit(&mut self) -> bool {
+        let mid_run = matches!(self.game.game_state, GameState::Countdown(_) | GameState::Playing | GameState::Paused);
+        if !mid_run {
+            return false;
+        }
+        let running = self.game.game_state != GameState::Paused && self.game.paused_at.is_none();
+        self.game.apply_input(InputEvent::ReleaseAll);
+        if running && !self.game.versus {
+            self.game.apply_input(InputEvent::Pause);
+        }
+        self.quit_prompt = Some(running && self.game.paused_at.is_some());
+        true
+    }
+
+    pub fn keep_playing(&mut self) {
+        if self.quit_prompt.take() == Some(true) {
+            self.game.focus_gained();
+        }
+    }
+
+    // G on the Ready screen of a versus match, local or online
+    pub fn open_match_setup(&mut self) {
+        if self.game.game_state != GameState::Ready {
+            return;
+        }
+        if let Some(versus) = &self.versus {
+            self.match_setup = Some(MatchSetup::new(2, versus.teams));
+        } else if self.netplay.is_some() {
+            self.match_setup = Some(MatchSetup::new(1, false));
+        }
+    }
+
+    // ← and → on the handicap screen. Online, the other side is told.
+    pub fn change_handicap(&mut self, delta: i32) {
+        let Some(setup) = self.match_setup else {
+            return;
+        };
+        if self.game.game_state != GameState::Ready {
+            return;
+        }
+        let Row::Handicap(player, setting) = setup.selected() else {
+            if let Some(versus) = self.versus.as_mut() {
+                versus.cycle_targeting(delta as isize);
+            }
+            return;
+        };
+        let game = match player {
+            0 => Some(&mut self.game),
+            _ => self.twin.as_mut(),
+        };
+        let Some(game) = game else {
+            return;
+        };
+        let mut handicap = game.handicap;
+        setting.step(&mut handicap, delta);
+        game.set_handicap(handicap);
+        tracing::info!(player, ?handicap, "handicap");
+        if let Some(netplay) = self.netplay.as_mut().filter(|_| player == 0) {
+            netplay.share_handicap(handicap);
+        }
+    }
+
+    pub fn open_handling_menu(&mut self) {
+        if self.game.game_state == GameState::Ready {
+            self.handling_menu = true;
+        }
+    }
+
+    pub fn preset_name(&self) -> String {
+        match self.preset {
+            Some(i) => self.config.handling.presets().swap_remove(i).0,
+            None => self.strings.get("presets.config").to_string(),
+        }
+    }
+
+    // Switch handling between runs; None goes back to [handling]'s own timings
+    pub fn select_preset(&mut self, preset: Option<usize>) {
+        let presets = self.config.handling.presets();
+        let timings = match preset {
+            Some(i) => match presets.get(i) {
+                Some((_, timings)) => *timings,
+                None => return,
+            },
+            None => self.config.handling.timings(),
+        };
+        if self.game.game_state != GameState::Ready {
+            return;
+        }
+        self.game.timings = timings;
+        if let Some(twin) = self.twin.as_mut() {
+            twin.timings = timings;
+        }
+        self.preset = preset;
+        self.handling_menu = false;
+        tracing::info!(preset = %self.preset_name(), ?timings, "handling preset");
+        self.toasts.push(ToastKind::Info, self.strings.fmt("toast.preset", &[&self.preset_name()]));
+    }
+
+    pub fn toggle_profiler(&mut self) {
+        self.show_profiler = !self.show_profiler;
+    }
+
+    pub fn start_calibration(&mut self) {
+        self.calibration = Some(Calibration::new(Instant::now()));
+    }
+
+    // `--frame-step`: time stands still until '.' or '>' moves it on
+    pub fn start_frame_step(&mut self) {
+        self.frame_step = true;
+        self.game.freeze_clock();
+    }
+
+    pub fn step_frames(&mut self, steps: u32) {
+        self.frame_steps += steps;
+    }
+
+    pub fn guess_next(&mut self, piece_type: PieceType) {
+        if self.game.game_state != GameState::Playing {
+            return;
+        }
+        if let Some(prediction) = self.prediction.as_mut() {
+            prediction.guess(piece_type);
+        }
+    }
+
+    // Each step moves the game's clock on by FRAME_STEP_MS and runs one
+    // update, logging the timers it left behind
+    fn run_frame_steps(&mut self) -> Result<()> {
+        for _ in 0..std::mem::take(&mut self.frame_steps) {
+            let clock = self.game.clock() + Duration::from_millis(FRAME_STEP_MS);
+            self.game.set_clock(clock);
+            self.game.update()?;
+            let report: Vec<String> = self.game.timer_report().into_iter().map(|(label, value)| format!("{}={}", label, value)).collect();
+            tracing::debug!(timers = %report.join(" "), "frame step");
+        }
+        Ok(())
+    }
+
+    // The countdown number to draw, visual_offset_ms ahead of the engine's
+    pub fn countdown_shown(&self, count: u32) -> u32 {
+        let offset = Duration::from_millis(self.config.handling.visual_offset_ms);
+        let now = if self.frame_step { self.game.clock() } else { Instant::now() };
+        let now = self.game.paused_at.unwrap_or(now);
+        let into_step = now.saturating_duration_since(self.game.countdown_timer);
+        if into_step + offset >= Duration::from_secs(1) {
+            count - 1
+        } else {
+            count
+        }
+    }
+
+    pub fn toggle_input_diagnostics(&mut self) {
+        self.show_input_diagnostics = !self.show_input_diagnostics;
+    }
+
+    pub fn toggle_event_log(&mut self) {
+        self.show_event_log = !self.show_event_log;
+    }
+
+    pub fn toggle_analysis(&mut self) {
+        self.show_analysis = !self.show_analysis;
+    }
+
+    // Show what a hard drop would leave, for a moment
+    pub fn show_preview(&mut self) {
+        self.preview_until = Some(Instant::now() + Duration::from_millis(PREVIEW_MS));
+    }
+
+    pub fn previewing(&self) -> bool {
+        self.playback.is_none() && self.preview_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    // Look for a T-spin the next few pieces can set up. Practice only: Zen
+    // and fixed-queue runs, which never count as PBs anyway
+    pub fn request_hint(&mut self) {
+        if self.game.game_state != GameState::Playing || self.playback.is_some() {
+            return;
+        }
+        if self.game.mode != GameMode::Zen && self.game.queue.is_empty() {
+            self.toasts.push(ToastKind::Warning, self.strings.get("toast.hint_practice"));
+            return;
+        }
+        let Some(current) = &self.game.current_piece else {
+            return;
+        };
+        let queue: Vec<_> = std::iter::once(current.piece_type)
+            .chain(self.game.next_pieces.iter().map(|piece| piece.piece_type))
+            .take(HINT_PIECES)
+            .collect();
+        self.hint = tspin::find_setup(&self.game.board, &queue);
+        self.hint_pieces = self.game.pieces_placed;
+        match &self.hint {
+            Some(hint) => {
+                let text = self.strings.fmt("toast.hint", &[&hint.steps.len(), &hint.lines]);
+                self.toasts.push(ToastKind::Info, text);
+            }
+            None => self.toasts.push(ToastKind::Info, self.strings.fmt("toast.no_hint", &[&HINT_PIECES])),
+        }
+    }
+
+    // Move the hint on as each step is placed, or drop it once the player goes their own way
+    fn follow_hint(&mut self) {
+        let Some(hint) = self.hint.as_mut() else {
+            return;
+        };
+        if self.game.pieces_placed == self.hint_pieces {
+            return;
+        }
+        self.hint_pieces = self.game.pieces_placed;
+        let followed = match (hint.next_step(), &self.game.last_locked) {
+            (Some(step), Some(placed)) => Hint::followed(step, placed),
+            _ => false,
+        };
+        if !followed {
+            self.hint = None;
+            self.toasts.push(ToastKind::Warning, self.strings.get("toast.hint_lost"));
+            return;
+        }
+        hint.done += 1;
+        if hint.next_step().is_none() {
+            self.hint = None;
+        }
+    }
+
+    pub fn toggle_session_panel(&mut self) {
+        self.show_session = !self.show_session;
+    }
+
+    pub fn toggle_stats(&mut self) {
+        // The dashboard covers the board, so keep it out of the way mid-run
+        if matches!(
+            self.game.game_state,
+            GameState::Countdown(_) | GameState::Playing | GameState::Paused
+        ) {
+            return;
+        }
+        self.show_stats = !self.show_stats;
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+use crate::constants::{CALIBRATION_BEAT_MS, CALIBRATION_FLASH_MS, CALIBRATION_TAPS, CALIBRATION_WARMUP_BEATS};
+
+// Tap-along latency test: a box flashes on a steady beat and the player
+// presses Space in time with it. Keeping time with a beat takes reaction
+// time out of it, so how late the taps land on average is mostly the lag
+// between the terminal drawing a frame and the player seeing it, plus the
+// lag reading the key back.
+#[derive(Clone, Debug)]
+pub struct Calibration {
+    started: Instant,
+    pub offsets: Vec<f64>, // Each tap against its beat in ms; negative is early
+}
+
+impl Calibration {
+    pub fn new(now: Instant) -> Self {
+        Self { started: now, offsets: Vec::new() }
+    }
+
+    pub fn lit(&self, now: Instant) -> bool {
+        let since = now.duration_since(self.started).as_millis() as u64;
+        since >= CALIBRATION_BEAT_MS && since % CALIBRATION_BEAT_MS < CALIBRATION_FLASH_MS
+    }
+
+    // Still in the first few beats, which are for finding the rhythm
+    pub fn warming_up(&self, now: Instant) -> bool {
+        now.duration_since(self.started) < Duration::from_millis(CALIBRATION_BEAT_MS * CALIBRATION_WARMUP_BEATS)
+    }
+
+    pub fn done(&self) -> bool {
+        self.offsets.len() >= CALIBRATION_TAPS
+    }
+
+    // Measured against the nearest flash; taps during the warm up don't count
+    pub fn tap(&mut self, now: Instant) {
+        if self.done() || self.warming_up(now) {
+            return;
+        }
+        let since = now.duration_since(self.started).as_secs_f64() * 1000.0;
+        let beat = CALIBRATION_BEAT_MS as f64;
+        self.offsets.push(since - (since / beat).round() * beat);
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        (!self.offsets.is_empty()).then(|| self.offsets.iter().sum::<f64>() / self.offsets.len() as f64)
+    }
+
+    // `fraction` from 0.0 (earliest tap) to 1.0 (latest)
+    pub fn percentile(&self, fraction: f64) -> Option<f64> {
+        let mut sorted = self.offsets.clone();
+        sorted.sort_by(f64::total_cmp);
+        let index = ((sorted.len().checked_sub(1)?) as f64 * fraction).round() as usize;
+        sorted.get(index).copied()
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crossterm::terminal;
+use serde_json::json;
+
+// The local terminal's output, also written to an asciinema v2 recording
+// when one was asked for. Every flush is one frame, stamped with the time
+// since recording started.
+pub struct Recorder<W: Write> {
+    inner: W,
+    cast: Option<Cast>,
+}
+
+struct Cast {
+    file: BufWriter<File>,
+    started: Instant,
+    frame: Vec<u8>,
+    size: (u16, u16),
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(inner: W, cast: Option<&Path>) -> io::Result<Self> {
+        let cast = cast.map(Cast::create).transpose()?;
+        Ok(Self { inner, cast })
+    }
+}
+
+impl Cast {
+    fn create(path: &Path) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let size = terminal::size()?;
+        let header = json!({
+            "version": 2,
+            "width": size.0,
+            "height": size.1,
+            "timestamp": SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs()),
+            "title": "tstris",
+            "env": { "TERM": std::env::var("TERM").unwrap_or_default() },
+        });
+        writeln!(file, "{}", header)?;
+        tracing::info!(path = %path.display(), "recording cast");
+        Ok(Self { file, started: Instant::now(), frame: Vec::new(), size })
+    }
+
+    fn write_frame(&mut self) -> io::Result<()> {
+        let time = self.started.elapsed().as_secs_f64();
+        let size = terminal::size()?;
+        if size != self.size {
+            self.size = size;
+            writeln!(self.file, "{}", json!([time, "r", format!("{}x{}", size.0, size.1)]))?;
+        }
+
+        // A character split across two flushes waits for the rest of it
+        let complete = match std::str::from_utf8(&self.frame) {
+            Ok(_) => self.frame.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        if complete == 0 {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.frame[..complete]);
+        writeln!(self.file, "{}", json!([time, "o", text]))?;
+        self.frame.drain(..complete);
+        self.file.flush()
+    }
+}
+
+impl<W: Write> Write for Recorder<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(bytes)?;
+        if let Some(cast) = self.cast.as_mut() {
+            cast.frame.extend_from_slice(&bytes[..written]);
+        }
+        Ok(written)
+    }
+
+    // A recording that can't be written stops, but the game goes on
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(Err(e)) = self.cast.as_mut().map(Cast::write_frame) {
+            tracing::warn!(error = %e, "cast recording stopped");
+            self.cast = None;
+        }
+        self.inner.flush()
+    }
+}
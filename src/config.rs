@@ -0,0 +1,452 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::constants::{MAX_FPS, NETPLAY_INPUT_DELAY_MS, RESTART_HOLD_MS};
+use crate::error::{Error, Result};
+use crate::game::garbage::{GarbageRules, HolePattern};
+use crate::game::level::{Breakpoint, CurveError, SpeedCurve};
+use crate::game::win::Goal;
+use crate::game::GameMode;
+use crate::i18n::Language;
+use crate::input::action::{parse_key, BindingError, Bindings};
+use crate::input::repeat::{Timings, BUILT_IN_PRESETS};
+use crate::ui::graphics::GraphicsMode;
+use crate::ui::layout::{CompactMode, LayoutPreset, Orientation};
+use crate::ui::theme::{BorderStyle, Palette};
+
+const CONFIG_FILE: &str = "config.toml";
+
+// The schema this build writes. Bump it along with a new entry in MIGRATIONS
+// whenever a setting is renamed, moved or changes meaning.
+pub const CONFIG_VERSION: u32 = 1;
+
+// MIGRATIONS[n] takes a config table from version n to n + 1. Files with no
+// version are version 0, from before the config had one.
+const MIGRATIONS: [fn(&mut Table); CONFIG_VERSION as usize] = [
+    unversioned, // 0 -> 1
+];
+
+// Nothing moved; these files only need their version written
+fn unversioned(_: &mut Table) {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub version: u32,         // Schema version the file was written for; see CONFIG_VERSION
+    pub name: Option<String>, // Player name for records and replays; asked for on first run
+    // Sprint distance in lines -> goal time, e.g. `40 = "0:59.999"`
+    pub goals: BTreeMap<String, String>,
+    pub theme: ThemeConfig,
+    pub board: BoardConfig,
+    pub layout: LayoutConfig,
+    pub display: DisplayConfig,
+    pub glyphs: GlyphConfig,
+    pub marathon: MarathonConfig,
+    pub handling: HandlingConfig,
+    pub garbage: GarbageConfig,
+    pub zen: ZenConfig,
+    pub netplay: NetplayConfig,
+    // Curve name -> breakpoints, as `[[curves.nes]]` tables
+    pub curves: BTreeMap<String, Vec<Breakpoint>>,
+    // Name -> custom mode, as `[modes.cheese]` tables; played with `--custom <name>`
+    pub modes: BTreeMap<String, CustomMode>,
+    pub language: Language, // UI language: "en", "es" or "ja"
+    // Key -> action over the built-in keys, e.g. `x = "rotate_ccw"` or `r = "none"`
+    pub bindings: BTreeMap<String, String>,
+    // Mode short name -> bindings over those, e.g. `[mode_bindings.40l]`
+    pub mode_bindings: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+// Cell glyphs are two columns wide; overrides of any other width are ignored
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlyphConfig {
+    pub ascii: bool, // "[]" blocks and plain ASCII everywhere for fonts without block characters
+    pub filled: Option<String>,
+    pub ghost: Option<String>,
+    pub background: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub preset: LayoutPreset,     // Layout to start with; P cycles presets while running
+    pub mirrored: bool,           // Hold panel on the right and next queue on the left
+    pub compact: CompactMode,     // One small panel, for terminals that can't fit the rest
+    pub orientation: Orientation, // Horizontal lays the board on its side, arrows turned to match
+    pub mini_boards: CompactMode, // The CPU's or right hand's board in braille, at quarter size
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub max_fps: u32,           // Frames drawn a second at most, e.g. 30, 60 or 120; 0 is uncapped
+    pub graphics: GraphicsMode, // Draw the board as pixels: off, auto, kitty or sixel
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self { max_fps: MAX_FPS, graphics: GraphicsMode::default() }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarathonConfig {
+    pub curve: Option<String>, // Speed curve from [curves] to play instead of guideline gravity
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandlingConfig {
+    pub hard_drop_protection_ms: u64, // Hard drops this soon after a piece spawns are ignored; 0 turns it off
+    pub hold_fresh_press: bool,       // Hold only on a new press, not a key still down from before
+    pub hold_cooldown_ms: u64,        // Holds this soon after the last one are ignored; 0 turns it off
+    pub rotate_repeat: bool,          // Holding a rotate key keeps rotating, at the terminal's key repeat
+    pub visual_offset_ms: u64,        // Countdown numbers are drawn this early, making up for display lag
+    pub das_ms: Option<u64>,          // Held sideways keys start repeating after this long
+    pub arr_ms: Option<u64>,          // Then repeat this often; 0 slides straight to the wall
+    pub soft_drop_ms: Option<u64>,    // Held soft drop moves a row this often; 0 drops as fast as allowed
+    pub lock_delay_ms: Option<u64>,   // A landed piece locks after resting this long
+    pub preset: Option<String>,       // Preset to start with instead of the timings above
+    // Mode short name -> how long restart has to be held mid-run, e.g. `marathon = 500`; 0 restarts at once
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub restart_hold_ms: BTreeMap<String, u64>,
+    // Name -> timings, as `[handling.presets."My sprint"]` tables; K on the Ready screen switches
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, Timings>,
+}
+
+impl HandlingConfig {
+    // Unset timings keep the built-in ones
+    pub fn timings(&self) -> Timings {
+        let default = Timings::default();
+        Timings {
+            das_ms: self.das_ms.unwrap_or(default.das_ms),
+            arr_ms: self.arr_ms.unwrap_or(default.arr_ms),
+            soft_drop_ms: self.soft_drop_ms.unwrap_or(default.soft_drop_ms),
+            lock_delay_ms: self.lock_delay_ms.unwrap_or(default.lock_delay_ms),
+        }
+    }
+
+    // Modes with long runs to lose want restart held unless the config says
+    // otherwise; the rest restart on the press
+    pub fn restart_hold(&self, mode: GameMode) -> Duration {
+        let built_in = match mode {
+            GameMode::Marathon | GameMode::Zen => RESTART_HOLD_MS,
+            _ => 0,
+        };
+        let millis = self.restart_hold_ms.get(&mode.short_name().to_lowercase()).copied();
+        Duration::from_millis(millis.unwrap_or(built_in))
+    }
+
+    // The built-in presets, then the config's own in name order
+    pub fn presets(&self) -> Vec<(String, Timings)> {
+        BUILT_IN_PRESETS
+            .iter()
+            .map(|(name, timings)| (name.to_string(), *timings))
+            .chain(self.presets.iter().map(|(name, timings)| (name.clone(), *timings)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZenConfig {
+    pub adaptive_gravity: bool, // Gravity speeds up while the stack stays low and play is quick, and eases off when it builds up
+}
+
+// A built-in mode's rules with a win condition of its own
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CustomMode {
+    pub base: GameMode, // Gravity, garbage and everything else come from this mode, e.g. "Survival"
+    pub win: Goal,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GarbageConfig {
+    pub pattern: HolePattern, // "clean" keeps every hole in one column, "messy" moves it each row
+    pub hole_width: usize,    // Columns open in each garbage row, 1 to 4
+    pub preview: bool,        // Survival: mark under the board where the next row's hole will be
+}
+
+impl Default for GarbageConfig {
+    fn default() -> Self {
+        let rules = GarbageRules::default();
+        Self { pattern: rules.pattern, hole_width: rules.hole_width, preview: false }
+    }
+}
+
+impl GarbageConfig {
+    pub fn rules(&self) -> GarbageRules {
+        GarbageRules { pattern: self.pattern, hole_width: self.hole_width }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoardConfig {
+    pub column_guides: bool, // Faint line down the left of every column
+    pub bottom_row: bool,    // Mark the floor row so it stands out on dense boards
+    pub drop_column: bool,   // Shade the columns the active piece will land in
+    pub trail: bool,         // Streak behind hard-dropped pieces, briefly
+    pub lock_meter: bool,    // Floor border empties while a grounded piece waits to lock
+    pub score_popups: bool,  // Points from each clear float up from the cleared rows
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub sound: bool, // Terminal bell on each countdown step and at GO
+    // Mode short name -> accent color, e.g. `dig = "lightred"` or `zen = "#aa88ff"`
+    pub accents: BTreeMap<String, String>,
+    pub border: BorderStyle,         // Playfield and panel borders
+    pub palette: Palette,            // Piece colors shifting with level
+    pub background: Option<PathBuf>, // Art drawn dim behind the panels, relative to the config directory
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetplayConfig {
+    pub input_delay_ms: u64, // Garbage lands this long after it's sent; later than that rewinds the board to fit it in
+    pub relay: Option<String>, // Rendezvous server for room codes, as "host" or "host:port"
+    // Key -> message it sends mid-match and flashes over your board on theirs
+    pub emotes: BTreeMap<String, String>,
+}
+
+impl Default for NetplayConfig {
+    fn default() -> Self {
+        let emotes = [("f5", "gg"), ("f6", "wow"), ("f7", "glhf"), ("f8", "nice")];
+        Self {
+            input_delay_ms: NETPLAY_INPUT_DELAY_MS,
+            relay: None,
+            emotes: emotes.into_iter().map(|(key, text)| (key.to_string(), text.to_string())).collect(),
+        }
+    }
+}
+
+impl Config {
+    pub fn goal_time(&self, lines: u32) -> Option<Duration> {
+        self.goals.get(&lines.to_string()).and_then(|goal| parse_time(goal))
+    }
+
+    // The marathon speed curve picked in [marathon], if any
+    pub fn speed_curve(&self) -> Result<Option<SpeedCurve>> {
+        let Some(name) = self.marathon.curve.as_deref() else {
+            return Ok(None);
+        };
+        let breakpoints = self.curves.get(name).ok_or_else(|| Error::Curve(name.to_string(), CurveError::Undefined))?;
+        let curve = SpeedCurve::new(name, breakpoints.clone()).map_err(|e| Error::Curve(name.to_string(), e))?;
+        Ok(Some(curve))
+    }
+
+    pub fn bindings(&self) -> Result<Bindings> {
+        let sideways = self.layout.orientation == Orientation::Horizontal;
+        Ok(Bindings::new(&self.bindings, &self.mode_bindings)?.sideways(sideways))
+    }
+
+    // Curves and bindings are checked up front, including ones not in use
+    // yet, so a typo shows up when the config is read rather than on the day
+    // it's used
+    fn validate(&self) -> Result<()> {
+        for (name, breakpoints) in &self.curves {
+            SpeedCurve::new(name, breakpoints.clone()).map_err(|e| Error::Curve(name.clone(), e))?;
+        }
+        self.bindings()?;
+        if let Some(key) = self.netplay.emotes.keys().find(|key| parse_key(key).is_none()) {
+            return Err(BindingError::UnknownKey(key.clone()).into());
+        }
+        self.speed_curve().map(|_| ())
+    }
+}
+
+// TSTRIS_CONFIG_DIR overrides the platform config directory
+// Files the config names, such as the background art, are looked for
+// beside it unless given in full
+pub fn config_relative(path: &Path) -> PathBuf {
+    match config_path().as_deref().and_then(Path::parent) {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("TSTRIS_CONFIG_DIR") {
+        return Some(PathBuf::from(dir).join(CONFIG_FILE));
+    }
+    dirs::config_dir().map(|dir| dir.join("tstris").join(CONFIG_FILE))
+}
+
+// A config file's text read as the current schema
+#[derive(Debug)]
+pub struct Parsed {
+    pub config: Config,
+    pub from_version: u32,
+    pub rewritten: Option<String>, // New file text, when the file was migrated
+    pub unknown: Vec<String>,      // Settings nothing reads, as dotted paths
+}
+
+// Older files are migrated in memory, and settings this version doesn't know
+// are reported rather than dropped without a word
+pub fn parse(contents: &str) -> Result<Parsed> {
+    let original: Table = toml::from_str(contents)?;
+    let from_version = match original.get("version") {
+        Some(version) => Deserialize::deserialize(version.clone())?,
+        None => 0,
+    };
+
+    let mut table = original.clone();
+    let mut rewritten = None;
+    if from_version < CONFIG_VERSION {
+        for migrate in &MIGRATIONS[from_version as usize..] {
+            migrate(&mut table);
+        }
+        table.insert("version".to_string(), Value::Integer(CONFIG_VERSION.into()));
+
+        // Only the version changed: stamp it and keep the file's comments
+        let mut stamped = original;
+        stamped.insert("version".to_string(), Value::Integer(CONFIG_VERSION.into()));
+        rewritten = Some(if stamped == table {
+            stamp_version(contents)
+        } else {
+            toml::to_string(&table).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        });
+    }
+
+    let config: Config = Config::deserialize(table.clone())?;
+    config.validate()?;
+    let mut unknown = Vec::new();
+    if let Ok(known) = Table::try_from(&config) {
+        unknown_keys("", &table, &known, &mut unknown);
+    }
+    Ok(Parsed { config, from_version, rewritten, unknown })
+}
+
+// Replaces or adds the top-level version line, leaving everything else as is
+fn stamp_version(contents: &str) -> String {
+    let mut in_tables = false;
+    let rest: Vec<&str> = contents
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            in_tables |= line.starts_with('[');
+            in_tables || !line.strip_prefix("version").is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .collect();
+    format!("version = {}\n{}\n", CONFIG_VERSION, rest.join("\n"))
+}
+
+// Keys in `table` that didn't survive a round trip through Config
+fn unknown_keys(prefix: &str, table: &Table, known: &Table, out: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = format!("{}{}", prefix, key);
+        match (value, known.get(key)) {
+            (_, None) => out.push(path),
+            (Value::Table(table), Some(Value::Table(known))) => unknown_keys(&format!("{}.", path), table, known, out),
+            _ => {}
+        }
+    }
+}
+
+// Something about the config file to tell the player at startup
+#[derive(Debug)]
+pub enum Notice {
+    Migrated { from: u32, backup: PathBuf },
+    Newer(u32), // Written by a later tstris
+    Unknown(Vec<String>),
+}
+
+// A missing config file is just the defaults; a broken one is an error. An
+// older file is upgraded on disk, with the original kept beside it.
+pub fn load() -> Result<(Config, Vec<Notice>)> {
+    let Some(path) = config_path() else {
+        return Ok((Config::default(), Vec::new()));
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((Config::default(), Vec::new())),
+        Err(e) => return Err(e.into()),
+    };
+
+    let parsed = parse(&contents)?;
+    let mut notices = Vec::new();
+    if let Some(rewritten) = &parsed.rewritten {
+        let backup = path.with_extension(format!("toml.v{}.bak", parsed.from_version));
+        // The upgraded settings still apply this session if the file can't be written
+        match fs::copy(&path, &backup).and_then(|_| fs::write(&path, rewritten)) {
+            Ok(()) => notices.push(Notice::Migrated { from: parsed.from_version, backup }),
+            Err(e) => tracing::warn!(error = %e, "couldn't save migrated config"),
+        }
+    }
+    if parsed.from_version > CONFIG_VERSION {
+        notices.push(Notice::Newer(parsed.from_version));
+    }
+    if !parsed.unknown.is_empty() {
+        notices.push(Notice::Unknown(parsed.unknown));
+    }
+    Ok((parsed.config, notices))
+}
+
+// What the first-run setup writes; everything else is left at its default
+#[derive(Serialize)]
+struct SetupFile<'a> {
+    version: u32,
+    handling: &'a HandlingConfig,
+    glyphs: &'a GlyphConfig,
+}
+
+// Starts a new config file from the first-run setup's choices
+pub fn save_setup(handling: &HandlingConfig, glyphs: &GlyphConfig) -> Result<PathBuf> {
+    let path = config_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let file = SetupFile { version: CONFIG_VERSION, handling, glyphs };
+    let contents = toml::to_string(&file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+// Adds the player name to the top of the config file, where top-level keys
+// have to go, leaving the rest of the file and its comments alone
+pub fn save_name(name: &str) -> Result<()> {
+    let path = config_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let existing = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // A new file is written at the current version, so it never needs migrating
+    let version = if existing.is_empty() { format!("version = {}\n", CONFIG_VERSION) } else { String::new() };
+    fs::write(&path, format!("{}name = {}\n{}", version, Value::String(name.to_string()), existing))?;
+    Ok(())
+}
+
+// Accepts "m:ss.SSS", "ss.SSS" or whole seconds
+pub fn parse_time(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (minutes, seconds, max_seconds) = match text.split_once(':') {
+        Some((minutes, seconds)) => (minutes.parse::<u64>().ok()?, seconds, 60.0),
+        None => (0, text, f64::MAX),
+    };
+    let seconds: f64 = seconds.parse().ok()?;
+    if !(0.0..max_seconds).contains(&seconds) {
+        return None;
+    }
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}
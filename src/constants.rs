@@ -1,13 +1,221 @@
 // Remove the Duration import since it's not used here
 pub const BOARD_WIDTH: usize = 10;
-pub const BOARD_HEIGHT: usize = 20;
+pub const VISIBLE_HEIGHT: usize = 20;               // Rows of the playfield proper
+pub const BOARD_HEIGHT: usize = VISIBLE_HEIGHT * 2; // Full matrix, including the hidden buffer above
+pub const BUFFER_HEIGHT: usize = BOARD_HEIGHT - VISIBLE_HEIGHT;
+pub const SPAWN_ROWS: usize = 2;                    // Buffer rows pieces spawn into (rows 21-22)
 
 // DAS and ARR settings (in milliseconds)
 pub const DAS_DELAY: u64 = 70;  // Delayed Auto Shift - delay before repeating
 pub const ARR_DELAY: u64 = 10;   // Auto Repeat Rate - delay between repeats
 pub const SOFT_DROP_DELAY: u64 = 0; // Instant soft drop for 40L
+pub const SOFT_DROP_BURST: u32 = 5; // Most rows soft drop moves in one update
 pub const KEY_TIMEOUT: u64 = 100; // Timeout for key release detection fallback
+pub const HOLD_RELEASE_GUESS_MS: u64 = 500; // Same for hold protection, past the terminal's first repeat delay
+pub const MUX_KEY_TIMEOUT: u64 = 150; // KEY_TIMEOUT under tmux or screen, which pass repeats on in bursts
+pub const MUX_HOLD_RELEASE_GUESS_MS: u64 = 650; // HOLD_RELEASE_GUESS_MS likewise
+pub const RESTART_HOLD_MS: u64 = 500; // Restart key hold in Marathon and Zen, unless the config says otherwise
 
 // 40L Sprint settings
 pub const TARGET_LINES: u32 = 40;   // Lines to clear for 40L sprint
-pub const GROUND_TIME: u64 = 500; // Time piece can stay on ground after soft drop (milliseconds)
\ No newline at end of file
+pub const GROUND_TIME: u64 = 500; // Time piece can stay on ground after soft drop (milliseconds)
+pub const COUNTDOWN_STEPS: u32 = 3; // 3-2-1, one second each
+pub const GO_DISPLAY_MS: u64 = 500; // How long GO stays on screen once play starts
+
+// Marathon settings
+pub const MARATHON_LINES: u32 = 150; // Lines to clear to finish a marathon
+pub const LINES_PER_LEVEL: u32 = 10;
+pub const MAX_LEVEL: u32 = 15;
+pub const LEVEL_UP_FLASH_MS: u64 = 1000; // How long the level widget flashes after a level up
+pub const CURVE_MAX_GRAVITY_MS: f64 = 60_000.0; // Slowest gravity a speed curve can set
+
+// Classic settings
+pub const CLASSIC_START_GRAVITY_MS: u64 = 1000; // Time per row at level 1
+pub const CLASSIC_GRAVITY_STEP_MS: u64 = 50;    // Taken off per level
+pub const CLASSIC_MIN_GRAVITY_MS: u64 = 50;     // Gravity stops speeding up here
+
+// Dig drill settings
+pub const DIG_GARBAGE_ROWS: u32 = 10; // Garbage rows to clear in the dig drill
+pub const DIG_PIECE_LIMIT: u32 = 4;   // Placements allowed without clearing garbage before the attempt fails
+
+// Garbage holes
+pub const MAX_HOLE_WIDTH: usize = 4;          // Widest hole a garbage row can have
+pub const GARBAGE_SEED_SALT: u64 = 0x9e3779b97f4a7c15; // Keeps the hole sequence apart from the piece sequence
+
+// Big pieces modifier
+pub const BIG_PIECE_CHANCE: f64 = 0.25; // Share of pieces that come at twice the size
+
+// Survival settings
+pub const SURVIVAL_START_INTERVAL_MS: u64 = 5000; // Time between garbage rows at the start of a run
+pub const SURVIVAL_MIN_INTERVAL_MS: u64 = 800;    // Garbage never comes faster than this
+pub const SURVIVAL_RAMP_SECS: u64 = 20;           // The interval shrinks by SURVIVAL_RAMP every this long
+pub const SURVIVAL_RAMP: f64 = 0.8;
+
+// Mystery settings
+pub const MYSTERY_INTERVAL_SECS: u64 = 30; // Time between events
+pub const MYSTERY_EFFECT_SECS: u64 = 10;   // How long a lasting event stays in play
+pub const MYSTERY_GARBAGE_ROWS: u32 = 3;   // Rows raised by a bonus garbage event
+pub const MYSTERY_FAST_DROP_MS: u64 = 50;  // Gravity while sped up
+
+// Ultra settings
+pub const ULTRA_SECS: u64 = 120;           // Length of a score attack
+pub const BONUS_PIECE_CHANCE: f64 = 0.1;   // Share of pieces that spawn as bonus pieces
+pub const BONUS_MULTIPLIER: u64 = 2;       // Clear points when a bonus piece completes a row
+
+// CPU sprint race
+pub const RACE_MAX_PIECES: u32 = 1000; // The CPU gives up on a race it hasn't finished by now
+
+// Palette shifts
+pub const PALETTE_SECTION_LEVELS: u32 = 5; // Levels per section for the "sections" palette
+
+// Attract mode
+pub const ATTRACT_IDLE_SECS: u64 = 30; // Ready screen left alone this long starts the demo
+pub const ATTRACT_PPS: f64 = 2.5;      // Demo placement speed
+
+// Title screen
+pub const TITLE_DROP_SPACING: u16 = 12; // Columns between the pieces falling behind the title
+pub const TITLE_DROP_SPEED: f64 = 3.0;  // Rows per second for the slowest of them
+pub const TITLE_PANEL_WIDTH: u16 = 54;  // Logo and menu panel, with room for the logo's 46 columns
+
+// Hard drop trail
+pub const TRAIL_MS: u64 = 50;    // How long the streak stays up, about three frames at 60 fps
+pub const TRAIL_STAGES: u32 = 3; // Steps it fades through: solid, ghost, dim
+
+// Score popups
+pub const POPUP_MS: u64 = 900;    // How long the points float over the board
+pub const POPUP_RISE: usize = 3;  // Rows they drift up in that time
+
+// Pixel board
+pub const GHOST_ALPHA: f64 = 0.3;    // Ghost cells' opacity over the board
+pub const KITTY_CHUNK: usize = 4096; // Most base64 bytes per kitty graphics escape
+
+// Placement preview
+pub const PREVIEW_MS: u64 = 1000; // How long V shows the board after a hard drop
+
+// Stack view, for terminals too short for the whole field
+pub const STACK_VIEW_MARGIN: usize = 3; // Rows kept in view above the falling piece
+pub const MINIMAP_WIDTH: u16 = 1;       // Column beside the field given to the minimap
+
+// Compact layout
+pub const COMPACT_WIDTH: u16 = 26;  // Panel with the half-height board and its border titles
+pub const COMPACT_HEIGHT: u16 = 13; // 22 rows of field at two per line, and the border
+pub const COMPACT_NEXT: usize = 5;  // Next pieces listed in the border
+
+// Braille boards for another player's field
+pub const MINI_BOARD_WIDTH: u16 = 7;  // Two cells a column, and the border
+pub const MINI_BOARD_HEIGHT: u16 = 8; // 22 rows at four a line, and the border
+
+// Results screen
+pub const PACE_GRAPH_WIDTH: usize = 32; // Most columns in the pieces-per-second graph
+
+// Stats dashboard
+pub const USAGE_BAR_WIDTH: usize = 20; // Columns of the widest bar in the action usage chart
+
+// Placement accuracy
+pub const GRADE_HALF_LOSS: f64 = 1.0; // A placement this far behind the AI's best, in evaluation points, grades 50%
+
+// T-spin hint
+pub const HINT_PIECES: usize = 3; // Pieces searched for a setup, counting the one in play
+
+// Key overlay
+pub const KEY_FLASH_MS: u64 = 150; // How long a one-shot action stays lit
+
+// Event log
+pub const EVENT_LOG_LEN: usize = 20; // Entries kept in the event log panel
+
+// Bug reports
+pub const REPORT_EVENTS: usize = 50; // Most recent engine events kept for a crash report
+
+// Replay playback
+pub const REPLAY_CHECKPOINT_PIECES: u32 = 10;        // Engine snapshot interval for fast backwards seeking
+pub const REPLAY_SEEK_STEP: u64 = 5000;               // Left/right arrow jump (milliseconds)
+pub const REPLAY_SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0]; // Playback speed steps
+
+// Zen settings
+pub const ZEN_AUTOSAVE_SECS: u64 = 30; // How often Zen progress is written to disk
+
+// Adaptive Zen gravity
+pub const ADAPTIVE_START_MS: u64 = 1000;      // Time per row to begin with, the same as plain Zen
+pub const ADAPTIVE_MIN_MS: u64 = 80;          // Fastest it gets
+pub const ADAPTIVE_MAX_MS: u64 = 1000;        // Slowest it eases back to
+pub const ADAPTIVE_SPEEDUP_PERCENT: u64 = 96; // Percent of the interval kept after a comfortable lock
+pub const ADAPTIVE_EASE_PERCENT: u64 = 115;   // Percent the interval grows to after a lock with the stack high
+pub const ADAPTIVE_LOW_STACK: usize = 6;      // Rows tall a stack can be and still count as comfortable
+pub const ADAPTIVE_HIGH_STACK: usize = 12;    // Rows tall a stack has to be to ease off
+pub const ADAPTIVE_FAST_PPS: f64 = 1.5;       // Pace over the recent locks that counts as comfortable
+pub const ADAPTIVE_PPS_WINDOW: usize = 10;    // Locks the recent pace is taken over
+
+// Player name
+pub const NAME_MAX_WIDTH: usize = 16; // Terminal columns allowed for a player name
+
+// Toasts
+pub const TOAST_MS: u64 = 3000;         // How long a toast stays up
+pub const TOAST_MAX_VISIBLE: usize = 3; // Toasts on screen at once; the rest wait their turn
+
+// Input diagnostics
+pub const CHATTER_MS: u64 = 5; // A release and press of the same key this close together is switch bounce
+
+// Latency calibration
+pub const CALIBRATION_BEAT_MS: u64 = 750;      // Time between flashes
+pub const CALIBRATION_FLASH_MS: u64 = 120;     // How long each flash stays lit
+pub const CALIBRATION_WARMUP_BEATS: u64 = 4;   // Flashes to find the rhythm on before taps count
+pub const CALIBRATION_TAPS: usize = 20;        // Taps measured per calibration
+
+// Frame step debugging
+pub const FRAME_STEP_MS: u64 = 16;    // Game time one step advances, about a frame at 60 fps
+pub const FRAME_STEP_BURST: u32 = 10; // Steps taken at once by '>'
+
+// Profiler overlay
+pub const TICK_BUDGET_MS: u64 = 17; // Loop iterations slower than this count as dropped ticks
+
+// Main loop
+pub const FRAME_POLL_MS: u64 = 8;  // Longest wait for input between game ticks while playing
+pub const IDLE_POLL_MS: u64 = 200; // Same when nothing on screen is moving, ~5 fps
+pub const MAX_FPS: u32 = 60;       // Default frame cap; the game still ticks every FRAME_POLL_MS
+
+// SSH server
+#[cfg(feature = "ssh")]
+pub const SSH_PORT: u16 = 2222;               // Default port for `serve-ssh`
+#[cfg(feature = "ssh")]
+pub const SSH_MAX_SESSIONS: usize = 16;       // Games running at once; further connections are turned away
+#[cfg(feature = "ssh")]
+pub const SSH_IDLE_TIMEOUT_SECS: u64 = 600;   // A game with no input for this long is ended
+#[cfg(feature = "ssh")]
+pub const SSH_DEFAULT_SIZE: (u16, u16) = (80, 24); // Screen size when the client asks for no pty
+#[cfg(feature = "ssh")]
+pub const SSH_OUTPUT_FRAMES: usize = 120;     // Frames a client can fall behind by before it's disconnected
+
+// Spectate stream
+pub const SPECTATE_FRAME_MS: u64 = 50;           // At most 20 frames a second go out to spectators
+pub const SPECTATE_MAX_CLIENTS: usize = 32;      // Further spectators are turned away
+pub const SPECTATE_QUEUE_FRAMES: usize = 4;      // Frames buffered per spectator before they start getting skipped
+pub const SPECTATE_WRITE_TIMEOUT_MS: u64 = 5000; // A spectator that can't take a frame for this long is dropped
+
+// Online versus
+pub const NETPLAY_PORT: u16 = 7878;              // Default port for `tstris host`
+pub const NETPLAY_VERSION: u32 = 3;              // Protocol version; both sides must match
+pub const NETPLAY_INPUT_DELAY_MS: u64 = 100;     // Garbage lands this long after it's sent, unless the config says otherwise
+pub const NETPLAY_SNAPSHOT_MS: u64 = 50;         // Time between the snapshots a late attack can rewind to
+pub const NETPLAY_ROLLBACK_MS: u64 = 1000;       // Furthest back either board is rewound; older is played from now or the start
+pub const NETPLAY_GUESS_MS: u64 = 1000;          // The other board stops being run ahead this far past their last input
+pub const NETPLAY_PING_MS: u64 = 1000;           // Time between latency checks
+pub const NETPLAY_QUEUE_MESSAGES: usize = 1024;  // Messages waiting to go out before the connection is given up as stalled
+pub const NETPLAY_LINE_BYTES: usize = 4096;      // Longest message line either side takes; a longer one drops the connection
+pub const NETPLAY_BATCH_EVENTS: usize = 48;      // Most inputs sent in one message, which keeps it well inside that
+pub const NETPLAY_PART_CHARS: usize = 1536;      // Snapshot JSON per message; escaped, it still fits in a line
+pub const NETPLAY_RESYNC_BYTES: usize = 1 << 20; // Largest snapshot taken in to resync the opponent's board
+pub const NETPLAY_RELAY_PORT: u16 = 7879;        // Default port for `tstris relay`
+pub const NETPLAY_DIRECT_TIMEOUT_MS: u64 = 2000; // A joiner gives up connecting straight to the host after this and relays instead
+pub const NETPLAY_ROOM_SECS: u64 = 600;          // A room nobody joined is closed after this long
+pub const NETPLAY_MAX_ROOMS: usize = 1024;       // Rooms open on a relay at once; further hosts are turned away
+pub const NETPLAY_CHAT_CHARS: usize = 80;        // Longest chat message; the rest is cut off
+pub const NETPLAY_CHAT_LINES: usize = 4;         // Chat lines kept and shown under the opponent's board
+pub const NETPLAY_EMOTE_MS: u64 = 2000;          // An emote stays over the opponent's board this long
+
+// Local versus
+pub const VERSUS_GARBAGE_MS: u64 = 500;          // Garbage lands this long after it's sent, time enough to cancel it
+pub const VERSUS_CPU_PPS: f64 = 1.5;             // How fast a CPU teammate places pieces in team play
+pub const VERSUS_MAX_GARBAGE_ROWS: u32 = 10;     // Most starting garbage the setup screen gives a player
+pub const VERSUS_MAX_GRAVITY: f64 = 4.0;         // Fastest gravity handicap, as a multiple of the mode's own
+pub const VERSUS_MAX_ATTACK: f64 = 2.0;          // Largest attack multiplier
+pub const VERSUS_STEP: f64 = 0.25;               // Gravity and attack change by this much a press
@@ -0,0 +1,44 @@
+use std::io;
+use std::num::ParseIntError;
+
+use crate::game::level::CurveError;
+use crate::input::action::BindingError;
+use crate::game::puzzle::PuzzleError;
+use crate::game::replay::ValidationError;
+
+// Everything that can go wrong at the top level, so failures carry what
+// kind of thing broke into the log and the exit message
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("invalid config: {0}")]
+    Config(#[from] toml::de::Error),
+    #[error("invalid speed curve '{0}': {1}")]
+    Curve(String, CurveError),
+    #[error("invalid binding: {0}")]
+    Binding(#[from] BindingError),
+    #[error("invalid puzzle {0}: {1}")]
+    Puzzle(String, PuzzleError),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("replay rejected: {0}")]
+    Validation(#[from] ValidationError),
+    #[error("replay rejected: validation hash {stored:016x} does not match {computed:016x}")]
+    ValidationHash { stored: u64, computed: u64 }, // The replay plays out but its stored hash is off
+    #[error("invalid number: {0}")]
+    Number(#[from] ParseIntError),
+    #[cfg(feature = "ssh")]
+    #[error("ssh: {0}")]
+    Ssh(#[from] russh::Error),
+    #[error("{0}")]
+    Usage(String), // Bad command line
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn usage(message: impl Into<String>) -> Self {
+        Error::Usage(message.into())
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{
+    ADAPTIVE_EASE_PERCENT, ADAPTIVE_FAST_PPS, ADAPTIVE_HIGH_STACK, ADAPTIVE_LOW_STACK, ADAPTIVE_MAX_MS, ADAPTIVE_MIN_MS,
+    ADAPTIVE_PPS_WINDOW, ADAPTIVE_SPEEDUP_PERCENT, ADAPTIVE_START_MS,
+};
+
+// Adaptive Zen gravity: each lock with a low stack at a quick pace makes the
+// piece fall a little faster, and each lock with a high stack backs it off
+// more sharply. Everything else leaves it where it is. Whole milliseconds
+// only, so a replay comes out the same on any machine.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdaptiveGravity {
+    interval_ms: u64,
+    locks: VecDeque<Duration>, // When the last few pieces locked, for the recent pace
+}
+
+impl AdaptiveGravity {
+    pub fn new() -> Self {
+        Self { interval_ms: ADAPTIVE_START_MS, locks: VecDeque::new() }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    // Pieces per second over the last few locks; None until there are enough
+    pub fn recent_pps(&self) -> Option<f64> {
+        let (first, last) = (self.locks.front()?, self.locks.back()?);
+        let span = last.saturating_sub(*first).as_secs_f64();
+        (self.locks.len() >= ADAPTIVE_PPS_WINDOW && span > 0.0).then(|| (self.locks.len() - 1) as f64 / span)
+    }
+
+    // A piece locked at `at` into a stack now `stack_height` rows tall
+    pub fn on_lock(&mut self, at: Duration, stack_height: usize) {
+        self.locks.push_back(at);
+        if self.locks.len() > ADAPTIVE_PPS_WINDOW {
+            self.locks.pop_front();
+        }
+
+        let percent = if stack_height >= ADAPTIVE_HIGH_STACK {
+            ADAPTIVE_EASE_PERCENT
+        } else if stack_height <= ADAPTIVE_LOW_STACK && self.recent_pps().is_some_and(|pps| pps >= ADAPTIVE_FAST_PPS) {
+            ADAPTIVE_SPEEDUP_PERCENT
+        } else {
+            return;
+        };
+        self.interval_ms = (self.interval_ms * percent / 100).clamp(ADAPTIVE_MIN_MS, ADAPTIVE_MAX_MS);
+    }
+
+    // Topping out: start over from the gentle end
+    pub fn reset(&mut self) {
+        self.interval_ms = ADAPTIVE_START_MS;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Locks `gap_ms` apart from `start_ms` on, all into a stack of `height`
+    fn lock_many(adaptive: &mut AdaptiveGravity, start_ms: u64, gap_ms: u64, count: u64, height: usize) -> u64 {
+        for i in 0..count {
+            adaptive.on_lock(Duration::from_millis(start_ms + i * gap_ms), height);
+        }
+        start_ms + count * gap_ms
+    }
+
+    #[test]
+    fn speeds_up_on_quick_clean_play() {
+        let mut adaptive = AdaptiveGravity::new();
+        // Not until there are enough locks to tell the pace
+        let at = lock_many(&mut adaptive, 0, 250, ADAPTIVE_PPS_WINDOW as u64 - 1, 2);
+        assert_eq!(adaptive.interval(), Duration::from_millis(ADAPTIVE_START_MS));
+        lock_many(&mut adaptive, at, 250, 5, 2);
+        assert!(adaptive.interval() < Duration::from_millis(ADAPTIVE_START_MS));
+    }
+
+    #[test]
+    fn holds_on_slow_or_middling_play() {
+        let mut adaptive = AdaptiveGravity::new();
+        let at = lock_many(&mut adaptive, 0, 1000, 30, 2); // Low stack but 1 PPS
+        lock_many(&mut adaptive, at, 250, 30, ADAPTIVE_LOW_STACK + 1); // Quick but neither low nor high
+        assert_eq!(adaptive.interval(), Duration::from_millis(ADAPTIVE_START_MS));
+    }
+
+    #[test]
+    fn eases_off_when_the_stack_gets_high() {
+        let mut adaptive = AdaptiveGravity::new();
+        let at = lock_many(&mut adaptive, 0, 250, 40, 2);
+        let fast = adaptive.interval();
+        lock_many(&mut adaptive, at, 250, 1, ADAPTIVE_HIGH_STACK);
+        assert!(adaptive.interval() > fast);
+        // More sharply than it sped up
+        let eased = adaptive.interval().as_millis() as u64;
+        assert!(eased > fast.as_millis() as u64 * 100 / ADAPTIVE_SPEEDUP_PERCENT);
+    }
+
+    #[test]
+    fn starts_over_after_topping_out() {
+        let mut adaptive = AdaptiveGravity::new();
+        lock_many(&mut adaptive, 0, 250, 40, 2);
+        adaptive.reset();
+        assert_eq!(adaptive.interval(), Duration::from_millis(ADAPTIVE_START_MS));
+    }
+
+    #[test]
+    fn stays_within_bounds() {
+        let mut adaptive = AdaptiveGravity::new();
+        let at = lock_many(&mut adaptive, 0, 100, 500, 0);
+        assert_eq!(adaptive.interval(), Duration::from_millis(ADAPTIVE_MIN_MS));
+        lock_many(&mut adaptive, at, 100, 500, ADAPTIVE_HIGH_STACK + 5);
+        assert_eq!(adaptive.interval(), Duration::from_millis(ADAPTIVE_MAX_MS));
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+// Blind modifiers: things a run is played without, as a challenge or to train
+// reading the stack. Saved with the replay and the run so results show what
+// the player went without.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct Blind {
+    #[serde(default)]
+    pub next: bool, // Next queue and bag hidden
+    #[serde(default)]
+    pub hold: bool, // Held piece hidden, though hold still works
+    #[serde(default)]
+    pub ghost: bool,
+}
+
+impl Blind {
+    // Comma separated, e.g. "next,ghost"; returns the first word that isn't one
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut blind = Self::default();
+        for word in text.split(',').map(str::trim).filter(|word| !word.is_empty()) {
+            match word.to_lowercase().as_str() {
+                "next" => blind.next = true,
+                "hold" => blind.hold = true,
+                "ghost" => blind.ghost = true,
+                _ => return Err(word.to_string()),
+            }
+        }
+        Ok(blind)
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Short tag for result lists: N, H and G for whatever was hidden
+    pub fn tag(&self) -> String {
+        [(self.next, 'N'), (self.hold, 'H'), (self.ghost, 'G')]
+            .into_iter()
+            .filter_map(|(hidden, letter)| hidden.then_some(letter))
+            .collect()
+    }
+}
@@ -1,15 +1,169 @@
-use ratatui::style::Color;
+use std::ops::Range;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::constants::{BOARD_WIDTH, BOARD_HEIGHT};
+use crate::game::color::PieceColor;
+use crate::game::piece::{Piece, PieceType};
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Cell {
     Empty,
-    Filled(Color),
-    Ghost(Color),
+    Filled(PieceColor),
+    Garbage,
 }
 
 pub type Board = [[Cell; BOARD_WIDTH]; BOARD_HEIGHT];
 
 pub fn empty_board() -> Board {
     [[Cell::Empty; BOARD_WIDTH]; BOARD_HEIGHT]
+}
+
+// A full garbage row with a single hole at the given column
+pub fn garbage_row(hole: usize) -> [Cell; BOARD_WIDTH] {
+    wide_garbage_row(hole..hole + 1)
+}
+
+// A full garbage row with the given columns left open
+pub fn wide_garbage_row(holes: Range<usize>) -> [Cell; BOARD_WIDTH] {
+    let mut row = [Cell::Garbage; BOARD_WIDTH];
+    for cell in &mut row[holes] {
+        *cell = Cell::Empty;
+    }
+    row
+}
+
+// Whether a piece is inside the walls and floor and overlaps nothing; blocks
+// above the top of the matrix are allowed
+pub fn fits(board: &Board, piece: &Piece) -> bool {
+    for (x, y) in piece.get_blocks() {
+        if x < 0 || x >= BOARD_WIDTH as i32 || y >= BOARD_HEIGHT as i32 {
+            return false;
+        }
+        if y >= 0 && board[y as usize][x as usize] != Cell::Empty {
+            return false;
+        }
+    }
+    true
+}
+
+// Remove full rows and shift everything above them down.
+// Returns (rows cleared, how many of those contained garbage).
+pub fn clear_full_rows(board: &mut Board) -> (u32, u32) {
+    let mut lines_cleared = 0;
+    let mut garbage_cleared = 0;
+    let mut write_row = BOARD_HEIGHT - 1;
+    
+    // Start from bottom and work up, copying non-full rows down
+    for read_row in (0..BOARD_HEIGHT).rev() {
+        if !board[read_row].iter().all(|&cell| cell != Cell::Empty) {
+            // This row is not full, keep it
+            if read_row != write_row {
+                board[write_row] = board[read_row];
+            }
+            write_row = write_row.saturating_sub(1);
+        } else {
+            // This row is full, skip it (clear it)
+            lines_cleared += 1;
+            if board[read_row].contains(&Cell::Garbage) {
+                garbage_cleared += 1;
+            }
+        }
+    }
+    
+    // Fill remaining top rows with empty
+    for row in board.iter_mut().take(write_row + 1) {
+        *row = [Cell::Empty; BOARD_WIDTH];
+    }
+    
+    (lines_cleared, garbage_cleared)
+}
+
+// Rows from the floor up to and including each column's highest block
+pub fn column_heights(board: &Board) -> [usize; BOARD_WIDTH] {
+    let mut heights = [0; BOARD_WIDTH];
+    for (x, height) in heights.iter_mut().enumerate() {
+        *height = board
+            .iter()
+            .position(|row| row[x] != Cell::Empty)
+            .map_or(0, |top| BOARD_HEIGHT - top);
+    }
+    heights
+}
+
+// (x, y) of every empty cell with a filled cell somewhere above it in the same column
+pub fn hole_positions(board: &Board) -> Vec<(usize, usize)> {
+    let mut holes = Vec::new();
+    for x in 0..BOARD_WIDTH {
+        let mut covered = false;
+        for (y, row) in board.iter().enumerate() {
+            if row[x] == Cell::Empty {
+                if covered {
+                    holes.push((x, y));
+                }
+            } else {
+                covered = true;
+            }
+        }
+    }
+    holes
+}
+
+pub fn covered_holes(board: &Board) -> u32 {
+    hole_positions(board).len() as u32
+}
+
+
+// Text form of a board for save files: one string per row, '.' for empty,
+// 'G' for garbage and the piece letter for filled cells
+pub fn board_to_rows(board: &Board) -> Vec<String> {
+    board
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&cell| match cell {
+                    Cell::Filled(color) => PieceType::ALL
+                        .iter()
+                        .find(|piece_type| piece_type.color() == color)
+                        .map_or('#', |piece_type| piece_type.letter()),
+                    Cell::Garbage => 'G',
+                    Cell::Empty => '.',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Rows are aligned to the bottom of the board; missing rows are empty
+pub fn board_from_rows(rows: &[String]) -> Board {
+    let mut board = empty_board();
+    let offset = BOARD_HEIGHT.saturating_sub(rows.len());
+    
+    for (board_row, text) in board.iter_mut().skip(offset).zip(rows.iter().skip(rows.len().saturating_sub(BOARD_HEIGHT))) {
+        for (cell, ch) in board_row.iter_mut().zip(text.chars()) {
+            *cell = match ch {
+                '.' => Cell::Empty,
+                'G' => Cell::Garbage,
+                ch => PieceType::ALL
+                    .iter()
+                    .find(|piece_type| piece_type.letter() == ch)
+                    .map_or(Cell::Garbage, |piece_type| Cell::Filled(piece_type.color())),
+            };
+        }
+    }
+    board
+}
+
+// Serde for a whole board, written as board_to_rows text like the save
+// files; for `#[serde(with)]` since Board is a plain array
+pub mod rows {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(board: &Board, serializer: S) -> Result<S::Ok, S::Error> {
+        board_to_rows(board).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        Ok(board_from_rows(&Vec::<String>::deserialize(deserializer)?))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+// What color a piece or a cell it filled is, by the guideline's names. The
+// engine only deals in these; ui::theme decides how each looks on screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PieceColor {
+    Cyan,
+    Yellow,
+    Purple,
+    Green,
+    Red,
+    Blue,
+    Orange,
+}
+
+impl PieceColor {
+    pub const ALL: [PieceColor; 7] = [
+        PieceColor::Cyan,
+        PieceColor::Yellow,
+        PieceColor::Purple,
+        PieceColor::Green,
+        PieceColor::Red,
+        PieceColor::Blue,
+        PieceColor::Orange,
+    ];
+}
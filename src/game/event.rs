@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use crate::game::mystery::Mystery;
+use crate::game::piece::PieceType;
+use crate::game::state::EndReason;
+
+// Notable things the engine reports as they happen, for the UI to pick up
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    LinesCleared(u32),
+    BonusClear, // A bonus piece doubled a clear
+    PerfectClear,
+    Hold(PieceType),
+    HardDrop(u32), // Rows the piece fell
+    Scored(u64, usize), // Points a clear earned, and the middle row it cleared
+    Misdrop,
+    BoardReset, // Zen top out
+    LevelUp(u32),
+    Mystery(Mystery),
+    Finished(EndReason),
+}
+
+// An event and the run time it happened at
+#[derive(Clone, Copy, Debug)]
+pub struct TimedEvent {
+    pub at: Duration,
+    pub event: GameEvent,
+}
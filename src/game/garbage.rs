@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{BOARD_WIDTH, GARBAGE_SEED_SALT, MAX_HOLE_WIDTH};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HolePattern {
+    Clean, // Every row's hole in the same column, for digging straight down
+    #[default]
+    Messy, // A new column each row, never the same twice running
+}
+
+// How garbage rows are cut, from the [garbage] config
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GarbageRules {
+    pub pattern: HolePattern,
+    pub hole_width: usize, // Columns open in each row, up to MAX_HOLE_WIDTH
+}
+
+impl Default for GarbageRules {
+    fn default() -> Self {
+        Self { pattern: HolePattern::Messy, hole_width: 1 }
+    }
+}
+
+// Where each garbage row's hole goes. Holes come from an RNG of their own,
+// seeded from the run's seed, so they don't depend on which pieces were
+// drawn in between and the next one is known before its row rises.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HoleGenerator {
+    rules: GarbageRules,
+    rng: ChaCha12Rng,
+    next: usize, // Leftmost open column of the next row
+}
+
+impl HoleGenerator {
+    pub fn new(rules: GarbageRules, seed: u64) -> Self {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed ^ GARBAGE_SEED_SALT);
+        let next = rng.gen_range(0..=BOARD_WIDTH - Self::width(rules));
+        Self { rules, rng, next }
+    }
+
+    fn width(rules: GarbageRules) -> usize {
+        rules.hole_width.clamp(1, MAX_HOLE_WIDTH)
+    }
+
+    // Columns the next row will leave open
+    pub fn peek(&self) -> Range<usize> {
+        self.next..self.next + Self::width(self.rules)
+    }
+
+    // The next row's hole, lining up the one after it
+    pub fn take(&mut self) -> Range<usize> {
+        let hole = self.peek();
+        if self.rules.pattern == HolePattern::Messy {
+            let last = self.next;
+            while self.next == last {
+                self.next = self.rng.gen_range(0..=BOARD_WIDTH - Self::width(self.rules));
+            }
+        }
+        hole
+    }
+}
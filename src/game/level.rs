@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{
+    CLASSIC_GRAVITY_STEP_MS, CLASSIC_MIN_GRAVITY_MS, CLASSIC_START_GRAVITY_MS, CURVE_MAX_GRAVITY_MS, GROUND_TIME, LINES_PER_LEVEL, MAX_LEVEL, SURVIVAL_MIN_INTERVAL_MS, SURVIVAL_RAMP, SURVIVAL_RAMP_SECS,
+    SURVIVAL_START_INTERVAL_MS,
+};
+
+pub fn level_for_lines(lines: u32) -> u32 {
+    (lines / LINES_PER_LEVEL + 1).min(MAX_LEVEL)
+}
+
+// Lines still needed to reach the next level, None at the top level
+pub fn lines_to_next_level(lines: u32) -> Option<u32> {
+    if level_for_lines(lines) >= MAX_LEVEL {
+        None
+    } else {
+        Some(LINES_PER_LEVEL - lines % LINES_PER_LEVEL)
+    }
+}
+
+// Classic levels have no top
+pub fn classic_level(lines: u32) -> u32 {
+    lines / LINES_PER_LEVEL + 1
+}
+
+// Classic gravity: a fixed step faster per level down to a floor
+pub fn classic_gravity_interval(level: u32) -> Duration {
+    let step = (level.max(1) - 1) as u64 * CLASSIC_GRAVITY_STEP_MS;
+    Duration::from_millis(CLASSIC_START_GRAVITY_MS.saturating_sub(step).max(CLASSIC_MIN_GRAVITY_MS))
+}
+
+// Guideline gravity: seconds per row = (0.8 - (level - 1) * 0.007) ^ (level - 1)
+pub fn gravity_interval(level: u32) -> Duration {
+    let step = (level.max(1) - 1) as f64;
+    Duration::from_secs_f64((0.8 - step * 0.007).powf(step))
+}
+
+// Survival: time until the next garbage row, `elapsed` into the run. Shrinks
+// smoothly rather than in steps so there's no moment to brace for.
+pub fn survival_interval(elapsed: Duration) -> Duration {
+    let ramps = elapsed.as_secs_f64() / SURVIVAL_RAMP_SECS as f64;
+    let interval = SURVIVAL_START_INTERVAL_MS as f64 * SURVIVAL_RAMP.powf(ramps);
+    Duration::from_secs_f64(interval.max(SURVIVAL_MIN_INTERVAL_MS as f64) / 1000.0)
+}
+
+// Gravity in rows per frame at 60 fps, the usual way of quoting it
+pub fn gravity_g(interval: Duration) -> f64 {
+    1.0 / (interval.as_secs_f64() * 60.0)
+}
+
+// From `level` on, until the next breakpoint, gravity and lock delay are these
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub level: u32,
+    pub gravity_ms: f64, // Time per row; under 1ms is past 20G
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_delay_ms: Option<u64>, // Carries over from the breakpoint before when left out
+}
+
+// A marathon speed curve from the config, used in place of guideline gravity.
+// Replays carry their curve too, so it's checked when read back like the
+// config's is.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedCurve")]
+pub struct SpeedCurve {
+    pub name: String,
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Deserialize)]
+struct UncheckedCurve {
+    name: String,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl TryFrom<UncheckedCurve> for SpeedCurve {
+    type Error = CurveError;
+
+    fn try_from(curve: UncheckedCurve) -> Result<Self, CurveError> {
+        Self::new(&curve.name, curve.breakpoints)
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum CurveError {
+    #[error("not defined under [curves]")]
+    Undefined,
+    #[error("no breakpoints")]
+    Empty,
+    #[error("first breakpoint must be level 1, not {0}")]
+    FirstLevel(u32),
+    #[error("level {0} comes after level {1}; levels must go up")]
+    OutOfOrder(u32, u32),
+    #[error("level {0} is past the top level, {MAX_LEVEL}")]
+    PastTop(u32),
+    #[error("gravity_ms at level {0} must be above 0 and at most {CURVE_MAX_GRAVITY_MS}")]
+    Gravity(u32),
+    #[error("lock_delay_ms at level {0} must be above 0")]
+    LockDelay(u32),
+}
+
+impl SpeedCurve {
+    pub fn new(name: &str, breakpoints: Vec<Breakpoint>) -> Result<Self, CurveError> {
+        let first = breakpoints.first().ok_or(CurveError::Empty)?;
+        if first.level != 1 {
+            return Err(CurveError::FirstLevel(first.level));
+        }
+        for pair in breakpoints.windows(2) {
+            if pair[1].level <= pair[0].level {
+                return Err(CurveError::OutOfOrder(pair[1].level, pair[0].level));
+            }
+        }
+        for breakpoint in &breakpoints {
+            if breakpoint.level > MAX_LEVEL {
+                return Err(CurveError::PastTop(breakpoint.level));
+            }
+            if !(breakpoint.gravity_ms > 0.0 && breakpoint.gravity_ms <= CURVE_MAX_GRAVITY_MS) {
+                return Err(CurveError::Gravity(breakpoint.level));
+            }
+            if breakpoint.lock_delay_ms == Some(0) {
+                return Err(CurveError::LockDelay(breakpoint.level));
+            }
+        }
+        Ok(Self { name: name.to_string(), breakpoints })
+    }
+
+    // The breakpoints in effect at `level`, latest first
+    fn in_effect(&self, level: u32) -> impl Iterator<Item = &Breakpoint> {
+        self.breakpoints.iter().rev().filter(move |breakpoint| breakpoint.level <= level)
+    }
+
+    pub fn gravity_interval(&self, level: u32) -> Duration {
+        self.in_effect(level)
+            .next()
+            .map_or_else(|| gravity_interval(level), |breakpoint| Duration::from_secs_f64(breakpoint.gravity_ms / 1000.0))
+    }
+
+    pub fn lock_delay(&self, level: u32) -> Duration {
+        let millis = self.in_effect(level).find_map(|breakpoint| breakpoint.lock_delay_ms);
+        Duration::from_millis(millis.unwrap_or(GROUND_TIME))
+    }
+}
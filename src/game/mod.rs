@@ -1,7 +1,24 @@
 pub mod piece;
+pub mod adaptive;
+pub mod blind;
 pub mod board;
+pub mod color;
+pub mod event;
+pub mod garbage;
+pub mod level;
 pub mod state;
+pub mod mode;
+pub mod mystery;
+pub mod puzzle;
+pub mod replay;
+pub mod playback;
+pub mod rollback;
+pub mod rotation;
+pub mod scoring;
+pub mod usage;
+pub mod win;
 
 // Piece and PieceType are used internally, not exported
 pub use board::Cell;
-pub use state::Game;
\ No newline at end of file
+pub use state::Game;
+pub use mode::GameMode;
\ No newline at end of file
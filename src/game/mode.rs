@@ -0,0 +1,119 @@
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{MARATHON_LINES, TARGET_LINES};
+use crate::game::win::Goal;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GameMode {
+    Sprint, // 40L sprint
+    Marathon, // 150 lines with gravity rising every level
+    Dig,    // Downstack-only drill: clear the garbage without stalling
+    Survival, // Last as long as possible under ever faster rising garbage
+    Mystery,  // 40 lines with a random event every 30 seconds
+    Ultra,    // Score attack: as many points as possible in two minutes
+    Puzzle,   // No gravity: fill a target shape exactly with a set queue
+    Zen,    // Endless, untimed, no game over
+    Classic,  // Endless with score and levels until topping out, like the original
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 9] = [
+        GameMode::Sprint,
+        GameMode::Marathon,
+        GameMode::Dig,
+        GameMode::Survival,
+        GameMode::Mystery,
+        GameMode::Ultra,
+        GameMode::Puzzle,
+        GameMode::Zen,
+        GameMode::Classic,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Sprint => GameMode::Marathon,
+            GameMode::Marathon => GameMode::Dig,
+            GameMode::Dig => GameMode::Survival,
+            GameMode::Survival => GameMode::Mystery,
+            GameMode::Mystery => GameMode::Ultra,
+            GameMode::Ultra => GameMode::Classic,
+            GameMode::Classic => GameMode::Zen,
+            GameMode::Zen => GameMode::Sprint,
+            // Puzzles are picked from the command line, not the mode cycle
+            GameMode::Puzzle => GameMode::Sprint,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            GameMode::Sprint => "40L SPRINT",
+            GameMode::Marathon => "MARATHON",
+            GameMode::Dig => "DIG DRILL",
+            GameMode::Survival => "SURVIVAL",
+            GameMode::Mystery => "MYSTERY",
+            GameMode::Ultra => "ULTRA",
+            GameMode::Puzzle => "PUZZLE",
+            GameMode::Zen => "ZEN",
+            GameMode::Classic => "CLASSIC",
+        }
+    }
+
+    pub fn short_name(self) -> &'static str {
+        match self {
+            GameMode::Sprint => "40L",
+            GameMode::Marathon => "MARATHON",
+            GameMode::Dig => "DIG",
+            GameMode::Survival => "SURVIVAL",
+            GameMode::Mystery => "MYSTERY",
+            GameMode::Ultra => "ULTRA",
+            GameMode::Puzzle => "PUZZLE",
+            GameMode::Zen => "ZEN",
+            GameMode::Classic => "CLASSIC",
+        }
+    }
+
+    // Timed modes keep running when the terminal loses focus so the clock
+    // can't be stopped mid-run
+    pub fn is_timed(self) -> bool {
+        match self {
+            GameMode::Sprint | GameMode::Dig | GameMode::Survival | GameMode::Mystery | GameMode::Ultra => true,
+            GameMode::Marathon | GameMode::Puzzle | GameMode::Zen | GameMode::Classic => false,
+        }
+    }
+
+    // Timed modes whose result is the time. Ultra's two minutes are the
+    // same every run, so it's ranked on points instead.
+    pub fn is_time_ranked(self) -> bool {
+        self.is_timed() && !self.is_scored()
+    }
+
+    // Greater when a finished run's time beats another's: survival is won
+    // by lasting longer, everything else by finishing sooner
+    pub fn compare_times(self, time: Duration, other: Duration) -> Ordering {
+        match self {
+            GameMode::Survival => time.cmp(&other),
+            _ => other.cmp(&time),
+        }
+    }
+
+    // What completes a run of each mode; the rest only end some other way
+    pub fn win_condition(self) -> Option<Goal> {
+        match self {
+            GameMode::Sprint | GameMode::Mystery => Some(Goal::Lines(TARGET_LINES)),
+            GameMode::Marathon => Some(Goal::Lines(MARATHON_LINES)),
+            GameMode::Dig => Some(Goal::AllGarbageCleared),
+            GameMode::Survival | GameMode::Ultra | GameMode::Puzzle | GameMode::Zen | GameMode::Classic => None,
+        }
+    }
+
+    // Modes that award points for drops and line clears
+    pub fn is_scored(self) -> bool {
+        match self {
+            GameMode::Sprint | GameMode::Dig | GameMode::Survival | GameMode::Mystery | GameMode::Puzzle => false,
+            GameMode::Marathon | GameMode::Ultra | GameMode::Zen | GameMode::Classic => true,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+// Random events in mystery mode, one every MYSTERY_INTERVAL_SECS
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Mystery {
+    Mirror,       // The stack flips left to right
+    HideGhost,    // No ghost piece for a while
+    SpeedUp,      // Much faster gravity for a while
+    BonusGarbage, // A few garbage rows rise at once
+    GiantPiece,   // The next piece comes at twice the size
+}
+
+impl Mystery {
+    pub const ALL: [Mystery; 5] = [
+        Mystery::Mirror,
+        Mystery::HideGhost,
+        Mystery::SpeedUp,
+        Mystery::BonusGarbage,
+        Mystery::GiantPiece,
+    ];
+
+    // Key under [mystery] in the locale bundles
+    pub fn key(self) -> &'static str {
+        match self {
+            Mystery::Mirror => "mirror",
+            Mystery::HideGhost => "hide_ghost",
+            Mystery::SpeedUp => "speed_up",
+            Mystery::BonusGarbage => "bonus_garbage",
+            Mystery::GiantPiece => "giant_piece",
+        }
+    }
+
+    // Effects that stay in play for MYSTERY_EFFECT_SECS rather than
+    // happening once
+    pub fn lasts(self) -> bool {
+        matches!(self, Mystery::HideGhost | Mystery::SpeedUp)
+    }
+}
@@ -1,66 +1,172 @@
-use ratatui::style::Color;
-use crate::constants::BOARD_WIDTH;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::constants::{BOARD_WIDTH, BUFFER_HEIGHT, SPAWN_ROWS};
+use crate::game::color::PieceColor;
+use crate::game::rotation::Rotation;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum PieceType {
     I, O, T, S, Z, J, L
 }
 
-#[derive(Clone, Debug)]
+impl PieceType {
+    pub const ALL: [PieceType; 7] = [
+        PieceType::I, PieceType::O, PieceType::T,
+        PieceType::S, PieceType::Z, PieceType::J, PieceType::L,
+    ];
+
+    pub fn color(self) -> PieceColor {
+        match self {
+            PieceType::I => PieceColor::Cyan,
+            PieceType::O => PieceColor::Yellow,
+            PieceType::T => PieceColor::Purple,
+            PieceType::S => PieceColor::Green,
+            PieceType::Z => PieceColor::Red,
+            PieceType::J => PieceColor::Blue,
+            PieceType::L => PieceColor::Orange,
+        }
+    }
+
+    pub fn letter(self) -> char {
+        match self {
+            PieceType::I => 'I',
+            PieceType::O => 'O',
+            PieceType::T => 'T',
+            PieceType::S => 'S',
+            PieceType::Z => 'Z',
+            PieceType::J => 'J',
+            PieceType::L => 'L',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|piece_type| piece_type.letter() == letter.to_ascii_uppercase())
+    }
+}
+
+// A piece sequence written as letters, e.g. "IJLO STZ"; spaces, commas and
+// line breaks are ignored. Returns the first character that isn't a piece.
+pub fn parse_queue(text: &str) -> Result<Vec<PieceType>, char> {
+    text.chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .map(|c| PieceType::from_letter(c).ok_or(c))
+        .collect()
+}
+
+// A fresh 7-bag, Fisher-Yates shuffled. Pieces are dealt from the end.
+pub fn shuffled_bag(rng: &mut impl Rng) -> Vec<PieceType> {
+    let mut bag = PieceType::ALL.to_vec();
+    for i in (1..bag.len()).rev() {
+        let j = rng.gen_range(0..=i);
+        bag.swap(i, j);
+    }
+    bag
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Piece {
     pub piece_type: PieceType,
     pub shape: Vec<Vec<bool>>,
     pub x: i32,
     pub y: i32,
-    pub color: Color,
+    pub color: PieceColor,
+    pub rotation: Rotation,
+    pub big: bool, // Big pieces modifier: every mino is a 2x2 block
+    pub bonus: bool, // Ultra: clears it completes score double
 }
 
 impl Piece {
     pub fn new(piece_type: PieceType) -> Self {
-        let (shape, color) = match piece_type {
-            PieceType::I => (vec![
+        let shape = match piece_type {
+            PieceType::I => vec![
                 vec![false, false, false, false],
                 vec![true, true, true, true],
                 vec![false, false, false, false],
                 vec![false, false, false, false],
-            ], Color::Cyan),
-            PieceType::O => (vec![
+            ],
+            PieceType::O => vec![
                 vec![true, true],
                 vec![true, true],
-            ], Color::Yellow),
-            PieceType::T => (vec![
+            ],
+            PieceType::T => vec![
                 vec![false, true, false],
                 vec![true, true, true],
                 vec![false, false, false],
-            ], Color::Magenta),
-            PieceType::S => (vec![
+            ],
+            PieceType::S => vec![
                 vec![false, true, true],
                 vec![true, true, false],
                 vec![false, false, false],
-            ], Color::Green),
-            PieceType::Z => (vec![
+            ],
+            PieceType::Z => vec![
                 vec![true, true, false],
                 vec![false, true, true],
                 vec![false, false, false],
-            ], Color::Red),
-            PieceType::J => (vec![
+            ],
+            PieceType::J => vec![
                 vec![true, false, false],
                 vec![true, true, true],
                 vec![false, false, false],
-            ], Color::Blue),
-            PieceType::L => (vec![
+            ],
+            PieceType::L => vec![
                 vec![false, false, true],
                 vec![true, true, true],
                 vec![false, false, false],
-            ], Color::LightYellow),
+            ],
         };
 
+        // Guideline spawn: flat side down, centered (3-wide pieces rounded to
+        // the left), with the top of the bounding box in row 22 of the matrix
+        let x = (BOARD_WIDTH - shape.len()) as i32 / 2;
+        let y = (BUFFER_HEIGHT - SPAWN_ROWS) as i32;
+        
         Self {
             piece_type,
             shape,
-            x: (BOARD_WIDTH as i32 - 4) / 2,
-            y: 0,
-            color,
+            x,
+            y,
+            color: piece_type.color(),
+            rotation: Rotation::Spawn,
+            big: false,
+            bonus: false,
+        }
+    }
+
+    // The piece at twice the size, spawning centered like a normal one.
+    // Rotating the doubled matrix gives the doubled rotation, so nothing
+    // else about it needs to change.
+    pub fn big(piece_type: PieceType) -> Self {
+        let mut piece = Self::new(piece_type);
+        piece.shape = piece
+            .shape
+            .iter()
+            .flat_map(|row| {
+                let doubled: Vec<bool> = row.iter().flat_map(|&cell| [cell, cell]).collect();
+                [doubled.clone(), doubled]
+            })
+            .collect();
+        piece.x = (BOARD_WIDTH - piece.shape.len()) as i32 / 2;
+        piece.big = true;
+        piece
+    }
+
+    // A fresh copy of this piece at its spawn position, e.g. going into hold
+    pub fn respawned(&self) -> Self {
+        let mut piece = if self.big {
+            Self::big(self.piece_type)
+        } else {
+            Self::new(self.piece_type)
+        };
+        piece.bonus = self.bonus;
+        piece
+    }
+
+    // Wall kicks move big pieces twice as far
+    pub fn scale(&self) -> i32 {
+        if self.big {
+            2
+        } else {
+            1
         }
     }
 
@@ -69,13 +175,14 @@ impl Piece {
         let size = self.shape.len();
         let mut new_shape = vec![vec![false; size]; size];
         
-        for i in 0..size {
-            for j in 0..size {
-                new_shape[j][size - 1 - i] = self.shape[i][j];
+        for (i, row) in self.shape.iter().enumerate() {
+            for (j, &cell) in row.iter().enumerate() {
+                new_shape[j][size - 1 - i] = cell;
             }
         }
         
         rotated.shape = new_shape;
+        rotated.rotation = self.rotation.clockwise();
         rotated
     }
 
@@ -84,13 +191,14 @@ impl Piece {
         let size = self.shape.len();
         let mut new_shape = vec![vec![false; size]; size];
         
-        for i in 0..size {
-            for j in 0..size {
-                new_shape[size - 1 - j][i] = self.shape[i][j];
+        for (i, row) in self.shape.iter().enumerate() {
+            for (j, &cell) in row.iter().enumerate() {
+                new_shape[size - 1 - j][i] = cell;
             }
         }
         
         rotated.shape = new_shape;
+        rotated.rotation = self.rotation.counter_clockwise();
         rotated
     }
 
@@ -99,13 +207,14 @@ impl Piece {
         let size = self.shape.len();
         let mut new_shape = vec![vec![false; size]; size];
         
-        for i in 0..size {
-            for j in 0..size {
-                new_shape[size - 1 - i][size - 1 - j] = self.shape[i][j];
+        for (i, row) in self.shape.iter().enumerate() {
+            for (j, &cell) in row.iter().enumerate() {
+                new_shape[size - 1 - i][size - 1 - j] = cell;
             }
         }
         
         rotated.shape = new_shape;
+        rotated.rotation = self.rotation.flipped();
         rotated
     }
 
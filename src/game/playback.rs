@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use crate::constants::{REPLAY_CHECKPOINT_PIECES, REPLAY_SPEEDS};
+use crate::game::replay::{self, InputEvent, Replay};
+use crate::game::state::GameSnapshot;
+use crate::game::Game;
+
+// Engine state after applying the first `events` events of the replay
+struct Checkpoint {
+    events: usize,
+    snapshot: GameSnapshot,
+}
+
+// Plays a replay back on screen with pause, stepping, seeking and speed
+// control. The whole replay is simulated once up front to snapshot the engine
+// every few pieces, so seeking backwards only re-runs from the nearest one.
+pub struct Playback {
+    replay: Replay,
+    origin: Instant,
+    pub game: Game,
+    next_event: usize,
+    position: u64, // Nanoseconds since the start of the countdown
+    pub paused: bool,
+    speed: usize, // Index into REPLAY_SPEEDS
+    checkpoints: Vec<Checkpoint>,
+    piece_times: Vec<u64>, // When each piece locked; index 0 is the start
+    pub grades: Vec<f64>,  // Every graded placement of the whole run, for the accuracy graph
+    last_advance: Instant,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        let origin = Instant::now();
+        let start = replay::start_simulation(&replay, origin);
+        let mut checkpoints = vec![Checkpoint { events: 0, snapshot: start.snapshot() }];
+        let mut piece_times = vec![0];
+
+        let mut game = start.clone();
+        for (i, event) in replay.events.iter().enumerate() {
+            let pieces = game.pieces_placed;
+            replay::apply_event(&mut game, origin, event);
+            if game.pieces_placed > pieces {
+                piece_times.push(event.at_nanos);
+                if game.pieces_placed.is_multiple_of(REPLAY_CHECKPOINT_PIECES) {
+                    checkpoints.push(Checkpoint { events: i + 1, snapshot: game.snapshot() });
+                }
+            }
+        }
+
+        Self {
+            replay,
+            origin,
+            grades: game.grades,
+            game: start,
+            next_event: 0,
+            position: 0,
+            paused: false,
+            speed: REPLAY_SPEEDS.iter().position(|&speed| speed == 1.0).unwrap_or(0),
+            checkpoints,
+            piece_times,
+            last_advance: Instant::now(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_nanos(self.replay.events.last().map_or(0, |event| event.at_nanos))
+    }
+
+    pub fn position(&self) -> Duration {
+        Duration::from_nanos(self.position)
+    }
+
+    pub fn speed(&self) -> f64 {
+        REPLAY_SPEEDS[self.speed]
+    }
+
+    // Move playback along by however much real time passed since the last call
+    pub fn advance(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_advance);
+        self.last_advance = now;
+        if self.paused {
+            return;
+        }
+
+        let target = self.position + (elapsed.as_nanos() as f64 * self.speed()) as u64;
+        self.run_to(target.min(self.duration().as_nanos() as u64));
+    }
+
+    // Apply events up to and including `target`
+    fn run_to(&mut self, target: u64) {
+        while let Some(event) = self.replay.events.get(self.next_event) {
+            if event.at_nanos > target {
+                break;
+            }
+            replay::apply_event(&mut self.game, self.origin, event);
+            self.next_event += 1;
+        }
+        self.position = target;
+    }
+
+    pub fn seek(&mut self, target: Duration) {
+        let target = (target.as_nanos() as u64).min(self.duration().as_nanos() as u64);
+
+        // Going backwards means starting over from the latest snapshot before the target
+        if target < self.position {
+            let checkpoint = self
+                .checkpoints
+                .iter()
+                .rev()
+                .find(|checkpoint| {
+                    checkpoint.events == 0 || self.replay.events[checkpoint.events - 1].at_nanos <= target
+                })
+                .unwrap_or(&self.checkpoints[0]);
+            self.game.set_clock(self.origin + checkpoint.snapshot.elapsed);
+            self.game.restore(&checkpoint.snapshot);
+            self.next_event = checkpoint.events;
+        }
+        self.run_to(target);
+    }
+
+    pub fn seek_by(&mut self, millis: i64) {
+        let target = self.position as i64 + millis * 1_000_000;
+        self.seek(Duration::from_nanos(target.max(0) as u64));
+    }
+
+    // Jump to a fraction (0.0-1.0) of the way through
+    pub fn seek_fraction(&mut self, fraction: f64) {
+        self.seek(self.duration().mul_f64(fraction.clamp(0.0, 1.0)));
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    // One frame is one engine update; inputs between frames come along with it
+    pub fn step_frame(&mut self) {
+        self.paused = true;
+        while let Some(event) = self.replay.events.get(self.next_event) {
+            let target = event.at_nanos;
+            let is_frame = event.input == InputEvent::Tick;
+            self.run_to(target);
+            if is_frame {
+                break;
+            }
+        }
+    }
+
+    pub fn step_frame_back(&mut self) {
+        self.paused = true;
+        let previous = self.replay.events[..self.next_event.saturating_sub(1)]
+            .iter()
+            .rposition(|event| event.input == InputEvent::Tick)
+            .map_or(0, |i| self.replay.events[i].at_nanos);
+        self.seek(Duration::from_nanos(previous));
+    }
+
+    // Jump to the moment the next (or previous) piece locked
+    pub fn step_piece(&mut self, forward: bool) {
+        self.paused = true;
+        let placed = self.game.pieces_placed as usize;
+        let index = if forward { placed + 1 } else { placed.saturating_sub(1) };
+        if let Some(&at) = self.piece_times.get(index) {
+            self.seek(Duration::from_nanos(at));
+        }
+    }
+
+    pub fn change_speed(&mut self, faster: bool) {
+        self.speed = if faster {
+            (self.speed + 1).min(REPLAY_SPEEDS.len() - 1)
+        } else {
+            self.speed.saturating_sub(1)
+        };
+    }
+}
+
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{BOARD_HEIGHT, BOARD_WIDTH, VISIBLE_HEIGHT};
+use crate::game::board::{board_from_rows, Board};
+use crate::game::piece::{parse_queue, PieceType};
+
+// Shipped with the game, easiest first
+const BUILT_IN: [&str; 5] = [
+    include_str!("../../puzzles/01-first-steps.toml"),
+    include_str!("../../puzzles/02-tee-time.toml"),
+    include_str!("../../puzzles/03-side-by-side.toml"),
+    include_str!("../../puzzles/04-zigzag.toml"),
+    include_str!("../../puzzles/05-square-up.toml"),
+];
+
+// Gravity-off puzzle: fill the target cells exactly with the given pieces.
+// Replays carry the puzzle they were played on, checked when read back like
+// a puzzle file is.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedPuzzle")]
+pub struct Puzzle {
+    pub name: String,
+    pub queue: Vec<PieceType>, // Every piece the player gets, in order
+    pub rows: Vec<String>,     // Bottom rows of the board: 'G' stack, '+' target, '.' empty
+}
+
+#[derive(Deserialize)]
+struct UncheckedPuzzle {
+    name: String,
+    queue: Vec<PieceType>,
+    rows: Vec<String>,
+}
+
+impl TryFrom<UncheckedPuzzle> for Puzzle {
+    type Error = PuzzleError;
+
+    fn try_from(puzzle: UncheckedPuzzle) -> Result<Self, PuzzleError> {
+        let puzzle = Self { name: puzzle.name, queue: puzzle.queue, rows: puzzle.rows };
+        puzzle.validate()?;
+        Ok(puzzle)
+    }
+}
+
+// Puzzle files spell the queue out as letters
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PuzzleFile {
+    #[serde(default)]
+    name: String,
+    queue: String,
+    rows: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PuzzleError {
+    #[error("{0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("'{0}' in queue is not a piece (use I, O, T, S, Z, J, L)")]
+    Piece(char),
+    #[error("queue is empty")]
+    EmptyQueue,
+    #[error("has no rows")]
+    NoRows,
+    #[error("has {0} rows but the board is only {VISIBLE_HEIGHT} tall")]
+    TooTall(usize),
+    #[error("row {0} is {1} cells wide, not {BOARD_WIDTH}")]
+    Width(usize, usize),
+    #[error("'{0}' is not a cell (use G, + or .)")]
+    Cell(char),
+    #[error("target has {0} cells but the queue fills {1}")]
+    TargetSize(usize, usize),
+}
+
+impl Puzzle {
+    // `name` stands in when the file doesn't give one
+    pub fn parse(text: &str, name: &str) -> Result<Self, PuzzleError> {
+        let file: PuzzleFile = toml::from_str(text)?;
+        let queue = parse_queue(&file.queue).map_err(PuzzleError::Piece)?;
+        let name = if file.name.is_empty() { name.to_string() } else { file.name };
+        let puzzle = Self { name, queue, rows: file.rows };
+        puzzle.validate()?;
+        Ok(puzzle)
+    }
+
+    pub fn built_in() -> Vec<Self> {
+        BUILT_IN
+            .iter()
+            .map(|text| Self::parse(text, "").expect("built-in puzzle"))
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), PuzzleError> {
+        if self.queue.is_empty() {
+            return Err(PuzzleError::EmptyQueue);
+        }
+        if self.rows.is_empty() {
+            return Err(PuzzleError::NoRows);
+        }
+        if self.rows.len() > VISIBLE_HEIGHT {
+            return Err(PuzzleError::TooTall(self.rows.len()));
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            if let Some(c) = row.chars().find(|c| !matches!(c, 'G' | '+' | '.')) {
+                return Err(PuzzleError::Cell(c));
+            }
+            if row.len() != BOARD_WIDTH {
+                return Err(PuzzleError::Width(i + 1, row.len()));
+            }
+        }
+        let cells = self.target().len();
+        if cells != self.queue.len() * 4 {
+            return Err(PuzzleError::TargetSize(cells, self.queue.len() * 4));
+        }
+        Ok(())
+    }
+
+    // The starting board: the stack, with the target left empty
+    pub fn board(&self) -> Board {
+        let rows: Vec<String> = self.rows.iter().map(|row| row.replace('+', ".")).collect();
+        board_from_rows(&rows)
+    }
+
+    // Board coordinates of the cells to fill
+    pub fn target(&self) -> Vec<(usize, usize)> {
+        let offset = BOARD_HEIGHT - self.rows.len();
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.chars().enumerate().filter(|&(_, c)| c == '+').map(move |(x, _)| (x, offset + y)))
+            .collect()
+    }
+}
+
@@ -0,0 +1,213 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::battle::Handicap;
+use crate::game::blind::Blind;
+use crate::game::adaptive::AdaptiveGravity;
+use crate::game::board::Board;
+use crate::game::garbage::GarbageRules;
+use crate::game::win::Goal;
+use crate::game::level::SpeedCurve;
+use crate::game::mode::GameMode;
+use crate::game::piece::PieceType;
+use crate::game::puzzle::Puzzle;
+use crate::game::state::{EndReason, GameState};
+use crate::game::Game;
+use crate::input::handler::ReleaseGuess;
+use crate::input::repeat::Timings;
+use crate::input::InputDirection;
+
+// Everything that can change the engine's state. Live play and replay
+// simulation both feed these through Game::apply_input / Game::update.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum InputEvent {
+    Tick, // One call to Game::update
+    Press(InputDirection),
+    Release(InputDirection),
+    KeyActivity(InputDirection), // Key repeat for an already held direction
+    ReleaseAll,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    HardDrop,
+    Hold,
+    Undo, // Zen: take back the last placement
+    Pause,
+    Resume,
+    Garbage(u32), // Versus: rows sent by the other player rise
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub at_nanos: u64, // Offset from the start of the countdown
+    pub input: InputEvent,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub mode: GameMode,
+    pub seed: u64,
+    #[serde(default = "legacy_countdown")]
+    pub countdown: u32, // Countdown length the run was played with
+    pub keyboard_enhancement: bool,
+    // Only written for runs under tmux or screen, so other replays hash as before
+    #[serde(default = "default_release_guess", skip_serializing_if = "ReleaseGuess::is_default")]
+    pub release_guess: ReleaseGuess,
+    pub events: Vec<ReplayEvent>,
+    // Left out when empty so replays from before names hash the same
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub player: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub queue: Vec<PieceType>, // Fixed opening sequence, for practice runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curve: Option<SpeedCurve>, // Custom marathon speed curve
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub big_pieces: bool,
+    #[serde(default, skip_serializing_if = "Blind::is_none")]
+    pub blind: Blind,
+    // Left out by replays from before hole patterns, which pick holes the original way
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub garbage: Option<GarbageRules>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<Goal>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub adaptive_gravity: bool, // Zen gravity that follows how the player is doing
+    // Left out at the built-in timings, which every replay from before them used
+    #[serde(default, skip_serializing_if = "Timings::is_default")]
+    pub timings: Timings,
+    // Left out by replays from before held keys kept to their repeat rate,
+    // which repeat the original way
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exact_repeat: bool,
+    #[serde(default, skip_serializing_if = "Handicap::is_default")]
+    pub handicap: Handicap, // Versus: left out when there's none, as in every run from before handicaps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub puzzle: Option<Puzzle>,
+}
+
+impl Replay {
+    // Whether the run was held paused at any point, time-ranked runs included
+    // under the quit prompt
+    pub fn paused(&self) -> bool {
+        self.events.iter().any(|event| event.input == InputEvent::Pause)
+    }
+}
+
+fn default_release_guess() -> ReleaseGuess {
+    ReleaseGuess::DEFAULT
+}
+
+// Replays from before the 3-2-1 countdown counted down from 2
+fn legacy_countdown() -> u32 {
+    2
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("replay does not complete the run")]
+    NotCompleted,
+    #[error("claimed time {claimed:?} but replay finishes in {simulated:?}")]
+    TimeMismatch { claimed: Duration, simulated: Option<Duration> },
+    #[error("final board does not match the replay")]
+    BoardMismatch,
+}
+
+// FNV-1a, chosen because it's stable across Rust versions unlike DefaultHasher
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+// Hash of which cells are occupied; colors are left out so the hash only
+// depends on the shape of the stack
+pub fn board_hash(board: &Board) -> u64 {
+    let cells: Vec<u8> = board
+        .iter()
+        .flatten()
+        .map(|&cell| u8::from(cell != crate::game::Cell::Empty))
+        .collect();
+    fnv1a(FNV_OFFSET, &cells)
+}
+
+// Running digest of the stack and the upcoming pieces, folded in after every
+// placement. Two copies of a run that agree on it have stayed in step; the
+// first piece where it differs is where they came apart.
+pub fn fold_state_hash(hash: u64, board: &Board, queue: impl Iterator<Item = PieceType>) -> u64 {
+    let queue: Vec<u8> = queue.map(|piece_type| piece_type.letter() as u8).collect();
+    fnv1a(fnv1a(hash, &board_hash(board).to_le_bytes()), &queue)
+}
+
+pub const STATE_HASH_START: u64 = FNV_OFFSET;
+
+// Digest tying a replay to the result it produces, stored with PBs so anyone
+// holding the replay can re-simulate it and check the claim
+pub fn validation_hash(replay: &Replay, final_time: Duration, board_hash: u64) -> u64 {
+    let replay_json = serde_json::to_vec(replay).unwrap_or_default();
+    let hash = fnv1a(FNV_OFFSET, &replay_json);
+    let hash = fnv1a(hash, &(final_time.as_nanos() as u64).to_le_bytes());
+    fnv1a(hash, &board_hash.to_le_bytes())
+}
+
+// A game set up the way the replay's run started, with its virtual clock at
+// `origin`, ready for the replay's events
+pub fn start_simulation(replay: &Replay, origin: Instant) -> Game {
+    let mut game = Game::new_simulated(origin);
+    game.mode = replay.mode;
+    game.input_state.keyboard_enhancement_active = replay.keyboard_enhancement;
+    game.input_state.release_guess = replay.release_guess;
+    game.countdown_steps = replay.countdown;
+    game.player = replay.player.clone();
+    game.queue = replay.queue.clone();
+    game.curve = replay.curve.clone();
+    game.big_pieces = replay.big_pieces;
+    game.blind = replay.blind;
+    game.garbage_rules = replay.garbage;
+    game.goal = replay.goal.clone();
+    game.adaptive = replay.adaptive_gravity.then(AdaptiveGravity::new);
+    game.timings = replay.timings;
+    game.exact_repeat = replay.exact_repeat;
+    game.handicap = replay.handicap;
+    game.puzzle = replay.puzzle.clone();
+    game.prepare_run(replay.seed);
+    game.begin_countdown();
+    game
+}
+
+pub fn apply_event(game: &mut Game, origin: Instant, event: &ReplayEvent) {
+    game.set_clock(origin + Duration::from_nanos(event.at_nanos));
+    match event.input {
+        InputEvent::Tick => {
+            // The engine's update never fails on a virtual clock
+            let _ = game.update();
+        }
+        input => game.apply_input(input),
+    }
+}
+
+// Re-run a replay headlessly on a virtual clock
+pub fn simulate(replay: &Replay) -> Game {
+    let origin = Instant::now();
+    let mut game = start_simulation(replay, origin);
+    for event in &replay.events {
+        apply_event(&mut game, origin, event);
+    }
+    game
+}
+
+pub fn verify(replay: &Replay, claimed_time: Duration, claimed_board_hash: u64) -> Result<u64, ValidationError> {
+    let game = simulate(replay);
+
+    if game.game_state != GameState::Finished || game.end_reason != Some(EndReason::Completed) {
+        return Err(ValidationError::NotCompleted);
+    }
+    if game.final_time != Some(claimed_time) {
+        return Err(ValidationError::TimeMismatch { claimed: claimed_time, simulated: game.final_time });
+    }
+    if board_hash(&game.board) != claimed_board_hash {
+        return Err(ValidationError::BoardMismatch);
+    }
+
+    Ok(validation_hash(replay, claimed_time, claimed_board_hash))
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::constants::{FRAME_POLL_MS, NETPLAY_GUESS_MS, NETPLAY_ROLLBACK_MS};
+use crate::game::replay::{self, Replay, ReplayEvent};
+use crate::game::state::{EndReason, GameSnapshot, GameState};
+use crate::game::Game;
+
+// Engine state after applying the first `events` inputs
+struct Checkpoint {
+    events: usize,
+    snapshot: GameSnapshot,
+}
+
+// Another player's run, played here from the inputs they send. Their inputs
+// arrive late, so in between the game is run ahead on a guess that nothing
+// new was pressed. When the real inputs come in, it goes back to the last
+// checkpoint before them, plays them where they belong and runs ahead again.
+// An input they rewrote (after garbage of ours reached them late) is
+// handled the same way, from further back. Should the board still drift
+// from theirs, a snapshot of theirs puts it right (see resync).
+pub struct Rollback {
+    header: Replay,  // Their run's settings, to start over from if need be
+    origin: Instant, // Start of their countdown on our clock
+    pub game: Game,
+    inputs: Vec<ReplayEvent>, // Everything received this run
+    applied: usize,           // Inputs the game has been through
+    guessed: bool,            // The game has been run past the last input
+    checkpoints: VecDeque<Checkpoint>,
+    pub confirmed_time: Duration, // Run time of their last input
+    confirmed_hash: (usize, u64), // Inputs received and the state hash once they were played
+    pub ended: Option<(Duration, bool)>, // Run time their run ended at, and whether they completed it
+}
+
+impl Rollback {
+    pub fn new(header: &Replay, origin: Instant) -> Self {
+        let header = Replay { events: Vec::new(), ..header.clone() };
+        let game = replay::start_simulation(&header, origin);
+        let checkpoints = VecDeque::from([Checkpoint { events: 0, snapshot: game.snapshot() }]);
+        Self {
+            header,
+            origin,
+            confirmed_hash: (0, game.state_hash),
+            game,
+            inputs: Vec::new(),
+            applied: 0,
+            guessed: false,
+            checkpoints,
+            confirmed_time: Duration::ZERO,
+            ended: None,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.header.seed
+    }
+
+    // Their inputs from index `from` on. One before the end of what's been
+    // received replaces the inputs from there.
+    pub fn receive(&mut self, from: usize, events: Vec<ReplayEvent>) {
+        let from = from.min(self.inputs.len());
+        self.inputs.truncate(from);
+        self.inputs.extend(events);
+        if self.guessed || from < self.applied {
+            self.rewind(from);
+        }
+        self.catch_up();
+    }
+
+    // Whether their board hashes as they say it does once their first
+    // `events` inputs are in. Inputs here that have moved on since can't
+    // tell, so they agree.
+    pub fn agrees(&self, events: usize, hash: u64) -> bool {
+        self.confirmed_hash.0 != events || self.confirmed_hash.1 == hash
+    }
+
+    // Their board as they saw it, in place of ours, with anything received
+    // since played on top
+    pub fn resync(&mut self, snapshot: &GameSnapshot) {
+        if snapshot.replay_len > self.inputs.len() {
+            return;
+        }
+        self.game.set_clock(self.origin + snapshot.elapsed);
+        self.game.restore(snapshot);
+        self.checkpoints.clear();
+        self.applied = snapshot.replay_len;
+        self.guessed = false;
+        if self.ended.is_some() && self.game.game_state != GameState::Finished {
+            self.ended = None;
+        }
+        self.catch_up();
+    }
+
+    // Play the inputs received but not yet played
+    fn catch_up(&mut self) {
+        for event in &self.inputs[self.applied..] {
+            replay::apply_event(&mut self.game, self.origin, event);
+        }
+        self.applied = self.inputs.len();
+
+        self.confirmed_time = self.game.get_current_time().unwrap_or_default();
+        self.confirmed_hash = (self.applied, self.game.state_hash);
+        if self.game.game_state == GameState::Finished && self.ended.is_none() {
+            self.ended = Some((self.confirmed_time, self.game.end_reason == Some(EndReason::Completed)));
+        }
+        self.checkpoint();
+    }
+
+    // Back to the latest checkpoint at or before input `to`, or the start if
+    // they're all past it
+    fn rewind(&mut self, to: usize) {
+        while self.checkpoints.back().is_some_and(|checkpoint| checkpoint.events > to) {
+            self.checkpoints.pop_back();
+        }
+        match self.checkpoints.back() {
+            Some(checkpoint) => {
+                self.game.set_clock(self.origin + checkpoint.snapshot.elapsed);
+                self.game.restore(&checkpoint.snapshot);
+                self.applied = checkpoint.events;
+            }
+            None => {
+                self.game = replay::start_simulation(&self.header, self.origin);
+                self.applied = 0;
+            }
+        }
+        // An end that was rewritten away didn't happen
+        if self.ended.is_some() && self.game.game_state != GameState::Finished {
+            self.ended = None;
+        }
+        self.guessed = false;
+    }
+
+    // Checkpoints only reach NETPLAY_ROLLBACK_MS back; anything older is
+    // played again from the start
+    fn checkpoint(&mut self) {
+        let snapshot = self.game.snapshot();
+        let oldest = snapshot.elapsed.saturating_sub(Duration::from_millis(NETPLAY_ROLLBACK_MS));
+        self.checkpoints.push_back(Checkpoint { events: self.applied, snapshot });
+        while self.checkpoints.front().is_some_and(|checkpoint| checkpoint.snapshot.elapsed < oldest) {
+            self.checkpoints.pop_front();
+        }
+    }
+
+    // Run the guess on to `elapsed` since their countdown, a tick at a time
+    // like the live game. A connection gone quiet doesn't get guessed
+    // through for more than NETPLAY_GUESS_MS.
+    pub fn advance(&mut self, elapsed: Duration) {
+        let last_input = Duration::from_nanos(self.inputs.last().map_or(0, |event| event.at_nanos));
+        let target = elapsed.min(last_input + Duration::from_millis(NETPLAY_GUESS_MS));
+        let step = Duration::from_millis(FRAME_POLL_MS);
+        let mut at = self.game.replay_elapsed();
+        while at + step <= target {
+            at += step;
+            self.game.set_clock(self.origin + at);
+            // The engine's update never fails on a virtual clock
+            let _ = self.game.update();
+            self.guessed = true;
+        }
+        // Nothing here shows their reports
+        self.game.drain_events();
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::piece::PieceType;
+
+// SRS rotation states
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Rotation {
+    Spawn, // 0
+    Right, // R
+    Two,   // 2
+    Left,  // L
+}
+
+impl Rotation {
+    pub fn clockwise(self) -> Self {
+        match self {
+            Rotation::Spawn => Rotation::Right,
+            Rotation::Right => Rotation::Two,
+            Rotation::Two => Rotation::Left,
+            Rotation::Left => Rotation::Spawn,
+        }
+    }
+
+    pub fn counter_clockwise(self) -> Self {
+        match self {
+            Rotation::Spawn => Rotation::Left,
+            Rotation::Left => Rotation::Two,
+            Rotation::Two => Rotation::Right,
+            Rotation::Right => Rotation::Spawn,
+        }
+    }
+
+    pub fn flipped(self) -> Self {
+        self.clockwise().clockwise()
+    }
+}
+
+// Kick offsets to try, in order, when rotating from one state to another.
+// Tables are written with y pointing up as in the SRS spec and flipped to
+// board coordinates (y down) on the way out.
+pub fn kicks(piece_type: PieceType, from: Rotation, to: Rotation) -> Vec<(i32, i32)> {
+    use Rotation::*;
+
+    let table: &[(i32, i32)] = match (piece_type, from, to) {
+        (PieceType::O, _, _) => &[(0, 0)],
+
+        // 180 kicks (SRS+), shared by every piece
+        (_, Spawn, Two) => &[(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+        (_, Two, Spawn) => &[(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+        (_, Right, Left) => &[(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+        (_, Left, Right) => &[(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+
+        (PieceType::I, Spawn, Right) => &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+        (PieceType::I, Right, Spawn) => &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+        (PieceType::I, Right, Two) => &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+        (PieceType::I, Two, Right) => &[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+        (PieceType::I, Two, Left) => &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+        (PieceType::I, Left, Two) => &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+        (PieceType::I, Left, Spawn) => &[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+        (PieceType::I, Spawn, Left) => &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+
+        (_, Spawn, Right) => &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+        (_, Right, Spawn) => &[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+        (_, Right, Two) => &[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+        (_, Two, Right) => &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+        (_, Two, Left) => &[(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+        (_, Left, Two) => &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+        (_, Left, Spawn) => &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+        (_, Spawn, Left) => &[(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+
+        // Same state: nothing to kick
+        _ => &[(0, 0)],
+    };
+
+    table.iter().map(|&(x, y)| (x, -y)).collect()
+}
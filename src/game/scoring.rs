@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::BONUS_MULTIPLIER;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DropKind {
+    Gravity,
+    Soft,
+    Hard,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Scoring {
+    pub points: u64,
+    pub piece_drop_cells: u32, // Cells the current piece has been soft/hard dropped
+    pub last_drop_cells: u32,  // Same, for the last piece that locked
+    pub bonus_clears: u32,     // Clears completed by a bonus piece
+}
+
+impl Scoring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 1 point per soft-dropped cell, 2 per hard-dropped cell; gravity is free
+    pub fn record_drop(&mut self, kind: DropKind, cells: u32) {
+        let per_cell = match kind {
+            DropKind::Gravity => return,
+            DropKind::Soft => 1,
+            DropKind::Hard => 2,
+        };
+        self.points += (per_cell * cells) as u64;
+        self.piece_drop_cells += cells;
+    }
+
+    // Clear points scale with the level the clear was made on, and double
+    // when a bonus piece completes a row
+    pub fn record_clear(&mut self, lines: u32, level: u32, bonus: bool) {
+        let base = match lines {
+            1 => 100,
+            2 => 300,
+            3 => 500,
+            4 => 800,
+            _ => 0,
+        };
+        let multiplier = if bonus && lines > 0 {
+            self.bonus_clears += 1;
+            BONUS_MULTIPLIER
+        } else {
+            1
+        };
+        self.points += base * level as u64 * multiplier;
+    }
+
+    pub fn piece_locked(&mut self) {
+        self.last_drop_cells = self.piece_drop_cells;
+        self.piece_drop_cells = 0;
+    }
+}
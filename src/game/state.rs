@@ -1,20 +1,59 @@
+use std::ops::Range;
 use std::time::{Duration, Instant};
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::constants::{BOARD_WIDTH, BOARD_HEIGHT, TARGET_LINES, GROUND_TIME};
-use crate::game::board::{Board, Cell, empty_board};
-use crate::game::piece::{Piece, PieceType};
-use crate::input::handler::InputState;
+use crate::ai::battle::Handicap;
+use crate::ai::heuristic;
+use crate::error::Result;
+use crate::constants::{
+    BOARD_WIDTH, BOARD_HEIGHT, BUFFER_HEIGHT, TARGET_LINES, COUNTDOWN_STEPS, DIG_GARBAGE_ROWS, DIG_PIECE_LIMIT,
+    BIG_PIECE_CHANCE, MYSTERY_FAST_DROP_MS, MYSTERY_GARBAGE_ROWS, MYSTERY_INTERVAL_SECS,
+    MYSTERY_EFFECT_SECS, ULTRA_SECS, BONUS_PIECE_CHANCE,
+    LINES_PER_LEVEL,
+};
+use crate::game::adaptive::AdaptiveGravity;
+use crate::game::blind::Blind;
+use crate::game::garbage::{GarbageRules, HoleGenerator};
+use crate::game::board::{Board, Cell, clear_full_rows, column_heights, covered_holes, empty_board, fits, garbage_row, wide_garbage_row};
+use crate::game::event::{GameEvent, TimedEvent};
+use crate::game::level::{
+    classic_gravity_interval, classic_level, gravity_interval, level_for_lines, lines_to_next_level, survival_interval,
+    SpeedCurve,
+};
+use crate::game::mode::GameMode;
+use crate::game::mystery::Mystery;
+use crate::game::piece::{shuffled_bag, Piece, PieceType};
+use crate::game::puzzle::Puzzle;
+use crate::game::replay::{apply_event, fold_state_hash, InputEvent, Replay, ReplayEvent, STATE_HASH_START};
+use crate::game::rotation::kicks;
+use crate::game::scoring::{DropKind, Scoring};
+use crate::game::usage::ActionCounts;
+use crate::game::win::{Goal, WinCondition};
+use crate::input::action::GameAction;
+use crate::input::handler::{InputState, ReleaseGuess};
+use crate::input::repeat::{AutoRepeat, Timings};
 use crate::input::InputDirection;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum GameState {
     Ready,
-    Countdown(u32), // Countdown number (2=Ready, 1=GO!)
+    Countdown(u32), // Number showing, counting down to 1
     Playing,
+    Paused,
     Finished,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum EndReason {
+    Completed,
+    ToppedOut,
+    DrillFailed, // Dig drill: too many placements without clearing garbage
+    PuzzleFailed, // Puzzle: a piece landed outside the target
+}
+
+#[derive(Clone)]
 pub struct Game {
     pub board: Board,
     pub current_piece: Option<Piece>,
@@ -27,14 +66,136 @@ pub struct Game {
     pub input_state: InputState,
     pub game_state: GameState,
     pub countdown_timer: Instant,
+    pub countdown_steps: u32,          // Length of the countdown in seconds
     pub game_timer: Option<Instant>,
     pub final_time: Option<Duration>,
     pub ground_timer: Option<Instant>, // Timer for piece on ground
-    pub piece_bag: Vec<PieceType>,     // 7-bag randomizer
+    pub spawned_at: Instant,           // When the piece in play came out
+    piece_bag: Vec<PieceType>,         // 7-bag randomizer
+    pieces_drawn: u32,                 // Total pieces taken from the randomizer
+    pub queue: Vec<PieceType>,         // Dealt in order at the start of each run, before the bag takes over
+    pub big_pieces: bool,              // Some pieces come at twice the size
+    pub blind: Blind,                  // What the player goes without this run
+    pub garbage_rules: Option<GarbageRules>, // None for the original hole picking, which old replays need
+    pub goal: Option<Goal>,            // A custom mode's win condition, in place of the mode's own
+    pub adaptive: Option<AdaptiveGravity>, // Zen only: gravity that speeds up and eases off with the player
+    pub timings: Timings,              // DAS, ARR, soft drop speed and lock delay
+    pub exact_repeat: bool,            // Held keys repeat on their rate exactly; old replays need the drifting original
+    pub handicap: Handicap,            // Versus: what this player gives up to even out the match
+    holes: Option<HoleGenerator>,
+    pub puzzle: Option<Puzzle>,        // Board and pieces for puzzle mode
+    pub mode: GameMode,
+    pub curve: Option<SpeedCurve>,     // Marathon gravity and lock delay from the config instead of the guideline
+    pub end_reason: Option<EndReason>,
+    pub pieces_placed: u32,
+    pub last_locked: Option<Piece>,    // Where the last piece came to rest
+    pub garbage_remaining: u32,        // Dig drill: garbage rows still on the board
+    pub garbage_cleared: u32,
+    pub pieces_since_dig: u32,         // Dig drill: placements since the last garbage clear
+    pub next_garbage_at: Duration,     // Survival: run time the next garbage row rises at
+    pub next_mystery_at: Duration,     // Mystery: run time of the next event
+    pub mystery: Option<Mystery>,      // Mystery: lasting event in play, until mystery_until
+    pub mystery_until: Duration,
+    pub misdrops: u32,                 // Placements that left new covered holes or were undone
+    pub grades: Vec<f64>,              // Each placement against the AI's choice, 0 to 1
+    pub lock_times: Vec<Duration>,     // Run time each piece locked at
+    pub actions: ActionCounts,         // Presses of each action this run
+    pub scoring: Scoring,
+    pub level_up_at: Option<Duration>, // Run time of the last level up
+    pub paused_at: Option<Instant>,
+    pub seed: u64,                     // Seeds the bag and garbage so runs can be replayed
+    pub fixed_seed: Option<u64>,       // Every new run uses this seed instead of a fresh one
+    pub runs: u32,                     // Countdowns started, so a new run can be told from a restored one
+    pub versus: bool,                  // Networked match: the other player's clock keeps going, so this never pauses
+    pub state_hash: u64,               // Rolling hash of board and queue, updated every placement
+    rng: ChaCha12Rng,                  // What StdRng uses, named so snapshots can save its state
+    clock: Instant,                    // Time of the event being processed
+    simulated: bool,                   // Clock is driven by a replay rather than real time
+    pub replay: Replay,                // Inputs recorded since the countdown started
+    pub player: String,                // Name recorded into replays
+    events: Vec<TimedEvent>,           // Reported events not yet taken by the UI
+    turn_start: Option<Box<GameSnapshot>>, // Zen: the game as the piece in play came out
+    undo_point: Option<Box<GameSnapshot>>, // Zen: the same for the last piece placed
+    replay_origin: Instant,
+}
+
+// Everything about a game in progress needed to pick it up again later,
+// for seeking, undo and checkpoints. Timers are kept as how long ago they
+// started rather than as instants, so a snapshot can be restored at any time.
+// Settings chosen before the run (queue, curve, puzzle and so on) and the
+// input handler's own key tracking are left out.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub elapsed: Duration, // Run time since the countdown started, when taken
+    #[serde(with = "crate::game::board::rows")]
+    board: Board,
+    current_piece: Option<Piece>,
+    next_pieces: Vec<Piece>,
+    hold_piece: Option<Piece>,
+    can_hold: bool,
+    lines_cleared: u32,
+    lines_remaining: u32,
+    game_state: GameState,
+    final_time: Option<Duration>,
+    piece_bag: Vec<PieceType>,
+    pieces_drawn: u32,
+    mode: GameMode,
+    end_reason: Option<EndReason>,
+    pieces_placed: u32,
+    last_locked: Option<Piece>,
+    garbage_remaining: u32,
+    garbage_cleared: u32,
+    pieces_since_dig: u32,
+    next_garbage_at: Duration,
+    next_mystery_at: Duration,
+    mystery: Option<Mystery>,
+    mystery_until: Duration,
+    misdrops: u32,
+    grades: Vec<f64>,
+    lock_times: Vec<Duration>,
+    actions: ActionCounts,
+    scoring: Scoring,
+    level_up_at: Option<Duration>,
+    seed: u64,
+    state_hash: u64,
+    rng: ChaCha12Rng,
+    holes: Option<HoleGenerator>,
+    adaptive: Option<AdaptiveGravity>,
+    turn_start: Option<Box<GameSnapshot>>,
+    undo_point: Option<Box<GameSnapshot>>,
+    pub replay_len: usize, // Replay events recorded so far; later ones are dropped on restore
+    drop_timer: Duration,
+    countdown_timer: Duration,
+    game_timer: Option<Duration>,
+    ground_timer: Option<Duration>,
+    spawned_at: Duration,
+    paused_at: Option<Duration>,
+    directions: Vec<(InputDirection, DirectionSnapshot)>,
+    last_horizontal_dir: Option<InputDirection>,
+}
+
+// Auto-shift state for one direction, timers as ages like the rest
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct DirectionSnapshot {
+    pressed: bool,
+    das_timer: Duration,
+    arr_timer: Duration,
+    das_charged: bool,
+    initial_move_done: bool,
+    last_update: Duration,
 }
 
 impl Game {
     pub fn new() -> Self {
+        Self::with_clock(Instant::now(), false)
+    }
+
+    pub fn new_simulated(origin: Instant) -> Self {
+        Self::with_clock(origin, true)
+    }
+
+    fn with_clock(now: Instant, simulated: bool) -> Self {
+        let seed = rand::thread_rng().gen();
         let mut game = Self {
             board: empty_board(),
             current_piece: None,
@@ -43,61 +204,872 @@ impl Game {
             can_hold: true,
             lines_cleared: 0,
             lines_remaining: TARGET_LINES,
-            drop_timer: Instant::now(),
+            drop_timer: now,
             input_state: InputState::new(),
             game_state: GameState::Ready,
-            countdown_timer: Instant::now(),
+            countdown_timer: now,
+            countdown_steps: COUNTDOWN_STEPS,
             game_timer: None,
             final_time: None,
             ground_timer: None,
+            spawned_at: now,
             piece_bag: Vec::new(),
+            pieces_drawn: 0,
+            queue: Vec::new(),
+            big_pieces: false,
+            blind: Blind::default(),
+            garbage_rules: Some(GarbageRules::default()),
+            goal: None,
+            adaptive: None,
+            timings: Timings::default(),
+            exact_repeat: true,
+            handicap: Handicap::default(),
+            holes: None,
+            puzzle: None,
+            mode: GameMode::Sprint,
+            curve: None,
+            end_reason: None,
+            pieces_placed: 0,
+            last_locked: None,
+            garbage_remaining: 0,
+            garbage_cleared: 0,
+            pieces_since_dig: 0,
+            next_garbage_at: Duration::ZERO,
+            next_mystery_at: Duration::ZERO,
+            mystery: None,
+            mystery_until: Duration::ZERO,
+            misdrops: 0,
+            grades: Vec::new(),
+            lock_times: Vec::new(),
+            actions: ActionCounts::default(),
+            scoring: Scoring::new(),
+            level_up_at: None,
+            paused_at: None,
+            seed,
+            fixed_seed: None,
+            runs: 0,
+            versus: false,
+            state_hash: STATE_HASH_START,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            clock: now,
+            simulated,
+            replay: Replay {
+                mode: GameMode::Sprint,
+                seed,
+                countdown: COUNTDOWN_STEPS,
+                keyboard_enhancement: false,
+                release_guess: ReleaseGuess::DEFAULT,
+                events: Vec::new(),
+                player: String::new(),
+                queue: Vec::new(),
+                curve: None,
+                big_pieces: false,
+                blind: Blind::default(),
+                garbage: None,
+                goal: None,
+                adaptive_gravity: false,
+                timings: Timings::default(),
+                exact_repeat: false,
+                handicap: Handicap::default(),
+                puzzle: None,
+            },
+            player: String::new(),
+            replay_origin: now,
+            events: Vec::new(),
+            turn_start: None,
+            undo_point: None,
         };
         
-        // Initialize the next pieces queue with 5 pieces
-        game.fill_next_pieces();
+        game.prepare_run(seed);
         game
     }
 
-    fn fill_bag(&mut self) {
-        // Create a new bag with all 7 piece types
-        self.piece_bag = vec![
-            PieceType::I, PieceType::O, PieceType::T, 
-            PieceType::S, PieceType::Z, PieceType::J, PieceType::L
+    // Only used by replay simulation, which drives time itself
+    pub fn set_clock(&mut self, now: Instant) {
+        self.clock = now;
+    }
+
+    fn advance_clock(&mut self) {
+        if !self.simulated {
+            self.clock = Instant::now();
+        }
+    }
+
+    // Frame-step debugging: stop following real time, so the clock only
+    // moves when set_clock steps it
+    pub fn freeze_clock(&mut self) {
+        self.simulated = true;
+    }
+
+    pub fn clock(&self) -> Instant {
+        self.clock
+    }
+
+    // Pieces taken from the queue so far, counting the ones shown in Next
+    pub fn pieces_drawn(&self) -> u32 {
+        self.pieces_drawn
+    }
+
+    pub fn snapshot(&self) -> GameSnapshot {
+        let age = |at: Instant| self.clock.saturating_duration_since(at);
+        GameSnapshot {
+            elapsed: self.replay_elapsed(),
+            board: self.board,
+            current_piece: self.current_piece.clone(),
+            next_pieces: self.next_pieces.clone(),
+            hold_piece: self.hold_piece.clone(),
+            can_hold: self.can_hold,
+            lines_cleared: self.lines_cleared,
+            lines_remaining: self.lines_remaining,
+            game_state: self.game_state,
+            final_time: self.final_time,
+            piece_bag: self.piece_bag.clone(),
+            pieces_drawn: self.pieces_drawn,
+            mode: self.mode,
+            end_reason: self.end_reason,
+            pieces_placed: self.pieces_placed,
+            last_locked: self.last_locked.clone(),
+            garbage_remaining: self.garbage_remaining,
+            garbage_cleared: self.garbage_cleared,
+            pieces_since_dig: self.pieces_since_dig,
+            next_garbage_at: self.next_garbage_at,
+            next_mystery_at: self.next_mystery_at,
+            mystery: self.mystery,
+            mystery_until: self.mystery_until,
+            misdrops: self.misdrops,
+            grades: self.grades.clone(),
+            lock_times: self.lock_times.clone(),
+            actions: self.actions,
+            scoring: self.scoring.clone(),
+            level_up_at: self.level_up_at,
+            seed: self.seed,
+            state_hash: self.state_hash,
+            rng: self.rng.clone(),
+            holes: self.holes.clone(),
+            adaptive: self.adaptive.clone(),
+            turn_start: self.turn_start.clone(),
+            undo_point: self.undo_point.clone(),
+            replay_len: self.replay.events.len(),
+            drop_timer: age(self.drop_timer),
+            countdown_timer: age(self.countdown_timer),
+            game_timer: self.game_timer.map(age),
+            ground_timer: self.ground_timer.map(age),
+            spawned_at: age(self.spawned_at),
+            paused_at: self.paused_at.map(age),
+            directions: self
+                .input_state
+                .directions
+                .iter()
+                .map(|(&direction, state)| {
+                    (direction, DirectionSnapshot {
+                        pressed: state.pressed,
+                        das_timer: age(state.das_timer),
+                        arr_timer: age(state.arr_timer),
+                        das_charged: state.das_charged,
+                        initial_move_done: state.initial_move_done,
+                        last_update: age(state.last_update),
+                    })
+                })
+                .collect(),
+            last_horizontal_dir: self.input_state.last_horizontal_dir,
+        }
+    }
+
+    // Put the game back as it was when `snapshot` was taken, with timers
+    // counting from now. A simulated game's clock should be set to where the
+    // snapshot belongs first.
+    pub fn restore(&mut self, snapshot: &GameSnapshot) {
+        self.advance_clock();
+        let clock = self.clock;
+        let at = |age: Duration| clock.checked_sub(age).unwrap_or(clock);
+        self.board = snapshot.board;
+        self.current_piece = snapshot.current_piece.clone();
+        self.next_pieces = snapshot.next_pieces.clone();
+        self.hold_piece = snapshot.hold_piece.clone();
+        self.can_hold = snapshot.can_hold;
+        self.lines_cleared = snapshot.lines_cleared;
+        self.lines_remaining = snapshot.lines_remaining;
+        self.game_state = snapshot.game_state;
+        self.final_time = snapshot.final_time;
+        self.piece_bag = snapshot.piece_bag.clone();
+        self.pieces_drawn = snapshot.pieces_drawn;
+        self.mode = snapshot.mode;
+        self.end_reason = snapshot.end_reason;
+        self.pieces_placed = snapshot.pieces_placed;
+        self.last_locked = snapshot.last_locked.clone();
+        self.garbage_remaining = snapshot.garbage_remaining;
+        self.garbage_cleared = snapshot.garbage_cleared;
+        self.pieces_since_dig = snapshot.pieces_since_dig;
+        self.next_garbage_at = snapshot.next_garbage_at;
+        self.next_mystery_at = snapshot.next_mystery_at;
+        self.mystery = snapshot.mystery;
+        self.mystery_until = snapshot.mystery_until;
+        self.misdrops = snapshot.misdrops;
+        self.grades = snapshot.grades.clone();
+        self.lock_times = snapshot.lock_times.clone();
+        self.actions = snapshot.actions;
+        self.scoring = snapshot.scoring.clone();
+        self.level_up_at = snapshot.level_up_at;
+        self.seed = snapshot.seed;
+        self.state_hash = snapshot.state_hash;
+        self.rng = snapshot.rng.clone();
+        self.holes = snapshot.holes.clone();
+        self.adaptive = snapshot.adaptive.clone();
+        self.turn_start = snapshot.turn_start.clone();
+        self.undo_point = snapshot.undo_point.clone();
+        self.replay.events.truncate(snapshot.replay_len);
+        self.replay_origin = at(snapshot.elapsed);
+        self.drop_timer = at(snapshot.drop_timer);
+        self.countdown_timer = at(snapshot.countdown_timer);
+        self.game_timer = snapshot.game_timer.map(at);
+        self.ground_timer = snapshot.ground_timer.map(at);
+        self.spawned_at = at(snapshot.spawned_at);
+        self.paused_at = snapshot.paused_at.map(at);
+        for &(direction, held) in &snapshot.directions {
+            if let Some(state) = self.input_state.directions.get_mut(&direction) {
+                state.pressed = held.pressed;
+                state.das_timer = at(held.das_timer);
+                state.arr_timer = at(held.arr_timer);
+                state.das_charged = held.das_charged;
+                state.initial_move_done = held.initial_move_done;
+                state.last_update = at(held.last_update);
+            }
+        }
+        self.input_state.last_horizontal_dir = snapshot.last_horizontal_dir;
+        self.events.clear(); // Reports from the future that was undone
+    }
+
+    // The timers behind gravity, lock delay and auto-shift as of the clock,
+    // for the frame-step readout
+    pub fn timer_report(&self) -> Vec<(&'static str, String)> {
+        let ms = |since: Instant| self.clock.saturating_duration_since(since).as_millis();
+        let mut report = vec![
+            ("clock", format!("{:.3}s", self.replay_elapsed().as_secs_f64())),
+            ("state", format!("{:?}", self.game_state)),
+            ("gravity", format!("{}/{}ms", ms(self.drop_timer), self.drop_delay().as_millis())),
+            ("lock", self.ground_timer.map_or("-".to_string(), |ground| format!("{}/{}ms", ms(ground), self.lock_delay().as_millis()))),
         ];
+        for (label, direction) in [("left", InputDirection::Left), ("right", InputDirection::Right), ("down", InputDirection::Down)] {
+            let value = match self.input_state.directions.get(&direction) {
+                Some(state) if state.pressed => format!(
+                    "das {}/{}ms{} arr {}/{}ms",
+                    ms(state.das_timer),
+                    self.timings.das_ms,
+                    if state.das_charged { " charged" } else { "" },
+                    ms(state.arr_timer),
+                    self.timings.arr_ms,
+                ),
+                _ => "-".to_string(),
+            };
+            report.push((label, value));
+        }
+        report
+    }
+
+    // Reseed and rebuild the board and queue. Everything random about a run
+    // comes from the seed, in this order, so a replay can rebuild it exactly.
+    pub fn prepare_run(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+        self.holes = self.garbage_rules.map(|rules| HoleGenerator::new(rules, seed));
+        if let Some(adaptive) = self.adaptive.as_mut() {
+            *adaptive = AdaptiveGravity::new();
+        }
+        self.next_pieces.clear();
+        self.piece_bag.clear();
+        self.pieces_drawn = 0;
+        self.state_hash = STATE_HASH_START;
+        self.events.clear();
         
-        // Shuffle the bag using Fisher-Yates shuffle
-        let mut rng = rand::thread_rng();
-        for i in (1..self.piece_bag.len()).rev() {
-            let j = rng.gen_range(0..=i);
-            self.piece_bag.swap(i, j);
+        self.setup_board();
+        
+        // Initialize the next pieces queue with 5 pieces
+        self.fill_next_pieces();
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        let at = self.get_current_time().unwrap_or_default();
+        self.events.push(TimedEvent { at, event });
+    }
+
+    // Hand over everything reported since the last call
+    pub fn drain_events(&mut self) -> Vec<TimedEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn record(&mut self, input: InputEvent) {
+        let paused = self.game_state == GameState::Paused || self.paused_at.is_some();
+        let resuming = paused && input == InputEvent::Resume;
+        if matches!(self.game_state, GameState::Countdown(_) | GameState::Playing) && !paused || resuming {
+            let at_nanos = self.clock.duration_since(self.replay_origin).as_nanos() as u64;
+            self.replay.events.push(ReplayEvent { at_nanos, input });
+            self.actions.count(input);
         }
     }
-    
-    fn get_next_piece_type(&mut self) -> PieceType {
+
+    pub fn apply_input(&mut self, input: InputEvent) {
+        self.advance_clock();
+        self.record(input);
+        
+        let now = self.clock;
+        match input {
+            InputEvent::Tick => {}
+            InputEvent::Press(dir) => self.input_state.press_direction(dir, now),
+            InputEvent::Release(dir) => self.input_state.release_direction(dir, now),
+            InputEvent::KeyActivity(dir) => self.input_state.update_key_activity(dir, now),
+            InputEvent::ReleaseAll => self.input_state.release_all(now),
+            InputEvent::RotateCw => {
+                self.rotate_piece();
+            }
+            InputEvent::RotateCcw => {
+                self.rotate_piece_left();
+            }
+            InputEvent::Rotate180 => {
+                self.rotate_piece_180();
+            }
+            InputEvent::HardDrop => self.hard_drop(),
+            InputEvent::Hold => self.hold_piece(),
+            InputEvent::Undo => self.undo(),
+            InputEvent::Pause => self.pause(),
+            InputEvent::Resume => self.resume(),
+            InputEvent::Garbage(rows) => {
+                if self.game_state == GameState::Playing {
+                    self.push_garbage(rows);
+                }
+            }
+        }
+    }
+
+    // A player action, from the keyboard bindings or anywhere else. Held
+    // movement keeps going until release_action.
+    pub fn press_action(&mut self, action: GameAction) {
+        if let Some(dir) = action.direction() {
+            if self.input_state.is_pressed(dir) {
+                self.apply_input(InputEvent::KeyActivity(dir));
+            } else {
+                self.apply_input(InputEvent::Press(dir));
+            }
+            return;
+        }
+        match action {
+            GameAction::RotateCw => self.apply_input(InputEvent::RotateCw),
+            GameAction::RotateCcw => self.apply_input(InputEvent::RotateCcw),
+            GameAction::Rotate180 => self.apply_input(InputEvent::Rotate180),
+            GameAction::HardDrop => match self.game_state {
+                GameState::Ready => self.start_countdown(),
+                GameState::Playing => self.apply_input(InputEvent::HardDrop),
+                _ => {}
+            },
+            GameAction::Hold => self.apply_input(InputEvent::Hold),
+            GameAction::Undo => self.apply_input(InputEvent::Undo),
+            GameAction::CycleMode => self.cycle_mode(),
+            GameAction::Restart => self.reset(),
+            // Same as the terminal losing and regaining focus
+            GameAction::Pause if self.game_state == GameState::Paused => self.focus_gained(),
+            GameAction::Pause => self.focus_lost(),
+            GameAction::MoveLeft | GameAction::MoveRight | GameAction::SoftDrop => {}
+        }
+    }
+
+    pub fn release_action(&mut self, action: GameAction) {
+        if let Some(dir) = action.direction() {
+            self.apply_input(InputEvent::Release(dir));
+        }
+    }
+
+    // Build the starting board for the current mode
+    fn setup_board(&mut self) {
+        self.board = empty_board();
+        self.garbage_remaining = 0;
+        self.garbage_cleared = 0;
+        self.pieces_since_dig = 0;
+        self.next_garbage_at = survival_interval(Duration::ZERO);
+        self.next_mystery_at = Duration::from_secs(MYSTERY_INTERVAL_SECS);
+        self.mystery = None;
+
+        if let (GameMode::Puzzle, Some(puzzle)) = (self.mode, &self.puzzle) {
+            self.board = puzzle.board();
+        }
+
+        if self.mode == GameMode::Dig {
+            self.add_garbage(DIG_GARBAGE_ROWS);
+        }
+        if self.handicap.garbage_rows > 0 {
+            self.add_garbage(self.handicap.garbage_rows);
+        }
+    }
+
+    // Survival: push up a garbage row whenever one is due
+    fn rise_garbage(&mut self) {
+        if self.mode != GameMode::Survival {
+            return;
+        }
+        let elapsed = self.get_current_time().unwrap_or_default();
+        while elapsed >= self.next_garbage_at && self.game_state == GameState::Playing {
+            self.push_garbage(1);
+            self.next_garbage_at += survival_interval(self.next_garbage_at);
+        }
+    }
+
+    // A score attack ends when its two minutes are up
+    fn check_time_limit(&mut self) {
+        if self.mode != GameMode::Ultra {
+            return;
+        }
+        if self.get_current_time().is_some_and(|elapsed| elapsed >= Duration::from_secs(ULTRA_SECS)) {
+            self.finish(EndReason::Completed);
+        }
+    }
+
+    // Mystery: end the lasting event once its time is up, and set off the
+    // next one when it's due
+    fn trigger_mystery(&mut self) {
+        if self.mode != GameMode::Mystery {
+            return;
+        }
+        let elapsed = self.get_current_time().unwrap_or_default();
+        if self.mystery.is_some() && elapsed >= self.mystery_until {
+            self.mystery = None;
+        }
+        if elapsed < self.next_mystery_at {
+            return;
+        }
+        self.next_mystery_at += Duration::from_secs(MYSTERY_INTERVAL_SECS);
+
+        let mystery = Mystery::ALL[self.rng.gen_range(0..Mystery::ALL.len())];
+        self.emit(GameEvent::Mystery(mystery));
+        if mystery.lasts() {
+            self.mystery = Some(mystery);
+            self.mystery_until = elapsed + Duration::from_secs(MYSTERY_EFFECT_SECS);
+        }
+        match mystery {
+            Mystery::Mirror => {
+                self.board.iter_mut().for_each(|row| row.reverse());
+                if !self.make_room(BUFFER_HEIGHT as i32) {
+                    self.top_out();
+                }
+            }
+            Mystery::BonusGarbage => self.push_garbage(MYSTERY_GARBAGE_ROWS),
+            Mystery::GiantPiece => {
+                if let Some(next) = self.next_pieces.first_mut() {
+                    *next = Piece::big(next.piece_type);
+                }
+            }
+            Mystery::HideGhost | Mystery::SpeedUp => {}
+        }
+    }
+
+    // Garbage rising under the stack mid-run. Anything pushed out of the top
+    // of the matrix tops out. More rows than the matrix holds is the same as
+    // a full matrix of them.
+    fn push_garbage(&mut self, rows: u32) {
+        let rows = rows.min(BOARD_HEIGHT as u32);
+        let overflowing = self.board[..rows as usize].iter().flatten().any(|&cell| cell != Cell::Empty);
+        self.add_garbage(rows);
+        if !self.make_room(rows as i32) || overflowing {
+            self.top_out();
+        }
+    }
+
+    // After the stack moved under it, move the falling piece up (at most
+    // `rows`) until it's clear again. False if it can't be.
+    fn make_room(&mut self, rows: i32) -> bool {
+        let board = self.board;
+        let Some(piece) = self.current_piece.as_mut() else {
+            return true;
+        };
+        for _ in 0..rows {
+            if fits(&board, piece) {
+                break;
+            }
+            piece.y -= 1;
+        }
+        fits(&board, piece)
+    }
+
+    // Survival: time between garbage rows right now
+    pub fn garbage_interval(&self) -> Duration {
+        survival_interval(self.get_current_time().unwrap_or_default())
+    }
+
+    fn add_garbage(&mut self, rows: u32) {
+        let mut last_hole = None;
+        
+        for _ in 0..rows {
+            // Shift the board up to make room at the bottom
+            for y in 0..BOARD_HEIGHT - 1 {
+                self.board[y] = self.board[y + 1];
+            }
+            
+            self.board[BOARD_HEIGHT - 1] = match self.holes.as_mut() {
+                Some(holes) => wide_garbage_row(holes.take()),
+                None => {
+                    // The original messy garbage off the piece RNG: never put
+                    // two holes in the same column in a row
+                    let mut hole = self.rng.gen_range(0..BOARD_WIDTH);
+                    while Some(hole) == last_hole {
+                        hole = self.rng.gen_range(0..BOARD_WIDTH);
+                    }
+                    last_hole = Some(hole);
+                    garbage_row(hole)
+                }
+            };
+            self.garbage_remaining += 1;
+        }
+    }
+
+    // Survival: columns the next garbage row will leave open, when they're
+    // known ahead
+    pub fn next_garbage_hole(&self) -> Option<Range<usize>> {
+        match (self.mode, &self.holes) {
+            (GameMode::Survival, Some(holes)) => Some(holes.peek()),
+            _ => None,
+        }
+    }
+
+    pub fn set_adaptive_gravity(&mut self, on: bool) {
+        self.adaptive = on.then(AdaptiveGravity::new);
+    }
+
+    pub fn set_garbage_rules(&mut self, rules: GarbageRules) {
+        self.garbage_rules = Some(rules);
+        self.prepare_run(self.seed);
+    }
+
+    // Deal these pieces first from now on, starting with a fresh run
+    pub fn set_queue(&mut self, queue: Vec<PieceType>) {
+        self.queue = queue;
+        self.prepare_run(self.seed);
+    }
+
+    // Turn big pieces on or off from the next run on
+    pub fn set_big_pieces(&mut self, big_pieces: bool) {
+        self.big_pieces = big_pieces;
+        self.prepare_run(self.seed);
+    }
+
+    // Versus: play under this handicap from the next run on
+    pub fn set_handicap(&mut self, handicap: Handicap) {
+        self.handicap = handicap;
+        self.prepare_run(self.seed);
+    }
+
+    pub fn cycle_mode(&mut self) {
+        self.set_mode(self.mode.next());
+    }
+
+    pub fn set_mode(&mut self, mode: GameMode) {
+        if self.game_state != GameState::Ready {
+            return;
+        }
+        self.mode = mode;
+        self.goal = None; // Custom modes are picked from the command line, not the mode cycle
+        self.prepare_run(self.next_seed());
+    }
+
+    fn next_seed(&self) -> u64 {
+        self.fixed_seed.unwrap_or_else(|| rand::thread_rng().gen())
+    }
+
+    // Zen never ends: topping out just clears the board and play carries on.
+    // Survival can only end this way, so there it completes the run.
+    pub fn top_out(&mut self) {
+        match self.mode {
+            GameMode::Zen => {
+                self.board = empty_board();
+                if let Some(adaptive) = self.adaptive.as_mut() {
+                    adaptive.reset();
+                }
+                self.emit(GameEvent::BoardReset);
+            }
+            GameMode::Survival => self.finish(EndReason::Completed),
+            _ => self.finish(EndReason::ToppedOut),
+        }
+    }
+
+    // Put back a saved board and counters, e.g. resuming Zen from disk
+    pub fn restore_progress(&mut self, board: Board, lines: u32, points: u64, pieces: u32) {
+        self.board = board;
+        self.lines_cleared = lines;
+        self.scoring.points = points;
+        self.pieces_placed = pieces;
+    }
+
+    // Versus: start over on the other player's run, with their mode, seed
+    // and countdown
+    pub fn follow_run(&mut self, replay: &Replay) {
+        self.mode = replay.mode;
+        self.goal = None;
+        self.countdown_steps = replay.countdown;
+        let fixed_seed = self.fixed_seed.replace(replay.seed);
+        self.reset();
+        self.fixed_seed = fixed_seed;
+    }
+
+    // Versus: the other player's run ended first and settled the match. A
+    // loss ends this run as a top out, since there's no finishing it now.
+    pub fn end_match(&mut self, won: bool) {
+        if self.game_state == GameState::Playing {
+            self.finish(if won { EndReason::Completed } else { EndReason::ToppedOut });
+        }
+    }
+
+    // Versus: play an input that belonged at `at` (time since the countdown)
+    // but turned up late. The game goes back to `snapshot`, taken before
+    // then, takes the input at its time and replays everything recorded
+    // since on top of it. Reports from the replayed stretch are dropped, as
+    // they've been shown once already.
+    pub fn insert_input(&mut self, snapshot: &GameSnapshot, at: Duration, input: InputEvent) {
+        self.advance_clock();
+        let (origin, now, simulated) = (self.replay_origin, self.clock, self.simulated);
+        let later = self.replay.events.split_off(snapshot.replay_len.min(self.replay.events.len()));
+        self.simulated = true;
+        self.clock = origin + snapshot.elapsed;
+        self.restore(snapshot);
+        
+        let at_nanos = at.as_nanos() as u64;
+        let mut pending = Some(input);
+        for event in &later {
+            if event.at_nanos >= at_nanos {
+                if let Some(input) = pending.take() {
+                    self.clock = origin + at;
+                    self.apply_input(input);
+                }
+            }
+            apply_event(self, origin, event);
+        }
+        if let Some(input) = pending {
+            self.clock = origin + at;
+            self.apply_input(input);
+        }
+        
+        self.clock = now;
+        self.simulated = simulated;
+        self.events.clear();
+    }
+
+    fn finish(&mut self, reason: EndReason) {
+        self.game_state = GameState::Finished;
+        self.end_reason = Some(reason);
+        if let Some(start_time) = self.game_timer {
+            self.final_time = Some(self.clock.duration_since(start_time));
+        }
+        self.emit(GameEvent::Finished(reason));
+    }
+
+    // Pieces dealt before the bag: a puzzle's own, otherwise the practice queue
+    fn fixed_queue(&self) -> &[PieceType] {
+        match (self.mode, &self.puzzle) {
+            (GameMode::Puzzle, Some(puzzle)) => &puzzle.queue,
+            _ => &self.queue,
+        }
+    }
+
+    // None once a puzzle has dealt all its pieces; there's no bag after them
+    fn get_next_piece_type(&mut self) -> Option<PieceType> {
+        if let Some(&piece_type) = self.fixed_queue().get(self.pieces_drawn as usize) {
+            self.pieces_drawn += 1;
+            return Some(piece_type);
+        }
+        if self.mode == GameMode::Puzzle {
+            return None;
+        }
         if self.piece_bag.is_empty() {
-            self.fill_bag();
+            self.piece_bag = shuffled_bag(&mut self.rng);
+        }
+        self.pieces_drawn += 1;
+        self.piece_bag.pop()
+    }
+
+    // Piece types the player has yet to receive from the bag they're currently in.
+    // The preview queue is drawn ahead of the player, so the rest of the bag is
+    // split between the front of the queue and whatever the randomizer still holds.
+    // Bags only start once a fixed queue has run out.
+    pub fn bag_remaining(&self) -> Vec<PieceType> {
+        let bag_size = PieceType::ALL.len();
+        let dealt = self.pieces_drawn as usize - self.next_pieces.len();
+        let fixed = self.fixed_queue().len();
+        let queued = fixed.saturating_sub(dealt); // Preview pieces still from the queue
+        let received = dealt.saturating_sub(fixed);
+        if self.pieces_drawn as usize <= fixed {
+            return PieceType::ALL.to_vec(); // First bag not opened yet
         }
-        self.piece_bag.pop().unwrap()
+        let remaining = if received == 0 {
+            bag_size
+        } else {
+            (bag_size - received % bag_size) % bag_size
+        };
+        
+        self.next_pieces
+            .iter()
+            .skip(queued)
+            .take(remaining)
+            .map(|piece| piece.piece_type)
+            .chain(
+                self.piece_bag
+                    .iter()
+                    .rev()
+                    .take(remaining.saturating_sub(self.next_pieces.len() - queued))
+                    .copied(),
+            )
+            .collect()
     }
     
     fn fill_next_pieces(&mut self) {
         while self.next_pieces.len() < 5 {
-            let piece_type = self.get_next_piece_type();
-            self.next_pieces.push(Piece::new(piece_type));
+            let Some(piece_type) = self.get_next_piece_type() else {
+                break;
+            };
+            // Puzzle targets are sized for normal pieces
+            let big = self.big_pieces && self.mode != GameMode::Puzzle;
+            let mut piece = if big && self.rng.gen_bool(BIG_PIECE_CHANCE) {
+                Piece::big(piece_type)
+            } else {
+                Piece::new(piece_type)
+            };
+            if self.mode == GameMode::Ultra {
+                piece.bonus = self.rng.gen_bool(BONUS_PIECE_CHANCE);
+            }
+            self.next_pieces.push(piece);
         }
     }
 
     pub fn start_countdown(&mut self) {
         if self.game_state == GameState::Ready {
-            self.game_state = GameState::Countdown(2);
-            self.countdown_timer = Instant::now();
+            self.advance_clock();
+            self.begin_countdown();
         }
     }
 
+    // Enter the countdown and start recording a fresh replay
+    pub fn begin_countdown(&mut self) {
+        self.runs += 1;
+        self.input_state.release_all(self.clock);
+        self.game_state = GameState::Countdown(self.countdown_steps);
+        self.countdown_timer = self.clock;
+        
+        self.replay_origin = self.clock;
+        self.replay = Replay {
+            mode: self.mode,
+            seed: self.seed,
+            countdown: self.countdown_steps,
+            keyboard_enhancement: self.input_state.keyboard_enhancement_active,
+            release_guess: self.input_state.release_guess,
+            events: Vec::new(),
+            player: self.player.clone(),
+            queue: self.queue.clone(),
+            curve: self.curve.clone(),
+            big_pieces: self.big_pieces,
+            blind: self.blind,
+            garbage: self.garbage_rules,
+            goal: self.goal.clone(),
+            adaptive_gravity: self.adaptive.is_some(),
+            timings: self.timings,
+            exact_repeat: self.exact_repeat,
+            handicap: self.handicap,
+            puzzle: self.puzzle.clone().filter(|_| self.mode == GameMode::Puzzle),
+        };
+    }
+
+    // Called when the terminal loses focus. Keys released while unfocused never
+    // reach us, so drop every held direction; untimed modes also pause.
+    pub fn focus_lost(&mut self) {
+        self.apply_input(InputEvent::ReleaseAll);
+        
+        if self.game_state == GameState::Playing && !self.mode.is_timed() && !self.versus {
+            self.apply_input(InputEvent::Pause);
+        }
+    }
+
+    pub fn focus_gained(&mut self) {
+        if self.game_state == GameState::Paused || self.paused_at.is_some() {
+            self.apply_input(InputEvent::Resume);
+        }
+    }
+
+    // Pausing and resuming go through the replay, so a replay sees the same
+    // gap in play and doesn't let gravity or the clock run through it. A
+    // countdown pauses where it stands, holding its number.
+    fn pause(&mut self) {
+        match self.game_state {
+            GameState::Playing => {
+                self.game_state = GameState::Paused;
+                self.paused_at = Some(self.clock);
+            }
+            GameState::Countdown(_) if self.paused_at.is_none() => self.paused_at = Some(self.clock),
+            _ => {}
+        }
+    }
+
+    fn resume(&mut self) {
+        if let GameState::Countdown(_) = self.game_state {
+            if let Some(paused_at) = self.paused_at.take() {
+                self.countdown_timer += self.clock.duration_since(paused_at);
+            }
+            return;
+        }
+        if self.game_state != GameState::Paused {
+            return;
+        }
+        
+        // Shift every running timer forward so the pause doesn't count
+        if let Some(paused_at) = self.paused_at.take() {
+            let paused_for = self.clock.duration_since(paused_at);
+            self.drop_timer += paused_for;
+            if let Some(ref mut start_time) = self.game_timer {
+                *start_time += paused_for;
+            }
+            if let Some(ref mut ground_time) = self.ground_timer {
+                *ground_time += paused_for;
+            }
+        }
+        self.game_state = GameState::Playing;
+    }
+
     pub fn start_game(&mut self) {
         self.game_state = GameState::Playing;
-        self.game_timer = Some(Instant::now());
+        self.game_timer = Some(self.clock);
         self.spawn_piece();
+        self.mark_turn_start();
+    }
+
+    // Zen: remember the game as the piece in play came out, so its placement
+    // can be taken back. Saved without its own earlier states, so only the
+    // last placement can be undone.
+    fn mark_turn_start(&mut self) {
+        if self.mode != GameMode::Zen || self.game_state != GameState::Playing {
+            return;
+        }
+        let mut start = self.snapshot();
+        start.turn_start = None;
+        start.undo_point = None;
+        self.turn_start = Some(Box::new(start));
+    }
+
+    // Zen: take back the last placement, with its piece back at the top.
+    // The run's clock, replay and held keys carry on as they were, and the
+    // placement counts as a misdrop.
+    fn undo(&mut self) {
+        if self.mode != GameMode::Zen || self.game_state != GameState::Playing {
+            return;
+        }
+        let Some(point) = self.undo_point.take() else {
+            return;
+        };
+        let events = std::mem::take(&mut self.replay.events);
+        let (replay_origin, game_timer) = (self.replay_origin, self.game_timer);
+        let input_state = self.input_state.clone();
+        let actions = self.actions;
+        self.restore(&point);
+        self.replay.events = events;
+        self.replay_origin = replay_origin;
+        self.game_timer = game_timer;
+        self.input_state = input_state;
+        self.actions = actions;
+        self.turn_start = Some(point);
+        self.drop_timer = self.clock;
+        self.misdrops += 1;
+        self.emit(GameEvent::Misdrop);
     }
 
     pub fn spawn_piece(&mut self) {
@@ -111,34 +1083,43 @@ impl Game {
             
             // Refill the queue to maintain 5 pieces
             self.fill_next_pieces();
+        } else if let Some(held) = self.hold_piece.take() {
+            // A puzzle out of new pieces still has the held one to play
+            self.current_piece = Some(held);
         }
         
         self.can_hold = true; // Reset hold ability when spawning new piece
         self.ground_timer = None; // Reset ground timer
         
+        self.enter_current_piece();
+    }
+
+    // A new piece that overlaps the stack is a block out; otherwise it drops
+    // one row straight away, as guideline spawns do
+    fn enter_current_piece(&mut self) {
+        self.spawned_at = self.clock;
         if let Some(ref piece) = self.current_piece {
             if !self.is_valid_position(piece) {
-                self.game_state = GameState::Finished;
-                if let Some(start_time) = self.game_timer {
-                    self.final_time = Some(start_time.elapsed());
-                }
+                self.top_out();
+                return;
+            }
+            
+            let mut dropped = piece.clone();
+            dropped.y += 1;
+            if self.is_valid_position(&dropped) {
+                self.current_piece = Some(dropped);
             }
         }
     }
 
     pub fn is_valid_position(&self, piece: &Piece) -> bool {
-        for (x, y) in piece.get_blocks() {
-            if x < 0 || x >= BOARD_WIDTH as i32 || y >= BOARD_HEIGHT as i32 {
-                return false;
-            }
-            if y >= 0 && self.board[y as usize][x as usize] != Cell::Empty {
-                return false;
-            }
-        }
-        true
+        fits(&self.board, piece)
     }
 
     pub fn get_ghost_piece(&self) -> Option<Piece> {
+        if self.mystery == Some(Mystery::HideGhost) || self.blind.ghost {
+            return None;
+        }
         if let Some(ref current_piece) = self.current_piece {
             let mut ghost = current_piece.clone();
             
@@ -183,7 +1164,7 @@ impl Game {
                 
                 // If moving down failed, start ground timer
                 if dy > 0 && self.ground_timer.is_none() {
-                    self.ground_timer = Some(Instant::now());
+                    self.ground_timer = Some(self.clock);
                 }
             }
         }
@@ -191,88 +1172,41 @@ impl Game {
     }
 
     pub fn rotate_piece(&mut self) -> bool {
-        if let Some(ref piece) = self.current_piece {
-            let rotated = piece.rotate_clockwise();
-            
-            // Try basic rotation
-            if self.is_valid_position(&rotated) {
-                self.current_piece = Some(rotated);
-                return true;
-            }
-            
-            // Try wall kicks
-            let kicks = match piece.piece_type {
-                PieceType::I => vec![(1, 0), (-1, 0), (2, 0), (-2, 0), (0, -1)],
-                _ => vec![(1, 0), (-1, 0), (0, -1), (1, -1), (-1, -1)],
-            };
-            
-            for (kick_x, kick_y) in kicks {
-                let mut kicked = rotated.clone();
-                kicked.x += kick_x;
-                kicked.y += kick_y;
-                
-                if self.is_valid_position(&kicked) {
-                    self.current_piece = Some(kicked);
-                    return true;
-                }
-            }
-        }
-        false
+        self.try_rotate(Piece::rotate_clockwise)
     }
 
     pub fn rotate_piece_left(&mut self) -> bool {
-        if let Some(ref piece) = self.current_piece {
-            let rotated = piece.rotate_counter_clockwise();
-            
-            // Try basic rotation
-            if self.is_valid_position(&rotated) {
-                self.current_piece = Some(rotated);
-                return true;
-            }
-            
-            // Try wall kicks
-            let kicks = match piece.piece_type {
-                PieceType::I => vec![(1, 0), (-1, 0), (2, 0), (-2, 0), (0, -1)],
-                _ => vec![(1, 0), (-1, 0), (0, -1), (1, -1), (-1, -1)],
-            };
-            
-            for (kick_x, kick_y) in kicks {
-                let mut kicked = rotated.clone();
-                kicked.x += kick_x;
-                kicked.y += kick_y;
-                
-                if self.is_valid_position(&kicked) {
-                    self.current_piece = Some(kicked);
-                    return true;
-                }
-            }
-        }
-        false
+        self.try_rotate(Piece::rotate_counter_clockwise)
     }
 
     pub fn rotate_piece_180(&mut self) -> bool {
+        self.try_rotate(Piece::rotate_180)
+    }
+
+    fn try_rotate(&mut self, rotate: fn(&Piece) -> Piece) -> bool {
+        if self.game_state != GameState::Playing {
+            return false;
+        }
+        
         if let Some(ref piece) = self.current_piece {
-            let rotated = piece.rotate_180();
-            
-            // Try basic rotation
-            if self.is_valid_position(&rotated) {
-                self.current_piece = Some(rotated);
-                return true;
+            // O looks the same in every orientation: no kick, no lock delay reset
+            if piece.piece_type == PieceType::O {
+                return false;
             }
             
-            // Try wall kicks (same as regular rotation)
-            let kicks = match piece.piece_type {
-                PieceType::I => vec![(1, 0), (-1, 0), (2, 0), (-2, 0), (0, -1)],
-                _ => vec![(1, 0), (-1, 0), (0, -1), (1, -1), (-1, -1)],
-            };
+            let rotated = rotate(piece);
             
-            for (kick_x, kick_y) in kicks {
+            // Try the kick table for this rotation in order; the first entry is (0, 0)
+            for (kick_x, kick_y) in kicks(piece.piece_type, piece.rotation, rotated.rotation) {
                 let mut kicked = rotated.clone();
-                kicked.x += kick_x;
-                kicked.y += kick_y;
+                kicked.x += kick_x * piece.scale();
+                kicked.y += kick_y * piece.scale();
                 
                 if self.is_valid_position(&kicked) {
                     self.current_piece = Some(kicked);
+                    
+                    // Like horizontal moves, a successful rotation resets the ground timer
+                    self.ground_timer = None;
                     return true;
                 }
             }
@@ -281,7 +1215,11 @@ impl Game {
     }
 
     pub fn hold_piece(&mut self) {
-        if !self.can_hold || self.game_state != GameState::Playing {
+        if !self.can_hold || self.handicap.no_hold || self.game_state != GameState::Playing {
+            return;
+        }
+        // Holding with nothing to swap in would leave no piece in play
+        if self.hold_piece.is_none() && self.next_pieces.is_empty() {
             return;
         }
         
@@ -297,104 +1235,277 @@ impl Game {
                 }
             }
             
-            // Reset the held piece to spawn position
-            let mut held_piece = current;
-            held_piece.x = (BOARD_WIDTH as i32 - 4) / 2;
-            held_piece.y = 0;
-            self.hold_piece = Some(held_piece);
+            // Reset the held piece to its spawn position and orientation
+            self.hold_piece = Some(current.respawned());
+            self.emit(GameEvent::Hold(current.piece_type));
             
             self.can_hold = false; // Can't hold again until next spawn
             
-            // Check if new current piece is valid
-            if let Some(ref piece) = self.current_piece {
-                if !self.is_valid_position(piece) {
-                    self.game_state = GameState::Finished;
-                    if let Some(start_time) = self.game_timer {
-                        self.final_time = Some(start_time.elapsed());
-                    }
-                }
-            }
+            self.enter_current_piece();
         }
     }
 
+    // Move the piece down one row, reporting the drop to scoring
+    pub fn drop_piece(&mut self, kind: DropKind) -> bool {
+        let moved = self.move_piece(0, 1);
+        if moved && self.is_scored() {
+            self.scoring.record_drop(kind, 1);
+        }
+        moved
+    }
+
     pub fn hard_drop(&mut self) {
-        while self.move_piece(0, 1) {}
+        let mut rows = 0;
+        while self.drop_piece(DropKind::Hard) {
+            rows += 1;
+        }
+        self.emit(GameEvent::HardDrop(rows));
         self.lock_piece();
     }
 
     pub fn lock_piece(&mut self) {
+        let holes_before = covered_holes(&self.board);
+        self.undo_point = self.turn_start.take();
+        
+        // Lock out: the piece came to rest entirely above the visible field
+        let locked_out = self.current_piece.as_ref().is_some_and(|piece| {
+            piece.get_blocks().iter().all(|&(_, y)| y < BUFFER_HEIGHT as i32)
+        });
+        
+        // Graded on the board it was placed on; the AI only plays normal pieces
+        if let Some(piece) = self.current_piece.as_ref().filter(|piece| !piece.big && self.mode != GameMode::Puzzle) {
+            self.grades.push(heuristic::grade(&self.board, piece));
+        }
+        self.lock_times.push(self.get_current_time().unwrap_or_default());
+        
+        let blocks = self.current_piece.as_ref().map(Piece::get_blocks).unwrap_or_default();
+        let bonus_piece = self.current_piece.as_ref().is_some_and(|piece| piece.bonus);
         if let Some(ref piece) = self.current_piece {
-            for (x, y) in piece.get_blocks() {
+            for &(x, y) in &blocks {
                 if y >= 0 && y < BOARD_HEIGHT as i32 && x >= 0 && x < BOARD_WIDTH as i32 {
                     self.board[y as usize][x as usize] = Cell::Filled(piece.color);
                 }
             }
         }
         
-        self.current_piece = None;
-        let lines = self.clear_lines();
-        self.update_lines(lines);
+        self.last_locked = self.current_piece.take();
+        self.pieces_placed += 1;
+        self.scoring.piece_locked();
+        // A bonus piece counts if any row it completes is part of the clear
+        let bonus = bonus_piece && blocks.iter().any(|&(_, y)| {
+            y >= 0 && (y as usize) < BOARD_HEIGHT && self.board[y as usize].iter().all(|&cell| cell != Cell::Empty)
+        });
+        let full_rows: Vec<usize> = (0..BOARD_HEIGHT).filter(|&y| self.board[y].iter().all(|&cell| cell != Cell::Empty)).collect();
+        let (lines, garbage_lines) = self.clear_lines();
+        
+        // A placement that leaves more covered holes than before is a misdrop
+        if covered_holes(&self.board) > holes_before {
+            self.misdrops += 1;
+            self.emit(GameEvent::Misdrop);
+        }
+        if lines > 0 {
+            self.emit(GameEvent::LinesCleared(lines));
+            if bonus {
+                self.emit(GameEvent::BonusClear);
+            }
+            if self.board.iter().flatten().all(|&cell| cell == Cell::Empty) {
+                self.emit(GameEvent::PerfectClear);
+            }
+        }
+        
+        let points = self.scoring.points;
+        self.update_lines(lines, garbage_lines, bonus);
+        if lines > 0 && self.scoring.points > points {
+            let middle = full_rows[full_rows.len() / 2];
+            self.emit(GameEvent::Scored(self.scoring.points - points, middle));
+        }
+        if self.mode == GameMode::Zen {
+            let at = self.get_current_time().unwrap_or_default();
+            let stack_height = column_heights(&self.board).into_iter().max().unwrap_or(0);
+            if let Some(adaptive) = self.adaptive.as_mut() {
+                adaptive.on_lock(at, stack_height);
+            }
+        }
+        if self.mode == GameMode::Puzzle {
+            self.judge_puzzle(&blocks);
+        }
+        
+        if locked_out && self.game_state != GameState::Finished {
+            self.top_out();
+        }
         
         // Reset DAS states when piece locks to prevent new piece from flying away
-        self.input_state.reset_das_states();
+        self.input_state.reset_das_states(self.clock);
         
         self.spawn_piece();
-        self.drop_timer = Instant::now();
+        self.drop_timer = self.clock;
+        
+        let queue = self.current_piece.iter().chain(&self.next_pieces).map(|piece| piece.piece_type);
+        self.state_hash = fold_state_hash(self.state_hash, &self.board, queue);
+        tracing::debug!(
+            piece = self.pieces_placed,
+            simulated = self.simulated,
+            hash = format_args!("{:016x}", self.state_hash),
+            "state hash"
+        );
+        self.mark_turn_start();
     }
 
-    fn clear_lines(&mut self) -> u32 {
-        let mut lines_cleared = 0;
-        let mut write_row = BOARD_HEIGHT - 1;
-        
-        // Start from bottom and work up, copying non-full rows down
-        for read_row in (0..BOARD_HEIGHT).rev() {
-            if !self.board[read_row].iter().all(|&cell| cell != Cell::Empty) {
-                // This row is not full, keep it
-                if read_row != write_row {
-                    self.board[write_row] = self.board[read_row];
-                }
-                if write_row > 0 {
-                    write_row -= 1;
-                }
-            } else {
-                // This row is full, skip it (clear it)
-                lines_cleared += 1;
-            }
+    // Returns (total lines cleared, how many of them were garbage rows)
+    fn clear_lines(&mut self) -> (u32, u32) {
+        // Puzzles are judged on the cells filled, so rows stay put
+        if self.mode == GameMode::Puzzle {
+            return (0, 0);
         }
-        
-        // Fill remaining top rows with empty
-        for row in 0..=write_row {
-            self.board[row] = [Cell::Empty; BOARD_WIDTH];
+        clear_full_rows(&mut self.board)
+    }
+
+    // A puzzle fails as soon as a mino lands outside the target and is
+    // solved once the target is full
+    fn judge_puzzle(&mut self, blocks: &[(i32, i32)]) {
+        let Some(puzzle) = &self.puzzle else {
+            return;
+        };
+        let target = puzzle.target();
+        let outside = blocks.iter().any(|&(x, y)| !target.contains(&(x as usize, y as usize)));
+        if outside {
+            self.finish(EndReason::PuzzleFailed);
+        } else if target.iter().all(|&(x, y)| self.board[y][x] != Cell::Empty) {
+            self.finish(EndReason::Completed);
         }
-        
-        lines_cleared
     }
 
-    fn update_lines(&mut self, lines: u32) {
+    fn update_lines(&mut self, lines: u32, garbage_lines: u32, bonus: bool) {
+        let level = self.level();
         self.lines_cleared += lines;
         self.lines_remaining = self.lines_remaining.saturating_sub(lines);
         
-        // Check if 40L sprint is complete
-        if self.lines_remaining == 0 {
-            self.game_state = GameState::Finished;
-            if let Some(start_time) = self.game_timer {
-                self.final_time = Some(start_time.elapsed());
-            }
+        if self.is_scored() {
+            self.scoring.record_clear(lines, level, bonus);
+        }
+        
+        self.garbage_cleared += garbage_lines;
+        self.garbage_remaining = self.garbage_remaining.saturating_sub(garbage_lines);
+        if garbage_lines > 0 {
+            self.pieces_since_dig = 0;
+        } else {
+            self.pieces_since_dig += 1;
+        }
+        
+        if matches!(self.mode, GameMode::Marathon | GameMode::Classic) && self.level() > level {
+            self.level_up_at = self.get_current_time();
+            self.emit(GameEvent::LevelUp(self.level()));
         }
+        
+        if self.win_condition().is_some_and(|goal| goal.met(self)) {
+            self.finish(EndReason::Completed);
+        } else if self.mode == GameMode::Dig && self.pieces_since_dig >= DIG_PIECE_LIMIT {
+            self.finish(EndReason::DrillFailed);
+        }
+    }
+
+    // The custom mode's goal if there is one, otherwise the mode's own
+    pub fn win_condition(&self) -> Option<Goal> {
+        self.goal.clone().or_else(|| self.mode.win_condition())
     }
 
-    fn get_drop_delay(&self) -> Duration {
-        Duration::from_millis(1000) // Fixed 1 second drop delay for 40L sprint
+    // Custom modes keep score whatever they're based on, so a score goal
+    // has something to count
+    pub fn is_scored(&self) -> bool {
+        self.mode.is_scored() || self.goal.is_some()
     }
 
-    pub fn update(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let now = Instant::now();
+    // Only marathon and classic level up; every other mode stays on level 1
+    pub fn level(&self) -> u32 {
+        match self.mode {
+            GameMode::Marathon => level_for_lines(self.lines_cleared),
+            GameMode::Classic => classic_level(self.lines_cleared),
+            _ => 1,
+        }
+    }
+
+    // Lines still needed to reach the next level, None once there isn't one
+    pub fn lines_to_next_level(&self) -> Option<u32> {
+        match self.mode {
+            GameMode::Marathon => lines_to_next_level(self.lines_cleared),
+            GameMode::Classic => Some(LINES_PER_LEVEL - self.lines_cleared % LINES_PER_LEVEL),
+            _ => None,
+        }
+    }
+
+    pub fn misdrop_rate(&self) -> f64 {
+        if self.pieces_placed == 0 {
+            0.0
+        } else {
+            self.misdrops as f64 / self.pieces_placed as f64
+        }
+    }
+
+    // Placement accuracy so far, once anything has been graded
+    pub fn accuracy(&self) -> Option<f64> {
+        heuristic::accuracy(&self.grades)
+    }
+
+    // Garbage lines cleared per piece placed
+    pub fn dig_efficiency(&self) -> f64 {
+        if self.pieces_placed == 0 {
+            0.0
+        } else {
+            self.garbage_cleared as f64 / self.pieces_placed as f64
+        }
+    }
+
+    // Left alone without a gravity handicap, so every other run falls at
+    // exactly the times it always did
+    pub fn drop_delay(&self) -> Duration {
+        let delay = self.mode_drop_delay();
+        if self.handicap.gravity == 1.0 {
+            return delay;
+        }
+        delay.div_f64(self.handicap.gravity)
+    }
+
+    fn mode_drop_delay(&self) -> Duration {
+        if let (GameMode::Zen, Some(adaptive)) = (self.mode, &self.adaptive) {
+            return adaptive.interval();
+        }
+        match (self.mode, &self.curve) {
+            (GameMode::Marathon, Some(curve)) => curve.gravity_interval(self.level()),
+            (GameMode::Marathon, None) => gravity_interval(self.level()),
+            (GameMode::Classic, _) => classic_gravity_interval(self.level()),
+            _ if self.mystery == Some(Mystery::SpeedUp) => Duration::from_millis(MYSTERY_FAST_DROP_MS),
+            _ => Duration::from_millis(1000), // Fixed 1 second drop delay for 40L sprint
+        }
+    }
+
+    pub fn lock_delay(&self) -> Duration {
+        match (self.mode, &self.curve) {
+            (GameMode::Marathon, Some(curve)) => curve.lock_delay(self.level()),
+            _ => Duration::from_millis(self.timings.lock_delay_ms),
+        }
+    }
+
+    // Share of the lock delay still to run while the piece is on the ground,
+    // from 1.0 as it lands to 0.0 as it locks
+    pub fn lock_left(&self) -> Option<f64> {
+        let ground = self.ground_timer?;
+        let waited = self.clock.duration_since(ground).as_secs_f64();
+        Some((1.0 - waited / self.lock_delay().as_secs_f64()).clamp(0.0, 1.0))
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        self.advance_clock();
+        self.record(InputEvent::Tick);
+        let now = self.clock;
         
         match self.game_state {
             GameState::Ready => {
                 // Waiting for user to start
                 return Ok(());
             }
+            GameState::Countdown(_) if self.paused_at.is_some() => {
+                return Ok(());
+            }
             GameState::Countdown(count) => {
                 if now.duration_since(self.countdown_timer) >= Duration::from_millis(1000) {
                     if count > 1 {
@@ -406,7 +1517,7 @@ impl Game {
                 }
                 return Ok(());
             }
-            GameState::Finished => {
+            GameState::Paused | GameState::Finished => {
                 return Ok(());
             }
             GameState::Playing => {
@@ -414,27 +1525,46 @@ impl Game {
             }
         }
 
-        self.input_state.check_timeouts();
+        self.input_state.check_timeouts(now);
+        self.rise_garbage();
+        self.trigger_mystery();
+        self.check_time_limit();
+        if self.game_state != GameState::Playing {
+            return Ok(());
+        }
 
-        self.handle_movement(InputDirection::Left, -1, 0, now);
-        self.handle_movement(InputDirection::Right, 1, 0, now);
-        self.handle_soft_drop(now);
+        for repeat in self.timings.auto_repeats(self.exact_repeat) {
+            self.auto_repeat(repeat, now);
+        }
 
         // Check ground timer for piece locking
         if let Some(ground_time) = self.ground_timer {
-            if now.duration_since(ground_time) >= Duration::from_millis(GROUND_TIME) {
+            if now.duration_since(ground_time) >= self.lock_delay() {
                 self.lock_piece();
                 return Ok(());
             }
         }
 
-        // Handle gravity drop
-        if now.duration_since(self.drop_timer) >= self.get_drop_delay() {
+        // Handle gravity drop; puzzles have none
+        if self.mode == GameMode::Puzzle {
+            return Ok(());
+        }
+        let delay = self.drop_delay();
+        let elapsed = now.duration_since(self.drop_timer);
+        if elapsed >= delay {
             self.drop_timer = now;
-            if !self.move_piece(0, 1) {
-                // Start ground timer if not already started
-                if self.ground_timer.is_none() {
-                    self.ground_timer = Some(now);
+            // Fast marathon gravity can owe several rows since the last update
+            let rows = match self.mode {
+                GameMode::Marathon => (elapsed.as_nanos() / delay.as_nanos().max(1)).min(BOARD_HEIGHT as u128),
+                _ => 1,
+            };
+            for _ in 0..rows {
+                if !self.drop_piece(DropKind::Gravity) {
+                    // Start ground timer if not already started
+                    if self.ground_timer.is_none() {
+                        self.ground_timer = Some(now);
+                    }
+                    break;
                 }
             }
         }
@@ -442,124 +1572,77 @@ impl Game {
         Ok(())
     }
 
-    fn handle_movement(&mut self, direction: InputDirection, dx: i32, dy: i32, now: Instant) {
-        if let Some(state) = self.input_state.directions.get_mut(&direction) {
-            if state.pressed {
-                let mut should_move = false;
-                
-                if !state.initial_move_done {
-                    should_move = true;
-                    state.initial_move_done = true;
-                } else if !state.das_charged {
-                    if now.duration_since(state.das_timer) >= Duration::from_millis(crate::constants::DAS_DELAY) {
-                        state.das_charged = true;
-                        state.arr_timer = now;
-                        should_move = true;
-                    }
-                } else {
-                    if now.duration_since(state.arr_timer) >= Duration::from_millis(crate::constants::ARR_DELAY) {
-                        state.arr_timer = now;
-                        should_move = true;
-                    }
-                }
-                
-                if should_move {
-                    self.move_piece(dx, dy);
-                }
-            }
-        }
-    }
-
-    fn handle_soft_drop(&mut self, now: Instant) {
-        // Check if down is pressed and get the state info we need
-        let (is_pressed, initial_move_needed, mut timer, delay) = {
-            if let Some(down_state) = self.input_state.directions.get(&InputDirection::Down) {
-                (
-                    down_state.pressed,
-                    !down_state.initial_move_done,
-                    down_state.arr_timer,
-                    Duration::from_millis(crate::constants::SOFT_DROP_DELAY)
-                )
-            } else {
-                return;
-            }
-        };
-        
-        if !is_pressed {
+    // Carry out whatever held movement is due, stopping at the first move
+    // that's blocked
+    fn auto_repeat(&mut self, repeat: AutoRepeat, now: Instant) {
+        let Some(state) = repeat.action.direction().and_then(|dir| self.input_state.directions.get_mut(&dir)) else {
             return;
-        }
-        
-        let mut moves_this_frame = 0;
-        let max_moves_per_frame = 5; // Limit to prevent infinite loops
-        
-        // Handle initial move
-        if initial_move_needed {
-            if self.move_piece(0, 1) {
-                moves_this_frame += 1;
-            } else {
-                // Don't immediately lock - let ground timer handle it
-                if self.ground_timer.is_none() {
-                    self.ground_timer = Some(now);
-                }
-            }
-            
-            // Update state after move
-            if let Some(down_state) = self.input_state.directions.get_mut(&InputDirection::Down) {
-                down_state.initial_move_done = true;
-                down_state.arr_timer = now;
-            }
-            timer = now;
-        }
-        
-        // Continue moving while there's time and space
-        while moves_this_frame < max_moves_per_frame && 
-              now.duration_since(timer) >= delay {
-            timer += delay;
-            
-            if self.move_piece(0, 1) {
-                moves_this_frame += 1;
-            } else {
-                // Don't immediately lock - let ground timer handle it
-                if self.ground_timer.is_none() {
+        };
+        for _ in 0..repeat.due(state, now) {
+            let moved = match repeat.action {
+                GameAction::MoveLeft => self.move_piece(-1, 0),
+                GameAction::MoveRight => self.move_piece(1, 0),
+                GameAction::SoftDrop => self.drop_piece(DropKind::Soft),
+                _ => false,
+            };
+            if !moved {
+                // A blocked soft drop doesn't lock; the ground timer does
+                if repeat.action == GameAction::SoftDrop && self.ground_timer.is_none() {
                     self.ground_timer = Some(now);
                 }
                 break;
             }
         }
-        
-        // Update the timer in the state
-        if let Some(down_state) = self.input_state.directions.get_mut(&InputDirection::Down) {
-            down_state.arr_timer = timer;
-        }
     }
 
     pub fn reset(&mut self) {
-        self.board = empty_board();
+        self.advance_clock();
         self.current_piece = None;
-        self.next_pieces.clear();
-        self.piece_bag.clear();
         self.hold_piece = None;
         self.can_hold = true;
         self.lines_cleared = 0;
         self.lines_remaining = TARGET_LINES;
-        self.drop_timer = Instant::now();
+        self.drop_timer = self.clock;
+        
+        let keyboard_enhancement_active = self.input_state.keyboard_enhancement_active;
+        let release_guess = self.input_state.release_guess;
         self.input_state = InputState::new();
+        self.input_state.keyboard_enhancement_active = keyboard_enhancement_active;
+        self.input_state.release_guess = release_guess;
+        
         self.game_timer = None;
         self.final_time = None;
         self.ground_timer = None;
+        self.end_reason = None;
+        self.paused_at = None;
+        self.pieces_placed = 0;
+        self.last_locked = None;
+        self.misdrops = 0;
+        self.turn_start = None;
+        self.undo_point = None;
+        self.grades.clear();
+        self.lock_times.clear();
+        self.actions = ActionCounts::default();
+        self.scoring = Scoring::new();
+        self.level_up_at = None;
         
-        // Refill the next pieces queue
-        self.fill_next_pieces();
+        // New board and queue from a fresh seed
+        self.prepare_run(self.next_seed());
         
         // Auto-start countdown
-        self.game_state = GameState::Countdown(2);
-        self.countdown_timer = Instant::now();
+        self.begin_countdown();
     }
     
+    // Time since the countdown started, on the same scale as replay timestamps
+    pub fn replay_elapsed(&self) -> Duration {
+        self.clock.duration_since(self.replay_origin)
+    }
+
     pub fn get_current_time(&self) -> Option<Duration> {
         if let Some(start_time) = self.game_timer {
             match self.game_state {
-                GameState::Playing => Some(start_time.elapsed()),
+                GameState::Playing => Some(self.clock.duration_since(start_time)),
+                GameState::Paused => self.paused_at.map(|paused_at| paused_at - start_time),
                 GameState::Finished => self.final_time,
                 _ => None,
             }
@@ -567,4 +1650,167 @@ impl Game {
             None
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::FRAME_POLL_MS;
+    use crate::game::replay;
+
+    // A frame at a time on the simulated clock, like the live loop
+    fn run_to(game: &mut Game, origin: Instant, ms: u64) {
+        let mut at = game.replay_elapsed();
+        while at < Duration::from_millis(ms) {
+            at += Duration::from_millis(FRAME_POLL_MS);
+            game.set_clock(origin + at);
+            game.update().unwrap();
+        }
+    }
+
+    fn play(game: &mut Game, origin: Instant, inputs: &[InputEvent]) {
+        for &input in inputs {
+            game.apply_input(input);
+            let ms = game.replay_elapsed().as_millis() as u64 + 200;
+            run_to(game, origin, ms);
+        }
+    }
+
+    fn piece_types(pieces: &[Piece]) -> Vec<PieceType> {
+        pieces.iter().map(|piece| piece.piece_type).collect()
+    }
+
+    fn placement(piece: &Option<Piece>) -> Option<(PieceType, i32, i32)> {
+        piece.as_ref().map(|piece| (piece.piece_type, piece.x, piece.y))
+    }
+
+    #[test]
+    fn restore_puts_the_run_back_as_it_was() {
+        let origin = Instant::now();
+        let mut game = Game::new_simulated(origin);
+        game.prepare_run(7);
+        game.begin_countdown();
+        run_to(&mut game, origin, 3500);
+        assert_eq!(game.game_state, GameState::Playing);
+        play(&mut game, origin, &[InputEvent::Hold, InputEvent::HardDrop, InputEvent::Press(InputDirection::Left), InputEvent::HardDrop]);
+
+        let snapshot = game.snapshot();
+        let before = game.clone();
+
+        // Far enough on that everything the snapshot holds has changed
+        play(&mut game, origin, &[InputEvent::Hold, InputEvent::HardDrop, InputEvent::HardDrop, InputEvent::HardDrop, InputEvent::HardDrop]);
+        run_to(&mut game, origin, 9000);
+        assert_ne!(game.board, before.board);
+        assert_ne!(game.pieces_drawn, before.pieces_drawn);
+
+        game.set_clock(origin + snapshot.elapsed);
+        game.restore(&snapshot);
+
+        assert_eq!(game.board, before.board);
+        assert_eq!(placement(&game.current_piece), placement(&before.current_piece));
+        assert_eq!(piece_types(&game.next_pieces), piece_types(&before.next_pieces));
+        assert_eq!(game.piece_bag, before.piece_bag);
+        assert_eq!(game.pieces_drawn, before.pieces_drawn);
+        assert_eq!(placement(&game.hold_piece), placement(&before.hold_piece));
+        assert_eq!(game.can_hold, before.can_hold);
+        assert_eq!(game.rng, before.rng);
+        assert_eq!(game.drop_timer, before.drop_timer);
+        assert_eq!(game.countdown_timer, before.countdown_timer);
+        assert_eq!(game.game_timer, before.game_timer);
+        assert_eq!(game.ground_timer, before.ground_timer);
+        assert_eq!(game.spawned_at, before.spawned_at);
+        assert_eq!(game.get_current_time(), before.get_current_time());
+        assert_eq!(game.replay.events.len(), snapshot.replay_len);
+
+        // And goes on from there the same way the original did
+        let mut original = before;
+        let inputs = [InputEvent::HardDrop, InputEvent::Hold, InputEvent::RotateCw, InputEvent::HardDrop];
+        play(&mut game, origin, &inputs);
+        play(&mut original, origin, &inputs);
+        assert_eq!(game.board, original.board);
+        assert_eq!(piece_types(&game.next_pieces), piece_types(&original.next_pieces));
+        assert_eq!(game.rng, original.rng);
+        assert_eq!(game.state_hash, original.state_hash);
+    }
+
+    #[test]
+    fn pauses_hold_a_timed_run_and_replay_the_same() {
+        let origin = Instant::now();
+        let mut game = Game::new_simulated(origin);
+        game.mode = GameMode::Sprint;
+        game.prepare_run(7);
+        game.begin_countdown();
+
+        // Paused mid-countdown, the number holds
+        run_to(&mut game, origin, 1500);
+        game.apply_input(InputEvent::Pause);
+        run_to(&mut game, origin, 6000);
+        assert_eq!(game.game_state, GameState::Countdown(2));
+        game.apply_input(InputEvent::Resume);
+        run_to(&mut game, origin, 8000);
+        assert_eq!(game.game_state, GameState::Playing);
+        assert!(game.get_current_time().unwrap() < Duration::from_millis(1000));
+
+        // Paused in play, the clock and gravity hold
+        play(&mut game, origin, &[InputEvent::HardDrop]);
+        game.apply_input(InputEvent::Pause);
+        let (time, piece) = (game.get_current_time(), placement(&game.current_piece));
+        run_to(&mut game, origin, 20000);
+        assert_eq!(game.get_current_time(), time);
+        assert_eq!(placement(&game.current_piece), piece);
+        game.apply_input(InputEvent::Resume);
+        play(&mut game, origin, &[InputEvent::Hold, InputEvent::HardDrop]);
+
+        // The stopped clock is on the record, which keeps the run from a PB
+        assert!(game.replay.paused());
+        let replayed = replay::simulate(&game.replay);
+        assert_eq!(replayed.game_state, game.game_state);
+        assert_eq!(replayed.get_current_time(), game.get_current_time());
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.state_hash, game.state_hash);
+    }
+
+    #[test]
+    fn more_garbage_than_the_matrix_holds_tops_out() {
+        let origin = Instant::now();
+        let mut game = Game::new_simulated(origin);
+        game.mode = GameMode::Sprint;
+        game.prepare_run(7);
+        game.begin_countdown();
+        run_to(&mut game, origin, 3500);
+        assert_eq!(game.game_state, GameState::Playing);
+
+        play(&mut game, origin, &[InputEvent::HardDrop]);
+
+        // Pushes the stack out of the top, rather than out of bounds
+        game.apply_input(InputEvent::Garbage(u32::MAX));
+        assert_eq!(game.game_state, GameState::Finished);
+        assert_eq!(game.end_reason, Some(EndReason::ToppedOut));
+
+        let replayed = replay::simulate(&game.replay);
+        assert_eq!(replayed.end_reason, Some(EndReason::ToppedOut));
+        assert_eq!(replayed.board, game.board);
+    }
+    #[test]
+    fn a_handicapped_run_replays_under_its_handicap() {
+        let origin = Instant::now();
+        let mut game = Game::new_simulated(origin);
+        game.mode = GameMode::Sprint;
+        game.handicap = Handicap { garbage_rows: 4, gravity: 2.0, no_hold: true, ..Handicap::default() };
+        game.prepare_run(7);
+        let garbage = &game.board[BOARD_HEIGHT - 4..];
+        assert!(garbage.iter().all(|row| row.iter().any(|&cell| cell != Cell::Empty)));
+        assert_eq!(game.drop_delay(), Duration::from_millis(500));
+        game.begin_countdown();
+        run_to(&mut game, origin, 3500);
+
+        play(&mut game, origin, &[InputEvent::Hold, InputEvent::HardDrop, InputEvent::Hold]);
+        assert!(game.hold_piece.is_none());
+
+        // The handicap goes with the replay, as it does to a netplay opponent
+        let json = serde_json::to_string(&game.replay).unwrap();
+        let replayed = replay::simulate(&serde_json::from_str(&json).unwrap());
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.state_hash, game.state_hash);
+    }
 }
\ No newline at end of file
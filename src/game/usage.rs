@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::replay::InputEvent;
+use crate::input::InputDirection;
+
+// How often each action was pressed in a run. Held movement counts once per
+// press, not per auto-repeat step.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct ActionCounts {
+    pub moves: u32,
+    pub soft_drops: u32,
+    pub rotate_cw: u32,
+    pub rotate_ccw: u32,
+    pub rotate_180: u32,
+    pub holds: u32,
+    pub hard_drops: u32,
+}
+
+impl ActionCounts {
+    pub fn count(&mut self, input: InputEvent) {
+        let counter = match input {
+            InputEvent::Press(InputDirection::Left | InputDirection::Right) => &mut self.moves,
+            InputEvent::Press(InputDirection::Down) => &mut self.soft_drops,
+            InputEvent::RotateCw => &mut self.rotate_cw,
+            InputEvent::RotateCcw => &mut self.rotate_ccw,
+            InputEvent::Rotate180 => &mut self.rotate_180,
+            InputEvent::Hold => &mut self.holds,
+            InputEvent::HardDrop => &mut self.hard_drops,
+            _ => return,
+        };
+        *counter += 1;
+    }
+
+    pub fn add(&mut self, other: &ActionCounts) {
+        self.moves += other.moves;
+        self.soft_drops += other.soft_drops;
+        self.rotate_cw += other.rotate_cw;
+        self.rotate_ccw += other.rotate_ccw;
+        self.rotate_180 += other.rotate_180;
+        self.holds += other.holds;
+        self.hard_drops += other.hard_drops;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    pub fn total(&self) -> u32 {
+        self.entries().iter().map(|(_, count)| count).sum()
+    }
+
+    // Locale key under [usage] and count, in display order
+    pub fn entries(&self) -> [(&'static str, u32); 7] {
+        [
+            ("usage.moves", self.moves),
+            ("usage.soft_drops", self.soft_drops),
+            ("usage.rotate_cw", self.rotate_cw),
+            ("usage.rotate_ccw", self.rotate_ccw),
+            ("usage.rotate_180", self.rotate_180),
+            ("usage.holds", self.holds),
+            ("usage.hard_drops", self.hard_drops),
+        ]
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+
+// What completes a run, checked after every lock
+pub trait WinCondition {
+    fn met(&self, game: &Game) -> bool;
+}
+
+// The win conditions a custom mode can be given in the config, e.g.
+// `win = { lines = 40 }` or `win = { any = [{ score = 100000 }, { survive_seconds = 180 }] }`
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    Lines(u32),
+    Score(u64),
+    SurviveSeconds(u64),
+    AllGarbageCleared, // Every garbage row on the board cleared, once there has been some
+    All(Vec<Goal>),
+    Any(Vec<Goal>),
+}
+
+impl WinCondition for Goal {
+    fn met(&self, game: &Game) -> bool {
+        match self {
+            Goal::Lines(lines) => game.lines_cleared >= *lines,
+            Goal::Score(points) => game.scoring.points >= *points,
+            Goal::SurviveSeconds(secs) => game.get_current_time().is_some_and(|elapsed| elapsed >= Duration::from_secs(*secs)),
+            Goal::AllGarbageCleared => game.garbage_cleared > 0 && game.garbage_remaining == 0,
+            Goal::All(goals) => goals.iter().all(|goal| goal.met(game)),
+            Goal::Any(goals) => goals.iter().any(|goal| goal.met(game)),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::blind::Blind;
+use crate::game::win::Goal;
+use crate::game::GameMode;
+use crate::ui::text;
+
+// Locale bundles are compiled in, keyed by the language config value
+const ENGLISH: &str = include_str!("../locales/en.toml");
+const SPANISH: &str = include_str!("../locales/es.toml");
+const JAPANESE: &str = include_str!("../locales/ja.toml");
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Es,
+    Ja,
+}
+
+impl Language {
+    fn bundle(self) -> &'static str {
+        match self {
+            Language::En => ENGLISH,
+            Language::Es => SPANISH,
+            Language::Ja => JAPANESE,
+        }
+    }
+}
+
+// UI strings for one language, looked up by "section.key"
+pub struct Strings {
+    table: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Strings {
+    pub fn new(language: Language) -> Self {
+        Self {
+            table: parse_bundle(language.bundle()),
+            fallback: parse_bundle(ENGLISH),
+        }
+    }
+
+    // Untranslated keys fall back to English, unknown ones to the key itself
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(key).unwrap_or(key)
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        self.table.get(key).or_else(|| self.fallback.get(key)).map(String::as_str)
+    }
+
+    // Fills each `{}` in turn; callers format numbers before passing them in
+    pub fn fmt(&self, key: &str, args: &[&dyn Display]) -> String {
+        let mut args = args.iter();
+        let mut parts = self.get(key).split("{}");
+        let mut text = parts.next().unwrap_or_default().to_string();
+        for part in parts {
+            if let Some(arg) = args.next() {
+                text.push_str(&arg.to_string());
+            }
+            text.push_str(part);
+        }
+        text
+    }
+
+    // Mode names are keyed by the mode's lowercased short name
+    pub fn mode_title(&self, mode: GameMode) -> &str {
+        self.lookup(&format!("mode.{}", mode.short_name().to_lowercase())).unwrap_or(mode.title())
+    }
+
+    pub fn mode_short(&self, mode: GameMode) -> &str {
+        self.lookup(&format!("mode_short.{}", mode.short_name().to_lowercase())).unwrap_or(mode.short_name())
+    }
+
+    // What a blind run went without, e.g. "next, ghost"
+    pub fn blind(&self, blind: Blind) -> String {
+        [(blind.next, "blind.next"), (blind.hold, "blind.hold"), (blind.ghost, "blind.ghost")]
+            .into_iter()
+            .filter(|&(hidden, _)| hidden)
+            .map(|(_, key)| self.get(key))
+            .collect::<Vec<_>>()
+            .join(self.get("blind.separator"))
+    }
+
+    // A win condition in words, e.g. "40 lines or 100000 points"
+    pub fn goal(&self, goal: &Goal) -> String {
+        match goal {
+            Goal::Lines(lines) => self.fmt("goal.lines", &[lines]),
+            Goal::Score(points) => self.fmt("goal.score", &[points]),
+            Goal::SurviveSeconds(secs) => self.fmt("goal.survive", &[secs]),
+            Goal::AllGarbageCleared => self.get("goal.garbage").to_string(),
+            Goal::All(goals) => self.goals(goals, "goal.and"),
+            Goal::Any(goals) => self.goals(goals, "goal.or"),
+        }
+    }
+
+    // Groups inside groups are bracketed so their "and" and "or" don't run together
+    fn goals(&self, goals: &[Goal], joiner: &str) -> String {
+        goals
+            .iter()
+            .map(|goal| match goal {
+                Goal::All(_) | Goal::Any(_) => format!("({})", self.goal(goal)),
+                _ => self.goal(goal),
+            })
+            .collect::<Vec<_>>()
+            .join(self.get(joiner))
+    }
+
+    // Terminal columns taken by a string; CJK characters take two
+    pub fn width(&self, key: &str) -> usize {
+        text::width(self.get(key))
+    }
+}
+
+// Flattens nested tables into dotted keys; a broken bundle is a build bug,
+// so it just comes out empty and everything falls back
+fn parse_bundle(source: &str) -> HashMap<String, String> {
+    fn flatten(prefix: &str, table: &toml::Table, out: &mut HashMap<String, String>) {
+        for (key, value) in table {
+            let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+            match value {
+                toml::Value::String(text) => {
+                    out.insert(key, text.clone());
+                }
+                toml::Value::Table(table) => flatten(&key, table, out),
+                _ => {}
+            }
+        }
+    }
+
+    let mut out = HashMap::new();
+    if let Ok(table) = source.parse::<toml::Table>() {
+        flatten("", &table, &mut out);
+    }
+    out
+}
@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crossterm::event::{KeyCode, KeyModifiers, ModifierKeyCode};
+
+use crate::game::GameMode;
+use crate::input::InputDirection;
+
+// Everything a player can ask of the game. Keys become actions through the
+// bindings below; anything else that plays (a bot, a gamepad, the network)
+// can hand the game actions directly.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameAction {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    HardDrop, // Also starts the countdown from the ready screen
+    Hold,
+    Undo, // Takes back the last placement in Zen
+    CycleMode,
+    Restart,
+    Pause, // Pauses or resumes untimed modes
+}
+
+impl GameAction {
+    // Held actions that auto-repeat until released
+    pub fn direction(self) -> Option<InputDirection> {
+        match self {
+            GameAction::MoveLeft => Some(InputDirection::Left),
+            GameAction::MoveRight => Some(InputDirection::Right),
+            GameAction::SoftDrop => Some(InputDirection::Down),
+            _ => None,
+        }
+    }
+
+    pub fn is_rotation(self) -> bool {
+        matches!(self, GameAction::RotateCw | GameAction::RotateCcw | GameAction::Rotate180)
+    }
+
+    // As written in the config's [bindings]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let action = match name {
+            "move_left" => GameAction::MoveLeft,
+            "move_right" => GameAction::MoveRight,
+            "soft_drop" => GameAction::SoftDrop,
+            "rotate_cw" => GameAction::RotateCw,
+            "rotate_ccw" => GameAction::RotateCcw,
+            "rotate_180" => GameAction::Rotate180,
+            "hard_drop" => GameAction::HardDrop,
+            "hold" => GameAction::Hold,
+            "undo" => GameAction::Undo,
+            "cycle_mode" => GameAction::CycleMode,
+            "restart" => GameAction::Restart,
+            "pause" => GameAction::Pause,
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+// Letters are matched lowercased, so Shift doesn't make a different key
+const BINDINGS: &[(KeyCode, GameAction)] = &[
+    (KeyCode::Left, GameAction::MoveLeft),
+    (KeyCode::Right, GameAction::MoveRight),
+    (KeyCode::Down, GameAction::SoftDrop),
+    (KeyCode::Up, GameAction::RotateCw),
+    (KeyCode::Char('d'), GameAction::RotateCcw),
+    (KeyCode::Char('a'), GameAction::Rotate180),
+    (KeyCode::Char(' '), GameAction::HardDrop),
+    (KeyCode::Char('s'), GameAction::HardDrop),
+    (KeyCode::Char('h'), GameAction::Hold),
+    (KeyCode::Modifier(ModifierKeyCode::LeftShift), GameAction::Hold),
+    (KeyCode::Modifier(ModifierKeyCode::RightShift), GameAction::Hold),
+    (KeyCode::Char('z'), GameAction::Undo),
+    (KeyCode::Char('m'), GameAction::CycleMode),
+    (KeyCode::Char('r'), GameAction::Restart),
+    (KeyCode::Esc, GameAction::Pause),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+// Two-board practice: each hand gets its own board and its own keys, WASD
+// for the left and the arrows for the right
+const TWO_HAND_BINDINGS: &[(KeyCode, Hand, GameAction)] = &[
+    (KeyCode::Char('a'), Hand::Left, GameAction::MoveLeft),
+    (KeyCode::Char('d'), Hand::Left, GameAction::MoveRight),
+    (KeyCode::Char('s'), Hand::Left, GameAction::SoftDrop),
+    (KeyCode::Char('w'), Hand::Left, GameAction::RotateCw),
+    (KeyCode::Char('e'), Hand::Left, GameAction::RotateCcw),
+    (KeyCode::Char(' '), Hand::Left, GameAction::HardDrop),
+    (KeyCode::Char('c'), Hand::Left, GameAction::Hold),
+    (KeyCode::Left, Hand::Right, GameAction::MoveLeft),
+    (KeyCode::Right, Hand::Right, GameAction::MoveRight),
+    (KeyCode::Down, Hand::Right, GameAction::SoftDrop),
+    (KeyCode::Up, Hand::Right, GameAction::RotateCw),
+    (KeyCode::Char('/'), Hand::Right, GameAction::RotateCcw),
+    (KeyCode::Enter, Hand::Right, GameAction::HardDrop),
+    (KeyCode::Char('.'), Hand::Right, GameAction::Hold),
+];
+
+pub fn two_hand_action(key_code: KeyCode) -> Option<(Hand, GameAction)> {
+    let key_code = match key_code {
+        KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+        key_code => key_code,
+    };
+    TWO_HAND_BINDINGS
+        .iter()
+        .find(|(key, _, _)| *key == key_code)
+        .map(|&(_, hand, action)| (hand, action))
+}
+
+// Terminals that don't report Shift on its own still send it along with
+// other keys, so any unbound key with Shift held counts as hold
+pub fn action_for(key_code: KeyCode, modifiers: KeyModifiers) -> Option<GameAction> {
+    let key_code = match key_code {
+        KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+        key_code => key_code,
+    };
+    BINDINGS
+        .iter()
+        .find(|(key, _)| *key == key_code)
+        .map(|&(_, action)| action)
+        .or_else(|| modifiers.contains(KeyModifiers::SHIFT).then_some(GameAction::Hold))
+}
+
+// Keys the config can bind besides single characters
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("esc", KeyCode::Esc),
+    ("lshift", KeyCode::Modifier(ModifierKeyCode::LeftShift)),
+    ("rshift", KeyCode::Modifier(ModifierKeyCode::RightShift)),
+];
+
+// "x", "space", "f5" and so on; letters either case
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let lower = name.to_ascii_lowercase();
+    let mut chars = lower.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok()).filter(|n| (1..=12).contains(n)) {
+        return Some(KeyCode::F(n));
+    }
+    KEY_NAMES.iter().find(|(key, _)| *key == lower).map(|&(_, key)| key)
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum BindingError {
+    #[error("unknown key '{0}'")]
+    UnknownKey(String),
+    #[error("unknown action '{0}' (or \"none\" to unbind)")]
+    UnknownAction(String),
+    #[error("unknown mode '{0}'")]
+    UnknownMode(String),
+}
+
+// Key -> action, where None unbinds a key that would otherwise do something
+type Layer = HashMap<KeyCode, Option<GameAction>>;
+
+fn layer(keys: &BTreeMap<String, String>) -> Result<Layer, BindingError> {
+    keys.iter()
+        .map(|(key, action)| {
+            let key = parse_key(key).ok_or_else(|| BindingError::UnknownKey(key.clone()))?;
+            let action = match action.as_str() {
+                "none" => None,
+                name => Some(GameAction::from_name(name).ok_or_else(|| BindingError::UnknownAction(name.to_string()))?),
+            };
+            Ok((key, action))
+        })
+        .collect()
+}
+
+// The config's keys layered over the built-in ones: a mode's own overrides
+// first, then the overrides for every mode, then BINDINGS
+#[derive(Clone, Debug, Default)]
+pub struct Bindings {
+    global: Layer,
+    modes: HashMap<String, Layer>, // By mode short name, lowercased
+    sideways: bool,                // Board on its side, so the built-in arrows turn with it
+}
+
+impl Bindings {
+    pub fn new(global: &BTreeMap<String, String>, modes: &BTreeMap<String, BTreeMap<String, String>>) -> Result<Self, BindingError> {
+        let modes = modes
+            .iter()
+            .map(|(mode, keys)| {
+                let mode = mode.to_lowercase();
+                if !GameMode::ALL.iter().any(|known| known.short_name().to_lowercase() == mode) {
+                    return Err(BindingError::UnknownMode(mode));
+                }
+                Ok((mode, layer(keys)?))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { global: layer(global)?, modes, sideways: false })
+    }
+
+    pub fn sideways(mut self, sideways: bool) -> Self {
+        self.sideways = sideways;
+        self
+    }
+
+    // What the config says about a key in this mode, if anything
+    fn overridden(&self, mode: GameMode, key_code: KeyCode) -> Option<Option<GameAction>> {
+        let key = match key_code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            key_code => key_code,
+        };
+        self.modes
+            .get(&mode.short_name().to_lowercase())
+            .and_then(|layer| layer.get(&key))
+            .or_else(|| self.global.get(&key))
+            .copied()
+    }
+
+    // Bound by the config, so it goes to the game ahead of the screen's own keys
+    pub fn overrides(&self, mode: GameMode, key_code: KeyCode) -> bool {
+        self.overridden(mode, key_code).is_some()
+    }
+
+    pub fn action(&self, mode: GameMode, key_code: KeyCode, modifiers: KeyModifiers) -> Option<GameAction> {
+        self.overridden(mode, key_code).unwrap_or_else(|| action_for(self.turned(key_code), modifiers))
+    }
+
+    // With the floor on the right, the arrow pointing at it drops and the
+    // one pointing away rotates; keys from the config stay as written
+    fn turned(&self, key_code: KeyCode) -> KeyCode {
+        if !self.sideways {
+            return key_code;
+        }
+        match key_code {
+            KeyCode::Right => KeyCode::Down,
+            KeyCode::Down => KeyCode::Left,
+            KeyCode::Left => KeyCode::Up,
+            KeyCode::Up => KeyCode::Right,
+            key_code => key_code,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+// Keys from raw terminal input, for sessions where crossterm isn't reading
+// the terminal itself. Only presses come through: plain terminals don't
+// report releases, which the engine's key timeouts already cover.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Event> {
+    let mut keys = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (code, used) = match rest {
+            [0x1b, b'[', b'A', ..] | [0x1b, b'O', b'A', ..] => (Some(KeyCode::Up), 3),
+            [0x1b, b'[', b'B', ..] | [0x1b, b'O', b'B', ..] => (Some(KeyCode::Down), 3),
+            [0x1b, b'[', b'C', ..] | [0x1b, b'O', b'C', ..] => (Some(KeyCode::Right), 3),
+            [0x1b, b'[', b'D', ..] | [0x1b, b'O', b'D', ..] => (Some(KeyCode::Left), 3),
+            [0x1b, b'[', b'H', ..] | [0x1b, b'O', b'H', ..] => (Some(KeyCode::Home), 3),
+            [0x1b, b'[', b'F', ..] | [0x1b, b'O', b'F', ..] => (Some(KeyCode::End), 3),
+            [0x1b, b'O', b'R', ..] => (Some(KeyCode::F(3)), 3),
+            [0x1b, b'[', b'1', b'3', b'~', ..] => (Some(KeyCode::F(3)), 5),
+            // Any other escape sequence is skipped whole
+            [0x1b, b'[', tail @ ..] => {
+                let end = tail.iter().position(|b| (0x40..=0x7e).contains(b)).map_or(tail.len(), |i| i + 1);
+                (None, 2 + end)
+            }
+            [0x1b, ..] => (Some(KeyCode::Esc), 1),
+            [b'\r', b'\n', ..] => (Some(KeyCode::Enter), 2),
+            [b'\r' | b'\n', ..] => (Some(KeyCode::Enter), 1),
+            [b'\t', ..] => (Some(KeyCode::Tab), 1),
+            [0x7f | 0x08, ..] => (Some(KeyCode::Backspace), 1),
+            [0x03, ..] => (Some(KeyCode::Char('q')), 1), // Ctrl-C quits
+            [byte, ..] if *byte < 0x20 => (None, 1),
+            _ => {
+                // One UTF-8 character
+                let len = match rest[0] {
+                    0xf0.. => 4,
+                    0xe0.. => 3,
+                    0xc0.. => 2,
+                    _ => 1,
+                }
+                .min(rest.len());
+                let code = std::str::from_utf8(&rest[..len]).ok().and_then(|text| text.chars().next()).map(KeyCode::Char);
+                (code, len)
+            }
+        };
+        if let Some(code) = code {
+            keys.push(Event::Key(KeyEvent::new_with_kind(code, KeyModifiers::NONE, KeyEventKind::Press)));
+        }
+        rest = &rest[used.min(rest.len())..];
+    }
+    keys
+}
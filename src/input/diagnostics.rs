@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEventKind};
+
+use crate::constants::CHATTER_MS;
+
+// Press and release timing for one key
+#[derive(Clone, Debug, Default)]
+pub struct KeyStats {
+    pub presses: u32,
+    pub releases: u32,
+    pub repeats: u32,
+    pub chatter: u32,          // Pressed again within CHATTER_MS of a release
+    pub missing_releases: u32, // Pressed again without a release in between
+    pub shortest_gap: Option<Duration>, // Release to the next press
+    held_total: Duration,
+    holds: u32,
+    down_since: Option<Instant>,
+    released_at: Option<Instant>,
+}
+
+impl KeyStats {
+    // Average time from press to release
+    pub fn average_hold(&self) -> Option<Duration> {
+        (self.holds > 0).then(|| self.held_total / self.holds)
+    }
+}
+
+// Every key event, tallied per key to help tell a chattering keyboard or a
+// terminal that drops release events apart from the player's own fingers
+#[derive(Clone, Debug, Default)]
+pub struct InputDiagnostics {
+    pub keys: Vec<(KeyCode, KeyStats)>, // In the order first pressed
+    pub releases_seen: bool,           // The terminal reports releases at all
+}
+
+impl InputDiagnostics {
+    pub fn record(&mut self, code: KeyCode, kind: KeyEventKind, now: Instant) {
+        let code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        if kind == KeyEventKind::Release {
+            self.releases_seen = true;
+        }
+        let releases_seen = self.releases_seen;
+        let index = match self.keys.iter().position(|(key, _)| *key == code) {
+            Some(index) => index,
+            None => {
+                self.keys.push((code, KeyStats::default()));
+                self.keys.len() - 1
+            }
+        };
+        let stats = &mut self.keys[index].1;
+
+        match kind {
+            KeyEventKind::Press => {
+                stats.presses += 1;
+                // Only a terminal known to report releases can be missing one
+                if stats.down_since.is_some() && releases_seen {
+                    stats.missing_releases += 1;
+                    tracing::warn!(key = ?code, "key pressed again without a release");
+                }
+                if let Some(released) = stats.released_at.take() {
+                    let gap = now.duration_since(released);
+                    stats.shortest_gap = Some(stats.shortest_gap.map_or(gap, |shortest| shortest.min(gap)));
+                    if gap < Duration::from_millis(CHATTER_MS) {
+                        stats.chatter += 1;
+                        tracing::warn!(key = ?code, gap_ms = gap.as_secs_f64() * 1000.0, "key chatter");
+                    }
+                    tracing::debug!(key = ?code, gap_ms = gap.as_secs_f64() * 1000.0, "key pressed");
+                }
+                stats.down_since = Some(now);
+            }
+            KeyEventKind::Repeat => stats.repeats += 1,
+            KeyEventKind::Release => {
+                stats.releases += 1;
+                if let Some(down) = stats.down_since.take() {
+                    let held = now.duration_since(down);
+                    stats.held_total += held;
+                    stats.holds += 1;
+                    tracing::debug!(key = ?code, held_ms = held.as_secs_f64() * 1000.0, "key released");
+                }
+                stats.released_at = Some(now);
+            }
+        }
+    }
+}
+
+// Short label for the diagnostics table
+pub fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Modifier(modifier) => format!("{:?}", modifier).to_lowercase(),
+        code => format!("{:?}", code).to_lowercase(),
+    }
+}
@@ -1,13 +1,14 @@
 use std::time::Instant;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum InputDirection {
     Left,
     Right,
     Down,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DirectionState {
     pub pressed: bool,
     pub das_timer: Instant,
@@ -29,9 +30,8 @@ impl DirectionState {
         }
     }
 
-    pub fn press(&mut self) {
+    pub fn press(&mut self, now: Instant) {
         self.pressed = true;
-        let now = Instant::now();
         self.das_timer = now;
         self.arr_timer = now;
         self.das_charged = false;
@@ -39,16 +39,15 @@ impl DirectionState {
         self.last_update = now;
     }
 
-    pub fn release(&mut self) {
+    pub fn release(&mut self, now: Instant) {
         self.pressed = false;
         self.das_charged = false;
         self.initial_move_done = false;
-        self.last_update = Instant::now();
+        self.last_update = now;
     }
 
-    pub fn reset_das(&mut self) {
+    pub fn reset_das(&mut self, now: Instant) {
         if self.pressed {
-            let now = Instant::now();
             self.das_timer = now;
             self.arr_timer = now;
             self.das_charged = false;
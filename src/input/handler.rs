@@ -1,14 +1,53 @@
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+use serde::{Deserialize, Serialize};
 
+use crate::config::HandlingConfig;
+use crate::game::playback::Playback;
+use crate::game::state::GameState;
+use crate::input::action::{Bindings, GameAction};
 use crate::input::direction::{InputDirection, DirectionState};
-use crate::constants::KEY_TIMEOUT;
+use crate::constants::{HOLD_RELEASE_GUESS_MS, KEY_TIMEOUT, MUX_HOLD_RELEASE_GUESS_MS, MUX_KEY_TIMEOUT, REPLAY_SEEK_STEP};
+use crate::wizard::Wizard;
 
+// How long a key counts as still held after its last press or repeat, on
+// terminals that never say when it's let go
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ReleaseGuess {
+    pub key_ms: u64,  // Held directions
+    pub hold_ms: u64, // Hold keys, for hold protection
+}
+
+impl ReleaseGuess {
+    pub const DEFAULT: ReleaseGuess = ReleaseGuess { key_ms: KEY_TIMEOUT, hold_ms: HOLD_RELEASE_GUESS_MS };
+    pub const MULTIPLEXED: ReleaseGuess = ReleaseGuess { key_ms: MUX_KEY_TIMEOUT, hold_ms: MUX_HOLD_RELEASE_GUESS_MS };
+
+    pub fn is_default(&self) -> bool {
+        *self == ReleaseGuess::DEFAULT
+    }
+}
+
+#[derive(Clone)]
 pub struct InputState {
     pub directions: HashMap<InputDirection, DirectionState>,
     pub last_horizontal_dir: Option<InputDirection>,
     pub keyboard_enhancement_active: bool,
+    pub release_guess: ReleaseGuess, // Used while keyboard enhancement is off
+    // Kept by the input handler for hold protection; the engine never reads them
+    pub hold_key: Option<Instant>,  // Last press or repeat of a hold key, until it's released
+    pub last_hold: Option<Instant>, // When a hold last went through
+    pub releases_seen: bool,        // The terminal has sent a key release, so it reports them
+    pub keys_down: HashMap<KeyCode, Instant>, // Keys not yet released, lowercased, by their last press or repeat
+    pub restart_key: Option<RestartKey>, // Restart held down mid-run, where it has to be held
+}
+
+// A restart key on its way to restarting
+#[derive(Clone, Copy)]
+pub struct RestartKey {
+    pub down: Instant, // First press
+    pub last: Instant, // Last press or repeat
+    pub hold: Duration,
 }
 
 impl InputState {
@@ -22,30 +61,36 @@ impl InputState {
             directions,
             last_horizontal_dir: None,
             keyboard_enhancement_active: false,
+            release_guess: ReleaseGuess::DEFAULT,
+            hold_key: None,
+            last_hold: None,
+            releases_seen: false,
+            keys_down: HashMap::new(),
+            restart_key: None,
         }
     }
 
-    pub fn press_direction(&mut self, dir: InputDirection) {
+    pub fn press_direction(&mut self, dir: InputDirection, now: Instant) {
         match dir {
             InputDirection::Left => {
-                self.release_direction(InputDirection::Right);
+                self.release_direction(InputDirection::Right, now);
                 self.last_horizontal_dir = Some(InputDirection::Left);
             }
             InputDirection::Right => {
-                self.release_direction(InputDirection::Left);
+                self.release_direction(InputDirection::Left, now);
                 self.last_horizontal_dir = Some(InputDirection::Right);
             }
             _ => {}
         }
 
         if let Some(state) = self.directions.get_mut(&dir) {
-            state.press();
+            state.press(now);
         }
     }
 
-    pub fn release_direction(&mut self, dir: InputDirection) {
+    pub fn release_direction(&mut self, dir: InputDirection, now: Instant) {
         if let Some(state) = self.directions.get_mut(&dir) {
-            state.release();
+            state.release(now);
         }
 
         if self.last_horizontal_dir == Some(dir) {
@@ -54,105 +99,219 @@ impl InputState {
     }
 
     pub fn is_pressed(&self, dir: InputDirection) -> bool {
-        self.directions.get(&dir).map_or(false, |s| s.pressed)
+        self.directions.get(&dir).is_some_and(|s| s.pressed)
     }
 
-    pub fn reset_das_states(&mut self) {
+    pub fn release_all(&mut self, now: Instant) {
         for state in self.directions.values_mut() {
-            state.reset_das();
+            state.release(now);
+        }
+        self.last_horizontal_dir = None;
+        self.hold_key = None;
+        self.restart_key = None;
+        self.keys_down.clear();
+    }
+
+    pub fn reset_das_states(&mut self, now: Instant) {
+        for state in self.directions.values_mut() {
+            state.reset_das(now);
+        }
+    }
+
+    // How far a held restart key is towards restarting, 0.0 to 1.0; None
+    // once it's let go. Without key releases it counts as down while repeats
+    // keep coming, as hold keys do, but only gets as far as the last one, so
+    // a tap can't restart
+    pub fn restart_progress(&self, now: Instant) -> Option<f64> {
+        let key = self.restart_key?;
+        if self.releases_seen {
+            return Some((now.duration_since(key.down).as_secs_f64() / key.hold.as_secs_f64()).min(1.0));
         }
+        let let_go = now.duration_since(key.last) > Duration::from_millis(self.release_guess.hold_ms);
+        (!let_go).then(|| (key.last.duration_since(key.down).as_secs_f64() / key.hold.as_secs_f64()).min(1.0))
     }
 
-    pub fn check_timeouts(&mut self) {
+    pub fn check_timeouts(&mut self, now: Instant) {
         if !self.keyboard_enhancement_active {
-            let now = Instant::now();
             for state in self.directions.values_mut() {
-                if state.pressed && now.duration_since(state.last_update) > Duration::from_millis(KEY_TIMEOUT) {
-                    state.release();
+                if state.pressed && now.duration_since(state.last_update) > Duration::from_millis(self.release_guess.key_ms) {
+                    state.release(now);
                 }
             }
         }
     }
 
-    pub fn update_key_activity(&mut self, dir: InputDirection) {
+    pub fn update_key_activity(&mut self, dir: InputDirection, now: Instant) {
         if let Some(state) = self.directions.get_mut(&dir) {
-            state.last_update = Instant::now();
+            state.last_update = now;
         }
     }
+
+    // Marks the key as down and says whether this is its initial press
+    // rather than a repeat. Until the terminal has shown it reports releases,
+    // a held key's repeats arrive as fresh presses, so a press counts as a
+    // repeat while it comes within the release guess of the key's last one.
+    pub fn initial_press(&mut self, key: KeyCode, kind: KeyEventKind, now: Instant) -> bool {
+        let last = self.keys_down.insert(lowercase(key), now);
+        let held = match last {
+            Some(_) if self.releases_seen => true,
+            Some(last) => now.duration_since(last) <= Duration::from_millis(self.release_guess.key_ms),
+            None => false,
+        };
+        kind == KeyEventKind::Press && !held
+    }
+
+    pub fn key_released(&mut self, key: KeyCode) {
+        self.keys_down.remove(&lowercase(key));
+    }
 }
 
-pub fn handle_input(game: &mut crate::game::Game, key_code: KeyCode, kind: KeyEventKind, modifiers: KeyModifiers) {
+// Shift doesn't make a different key
+fn lowercase(key: KeyCode) -> KeyCode {
+    match key {
+        KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+        key => key,
+    }
+}
+
+pub fn handle_input(
+    game: &mut crate::game::Game,
+    handling: &HandlingConfig,
+    bindings: &Bindings,
+    key_code: KeyCode,
+    kind: KeyEventKind,
+    modifiers: KeyModifiers,
+) {
+    let action = bindings.action(game.mode, key_code, modifiers);
+    handle_action(game, handling, key_code, kind, action);
+}
+
+// A key already turned into an action, or into nothing, by whichever
+// bindings are in use
+pub fn handle_action(game: &mut crate::game::Game, handling: &HandlingConfig, key_code: KeyCode, kind: KeyEventKind, action: Option<GameAction>) {
     match kind {
         KeyEventKind::Press | KeyEventKind::Repeat => {
-            match key_code {
-                KeyCode::Left => {
-                    if !game.input_state.is_pressed(InputDirection::Left) {
-                        game.input_state.press_direction(InputDirection::Left);
-                    } else {
-                        game.input_state.update_key_activity(InputDirection::Left);
-                    }
-                }
-                KeyCode::Right => {
-                    if !game.input_state.is_pressed(InputDirection::Right) {
-                        game.input_state.press_direction(InputDirection::Right);
-                    } else {
-                        game.input_state.update_key_activity(InputDirection::Right);
-                    }
-                }
-                KeyCode::Down => {
-                    if !game.input_state.is_pressed(InputDirection::Down) {
-                        game.input_state.press_direction(InputDirection::Down);
-                    } else {
-                        game.input_state.update_key_activity(InputDirection::Down);
-                    }
-                }
-                KeyCode::Up => {
-                    game.rotate_piece(); // Rotate right (clockwise)
-                }
-                KeyCode::Char('d') | KeyCode::Char('D') => {
-                    game.rotate_piece_left(); // Rotate left (counter-clockwise)
-                }
-                KeyCode::Char('a') | KeyCode::Char('A') => {
-                    game.rotate_piece_180();
-                }
-                KeyCode::Char('s') | KeyCode::Char('S') => {
-                    game.hard_drop();
-                }
-                KeyCode::Char(' ') => {
-                    match game.game_state {
-                        crate::game::state::GameState::Ready => {
-                            game.start_countdown();
-                        }
-                        crate::game::state::GameState::Playing => {
-                            game.hard_drop();
-                        }
-                        _ => {}
-                    }
-                }
-                KeyCode::Char('h') | KeyCode::Char('H') => {
-                    game.hold_piece();
-                }
-                _ => {
-                    // Handle left shift for hold
-                    if modifiers.contains(KeyModifiers::SHIFT) {
-                        game.hold_piece();
-                    }
-                }
+            // Rotations, hard drops and holds act once per press; holding
+            // the key down doesn't repeat them unless rotate_repeat is on
+            let initial = game.input_state.initial_press(key_code, kind, Instant::now());
+            let Some(action) = action else {
+                return;
+            };
+            // Nothing moves while paused; restarting and resuming still work
+            if game.game_state == GameState::Paused && !matches!(action, GameAction::Pause | GameAction::Restart) {
+                return;
+            }
+            match action {
+                action if action.direction().is_some() => game.press_action(action),
+                action if action.is_rotation() && (initial || handling.rotate_repeat) => game.press_action(action),
+                GameAction::HardDrop if initial => hard_drop(game, handling),
+                GameAction::Hold if initial => hold(game, handling, kind),
+                GameAction::Undo if initial => game.press_action(action),
+                GameAction::CycleMode => game.press_action(action),
+                GameAction::Restart => restart(game, handling, initial),
+                GameAction::Pause if initial => game.press_action(action),
+                _ => {}
             }
         }
         KeyEventKind::Release => {
-            match key_code {
-                KeyCode::Left => {
-                    game.input_state.release_direction(InputDirection::Left);
-                }
-                KeyCode::Right => {
-                    game.input_state.release_direction(InputDirection::Right);
-                }
-                KeyCode::Down => {
-                    game.input_state.release_direction(InputDirection::Down);
-                }
-                _ => {}
+            game.input_state.releases_seen = true;
+            game.input_state.key_released(key_code);
+            match action {
+                Some(GameAction::Hold) => game.input_state.hold_key = None,
+                Some(GameAction::Restart) => game.input_state.restart_key = None,
+                Some(action) => game.release_action(action),
+                None => {}
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// A hard drop too soon after the piece spawned is most likely a key still
+// being mashed from the last one. It's ignored before reaching the engine, so
+// replays never see it.
+fn hard_drop(game: &mut crate::game::Game, handling: &HandlingConfig) {
+    let protection = Duration::from_millis(handling.hard_drop_protection_ms);
+    let playing = game.game_state == GameState::Playing;
+    if playing && Instant::now().saturating_duration_since(game.spawned_at) < protection {
+        return;
+    }
+    game.press_action(GameAction::HardDrop);
+}
+
+// Restart, at once or, mid-run in a mode that wants it held, once the key
+// has stayed down long enough; App::update watches for that
+fn restart(game: &mut crate::game::Game, handling: &HandlingConfig, initial: bool) {
+    let hold = handling.restart_hold(game.mode);
+    let mid_run = matches!(game.game_state, GameState::Playing | GameState::Paused);
+    if hold.is_zero() || !mid_run {
+        if initial {
+            game.press_action(GameAction::Restart);
+        }
+        return;
+    }
+    let now = Instant::now();
+    let input = &mut game.input_state;
+    let down = input.restart_progress(now).and(input.restart_key).map_or(now, |key| key.down);
+    input.restart_key = Some(RestartKey { down, last: now, hold });
+}
+
+// Hold, unless the key is still down from an earlier press or the last hold
+// was too recent, as configured. Terminals that never report releases send
+// key repeats as fresh presses, so there a hold key counts as let go once
+// it's gone quiet for the release guess.
+fn hold(game: &mut crate::game::Game, handling: &HandlingConfig, kind: KeyEventKind) {
+    let now = Instant::now();
+    let input = &mut game.input_state;
+    let still_down = kind == KeyEventKind::Repeat
+        || input.hold_key.is_some_and(|last| {
+            input.releases_seen || now.duration_since(last) <= Duration::from_millis(input.release_guess.hold_ms)
+        });
+    input.hold_key = Some(now);
+    if handling.hold_fresh_press && still_down {
+        return;
+    }
+    let cooldown = Duration::from_millis(handling.hold_cooldown_ms);
+    if input.last_hold.is_some_and(|at| now.duration_since(at) < cooldown) {
+        return;
+    }
+    
+    let could_hold = game.can_hold;
+    game.press_action(GameAction::Hold);
+    if could_hold && !game.can_hold {
+        game.input_state.last_hold = Some(now);
+    }
+}
+
+// First-run setup choices; Enter and Esc are handled by the caller. Space
+// is watched going down and coming back up for the key release check.
+pub fn handle_wizard_input(wizard: &mut Wizard, key_code: KeyCode, kind: KeyEventKind) {
+    match (key_code, kind) {
+        (KeyCode::Up, KeyEventKind::Press | KeyEventKind::Repeat) => wizard.choose(-1),
+        (KeyCode::Down, KeyEventKind::Press | KeyEventKind::Repeat) => wizard.choose(1),
+        (KeyCode::Char(' '), KeyEventKind::Press) => wizard.space(true),
+        (KeyCode::Char(' '), KeyEventKind::Release) => wizard.space(false),
+        _ => {}
+    }
+}
+
+// Replay playback controls; Esc to leave playback is handled by the caller
+pub fn handle_playback_input(playback: &mut Playback, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Char(' ') => playback.toggle_pause(),
+        KeyCode::Left => playback.seek_by(-(REPLAY_SEEK_STEP as i64)),
+        KeyCode::Right => playback.seek_by(REPLAY_SEEK_STEP as i64),
+        KeyCode::Char(',') => playback.step_frame_back(),
+        KeyCode::Char('.') => playback.step_frame(),
+        KeyCode::Char('[') => playback.step_piece(false),
+        KeyCode::Char(']') => playback.step_piece(true),
+        KeyCode::Char('-') => playback.change_speed(false),
+        KeyCode::Char('+') | KeyCode::Char('=') => playback.change_speed(true),
+        KeyCode::Home => playback.seek_fraction(0.0),
+        KeyCode::End => playback.seek_fraction(1.0),
+        KeyCode::Char(digit @ '0'..='9') => {
+            // Number keys jump to 0%-90% of the way through
+            playback.seek_fraction(digit.to_digit(10).unwrap_or(0) as f64 / 10.0);
+        }
+        _ => {}
+    }
+}
@@ -1,5 +1,11 @@
+pub mod action;
+#[cfg(feature = "ssh")]
+pub mod ansi;
+pub mod diagnostics;
 pub mod direction;
 pub mod handler;
+pub mod multiplexer;
+pub mod repeat;
 
 pub use direction::InputDirection;
-pub use handler::handle_input;
\ No newline at end of file
+pub use handler::{handle_action, handle_input, handle_playback_input, handle_wizard_input};
\ No newline at end of file
@@ -0,0 +1,44 @@
+use crate::input::handler::ReleaseGuess;
+
+// tmux or screen between tstris and the terminal. Both take the keyboard
+// enhancement flags without complaint and then don't pass them on, so key
+// releases never arrive however capable the terminal outside is.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+impl Multiplexer {
+    // From the variables each sets for what runs inside it
+    pub fn detect() -> Option<Self> {
+        if std::env::var_os("TMUX").is_some() {
+            Some(Multiplexer::Tmux)
+        } else if std::env::var_os("STY").is_some() {
+            Some(Multiplexer::Screen)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Screen => "screen",
+        }
+    }
+
+    // Key repeats come through in bursts, so a held key goes quiet for
+    // longer between them than it would outside
+    pub fn release_guess(self) -> ReleaseGuess {
+        ReleaseGuess::MULTIPLEXED
+    }
+
+    // Locale key for what to change so releases come through, where anything can
+    pub fn hint(self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "toast.tmux",
+            Multiplexer::Screen => "toast.screen",
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{ARR_DELAY, BOARD_WIDTH, DAS_DELAY, GROUND_TIME, SOFT_DROP_BURST, SOFT_DROP_DELAY};
+use crate::input::action::GameAction;
+use crate::input::direction::DirectionState;
+
+// A held action that repeats: once on the press, again once it has been
+// held for `delay_ms`, then every `rate_ms` from there, however the updates
+// fall. Repeats that came due since the last update all happen in this one,
+// up to `burst`; any past that are dropped rather than owed. Without `exact`
+// (replays from before that) each repeat restarts the rate timer from the
+// update it happened in instead, which drifts.
+#[derive(Clone, Copy, Debug)]
+pub struct AutoRepeat {
+    pub action: GameAction,
+    delay_ms: u64,
+    rate_ms: u64,
+    burst: u32,
+    exact: bool,
+}
+
+impl AutoRepeat {
+    pub const fn new(action: GameAction, delay_ms: u64, rate_ms: u64, burst: u32, exact: bool) -> Self {
+        Self { action, delay_ms, rate_ms, burst, exact }
+    }
+
+    // How many times the action should happen this update
+    pub fn due(&self, state: &mut DirectionState, now: Instant) -> u32 {
+        if !state.pressed {
+            return 0;
+        }
+        let (delay, rate) = (Duration::from_millis(self.delay_ms), Duration::from_millis(self.rate_ms));
+        let mut count = 0;
+        if !state.initial_move_done {
+            state.initial_move_done = true;
+            count += 1;
+        }
+        while count < self.burst {
+            let due = if !state.das_charged {
+                if now.duration_since(state.das_timer) < delay {
+                    break;
+                }
+                state.das_charged = true;
+                state.das_timer + delay
+            } else if now.duration_since(state.arr_timer) < rate {
+                break;
+            } else {
+                state.arr_timer + rate
+            };
+            state.arr_timer = if self.exact { due } else { now };
+            count += 1;
+        }
+        if self.exact && count == self.burst && now.duration_since(state.arr_timer) >= rate {
+            state.arr_timer = now;
+        }
+        count
+    }
+}
+
+// How fast held keys repeat and how long a landed piece waits, in
+// milliseconds. Part of a run's rules, since a replay moves the same way
+// only with the same timings.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timings {
+    pub das_ms: u64,
+    pub arr_ms: u64,
+    pub soft_drop_ms: u64,
+    pub lock_delay_ms: u64, // Marathon speed curves set their own
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self { das_ms: DAS_DELAY, arr_ms: ARR_DELAY, soft_drop_ms: SOFT_DROP_DELAY, lock_delay_ms: GROUND_TIME }
+    }
+}
+
+// Close to other games' out-of-the-box handling, for players coming from them
+pub const BUILT_IN_PRESETS: [(&str, Timings); 2] = [
+    ("Jstris default", Timings { das_ms: 133, arr_ms: 10, soft_drop_ms: 20, lock_delay_ms: 500 }),
+    ("Tetr.io default", Timings { das_ms: 167, arr_ms: 33, soft_drop_ms: 17, lock_delay_ms: 500 }),
+];
+
+impl Timings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Held movement: sideways with DAS and ARR, soft drop as fast as allowed.
+    // An ARR of 0 slides all the way to the wall as soon as DAS charges.
+    pub fn auto_repeats(&self, exact: bool) -> [AutoRepeat; 3] {
+        let sideways = if exact && self.arr_ms == 0 { BOARD_WIDTH as u32 } else { 1 };
+        [
+            AutoRepeat::new(GameAction::MoveLeft, self.das_ms, self.arr_ms, sideways, exact),
+            AutoRepeat::new(GameAction::MoveRight, self.das_ms, self.arr_ms, sideways, exact),
+            AutoRepeat::new(GameAction::SoftDrop, 0, self.soft_drop_ms, SOFT_DROP_BURST, exact),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(exact: bool, delay_ms: u64, rate_ms: u64, burst: u32) -> (AutoRepeat, DirectionState, Instant) {
+        let start = Instant::now();
+        let mut state = DirectionState::new();
+        state.press(start);
+        (AutoRepeat::new(GameAction::MoveRight, delay_ms, rate_ms, burst, exact), state, start)
+    }
+
+    #[test]
+    fn repeats_keep_to_the_rate_between_updates() {
+        // Updates every 8ms against a 10ms rate, for 800ms
+        let repeats = |exact| {
+            let (repeat, mut state, start) = held(exact, 100, 10, 3);
+            (0..=100).map(|i| repeat.due(&mut state, start + Duration::from_millis(8 * i))).sum::<u32>()
+        };
+        // The press, DAS at 100ms, then every 10ms from there
+        assert_eq!(repeats(true), 1 + 1 + 70);
+        // Restarting the timer at each update let it slip to every 16ms
+        assert_eq!(repeats(false), 1 + 1 + 43);
+    }
+
+    #[test]
+    fn a_stall_catches_up_only_as_far_as_the_burst() {
+        let (repeat, mut state, start) = held(true, 0, 10, 3);
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(repeat.due(&mut state, at(0)), 2);
+        assert_eq!(repeat.due(&mut state, at(1000)), 3);
+        assert_eq!(repeat.due(&mut state, at(1005)), 0);
+        assert_eq!(repeat.due(&mut state, at(1010)), 1);
+    }
+
+    #[test]
+    fn arr_0_reaches_the_wall_once_das_charges() {
+        let timings = Timings { arr_ms: 0, ..Timings::default() };
+        let [_, right, _] = timings.auto_repeats(true);
+        let mut state = DirectionState::new();
+        let start = Instant::now();
+        state.press(start);
+        assert_eq!(right.due(&mut state, start), 1);
+        assert_eq!(right.due(&mut state, start + Duration::from_millis(timings.das_ms)), BOARD_WIDTH as u32);
+    }
+}
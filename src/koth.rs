@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::game::state::EndReason;
+use crate::game::{Game, GameMode};
+
+// How one player's turn went, enough to compare it with their opponent's
+#[derive(Clone, Debug)]
+pub struct TurnResult {
+    pub completed: bool,
+    pub time: Option<Duration>,
+    pub points: u64,
+    pub lines: u32,
+}
+
+impl TurnResult {
+    pub fn from_game(game: &Game) -> Self {
+        Self {
+            completed: game.end_reason == Some(EndReason::Completed),
+            time: game.final_time,
+            points: game.scoring.points,
+            lines: game.lines_cleared,
+        }
+    }
+
+    // Finishing beats not finishing; then the better time in timed modes or
+    // more points in scored ones, and more lines if that's still level
+    fn beats(&self, other: &TurnResult, mode: GameMode) -> bool {
+        if self.completed != other.completed {
+            return self.completed;
+        }
+        let primary = if mode.is_time_ranked() && self.completed {
+            self.time.zip(other.time).map_or(Ordering::Equal, |(time, other)| mode.compare_times(time, other))
+        } else {
+            self.points.cmp(&other.points)
+        };
+        primary.then(self.lines.cmp(&other.lines)).is_gt()
+    }
+}
+
+// King of the hill on one keyboard: the king and a challenger take turns on
+// the same mode and seed, the better run wins the match, and the loser goes
+// to the back of the line
+pub struct Koth {
+    pub players: VecDeque<String>, // King first, then the challenger, then everyone waiting
+    pub wins: Vec<(String, u32)>,  // Scoreboard, in the order players joined
+    pub mode: Option<GameMode>,    // Set by the king's run, which the challenger must match
+    pub seed: u64,                 // Both players in a match get the same pieces
+    pub king_result: Option<TurnResult>,
+    pub handoff: bool,                         // Waiting for the next player to take the keyboard
+    pub last_match: Option<(String, String)>, // Winner and loser
+}
+
+impl Koth {
+    pub fn new(players: Vec<String>) -> Self {
+        Self {
+            wins: players.iter().map(|name| (name.clone(), 0)).collect(),
+            players: players.into(),
+            mode: None,
+            seed: rand::random(),
+            king_result: None,
+            handoff: true,
+            last_match: None,
+        }
+    }
+
+    // Whose turn it is next
+    pub fn up_next(&self) -> &str {
+        let turn = usize::from(self.king_result.is_some());
+        self.players.get(turn).map_or("", String::as_str)
+    }
+
+    // Takes a finished run. Returns false if it doesn't count because it was
+    // played in another mode than the king's.
+    pub fn finish_turn(&mut self, game: &Game) -> bool {
+        let result = TurnResult::from_game(game);
+        let Some(king_result) = self.king_result.take() else {
+            self.mode = Some(game.mode);
+            self.king_result = Some(result);
+            self.handoff = true;
+            return true;
+        };
+        let mode = self.mode.unwrap_or(game.mode);
+        if game.mode != mode {
+            self.king_result = Some(king_result);
+            return false;
+        }
+
+        // A tie keeps the king on the hill
+        if result.beats(&king_result, mode) {
+            self.players.swap(0, 1);
+        }
+        let winner = self.players[0].clone();
+        if let Some(loser) = self.players.remove(1) {
+            self.players.push_back(loser.clone());
+            self.last_match = Some((winner.clone(), loser));
+        }
+        if let Some((_, wins)) = self.wins.iter_mut().find(|(name, _)| *name == winner) {
+            *wins += 1;
+        }
+
+        self.mode = None;
+        self.seed = rand::random();
+        self.handoff = true;
+        true
+    }
+}
@@ -0,0 +1,34 @@
+// The game as a library, so benches can reach the engine. The binary in
+// main.rs is a thin front end over it.
+
+// Constructors here read the clock, the disk or the RNG, which Default would hide
+#![allow(clippy::new_without_default)]
+
+pub mod ai;
+pub mod app;
+pub mod calibration;
+pub mod cast;
+pub mod config;
+pub mod constants;
+pub mod error;
+pub mod game;
+pub mod i18n;
+pub mod input;
+pub mod koth;
+pub mod logging;
+pub mod netplay;
+pub mod pacing;
+pub mod persistence;
+pub mod prediction;
+pub mod profiler;
+pub mod relay;
+pub mod render;
+pub mod report;
+pub mod session;
+pub mod spectate;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod title;
+pub mod ui;
+pub mod versus;
+pub mod wizard;
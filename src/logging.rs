@@ -0,0 +1,25 @@
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use tracing::Level;
+
+use crate::error::{Error, Result};
+
+// Logging is off unless a log file is given; the terminal belongs to the
+// TUI, so nothing is ever written to stdout or stderr
+pub fn init(path: &Path, level: Option<&str>) -> Result<()> {
+    let level = match level {
+        Some(level) => Level::from_str(level)
+            .map_err(|_| Error::usage(format!("unknown log level '{}' (trace, debug, info, warn, error)", level)))?,
+        None => Level::INFO,
+    };
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_max_level(level)
+        .init();
+    Ok(())
+}
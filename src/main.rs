@@ -1,10 +1,11 @@
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
         KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
     },
+    cursor::MoveTo,
     execute,
-    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
@@ -12,23 +13,431 @@ use ratatui::{
 };
 use std::{
     io::stdout,
+    net::TcpStream,
+    path::Path,
+    process::ExitCode,
     time::Duration,
 };
 
-mod constants;
-mod game;
-mod input;
-mod ui;
+use tstris::{
+    ai, app, cast, config, constants, error, game, input, logging, netplay, persistence, prediction, relay, report, session, spectate, ui,
+};
+#[cfg(feature = "ssh")]
+use tstris::ssh;
+
+use ai::battle::{self, Handicap, Outcome, Setup, Targeting, Versus};
+use ai::race::Pace;
+use ai::AiConfig;
+use app::App;
+use constants::{NAME_MAX_WIDTH, NETPLAY_PORT, TOAST_MS};
+use cast::Recorder;
+use error::{Error, Result};
+use game::blind::Blind;
+use game::piece::{parse_queue, PieceType};
+use game::puzzle::Puzzle;
+use prediction::Prediction;
+use game::replay;
+use input::multiplexer::Multiplexer;
+use spectate::Broadcast;
+use ui::text;
+use ui::toast::ToastKind;
+
+// `tstris verify <replay.json>`: re-simulate a shared replay and check it
+// produces the time, board and validation hash it claims
+fn verify_replay(path: &Path) -> Result<()> {
+    let file = persistence::load_replay(path)?;
+    let claimed_time = Duration::from_nanos(file.time_nanos);
+    let validation_hash = replay::verify(&file.replay, claimed_time, file.board_hash)?;
+
+    if validation_hash != file.validation_hash {
+        return Err(Error::ValidationHash { stored: file.validation_hash, computed: validation_hash });
+    }
+
+    let player = match file.replay.player.as_str() {
+        "" => String::new(),
+        name => format!(" by {}", name),
+    };
+    println!(
+        "OK: {}{} in {:.3}s (validation hash {:016x})",
+        file.replay.mode.title(),
+        player,
+        claimed_time.as_secs_f64(),
+        validation_hash
+    );
+    Ok(())
+}
+
+// `tstris doctor`: print a report of the setup for a bug report and save a
+// copy alongside the crash reports
+fn doctor() -> Result<()> {
+    if let Ok(supported) = terminal::supports_keyboard_enhancement() {
+        report::set_keyboard_enhancement(supported);
+    }
+    print!("{}", report::bundle(None));
+    let path = report::save(None)?;
+    println!("\nSaved to {}; attach it to your bug report.", path.display());
+    Ok(())
+}
+
+// `tstris ai-battle --games N [--a PRESET] [--b PRESET] [--handicap-a SPEC]
+// [--handicap-b SPEC] [--team-size N] [--targeting even|random|focus]
+// [--seed S] [--watch]`: pit two AI configurations against each other under
+// versus rules, one on one or in teams
+fn ai_battle(args: &[String]) -> Result<()> {
+    let mut games = 100;
+    let mut setup = Setup {
+        sides: [AiConfig::PRESETS[0], AiConfig::PRESETS[1]],
+        handicaps: [Handicap::default(); 2],
+        team_size: 1,
+        targeting: Targeting::Even,
+    };
+    let mut seed = rand::random();
+    let mut watch = false;
+    
+    let handicap = |spec: Option<&String>| -> Result<Handicap> {
+        let spec = spec.ok_or_else(|| Error::usage("missing handicap"))?;
+        Handicap::parse(spec).map_err(Error::usage)
+    };
+    let preset = |name: Option<&String>| -> Result<AiConfig> {
+        let name = name.ok_or_else(|| Error::usage("missing preset name"))?;
+        AiConfig::preset(name).ok_or_else(|| {
+            let names: Vec<_> = AiConfig::PRESETS.iter().map(|config| config.name).collect();
+            Error::usage(format!("unknown AI preset '{}' (available: {})", name, names.join(", ")))
+        })
+    };
+    let targeting = |name: Option<&String>| -> Result<Targeting> {
+        let name = name.ok_or_else(|| Error::usage("missing targeting"))?;
+        Targeting::from_name(name).ok_or_else(|| {
+            let names: Vec<_> = Targeting::ALL.iter().map(|targeting| targeting.name()).collect();
+            Error::usage(format!("unknown targeting '{}' (available: {})", name, names.join(", ")))
+        })
+    };
+    
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = args.next().ok_or_else(|| Error::usage("missing game count"))?.parse()?,
+            "--a" => setup.sides[0] = preset(args.next())?,
+            "--b" => setup.sides[1] = preset(args.next())?,
+            "--handicap-a" => setup.handicaps[0] = handicap(args.next())?,
+            "--handicap-b" => setup.handicaps[1] = handicap(args.next())?,
+            "--team-size" => setup.team_size = args.next().ok_or_else(|| Error::usage("missing team size"))?.parse()?,
+            "--targeting" => setup.targeting = targeting(args.next())?,
+            "--seed" => seed = args.next().ok_or_else(|| Error::usage("missing seed"))?.parse()?,
+            "--watch" => watch = true,
+            other => return Err(Error::usage(format!("unknown option '{}'", other))),
+        }
+    }
+    if !(1..=4).contains(&setup.team_size) {
+        return Err(Error::usage("team size must be 1 to 4"));
+    }
+    
+    if watch {
+        watch_battle(&setup, seed)?;
+    }
+    
+    let result = battle::run_battle(&setup, games, seed);
+    if setup.team_size == 1 {
+        println!("{} games, seed {}", result.games, seed);
+    } else {
+        let size = setup.team_size;
+        println!("{} games, seed {}, {}v{}, {} targeting", result.games, seed, size, size, setup.targeting.name());
+    }
+    for (side, config) in setup.sides.iter().enumerate() {
+        println!(
+            "{}: {:<10} {:>4} wins ({:5.1}%)  {:6.1} APM",
+            ["A", "B"][side],
+            config.name,
+            result.wins[side],
+            result.win_rate(side) * 100.0,
+            result.average_apm(side)
+        );
+    }
+    println!("Draws: {}", result.draws);
+    Ok(())
+}
+
+// Play the first game of the batch at real speed, drawing every board: side
+// A on the left, side B on the right, teammates one under the other
+fn watch_battle(setup: &Setup, seed: u64) -> Result<()> {
+    let mut versus = Versus::new(setup, seed);
+    let mut out = stdout();
+    
+    let outcome = loop {
+        let before = versus.elapsed;
+        let outcome = versus.step();
+        std::thread::sleep(Duration::from_secs_f64((versus.elapsed - before).max(0.0)));
+        
+        execute!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        for slot in 0..setup.team_size {
+            let players = [&versus.players[slot], &versus.players[setup.team_size + slot]];
+            let names = [0, 1].map(|side| match setup.team_size {
+                1 => setup.sides[side].name.to_string(),
+                _ => format!("{}{} {}", ["A", "B"][side], slot + 1, setup.sides[side].name),
+            });
+            let [left, right] = players.map(battle::visible_rows);
+            println!("{}  {}", text::pad_right(&text::truncate(&names[0], 22), 22), names[1]);
+            for (l, r) in left.iter().zip(right.iter()) {
+                println!("|{}|  |{}|", l, r);
+            }
+            println!(
+                "{}  {}",
+                text::pad_right(&battle::status_line(players[0]), 22),
+                battle::status_line(players[1])
+            );
+        }
+        
+        if let Some(outcome) = outcome {
+            break outcome;
+        }
+    };
+    
+    match outcome {
+        Outcome::Win(side) => println!("{} wins\n", setup.sides[side].name),
+        Outcome::Draw => println!("Draw\n"),
+    }
+    Ok(())
+}
+
+// Either the pieces themselves or a file holding them
+fn load_queue(queue: &str) -> Result<Vec<PieceType>> {
+    let path = Path::new(queue);
+    let text = if path.is_file() { std::fs::read_to_string(path)? } else { queue.to_string() };
+    let pieces = parse_queue(&text).map_err(|c| Error::usage(format!("'{}' in queue is not a piece (use I, O, T, S, Z, J, L)", c)))?;
+    if pieces.is_empty() {
+        return Err(Error::usage("queue is empty"));
+    }
+    Ok(pieces)
+}
+
+// The built-in set from the named puzzle on, one puzzle file, or every
+// .toml file in a directory
+fn load_puzzles(arg: Option<&String>) -> Result<Vec<Puzzle>> {
+    let built_in = Puzzle::built_in();
+    let Some(arg) = arg else {
+        return Ok(built_in);
+    };
+    let path = Path::new(arg);
+    if path.is_dir() {
+        let mut paths: Vec<_> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        if paths.is_empty() {
+            return Err(Error::usage(format!("no .toml puzzles in {}", arg)));
+        }
+        paths.sort();
+        return paths.iter().map(|path| load_puzzle(path)).collect();
+    }
+    if path.is_file() {
+        return Ok(vec![load_puzzle(path)?]);
+    }
+    match built_in.iter().position(|puzzle| puzzle.name.eq_ignore_ascii_case(arg)) {
+        Some(i) => Ok(built_in[i..].to_vec()),
+        None => {
+            let names: Vec<_> = built_in.iter().map(|puzzle| puzzle.name.as_str()).collect();
+            Err(Error::usage(format!("no puzzle or file '{}' (built in: {})", arg, names.join(", "))))
+        }
+    }
+}
+
+fn load_puzzle(path: &Path) -> Result<Puzzle> {
+    let text = std::fs::read_to_string(path)?;
+    let name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+    Puzzle::parse(&text, &name).map_err(|e| Error::Puzzle(path.display().to_string(), e))
+}
+
+fn koth_players(names: &[String]) -> Result<Vec<String>> {
+    if names.len() < 2 {
+        return Err(Error::usage("usage: tstris koth <player> <player> [<player>...]"));
+    }
+    for (i, name) in names.iter().enumerate() {
+        if text::width(name) > NAME_MAX_WIDTH {
+            return Err(Error::usage(format!("player name '{}' is longer than {} columns", name, NAME_MAX_WIDTH)));
+        }
+        if names[..i].contains(name) {
+            return Err(Error::usage(format!("player '{}' is listed twice", name)));
+        }
+    }
+    Ok(names.to_vec())
+}
+
+// Finds the opponent for `tstris host` or `tstris join`
+fn connect(args: &mut Vec<String>) -> Result<TcpStream> {
+    let relay = match take_option(args, "--relay")? {
+        Some(relay) => Some(relay),
+        None => config::load().ok().and_then(|(config, _)| config.netplay.relay),
+    };
+    let relay = relay.as_deref();
+    if args[1] == "host" {
+        let port = take_option(args, "--port")?.map(|port| port.parse()).transpose()?.unwrap_or(NETPLAY_PORT);
+        return Ok(match relay {
+            Some(relay) => relay::host(relay, port, |code| {
+                println!("Room {}: your opponent joins with `tstris join {}` (Ctrl+C to give up)...", code, code);
+            })?,
+            None => {
+                println!("Waiting for an opponent on port {} (Ctrl+C to give up)...", port);
+                netplay::host(port)?
+            }
+        });
+    }
+    let target = args.get(2).ok_or_else(|| Error::usage("usage: tstris join <host:port or room code>"))?;
+    if relay::is_room_code(target) {
+        let relay = relay.ok_or_else(|| Error::usage("joining by room code needs a relay: --relay <address> or relay under [netplay]"))?;
+        println!("Joining room {}...", target);
+        return Ok(relay::join(relay, target)?);
+    }
+    let address = if target.contains(':') { target.clone() } else { format!("{}:{}", target, NETPLAY_PORT) };
+    println!("Connecting to {}...", address);
+    Ok(netplay::join(&address)?)
+}
+
+// Removes `<flag> <value>` from the arguments, wherever it appears
+fn take_option(args: &mut Vec<String>, flag: &str) -> Result<Option<String>> {
+    let Some(i) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    if i + 1 >= args.len() {
+        return Err(Error::usage(format!("missing value for {}", flag)));
+    }
+    let value = args.remove(i + 1);
+    args.remove(i);
+    Ok(Some(value))
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let found = args.iter().position(|arg| arg == flag).map(|i| args.remove(i));
+    found.is_some()
+}
 
-use game::Game;
-use input::handle_input;
-use ui::ui;
+// Pulls `--log-file <path>` and `--log-level <level>` out of the arguments
+// and starts logging if a file was given
+fn init_logging(args: &mut Vec<String>) -> Result<()> {
+    let log_file = take_option(args, "--log-file")?;
+    let log_level = take_option(args, "--log-level")?;
+    match log_file {
+        Some(path) => logging::init(Path::new(&path), log_level.as_deref()),
+        None if log_level.is_some() => Err(Error::usage("--log-level needs --log-file")),
+        None => Ok(()),
+    }
+}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!(error = %e, "exiting");
+            eprintln!("tstris: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<()> {
+    let mut args: Vec<String> = std::env::args().collect();
+    init_logging(&mut args)?;
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting");
+    
+    match args.get(1).map(String::as_str) {
+        Some("verify") => {
+            let path = args.get(2).ok_or_else(|| Error::usage("usage: tstris verify <replay.json>"))?;
+            return verify_replay(Path::new(path));
+        }
+        Some("ai-battle") => return ai_battle(&args[2..]),
+        Some("doctor") => return doctor(),
+        Some("relay") => return relay::serve(&args[2..]),
+        #[cfg(feature = "ssh")]
+        Some("serve-ssh") => return ssh::serve(&args[2..]),
+        #[cfg(not(feature = "ssh"))]
+        Some("serve-ssh") => return Err(Error::usage("this build has no SSH server; rebuild with --features ssh")),
+        _ => {}
+    }
+    
+    // `tstris replay <replay.json>`: open straight into playback
+    let watch = match args.get(1).map(String::as_str) {
+        Some("replay") => {
+            let path = args.get(2).ok_or_else(|| Error::usage("usage: tstris replay <replay.json>"))?;
+            tracing::info!(path, "opening replay");
+            Some(persistence::load_replay(Path::new(path))?.replay)
+        }
+        _ => None,
+    };
+    
+    // `--spectate <port>`: stream the screen for others to watch with `nc`
+    let spectate_port: Option<u16> = take_option(&mut args, "--spectate")?.map(|port| port.parse()).transpose()?;
+    let mut spectate = spectate_port.map(Broadcast::start).transpose()?;
+    
+    // `--record-cast <file>`: save the session as an asciinema recording
+    let cast = take_option(&mut args, "--record-cast")?;
+    
+    // `--queue <pieces or file>`: deal these pieces first in every run
+    let queue = take_option(&mut args, "--queue")?.map(|queue| load_queue(&queue)).transpose()?;
+    
+    // `--big-pieces`: some pieces come at twice the size
+    let big_pieces = take_flag(&mut args, "--big-pieces");
+    
+    // `--blind <next,hold,ghost>`: play without some of the usual help
+    let blind = take_option(&mut args, "--blind")?
+        .map(|text| {
+            Blind::parse(&text).map_err(|word| Error::usage(format!("can't play blind to '{}' (use next, hold or ghost)", word)))
+        })
+        .transpose()?;
+    
+    // `--custom <name>`: play a custom mode from the config's [modes]
+    let custom = take_option(&mut args, "--custom")?;
+    
+    // `--predict`: call each next piece with the Next panel hidden
+    let predict = take_flag(&mut args, "--predict");
+    
+    // `--two-boards`: practice each hand on a board of its own
+    let two_boards = take_flag(&mut args, "--two-boards");
+    
+    // `--versus`: two players on one keyboard, a board each, with handicaps.
+    // `--teams`: the same, two on two, with a CPU teammate each.
+    let versus = take_flag(&mut args, "--versus");
+    let teams = take_flag(&mut args, "--teams");
+    
+    // `--skip-setup`: straight to the game, even on a first launch
+    let skip_setup = take_flag(&mut args, "--skip-setup");
+    
+    // `--frame-step`: debugging, the game only moves a tick per '.' press
+    let frame_step = take_flag(&mut args, "--frame-step");
+    
+    // `--race <2.5pps or finish time>`: race a CPU through every sprint
+    let race = take_option(&mut args, "--race")?
+        .map(|pace| Pace::parse(&pace).ok_or_else(|| Error::usage(format!("invalid race pace '{}' (e.g. 2.5pps or 1:05)", pace))))
+        .transpose()?;
+    
+    // `tstris koth <player> <player>...`: king of the hill on one keyboard
+    let koth = match args.get(1).map(String::as_str) {
+        Some("koth") => Some(koth_players(&args[2..])?),
+        _ => None,
+    };
+    
+    // `tstris host [--port N] [--relay <address>]` and `tstris join <host:port
+    // or room code>`: online versus. With a relay, from --relay or the
+    // config, hosting opens a room to join by its code. The connection is
+    // made before the game starts.
+    let opponent = match args.get(1).map(String::as_str) {
+        Some("host" | "join") => Some(connect(&mut args)?),
+        _ => None,
+    };
+    
+    // `tstris calibrate`: measure input and display latency by tapping along
+    let calibrate = args.get(1).map(String::as_str) == Some("calibrate");
+    
+    // `tstris puzzle [name | file.toml | directory]`: gravity-off puzzles
+    let puzzles = match args.get(1).map(String::as_str) {
+        Some("puzzle") => Some(load_puzzles(args.get(2))?),
+        _ => None,
+    };
+    
+    report::install_panic_hook();
+    
     // Setup terminal
+    let mut stdout = Recorder::new(stdout(), cast.as_deref().map(Path::new))?;
     terminal::enable_raw_mode()?;
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableFocusChange)?;
     
     // Try to enable keyboard enhancement for better key release detection
     let keyboard_enhancement_active = matches!(
@@ -42,49 +451,101 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         ),
         Ok(())
     );
+    // tmux and screen take the flags whether or not they pass them on, so
+    // under one the terminal is asked whether they got through
+    let multiplexer = Multiplexer::detect();
+    let keyboard_enhancement_active = keyboard_enhancement_active
+        && (multiplexer.is_none() || terminal::supports_keyboard_enhancement().unwrap_or(false));
+    
+    tracing::info!(keyboard_enhancement_active, multiplexer = multiplexer.map(Multiplexer::name), "terminal ready");
+    report::set_keyboard_enhancement(keyboard_enhancement_active);
     
     let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let terminal = Terminal::new(backend)?;
 
-    let mut game = Game::new();
-    game.input_state.keyboard_enhancement_active = keyboard_enhancement_active;
+    let mut app = App::new();
+    if let Some(replay) = watch {
+        app.watch(replay);
+    }
+    app.game.input_state.keyboard_enhancement_active = keyboard_enhancement_active;
+    if let Some(multiplexer) = multiplexer.filter(|_| !keyboard_enhancement_active) {
+        app.game.input_state.release_guess = multiplexer.release_guess();
+        app.toasts.push_for(ToastKind::Warning, app.strings.get(multiplexer.hint()), Duration::from_millis(TOAST_MS * 3));
+    }
+    if skip_setup {
+        app.wizard = None;
+    }
+    if let Some(players) = koth {
+        app.start_koth(players);
+    }
+    if let Some(puzzles) = puzzles {
+        app.start_puzzles(puzzles);
+    }
+    if calibrate {
+        app.start_calibration();
+    }
+    if let Some(stream) = opponent {
+        app.start_netplay(stream)?;
+    }
+    if let Some(queue) = queue {
+        app.toasts.push(ToastKind::Info, app.strings.fmt("toast.queue", &[&queue.len()]));
+        app.game.set_queue(queue);
+    }
+    if big_pieces {
+        app.toasts.push(ToastKind::Info, app.strings.get("toast.big_pieces"));
+        app.game.set_big_pieces(true);
+    }
+    if let Some(blind) = blind.filter(|blind| !blind.is_none()) {
+        app.toasts.push(ToastKind::Info, app.strings.fmt("toast.blind", &[&app.strings.blind(blind)]));
+        app.game.blind = blind;
+    }
+    if let Some(name) = custom {
+        app.start_custom(&name);
+    }
+    if predict {
+        app.toasts.push(ToastKind::Info, app.strings.get("toast.predict"));
+        app.prediction = Some(Prediction::default());
+    }
+    if two_boards {
+        app.toasts.push(ToastKind::Info, app.strings.get("toast.two_boards"));
+        app.start_two_boards();
+    }
+    if versus || teams {
+        app.toasts.push(ToastKind::Info, app.strings.get("toast.versus"));
+        app.start_versus(teams);
+    }
+    if frame_step {
+        app.toasts.push(ToastKind::Info, app.strings.get("toast.frame_step"));
+        app.start_frame_step();
+    }
+    if let Some(pace) = race {
+        let label = match pace {
+            Pace::Pps(pps) => format!("{:.2} PPS", pps),
+            Pace::Time(time) => config::format_time(time),
+        };
+        app.toasts.push(ToastKind::Info, app.strings.fmt("toast.race", &[&label]));
+        app.race = Some(pace);
+    }
+    if let Some(port) = spectate_port {
+        app.toasts.push(ToastKind::Info, app.strings.fmt("toast.spectating", &[&port]));
+    }
     
-    // Game loop
-    loop {
-        // Render
-        terminal.draw(|f| ui(f, &game))?;
-        
-        // Handle input
-        if event::poll(Duration::from_millis(8))? {
-            if let Event::Key(KeyEvent { code, kind, modifiers, .. }) = event::read()? {
-                match code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => {
-                        if kind == KeyEventKind::Press {
-                            break;
-                        }
-                    }
-                    KeyCode::Char('r') | KeyCode::Char('R') => {
-                        if kind == KeyEventKind::Press {
-                            game.reset();
-                            game.input_state.keyboard_enhancement_active = keyboard_enhancement_active;
-                        }
-                    }
-                    _ => {
-                        handle_input(&mut game, code, kind, modifiers);
-                    }
-                }
-            }
+    let mut terminal = session::run_loop(&mut app, terminal, spectate.as_mut(), |timeout| {
+        if event::poll(timeout)? {
+            Ok(Some(event::read()?))
+        } else {
+            Ok(None)
         }
-        
-        // Update game state
-        game.update()?;
-    }
+    })?;
+
+    // Keep Zen progress made since the last autosave
+    app.save_zen();
 
     // Cleanup
     if keyboard_enhancement_active {
         execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
     }
-    execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    execute!(terminal.backend_mut(), DisableMouseCapture, DisableFocusChange)?;
     terminal::disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
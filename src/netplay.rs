@@ -0,0 +1,528 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::battle::Handicap;
+use crate::constants::{
+    BOARD_HEIGHT, NETPLAY_BATCH_EVENTS, NETPLAY_CHAT_CHARS, NETPLAY_CHAT_LINES, NETPLAY_LINE_BYTES, NETPLAY_PART_CHARS, NETPLAY_PING_MS, NETPLAY_QUEUE_MESSAGES, NETPLAY_RESYNC_BYTES, NETPLAY_ROLLBACK_MS, NETPLAY_SNAPSHOT_MS, NETPLAY_VERSION,
+};
+use crate::game::event::TimedEvent;
+use crate::game::replay::{InputEvent, Replay, ReplayEvent};
+use crate::game::rollback::Rollback;
+use crate::game::state::{EndReason, GameSnapshot, GameState};
+use crate::game::Game;
+use crate::versus::{cancel, Attacks, Incoming};
+
+// What the two sides of a match say to each other, one JSON object per line.
+// Each side sends its own inputs as they're recorded and the other plays
+// them on a copy of its game, so no board crosses the wire unless that copy
+// drifts from the real one and needs a snapshot of it.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Hello { version: u32, name: String },
+    Start { replay: Box<Replay> }, // A new run, by its replay header
+    Inputs { seed: u64, from: usize, events: Vec<ReplayEvent> }, // From index `from` of the run's replay on
+    Attack { seed: u64, rows: u32, at_nanos: u64 }, // Garbage sent at this point in the sender's run
+    Check { seed: u64, events: usize, hash: u64 }, // State hash after the first `events` inputs, sent with each placement
+    Resync { seed: u64 }, // The board played here from the sender's inputs drifted; send a snapshot
+    Snapshot { seed: u64, part: String, last: bool }, // The next piece of one, as JSON cut to fit in lines
+    Ping { nanos: u64 },
+    Pong { nanos: u64 },
+    Chat { text: String, emote: bool },
+    Handicap { handicap: Handicap }, // What the sender set on the match setup screen
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Won,
+    Lost,
+}
+
+// Things the UI should tell the player about
+pub enum MatchEvent {
+    Joined(String),
+    Left,
+    Decided(Outcome),
+}
+
+// A line of chat, either side's
+pub struct ChatLine {
+    pub ours: bool,
+    pub text: String,
+}
+
+// The connection itself: a thread reading messages in and one writing them
+// out, so a slow network never holds up the game
+struct Link {
+    outgoing: SyncSender<Message>,
+    incoming: Receiver<Message>,
+}
+
+impl Link {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let (outgoing, queue) = sync_channel(NETPLAY_QUEUE_MESSAGES);
+        let (deliver, incoming) = sync_channel(NETPLAY_QUEUE_MESSAGES);
+        std::thread::spawn(move || send_messages(stream, queue));
+        std::thread::spawn(move || read_messages(reader, deliver));
+        Ok(Self { outgoing, incoming })
+    }
+
+    // False once the connection is gone or has fallen too far behind to
+    // catch up
+    fn send(&self, message: Message) -> bool {
+        match self.outgoing.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("opponent's connection stalled");
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+fn send_messages(mut stream: TcpStream, queue: Receiver<Message>) {
+    for message in queue {
+        let Ok(mut line) = serde_json::to_vec(&message) else {
+            continue;
+        };
+        line.push(b'\n');
+        if stream.write_all(&line).is_err() {
+            return;
+        }
+    }
+}
+
+// Ends at the first line that isn't a message, or that runs on past
+// NETPLAY_LINE_BYTES, which leaves the match
+fn read_messages(mut reader: BufReader<TcpStream>, deliver: SyncSender<Message>) {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match (&mut reader).take(NETPLAY_LINE_BYTES as u64).read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) if line.last() != Some(&b'\n') => {
+                tracing::warn!(bytes = line.len(), "message from opponent too long or cut off");
+                return;
+            }
+            Ok(_) => {}
+        }
+        match serde_json::from_slice(&line) {
+            Ok(message) => {
+                if deliver.send(message).is_err() {
+                    return;
+                }
+            }
+            Err(error) => {
+                tracing::warn!(%error, "unreadable message from opponent");
+                return;
+            }
+        }
+    }
+}
+
+// `tstris host`: wait for someone to connect
+pub fn host(port: u16) -> io::Result<TcpStream> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    let (stream, peer) = listener.accept()?;
+    tracing::info!(%peer, "opponent connected");
+    Ok(stream)
+}
+
+// `tstris join`: connect to someone hosting
+pub fn join(address: &str) -> io::Result<TcpStream> {
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no address for {}", address)))?;
+    let stream = TcpStream::connect(address)?;
+    tracing::info!(peer = %address, "connected to host");
+    Ok(stream)
+}
+
+// One side of an online versus match. Either player starting a run starts
+// it for both on the same seed, and from there each plays their own board
+// while the other's is played here from their inputs (see Rollback).
+// Clears send garbage under the same rules as AI battles, times the
+// sender's handicap; it lands the input delay after it was sent. Garbage
+// that turns up later than that rewinds this board to a snapshot from
+// before it was due and plays it in there, so both sides agree on where it
+// landed.
+pub struct Netplay {
+    link: Option<Link>,
+    pub opponent: Option<String>, // Their name, once they've said hello
+    pub mirror: Option<Rollback>, // Their current run
+    input_delay: Duration,
+    run: u32,    // Our game's run last announced
+    sent: usize, // Inputs of that run sent so far
+    snapshots: VecDeque<GameSnapshot>, // Recent states of our board, oldest first
+    incoming: VecDeque<Incoming>, // Garbage on its way to our board
+    attacks: Option<Attacks>,     // Our run's clears so far
+    pub their_handicap: Handicap,
+    pub outcome: Option<Outcome>,
+    pub ping: Option<Duration>, // Latest round trip
+    last_ping: Option<Instant>,
+    started: Instant, // Ping timestamps count from here
+    pub chat: VecDeque<ChatLine>, // The latest lines, oldest first
+    pub emote: Option<(String, Instant)>, // Their latest emote and when it came
+    hash_sent: u64,         // Our state hash as last checked against theirs
+    resync: Option<String>, // Their snapshot so far, while one is asked for
+}
+
+impl Netplay {
+    pub fn start(stream: TcpStream, name: &str, input_delay: Duration) -> io::Result<Self> {
+        let link = Link::new(stream)?;
+        link.send(Message::Hello { version: NETPLAY_VERSION, name: name.to_string() });
+        Ok(Self {
+            link: Some(link),
+            opponent: None,
+            mirror: None,
+            input_delay,
+            run: 0,
+            sent: 0,
+            snapshots: VecDeque::new(),
+            incoming: VecDeque::new(),
+            attacks: None,
+            their_handicap: Handicap::default(),
+            outcome: None,
+            ping: None,
+            last_ping: None,
+            started: Instant::now(),
+            chat: VecDeque::new(),
+            emote: None,
+            hash_sent: 0,
+            resync: None,
+        })
+    }
+
+    pub fn connected(&self) -> bool {
+        self.link.is_some()
+    }
+
+    fn send(&mut self, message: Message) {
+        if self.link.as_ref().is_some_and(|link| !link.send(message)) {
+            self.link = None;
+        }
+    }
+
+    // Ours as just set, for their setup screen; their copy of our board
+    // plays under it from the next run's header
+    pub fn share_handicap(&mut self, handicap: Handicap) {
+        self.send(Message::Handicap { handicap });
+    }
+
+    // A chat line, or an emote to flash over our board on their side
+    pub fn say(&mut self, text: &str, emote: bool) {
+        let text = self.log_chat(true, text);
+        self.send(Message::Chat { text, emote });
+    }
+
+    // Control characters would upset the terminal, and the panel only has
+    // room for so much
+    fn log_chat(&mut self, ours: bool, text: &str) -> String {
+        let text: String = text.chars().filter(|c| !c.is_control()).take(NETPLAY_CHAT_CHARS).collect();
+        self.chat.push_back(ChatLine { ours, text: text.clone() });
+        if self.chat.len() > NETPLAY_CHAT_LINES {
+            self.chat.pop_front();
+        }
+        text
+    }
+
+    // Once per App update, with the reports the game just gave
+    pub fn update(&mut self, game: &mut Game, events: &[TimedEvent]) -> Vec<MatchEvent> {
+        if !self.connected() {
+            return Vec::new();
+        }
+        let mut news = self.receive(game);
+        if game.runs != self.run && matches!(game.game_state, GameState::Countdown(_) | GameState::Playing) {
+            self.announce(game);
+        }
+        if game.runs == self.run {
+            self.send_attacks(game, events);
+            self.land_garbage(game);
+            self.keep_snapshot(game);
+            self.send_inputs(game);
+            self.send_check(game);
+        }
+        if let Some(mirror) = self.mirror.as_mut() {
+            mirror.advance(game.replay_elapsed());
+        }
+        if let Some(outcome) = self.decide(game) {
+            news.push(MatchEvent::Decided(outcome));
+        }
+        if self.last_ping.is_none_or(|last| last.elapsed() >= Duration::from_millis(NETPLAY_PING_MS)) {
+            self.last_ping = Some(Instant::now());
+            let nanos = self.started.elapsed().as_nanos() as u64;
+            self.send(Message::Ping { nanos });
+        }
+        if !self.connected() {
+            news.push(MatchEvent::Left);
+        }
+        news
+    }
+
+    fn receive(&mut self, game: &mut Game) -> Vec<MatchEvent> {
+        let mut news = Vec::new();
+        while let Some(link) = self.link.as_ref() {
+            let message = match link.incoming.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    tracing::info!("opponent left");
+                    self.link = None;
+                    break;
+                }
+            };
+            match message {
+                Message::Hello { version, name } => {
+                    if version != NETPLAY_VERSION {
+                        tracing::warn!(version, "opponent runs another protocol version");
+                        self.link = None;
+                        break;
+                    }
+                    tracing::info!(opponent = %name, "opponent said hello");
+                    self.opponent = Some(name.clone());
+                    self.share_handicap(game.handicap);
+                    news.push(MatchEvent::Joined(name));
+                }
+                Message::Start { replay } => self.follow(game, &replay),
+                Message::Inputs { seed, from, events } => {
+                    if let Some(mirror) = self.mirror.as_mut().filter(|mirror| mirror.seed() == seed) {
+                        mirror.receive(from, events);
+                    }
+                }
+                Message::Attack { seed, rows, at_nanos } => {
+                    if seed == game.seed && game.runs == self.run {
+                        let due = Duration::from_nanos(at_nanos) + self.input_delay;
+                        self.incoming.push_back(Incoming { rows: rows.min(BOARD_HEIGHT as u32), due });
+                    }
+                }
+                Message::Check { seed, events, hash } => self.check(seed, events, hash),
+                Message::Resync { seed } => {
+                    if seed == game.seed && game.runs == self.run {
+                        self.send_snapshot(game);
+                    }
+                }
+                Message::Snapshot { seed, part, last } => self.take_snapshot(seed, &part, last),
+                Message::Ping { nanos } => self.send(Message::Pong { nanos }),
+                Message::Pong { nanos } => {
+                    self.ping = Some(self.started.elapsed().saturating_sub(Duration::from_nanos(nanos)));
+                }
+                Message::Handicap { handicap } => self.their_handicap = handicap,
+                Message::Chat { text, emote } => {
+                    let text = self.log_chat(false, &text);
+                    if emote {
+                        self.emote = Some((text, Instant::now()));
+                    }
+                }
+            }
+        }
+        news
+    }
+
+    // Their new run. If ours is already on the same seed, this is them
+    // following us. If both started at once on different seeds, the lower
+    // seed is the one both end up on.
+    fn follow(&mut self, game: &mut Game, replay: &Replay) {
+        let running = matches!(game.game_state, GameState::Countdown(_) | GameState::Playing);
+        let same = game.seed == replay.seed && game.mode == replay.mode;
+        if running && !same && game.seed < replay.seed {
+            return;
+        }
+        if !(running && same) {
+            game.follow_run(replay);
+        }
+        let origin = game.clock() - game.replay_elapsed();
+        self.mirror = Some(Rollback::new(replay, origin));
+        self.outcome = None;
+        self.resync = None;
+    }
+
+    // Our new run: theirs starts on it too
+    fn announce(&mut self, game: &Game) {
+        self.run = game.runs;
+        self.sent = 0;
+        self.snapshots.clear();
+        self.incoming.clear();
+        self.attacks = Some(Attacks::new(game));
+        self.outcome = None;
+        self.hash_sent = game.state_hash;
+        let replay = Box::new(Replay { events: Vec::new(), ..game.replay.clone() });
+        self.send(Message::Start { replay });
+    }
+
+    // A batch at a time, so a rewind's worth of inputs still fits in lines
+    // of NETPLAY_LINE_BYTES
+    fn send_inputs(&mut self, game: &Game) {
+        while game.replay.events.len() > self.sent {
+            let end = game.replay.events.len().min(self.sent + NETPLAY_BATCH_EVENTS);
+            let events = game.replay.events[self.sent..end].to_vec();
+            let (seed, from) = (game.seed, self.sent);
+            self.sent = end;
+            self.send(Message::Inputs { seed, from, events });
+        }
+    }
+
+    // Each placement's hash goes with the inputs that led to it, for them to
+    // check their copy of our board against
+    fn send_check(&mut self, game: &Game) {
+        if game.state_hash != self.hash_sent {
+            self.hash_sent = game.state_hash;
+            let (seed, events) = (game.seed, game.replay.events.len());
+            self.send(Message::Check { seed, events, hash: game.state_hash });
+        }
+    }
+
+    // Their board played out here doesn't match theirs: log it and ask for
+    // the real one, unless that's on its way already
+    fn check(&mut self, seed: u64, events: usize, hash: u64) {
+        let Some(mirror) = self.mirror.as_ref().filter(|mirror| mirror.seed() == seed) else {
+            return;
+        };
+        if self.resync.is_some() || mirror.agrees(events, hash) {
+            return;
+        }
+        tracing::warn!(events, hash, "desync with the opponent's board, asking for a snapshot");
+        self.resync = Some(String::new());
+        self.send(Message::Resync { seed });
+    }
+
+    // Our board as it stands, after the inputs that led to it so it lands
+    // on top of them
+    fn send_snapshot(&mut self, game: &Game) {
+        self.send_inputs(game);
+        let Ok(json) = serde_json::to_string(&game.snapshot()) else {
+            return;
+        };
+        let chars: Vec<char> = json.chars().collect();
+        let parts = chars.chunks(NETPLAY_PART_CHARS).count();
+        for (i, part) in chars.chunks(NETPLAY_PART_CHARS).enumerate() {
+            let part = part.iter().collect();
+            self.send(Message::Snapshot { seed: game.seed, part, last: i + 1 == parts });
+        }
+        tracing::info!(events = game.replay.events.len(), "sent the opponent a snapshot of our board");
+    }
+
+    fn take_snapshot(&mut self, seed: u64, part: &str, last: bool) {
+        let Some(json) = self.resync.as_mut() else {
+            return;
+        };
+        json.push_str(part);
+        if json.len() > NETPLAY_RESYNC_BYTES {
+            tracing::warn!("opponent's snapshot too large");
+            self.resync = None;
+            return;
+        }
+        if !last {
+            return;
+        }
+        let json = self.resync.take().unwrap_or_default();
+        let Some(mirror) = self.mirror.as_mut().filter(|mirror| mirror.seed() == seed) else {
+            return;
+        };
+        match serde_json::from_str::<GameSnapshot>(&json) {
+            Ok(snapshot) => {
+                mirror.resync(&snapshot);
+                tracing::info!(events = snapshot.replay_len, "opponent's board resynced");
+            }
+            Err(error) => tracing::warn!(%error, "unreadable snapshot from opponent"),
+        }
+    }
+
+    // Each clear's attack first cancels garbage still on its way in, and
+    // the rest goes to them
+    fn send_attacks(&mut self, game: &Game, events: &[TimedEvent]) {
+        let Some(attacks) = self.attacks.as_mut() else {
+            return;
+        };
+        for rows in attacks.sent(game, events) {
+            let rows = cancel(&mut self.incoming, rows);
+            if rows > 0 {
+                let at_nanos = game.replay_elapsed().as_nanos() as u64;
+                self.send(Message::Attack { seed: game.seed, rows, at_nanos });
+            }
+        }
+    }
+
+    // Garbage that's due rises now. Garbage that was due a while ago goes in
+    // where it belonged, from the latest snapshot before then; snapshots
+    // past that point are of a run that no longer happened.
+    fn land_garbage(&mut self, game: &mut Game) {
+        if game.game_state != GameState::Playing {
+            return;
+        }
+        let now = game.replay_elapsed();
+        while self.incoming.front().is_some_and(|incoming| incoming.due <= now) {
+            let Some(Incoming { rows, due }) = self.incoming.pop_front() else {
+                break;
+            };
+            let late = now - due > Duration::from_millis(NETPLAY_SNAPSHOT_MS);
+            match self.snapshots.iter().rposition(|snapshot| snapshot.elapsed <= due).filter(|_| late) {
+                Some(i) => {
+                    tracing::debug!(late_ms = (now - due).as_millis() as u64, rows, "garbage rolled back in");
+                    let snapshot = self.snapshots[i].clone();
+                    game.insert_input(&snapshot, due, InputEvent::Garbage(rows));
+                    self.snapshots.truncate(i + 1);
+                    self.sent = self.sent.min(snapshot.replay_len);
+                }
+                None => game.apply_input(InputEvent::Garbage(rows)),
+            }
+        }
+    }
+
+    fn keep_snapshot(&mut self, game: &Game) {
+        if game.game_state != GameState::Playing {
+            return;
+        }
+        let now = game.replay_elapsed();
+        let due = self
+            .snapshots
+            .back()
+            .is_none_or(|last| now >= last.elapsed + Duration::from_millis(NETPLAY_SNAPSHOT_MS));
+        if due {
+            self.snapshots.push_back(game.snapshot());
+        }
+        let oldest = now.saturating_sub(Duration::from_millis(NETPLAY_ROLLBACK_MS));
+        while self.snapshots.front().is_some_and(|snapshot| snapshot.elapsed < oldest) {
+            self.snapshots.pop_front();
+        }
+    }
+
+    // Whichever run ended first settles the match: completing it wins and
+    // topping out loses. Ours ending only counts once their inputs have
+    // caught up to that point, in case theirs ended sooner. A run of ours
+    // still going when theirs settles it ends there.
+    fn decide(&mut self, game: &mut Game) -> Option<Outcome> {
+        let mirror = self.mirror.as_ref().filter(|mirror| mirror.seed() == game.seed)?;
+        if self.outcome.is_some() || game.runs != self.run {
+            return None;
+        }
+        let ours = (game.game_state == GameState::Finished)
+            .then(|| (game.final_time.unwrap_or_default(), game.end_reason == Some(EndReason::Completed)));
+        let outcome = match (ours, mirror.ended) {
+            (_, Some((theirs, completed))) if ours.is_none_or(|(ours, _)| theirs < ours) => {
+                let outcome = if completed { Outcome::Lost } else { Outcome::Won };
+                game.end_match(outcome == Outcome::Won);
+                outcome
+            }
+            (Some((ours, completed)), theirs) if theirs.is_some() || mirror.confirmed_time >= ours => {
+                if completed {
+                    Outcome::Won
+                } else {
+                    Outcome::Lost
+                }
+            }
+            _ => return None,
+        };
+        tracing::info!(?outcome, "match decided");
+        self.outcome = Some(outcome);
+        Some(outcome)
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+// When the main loop draws, apart from how often it ticks the game. Frames
+// are due on a fixed schedule rather than a set time after the last one, so
+// a late wakeup is made up on the next frame instead of pushing every frame
+// after it back.
+#[derive(Clone, Debug)]
+pub struct FramePacer {
+    interval: Option<Duration>, // None draws every tick
+    next: Instant,
+}
+
+impl FramePacer {
+    // 0 is uncapped
+    pub fn new(max_fps: u32, now: Instant) -> Self {
+        let interval = (max_fps > 0).then(|| Duration::from_secs(1) / max_fps);
+        Self { interval, next: now }
+    }
+
+    pub fn due(&self, now: Instant) -> bool {
+        self.interval.is_none() || now >= self.next
+    }
+
+    // A frame went out at `now`; more than a frame behind, the schedule
+    // starts over rather than drawing a burst to catch up
+    pub fn drawn(&mut self, now: Instant) {
+        if let Some(interval) = self.interval {
+            self.next += interval;
+            if self.next <= now {
+                self.next = now + interval;
+            }
+        }
+    }
+
+    // How long to wait for input: until the next tick or the next frame,
+    // whichever comes first
+    pub fn wait(&self, now: Instant, tick: Duration) -> Duration {
+        match self.interval {
+            Some(_) => self.next.saturating_duration_since(now).min(tick),
+            None => tick,
+        }
+    }
+}
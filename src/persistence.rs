@@ -0,0 +1,200 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::blind::Blind;
+use crate::game::replay::Replay;
+use crate::game::state::EndReason;
+use crate::game::usage::ActionCounts;
+use crate::game::{Game, GameMode};
+
+const RUNS_FILE: &str = "runs.jsonl";
+const PBS_FILE: &str = "pbs.json";
+const REPLAY_DIR: &str = "replays";
+const ZEN_FILE: &str = "zen.json";
+const REPORT_DIR: &str = "reports";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub mode: GameMode,
+    pub completed: bool,
+    pub time_ms: u64,
+    pub lines: u32,
+    pub pieces: u32,
+    pub misdrops: u32,
+    pub timestamp: u64, // Unix seconds
+    #[serde(default)]
+    pub player: String,
+    #[serde(default, skip_serializing_if = "Blind::is_none")]
+    pub blind: Blind,
+    #[serde(default, skip_serializing_if = "ActionCounts::is_empty")]
+    pub actions: ActionCounts,
+}
+
+impl RunRecord {
+    pub fn from_game(game: &Game) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            mode: game.mode,
+            completed: game.end_reason == Some(EndReason::Completed),
+            time_ms: game.final_time.map_or(0, |t| t.as_millis() as u64),
+            lines: game.lines_cleared,
+            pieces: game.pieces_placed,
+            misdrops: game.misdrops,
+            timestamp,
+            player: game.player.clone(),
+            blind: game.blind,
+            actions: game.actions,
+        }
+    }
+
+    pub fn time(&self) -> Duration {
+        Duration::from_millis(self.time_ms)
+    }
+
+    pub fn misdrop_rate(&self) -> f64 {
+        if self.pieces == 0 {
+            0.0
+        } else {
+            self.misdrops as f64 / self.pieces as f64
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersonalBest {
+    pub mode: GameMode,
+    pub time_nanos: u64,
+    pub board_hash: u64,
+    pub validation_hash: u64, // From re-simulating the replay; see replay::validation_hash
+    pub timestamp: u64,
+}
+
+impl PersonalBest {
+    pub fn time(&self) -> Duration {
+        Duration::from_nanos(self.time_nanos)
+    }
+}
+
+// A replay together with the result it claims, as written to disk
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub replay: Replay,
+    pub time_nanos: u64,
+    pub board_hash: u64,
+    pub validation_hash: u64,
+}
+
+// Zen progress, autosaved while playing and restored on the next launch
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ZenSave {
+    pub lines: u32,
+    pub score: u64,
+    pub pieces: u32,
+    pub board: Vec<String>, // See board::board_to_rows
+    pub lifetime_lines: u64,
+}
+
+// Where saved data lives; TSTRIS_DATA_DIR overrides the platform default
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("TSTRIS_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::data_dir().map(|dir| dir.join("tstris"))
+}
+
+pub fn load_runs() -> Vec<RunRecord> {
+    let Some(path) = data_dir().map(|dir| dir.join(RUNS_FILE)) else {
+        return Vec::new();
+    };
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+
+    // Skip lines we can't parse rather than throwing away the whole history
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+fn ensure_data_dir() -> io::Result<PathBuf> {
+    let dir = data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+pub fn append_run(record: &RunRecord) -> io::Result<()> {
+    let dir = ensure_data_dir()?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(RUNS_FILE))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+pub fn load_pbs() -> Vec<PersonalBest> {
+    data_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(PBS_FILE)).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn pb_replay_path(mode: GameMode) -> Option<PathBuf> {
+    data_dir().map(|dir| {
+        dir.join(REPLAY_DIR)
+            .join(format!("pb-{}.json", mode.short_name().to_lowercase()))
+    })
+}
+
+// Replace the PB for its mode and write its replay alongside
+pub fn save_pb(pbs: &[PersonalBest], replay: &ReplayFile) -> io::Result<()> {
+    let dir = ensure_data_dir()?;
+    fs::write(dir.join(PBS_FILE), serde_json::to_string_pretty(pbs)?)?;
+
+    if let Some(path) = pb_replay_path(replay.replay.mode) {
+        save_replay(&path, replay)?;
+    }
+    Ok(())
+}
+
+pub fn save_replay(path: &Path, replay: &ReplayFile) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(replay)?)
+}
+
+// A bug report bundle; see report::bundle
+pub fn save_report(name: &str, contents: &str) -> io::Result<PathBuf> {
+    let dir = ensure_data_dir()?.join(REPORT_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(name);
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+pub fn load_replay(path: &Path) -> io::Result<ReplayFile> {
+    let contents = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+pub fn load_zen() -> Option<ZenSave> {
+    let contents = fs::read_to_string(data_dir()?.join(ZEN_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+pub fn save_zen(save: &ZenSave) -> io::Result<()> {
+    let dir = ensure_data_dir()?;
+    fs::write(dir.join(ZEN_FILE), serde_json::to_string(save)?)
+}
@@ -0,0 +1,45 @@
+use crate::game::piece::PieceType;
+use crate::game::Game;
+
+// Next piece drill: the Next panel is hidden, and after each piece comes out
+// the player calls the one after it by typing its letter. The call is judged
+// when that piece leaves the queue, so counting the bag is the only way to
+// get it right.
+#[derive(Clone, Debug, Default)]
+pub struct Prediction {
+    target: Option<(PieceType, u32)>, // Piece to call, and pieces drawn while it was at the front of the queue
+    pub guess: Option<PieceType>,
+    pub last: Option<(PieceType, bool)>, // Last piece judged and whether it was called
+    pub correct: u32,
+    pub total: u32,
+}
+
+impl Prediction {
+    // The first call for each piece is the one that counts
+    pub fn guess(&mut self, piece_type: PieceType) {
+        if self.target.is_some() && self.guess.is_none() {
+            self.guess = Some(piece_type);
+        }
+    }
+
+    // Judge the call once its piece comes out, then move on to the one behind
+    // it. A piece that comes out before it was called counts as a miss.
+    pub fn follow(&mut self, game: &Game) {
+        let drawn = game.pieces_drawn();
+        if let Some((piece_type, at)) = self.target {
+            if drawn == at {
+                return;
+            }
+            let called = self.guess == Some(piece_type);
+            self.total += 1;
+            self.correct += called as u32;
+            self.last = Some((piece_type, called));
+        }
+        self.guess = None;
+        self.target = game.next_pieces.first().map(|piece| (piece.piece_type, drawn));
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.correct as f64 / self.total as f64)
+    }
+}
@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+use crate::constants::TICK_BUDGET_MS;
+
+// Average and worst of one kind of sample over a second
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timing {
+    total: Duration,
+    pub max: Duration,
+    count: u32,
+}
+
+impl Timing {
+    fn add(&mut self, sample: Duration) {
+        self.total += sample;
+        self.max = self.max.max(sample);
+        self.count += 1;
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total / self.count
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Window {
+    pub render: Timing,
+    pub update: Timing,
+    pub input: Timing, // Key press read to the end of the frame that shows it
+    pub frames: u32,
+    pub dropped_ticks: u32,
+}
+
+// Main loop timings, summed per second; the overlay shows the last full second
+#[derive(Clone, Debug)]
+pub struct Profiler {
+    pub last: Window,
+    current: Window,
+    window_start: Instant,
+    last_tick: Option<Instant>,
+    pending_input: Option<Instant>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            last: Window::default(),
+            current: Window::default(),
+            window_start: Instant::now(),
+            last_tick: None,
+            pending_input: None,
+        }
+    }
+
+    // Start of a loop iteration; a late one means the loop dropped a tick
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(last) = self.last_tick {
+            if now.duration_since(last) > Duration::from_millis(TICK_BUDGET_MS) {
+                self.current.dropped_ticks += 1;
+            }
+        }
+        self.last_tick = Some(now);
+
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.last = std::mem::take(&mut self.current);
+            self.window_start = now;
+        }
+    }
+
+    // The loop is about to wait idle, so the next gap isn't a dropped tick
+    pub fn skip_tick(&mut self) {
+        self.last_tick = None;
+    }
+
+    pub fn record_render(&mut self, started: Instant, now: Instant) {
+        self.current.render.add(now.duration_since(started));
+        self.current.frames += 1;
+        // The frame just drawn is the first to show the pending input
+        if let Some(input) = self.pending_input.take() {
+            self.current.input.add(now.duration_since(input));
+        }
+    }
+
+    pub fn record_update(&mut self, started: Instant, now: Instant) {
+        self.current.update.add(now.duration_since(started));
+    }
+
+    // Only the first press before a frame is timed
+    pub fn record_input(&mut self, at: Instant) {
+        self.pending_input.get_or_insert(at);
+    }
+}
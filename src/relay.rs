@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{NETPLAY_DIRECT_TIMEOUT_MS, NETPLAY_MAX_ROOMS, NETPLAY_RELAY_PORT, NETPLAY_ROOM_SECS};
+use crate::error::{Error, Result};
+
+// How players find each other through a relay, before the game itself
+// starts. A host opens a room and gets its code; whoever joins with that
+// code is told the host's address and tries it directly. If that fails
+// (usually a router in the way), the relay carries the game between them.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Rendezvous {
+    Host { port: u16 },       // Host to relay: open a room; direct connections come in on this port
+    Room { code: String },    // Relay to host
+    Join { code: String },    // Joiner to relay
+    Peer { address: String }, // Relay to joiner: the host's address to try
+    NoRoom,                   // Relay to joiner: no room by that code
+    Direct,                   // Joiner to relay, passed on to the host: connected straight through
+    Relay,                    // Joiner to relay: couldn't connect, carry the game
+    Relayed,                  // Relay to both: the game follows on this connection
+}
+
+// Four capital letters
+pub fn is_room_code(text: &str) -> bool {
+    text.len() == 4 && text.bytes().all(|byte| byte.is_ascii_uppercase())
+}
+
+// `host` or `host:port`, the relay's own port if none is given
+fn relay_address(relay: &str) -> String {
+    if relay.contains(':') {
+        relay.to_string()
+    } else {
+        format!("{}:{}", relay, NETPLAY_RELAY_PORT)
+    }
+}
+
+fn send(stream: &mut TcpStream, message: &Rendezvous) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+// A byte at a time, so nothing after the line is read: that may already
+// be the game
+fn receive(stream: &mut TcpStream) -> io::Result<Rendezvous> {
+    let mut line = Vec::new();
+    let mut byte = [0];
+    while line.len() < 4096 {
+        stream.read_exact(&mut byte)?;
+        if byte[0] == b'\n' {
+            return Ok(serde_json::from_slice(&line)?);
+        }
+        line.push(byte[0]);
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "rendezvous message too long"))
+}
+
+fn unexpected() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "unexpected reply from the relay")
+}
+
+// `tstris host` through a relay: open a room, hand its code to `opened`,
+// then wait on both the relay and the local port for the opponent
+pub fn host(relay: &str, port: u16, opened: impl FnOnce(&str)) -> io::Result<TcpStream> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    let mut server = TcpStream::connect(relay_address(relay))?;
+    send(&mut server, &Rendezvous::Host { port })?;
+    let Rendezvous::Room { code } = receive(&mut server)? else {
+        return Err(unexpected());
+    };
+    tracing::info!(code, "room opened");
+    opened(&code);
+    match receive(&mut server)? {
+        Rendezvous::Direct => {
+            let (stream, peer) = listener.accept()?;
+            tracing::info!(%peer, "opponent connected directly");
+            Ok(stream)
+        }
+        Rendezvous::Relayed => {
+            tracing::info!("opponent connected through the relay");
+            Ok(server)
+        }
+        _ => Err(unexpected()),
+    }
+}
+
+// `tstris join ABCD`: straight to the room's host if possible, through the
+// relay if not
+pub fn join(relay: &str, code: &str) -> io::Result<TcpStream> {
+    let mut server = TcpStream::connect(relay_address(relay))?;
+    send(&mut server, &Rendezvous::Join { code: code.to_string() })?;
+    let address = match receive(&mut server)? {
+        Rendezvous::Peer { address } => address,
+        Rendezvous::NoRoom => return Err(io::Error::new(io::ErrorKind::NotFound, format!("no room {} on the relay", code))),
+        _ => return Err(unexpected()),
+    };
+    let direct = address
+        .parse::<SocketAddr>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|address| TcpStream::connect_timeout(&address, Duration::from_millis(NETPLAY_DIRECT_TIMEOUT_MS)));
+    match direct {
+        Ok(stream) => {
+            tracing::info!(%address, "connected to host directly");
+            send(&mut server, &Rendezvous::Direct)?;
+            Ok(stream)
+        }
+        Err(error) => {
+            tracing::info!(%address, %error, "host unreachable, relaying");
+            send(&mut server, &Rendezvous::Relay)?;
+            match receive(&mut server)? {
+                Rendezvous::Relayed => Ok(server),
+                _ => Err(unexpected()),
+            }
+        }
+    }
+}
+
+// A host waiting for someone to join
+struct Room {
+    stream: TcpStream,
+    address: SocketAddr,
+    opened: Instant,
+}
+
+type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+// `tstris relay [--port N]`: match hosts and joiners by room code, and
+// carry games for pairs that can't connect to each other
+pub fn serve(args: &[String]) -> Result<()> {
+    let mut port = NETPLAY_RELAY_PORT;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => port = args.next().ok_or_else(|| Error::usage("missing port"))?.parse()?,
+            other => return Err(Error::usage(format!("unknown option '{}'", other))),
+        }
+    }
+
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    let rooms: Rooms = Arc::default();
+    println!("Relaying tstris matches on port {}", port);
+    tracing::info!(port, "relay listening");
+    for stream in listener.incoming().flatten() {
+        let rooms = rooms.clone();
+        std::thread::spawn(move || {
+            let peer = stream.peer_addr().ok();
+            if let Err(error) = rendezvous(stream, &rooms) {
+                tracing::info!(?peer, %error, "rendezvous ended");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn rendezvous(mut stream: TcpStream, rooms: &Mutex<HashMap<String, Room>>) -> io::Result<()> {
+    // Nobody gets to hold a thread by saying nothing
+    stream.set_read_timeout(Some(Duration::from_secs(NETPLAY_ROOM_SECS)))?;
+    match receive(&mut stream)? {
+        Rendezvous::Host { port } => {
+            let address = SocketAddr::new(stream.peer_addr()?.ip(), port);
+            let mut rooms = rooms.lock().unwrap_or_else(|e| e.into_inner());
+            rooms.retain(|_, room| room.opened.elapsed() < Duration::from_secs(NETPLAY_ROOM_SECS));
+            if rooms.len() >= NETPLAY_MAX_ROOMS {
+                tracing::warn!("too many rooms open");
+                return Ok(());
+            }
+            let code = loop {
+                let code: String = (0..4).map(|_| rand::thread_rng().gen_range(b'A'..=b'Z') as char).collect();
+                if !rooms.contains_key(&code) {
+                    break code;
+                }
+            };
+            send(&mut stream, &Rendezvous::Room { code: code.clone() })?;
+            tracing::info!(code, %address, open = rooms.len() + 1, "room opened");
+            rooms.insert(code, Room { stream, address, opened: Instant::now() });
+        }
+        Rendezvous::Join { code } => {
+            let room = rooms.lock().unwrap_or_else(|e| e.into_inner()).remove(&code);
+            let Some(mut room) = room else {
+                return send(&mut stream, &Rendezvous::NoRoom);
+            };
+            send(&mut stream, &Rendezvous::Peer { address: room.address.to_string() })?;
+            match receive(&mut stream)? {
+                Rendezvous::Direct => send(&mut room.stream, &Rendezvous::Direct)?,
+                Rendezvous::Relay => {
+                    tracing::info!(code, "relaying a match");
+                    send(&mut room.stream, &Rendezvous::Relayed)?;
+                    send(&mut stream, &Rendezvous::Relayed)?;
+                    pipe(stream, room.stream)?;
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// Everything either side sends goes to the other, until one hangs up
+fn pipe(joiner: TcpStream, host: TcpStream) -> io::Result<()> {
+    joiner.set_read_timeout(None)?;
+    host.set_read_timeout(None)?;
+    let _ = joiner.set_nodelay(true);
+    let _ = host.set_nodelay(true);
+    let (mut joiner_in, mut host_out) = (joiner.try_clone()?, host.try_clone()?);
+    let upstream = std::thread::spawn(move || {
+        let _ = io::copy(&mut joiner_in, &mut host_out);
+        let _ = host_out.shutdown(Shutdown::Both);
+    });
+    let (mut host_in, mut joiner_out) = (host, joiner);
+    let _ = io::copy(&mut host_in, &mut joiner_out);
+    let _ = joiner_out.shutdown(Shutdown::Both);
+    let _ = upstream.join();
+    Ok(())
+}
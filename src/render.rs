@@ -0,0 +1,155 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use ratatui::{
+    backend::{Backend, TestBackend},
+    buffer::Buffer,
+    layout::Rect,
+    Terminal,
+};
+
+use crate::app::App;
+use crate::ui::graphics::{BoardPixels, Graphics, Protocol};
+use crate::ui::ui;
+
+// One screenful, laid out and never touched again: everything the render
+// thread needs to put it up
+struct Frame {
+    buffer: Buffer,
+    bell: bool,
+    graphics: Option<Protocol>,
+    pixels: Option<BoardPixels>,
+}
+
+// The frame waiting to be drawn. A newer one replaces it rather than queue
+// behind it, so a slow terminal skips frames instead of falling behind.
+#[derive(Default)]
+struct Mailbox {
+    slot: Mutex<Slot>,
+    posted: Condvar,
+}
+
+#[derive(Default)]
+struct Slot {
+    frame: Option<Frame>,
+    closed: bool,
+}
+
+impl Mailbox {
+    // A bell in the frame it replaces still rings
+    fn post(&self, mut frame: Frame) {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        frame.bell |= slot.frame.take().is_some_and(|old| old.bell);
+        slot.frame = Some(frame);
+        self.posted.notify_one();
+    }
+
+    fn close(&self) {
+        self.slot.lock().unwrap_or_else(|e| e.into_inner()).closed = true;
+        self.posted.notify_one();
+    }
+
+    // Waits for the next frame; None once closed and the last one's drawn
+    fn take(&self) -> Option<Frame> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(frame) = slot.frame.take() {
+                return Some(frame);
+            }
+            if slot.closed {
+                return None;
+            }
+            slot = self.posted.wait(slot).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+// Drawing to a terminal, on a thread of its own. The loop lays each frame out
+// into a buffer and hands it over; the thread writes whatever changed since
+// the frame it last drew. A terminal slow to take it, as over SSH, holds up
+// only that thread, and input and the game clock carry on.
+pub struct Renderer<B: Backend + Write + Send + 'static> {
+    layout: Terminal<TestBackend>, // Lays frames out without writing anywhere
+    mailbox: Arc<Mailbox>,
+    size: Arc<Mutex<Rect>>, // The terminal's size as of the last frame drawn
+    thread: Option<JoinHandle<io::Result<Terminal<B>>>>,
+}
+
+impl<B: Backend + Write + Send + 'static> Renderer<B> {
+    pub fn spawn(terminal: Terminal<B>) -> io::Result<Self> {
+        let area = terminal.size()?;
+        let layout = Terminal::new(TestBackend::new(area.width, area.height))?;
+        let mailbox = Arc::new(Mailbox::default());
+        let size = Arc::new(Mutex::new(area));
+        let thread = {
+            let (mailbox, size) = (mailbox.clone(), size.clone());
+            std::thread::spawn(move || draw_frames(terminal, &mailbox, &size))
+        };
+        Ok(Self { layout, mailbox, size, thread: Some(thread) })
+    }
+
+    // Lays out a frame of `app` at the terminal's size and posts it. The
+    // buffer comes back for anyone else showing the same screen.
+    pub fn draw(&mut self, app: &App, bell: bool) -> io::Result<&Buffer> {
+        if self.thread.as_ref().is_none_or(JoinHandle::is_finished) {
+            return Err(self.finish().err().unwrap_or_else(stopped));
+        }
+        let area = *self.size.lock().unwrap_or_else(|e| e.into_inner());
+        if self.layout.size()? != area {
+            self.layout.backend_mut().resize(area.width, area.height);
+        }
+        let mut pixels = None;
+        let completed = self.layout.draw(|f| pixels = ui(f, app))?;
+        self.mailbox.post(Frame { buffer: completed.buffer.clone(), bell, graphics: app.graphics, pixels });
+        Ok(completed.buffer)
+    }
+
+    // Waits for the last frame to go out and hands the terminal back
+    pub fn finish(&mut self) -> io::Result<Terminal<B>> {
+        self.mailbox.close();
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) | None => Err(stopped()),
+        }
+    }
+}
+
+impl<B: Backend + Write + Send + 'static> Drop for Renderer<B> {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            let _ = self.finish();
+        }
+    }
+}
+
+fn stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "render thread stopped")
+}
+
+fn draw_frames<B: Backend + Write>(mut terminal: Terminal<B>, mailbox: &Mailbox, size: &Mutex<Rect>) -> io::Result<Terminal<B>> {
+    let mut graphics = Graphics::default();
+    while let Some(frame) = mailbox.take() {
+        // The bell goes to whoever is playing, local or remote
+        if frame.bell {
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            Write::flush(backend)?;
+        }
+        // Laid out for a size the terminal has since left: the loop lays
+        // the next one out at the new size
+        let area = terminal.size()?;
+        *size.lock().unwrap_or_else(|e| e.into_inner()) = area;
+        if frame.buffer.area != area {
+            continue;
+        }
+        terminal.draw(|f| *f.buffer_mut() = frame.buffer)?;
+        if frame.graphics.is_some() {
+            let window = terminal.backend_mut().window_size()?;
+            if graphics.show(terminal.backend_mut(), frame.graphics, frame.pixels, window)? {
+                terminal.clear()?;
+            }
+        }
+    }
+    Ok(terminal)
+}
@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crossterm::{
+    event::{DisableFocusChange, DisableMouseCapture, PopKeyboardEnhancementFlags},
+    execute,
+    terminal::{self, LeaveAlternateScreen},
+};
+
+use crate::config;
+use crate::constants::REPORT_EVENTS;
+use crate::game::event::TimedEvent;
+use crate::input::multiplexer::Multiplexer;
+use crate::persistence;
+
+// What the running session knows that a bug report wants, kept where the
+// panic hook can reach it without the App
+struct Context {
+    keyboard_enhancement: Option<bool>,
+    events: VecDeque<String>,
+}
+
+// One per thread, since every SSH game runs on its own; the panic hook runs
+// on the thread that panicked, so a report only has that session's events
+thread_local! {
+    static CONTEXT: RefCell<Context> = const {
+        RefCell::new(Context { keyboard_enhancement: None, events: VecDeque::new() })
+    };
+}
+
+fn with_context<T>(f: impl FnOnce(&mut Context) -> T) -> T {
+    CONTEXT.with(|context| f(&mut context.borrow_mut()))
+}
+
+pub fn set_keyboard_enhancement(active: bool) {
+    with_context(|context| context.keyboard_enhancement = Some(active));
+}
+
+// Keeps the last few engine events for the next report
+pub fn remember(event: &TimedEvent) {
+    with_context(|context| {
+        if context.events.len() == REPORT_EVENTS {
+            context.events.pop_front();
+        }
+        context.events.push_back(format!("{:>9.3}s {:?}", event.at.as_secs_f64(), event.event));
+    });
+}
+
+// Plain text, so it can go straight into an issue
+pub fn bundle(panic: Option<&str>) -> String {
+    let mut out = String::new();
+    let env = |name: &str| std::env::var(name).unwrap_or_else(|_| "-".to_string());
+
+    let _ = writeln!(out, "tstris {} ({} {})", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH);
+    if let Some(panic) = panic {
+        let _ = writeln!(out, "\n[panic]\n{}", panic);
+    }
+
+    let _ = writeln!(out, "\n[terminal]");
+    for name in ["TERM", "COLORTERM", "TERM_PROGRAM", "TMUX", "LANG"] {
+        let _ = writeln!(out, "{:<13} {}", name, env(name));
+    }
+    let size = terminal::size().map_or("unknown".to_string(), |(width, height)| format!("{}x{}", width, height));
+    let _ = writeln!(out, "{:<13} {}", "size", size);
+
+    let _ = writeln!(out, "\n[capabilities]");
+    let keyboard = with_context(|context| context.keyboard_enhancement);
+    let keyboard = keyboard.map_or("not checked", |active| if active { "on" } else { "off" });
+    let _ = writeln!(out, "{:<13} {}", "key release", keyboard);
+    let _ = writeln!(out, "{:<13} {}", "multiplexer", Multiplexer::detect().map_or("none", Multiplexer::name));
+    let _ = writeln!(out, "{:<13} {}", "ssh server", cfg!(feature = "ssh"));
+
+    let _ = writeln!(out, "\n[config]");
+    match config::config_path() {
+        Some(path) => {
+            let _ = writeln!(out, "# {}", path.display());
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => {
+                    // Checked without migrating, so the file is left as the player has it
+                    match config::parse(&contents) {
+                        Ok(parsed) => {
+                            let _ = writeln!(out, "# version {} (current {})", parsed.from_version, config::CONFIG_VERSION);
+                            if !parsed.unknown.is_empty() {
+                                let _ = writeln!(out, "# unknown: {}", parsed.unknown.join(", "));
+                            }
+                        }
+                        Err(e) => {
+                            let _ = writeln!(out, "# {}", e);
+                        }
+                    }
+                    out.push_str(&contents);
+                }
+                Err(e) => {
+                    let _ = writeln!(out, "# not read: {}", e);
+                }
+            }
+        }
+        None => out.push_str("# no config directory\n"),
+    }
+
+    let _ = writeln!(out, "\n[events]");
+    let events = with_context(|context| context.events.clone());
+    if events.is_empty() {
+        out.push_str("none\n");
+    }
+    for event in events {
+        let _ = writeln!(out, "{}", event);
+    }
+    out
+}
+
+// Writes a bundle to the data directory and says where it went
+pub fn save(panic: Option<&str>) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    persistence::save_report(&format!("report-{}.txt", timestamp), &bundle(panic))
+}
+
+// On a panic, give the terminal back before anything is printed, then
+// leave a report behind for the bug tracker
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+        let mut out = io::stdout();
+        let _ = execute!(out, PopKeyboardEnhancementFlags, DisableMouseCapture, DisableFocusChange, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+
+        default_hook(info);
+        match save(Some(&info.to_string())) {
+            Ok(path) => eprintln!("tstris: crash report written to {}; please attach it to a bug report", path.display()),
+            Err(e) => eprintln!("tstris: couldn't write a crash report: {}", e),
+        }
+    }));
+}
@@ -0,0 +1,343 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, ModifierKeyCode};
+use std::io::Write;
+
+use ratatui::{backend::Backend, Terminal};
+
+use crate::app::App;
+use crate::constants::{FRAME_POLL_MS, FRAME_STEP_BURST, IDLE_POLL_MS};
+use crate::pacing::FramePacer;
+use crate::error::Result;
+use crate::game::piece::PieceType;
+use crate::input::action::{two_hand_action, Hand};
+use crate::input::{handle_action, handle_input, handle_playback_input, handle_wizard_input};
+use crate::spectate::Broadcast;
+use crate::render::Renderer;
+
+// One player's draw/input/update loop, until they quit. `next_event` waits up
+// to the given time for input, so the same loop drives the local terminal
+// and remote sessions. Frames are drawn to the terminal on a render thread,
+// which hands it back at the end, and go out to `spectate`'s watchers too.
+pub fn run_loop<B: Backend + Write + Send + 'static>(
+    app: &mut App,
+    terminal: Terminal<B>,
+    mut spectate: Option<&mut Broadcast>,
+    mut next_event: impl FnMut(Duration) -> Result<Option<Event>>,
+) -> Result<Terminal<B>> {
+    let mut pacer = FramePacer::new(app.config.display.max_fps, Instant::now());
+    let mut renderer = Renderer::spawn(terminal)?;
+    loop {
+        app.profiler.tick(Instant::now());
+        
+        // Render, when a frame is due under the cap
+        let idle = app.idle();
+        if idle || pacer.due(Instant::now()) {
+            let render_start = Instant::now();
+            let bell = std::mem::take(&mut app.bell);
+            let buffer = renderer.draw(app, bell)?;
+            if let Some(broadcast) = spectate.as_deref_mut() {
+                if bell {
+                    broadcast.bell();
+                }
+                broadcast.frame(buffer)?;
+            }
+            app.profiler.record_render(render_start, Instant::now());
+            pacer.drawn(render_start);
+        }
+        
+        // Handle input; while idle, block until a key or the next slow frame,
+        // otherwise until the next tick or frame
+        let poll = if idle {
+            app.profiler.skip_tick();
+            Duration::from_millis(IDLE_POLL_MS)
+        } else {
+            pacer.wait(Instant::now(), Duration::from_millis(FRAME_POLL_MS))
+        };
+        if let Some(event) = next_event(poll)? {
+            tracing::trace!(?event, "terminal event");
+            if !handle_event(app, event) {
+                return Ok(renderer.finish()?);
+            }
+        }
+        
+        // Update game state
+        let update_start = Instant::now();
+        app.update()?;
+        app.profiler.record_update(update_start, Instant::now());
+    }
+}
+
+// Returns false when the player asked to quit
+pub fn handle_event(app: &mut App, event: Event) -> bool {
+    if let Event::Key(KeyEvent { kind: KeyEventKind::Press, .. }) = event {
+        app.profiler.record_input(Instant::now());
+        if app.wake() {
+            return true;
+        }
+    }
+    if let Event::Key(KeyEvent { code, kind, .. }) = event {
+        app.input_diagnostics.record(code, kind, Instant::now());
+    }
+    // Ctrl+C always quits at once
+    if let Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers, kind: KeyEventKind::Press, .. }) = event {
+        if modifiers.contains(KeyModifiers::CONTROL) {
+            return false;
+        }
+    }
+    match event {
+        Event::FocusLost => {
+            tracing::debug!("focus lost");
+            app.game.focus_lost();
+        }
+        Event::FocusGained => {
+            tracing::debug!("focus gained");
+            // The quit prompt's pause holds until it's answered
+            if app.quit_prompt.is_none() {
+                app.game.focus_gained();
+            }
+        }
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.playback.is_some() => match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => return false,
+            KeyCode::Esc => app.playback = None,
+            KeyCode::F(3) => app.toggle_profiler(),
+            KeyCode::F(4) => app.toggle_input_diagnostics(),
+            _ => {
+                if let Some(playback) = app.playback.as_mut() {
+                    handle_playback_input(playback, code);
+                }
+            }
+        },
+        Event::Key(_) if app.playback.is_some() => {}
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.calibration.is_some() => match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => return false,
+            KeyCode::Esc => app.calibration = None,
+            KeyCode::Char('r') | KeyCode::Char('R') => app.start_calibration(),
+            KeyCode::Char(' ') => {
+                if let Some(calibration) = app.calibration.as_mut() {
+                    calibration.tap(Instant::now());
+                }
+            }
+            KeyCode::F(3) => app.toggle_profiler(),
+            KeyCode::F(4) => app.toggle_input_diagnostics(),
+            _ => {}
+        },
+        Event::Key(_) if app.calibration.is_some() => {}
+        Event::Key(KeyEvent { code, kind, .. }) if app.wizard.is_some() => match (code, kind) {
+            (KeyCode::Esc, KeyEventKind::Press) => app.wizard = None, // Ask again next launch
+            (KeyCode::Enter, KeyEventKind::Press) => app.advance_wizard(),
+            _ => {
+                if let Some(wizard) = app.wizard.as_mut() {
+                    handle_wizard_input(wizard, code, kind);
+                }
+            }
+        },
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.name_entry.is_some() => match code {
+            KeyCode::Enter => app.confirm_name(),
+            KeyCode::Esc => app.name_entry = None, // Ask again next launch
+            KeyCode::Backspace => app.erase_name_char(),
+            KeyCode::Char(c) => app.type_name_char(c),
+            _ => {}
+        },
+        Event::Key(_) if app.name_entry.is_some() => {}
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.chat_entry.is_some() => match code {
+            KeyCode::Enter => app.send_chat(),
+            KeyCode::Esc => app.chat_entry = None,
+            KeyCode::Backspace => app.erase_chat_char(),
+            KeyCode::Char(c) => app.type_chat_char(c),
+            _ => {}
+        },
+        Event::Key(_) if app.chat_entry.is_some() => {}
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.quit_prompt.is_some() => match code {
+            KeyCode::Char('q' | 'Q' | 'y' | 'Y') | KeyCode::Enter => return false,
+            KeyCode::Esc | KeyCode::Char('n' | 'N') => app.keep_playing(),
+            _ => {}
+        },
+        Event::Key(_) if app.quit_prompt.is_some() => {}
+        // Handling presets: 1 for [handling]'s own timings, then the presets in order
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.handling_menu => match code {
+            KeyCode::Char(digit @ '1'..='9') => {
+                let preset = (digit as usize - '1' as usize).checked_sub(1);
+                app.select_preset(preset);
+            }
+            KeyCode::Esc | KeyCode::Char('k') | KeyCode::Char('K') => app.handling_menu = false,
+            _ => {}
+        },
+        Event::Key(_) if app.handling_menu => {}
+        // Versus handicaps: ↑/↓ pick a row, ←/→ change it
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.match_setup.is_some() => match code {
+            KeyCode::Up | KeyCode::Down => {
+                if let Some(setup) = app.match_setup.as_mut() {
+                    setup.select(if code == KeyCode::Up { -1 } else { 1 });
+                }
+            }
+            KeyCode::Left => app.change_handicap(-1),
+            KeyCode::Right => app.change_handicap(1),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('g') | KeyCode::Char('G') => app.match_setup = None,
+            _ => {}
+        },
+        Event::Key(_) if app.match_setup.is_some() => {}
+        // Title screen menu; every other key works as it does on the Ready screen
+        Event::Key(KeyEvent { code: code @ (KeyCode::Up | KeyCode::Down | KeyCode::Enter | KeyCode::Esc), kind: KeyEventKind::Press, .. }) if app.on_title() => match code {
+            KeyCode::Up => app.title.step(-1),
+            KeyCode::Down => app.title.step(1),
+            KeyCode::Esc => app.title.picking_mode = None,
+            _ => {
+                if !app.choose_title_item() {
+                    return false;
+                }
+            }
+        },
+        Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) if app.koth.as_ref().is_some_and(|koth| koth.handoff) => match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => return false,
+            KeyCode::Enter => app.take_turn(),
+            _ => {}
+        },
+        Event::Key(_) if app.koth.as_ref().is_some_and(|koth| koth.handoff) => {}
+        Event::Key(KeyEvent { code, kind, modifiers, .. }) => match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => {
+                if kind == KeyEventKind::Press && !app.ask_quit() {
+                    return false;
+                }
+            }
+            // Keys the config binds win over the ones below
+            _ if app.bindings.overrides(app.game.mode, code) => {
+                handle_input(&mut app.game, &app.config.handling, &app.bindings, code, kind, modifiers);
+            }
+            // Two boards: each hand's keys go to its own board, over whatever
+            // else they're bound to
+            _ if app.twin.is_some() && two_hand_action(code).is_some() => {
+                if let Some((hand, action)) = two_hand_action(code) {
+                    let game = match hand {
+                        Hand::Left => Some(&mut app.game),
+                        Hand::Right => app.twin.as_mut(),
+                    };
+                    if let Some(game) = game {
+                        handle_action(game, &app.config.handling, code, kind, Some(action));
+                    }
+                }
+            }
+            // The drill calls pieces with Shift and their letter, so the
+            // lowercase keys keep doing what they do. Shift on its own
+            // doesn't hold meanwhile, or every call would.
+            KeyCode::Char(c) if app.prediction.is_some() && is_call(c, modifiers) => {
+                if let (KeyEventKind::Press, Some(piece_type)) = (kind, PieceType::from_letter(c)) {
+                    app.guess_next(piece_type);
+                }
+            }
+            KeyCode::Modifier(ModifierKeyCode::LeftShift | ModifierKeyCode::RightShift) if app.prediction.is_some() => {}
+            KeyCode::Tab => {
+                if kind == KeyEventKind::Press {
+                    app.toggle_stats();
+                }
+            }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                if kind == KeyEventKind::Press {
+                    app.toggle_session_panel();
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                if kind == KeyEventKind::Press {
+                    app.reset_session();
+                }
+            }
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                if kind == KeyEventKind::Press {
+                    app.layout.cycle_preset();
+                }
+            }
+            KeyCode::Char('1') => {
+                if kind == KeyEventKind::Press {
+                    app.layout.hold = !app.layout.hold;
+                }
+            }
+            KeyCode::Char('2') => {
+                if kind == KeyEventKind::Press {
+                    app.layout.next = !app.layout.next;
+                }
+            }
+            KeyCode::Char('3') => {
+                if kind == KeyEventKind::Press {
+                    app.layout.stats = !app.layout.stats;
+                }
+            }
+            KeyCode::Char('k') | KeyCode::Char('K') => {
+                if kind == KeyEventKind::Press {
+                    app.open_handling_menu();
+                }
+            }
+            KeyCode::Char('g') | KeyCode::Char('G') if app.versus.is_some() || app.netplay.is_some() => {
+                if kind == KeyEventKind::Press {
+                    app.open_match_setup();
+                }
+            }
+            KeyCode::Char('l') | KeyCode::Char('L') => {
+                if kind == KeyEventKind::Press {
+                    app.toggle_event_log();
+                }
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => {
+                if kind == KeyEventKind::Press {
+                    app.toggle_analysis();
+                }
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                if kind == KeyEventKind::Press {
+                    app.show_preview();
+                }
+            }
+            // Emote keys in a match, over whatever else they're bound to
+            _ if app.emote(code).is_some() => {
+                if kind == KeyEventKind::Press {
+                    app.send_emote(code);
+                }
+            }
+            // Chat in a match, a hint otherwise
+            KeyCode::Char('t') | KeyCode::Char('T') if app.netplay.is_some() => {
+                if kind == KeyEventKind::Press {
+                    app.open_chat();
+                }
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                if kind == KeyEventKind::Press {
+                    app.request_hint();
+                }
+            }
+            KeyCode::F(3) => {
+                if kind == KeyEventKind::Press {
+                    app.toggle_profiler();
+                }
+            }
+            KeyCode::F(4) => {
+                if kind == KeyEventKind::Press {
+                    app.toggle_input_diagnostics();
+                }
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                if kind == KeyEventKind::Press {
+                    app.watch_last_run();
+                }
+            }
+            KeyCode::Char('.') if app.frame_step => {
+                if kind != KeyEventKind::Release {
+                    app.step_frames(1);
+                }
+            }
+            KeyCode::Char('>') if app.frame_step => {
+                if kind != KeyEventKind::Release {
+                    app.step_frames(FRAME_STEP_BURST);
+                }
+            }
+            _ => {
+                handle_input(&mut app.game, &app.config.handling, &app.bindings, code, kind, modifiers);
+            }
+        },
+        _ => {}
+    }
+    true
+}
+
+// A prediction call: a piece letter typed with Shift
+fn is_call(c: char, modifiers: KeyModifiers) -> bool {
+    (c.is_ascii_uppercase() || modifiers.contains(KeyModifiers::SHIFT)) && PieceType::from_letter(c).is_some()
+}
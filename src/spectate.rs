@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::rc::Rc;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    backend::CrosstermBackend,
+    buffer::Buffer,
+    layout::Rect,
+    Terminal, TerminalOptions, Viewport,
+};
+
+use crate::constants::{SPECTATE_FRAME_MS, SPECTATE_MAX_CLIENTS, SPECTATE_QUEUE_FRAMES, SPECTATE_WRITE_TIMEOUT_MS};
+
+// Frames of the local game, rendered a second time as plain ANSI and sent to
+// anyone who connects to the port: `nc <host> <port>` is enough to watch.
+// Spectators never send anything back.
+pub struct Broadcast {
+    terminal: Option<(Terminal<CrosstermBackend<Capture>>, Rect)>,
+    output: Rc<RefCell<Vec<u8>>>,
+    spectators: Arc<Mutex<Vec<Spectator>>>,
+    last_frame: Option<Instant>,
+}
+
+struct Spectator {
+    frames: SyncSender<Arc<[u8]>>,
+    // Missed a frame, or just joined: the screen it has can't be patched,
+    // so it needs a full redraw
+    resync: bool,
+}
+
+impl Broadcast {
+    pub fn start(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let spectators = Arc::new(Mutex::new(Vec::new()));
+        let accepting = spectators.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                accept(stream, &accepting);
+            }
+        });
+        tracing::info!(port, "spectate stream listening");
+        Ok(Self { terminal: None, output: Rc::default(), spectators, last_frame: None })
+    }
+
+    // Queues the player's latest frame for every spectator, at most every
+    // SPECTATE_FRAME_MS. Nothing is encoded while nobody is watching.
+    pub fn frame(&mut self, buffer: &Buffer) -> io::Result<()> {
+        let now = Instant::now();
+        if self.last_frame.is_some_and(|last| now.duration_since(last) < Duration::from_millis(SPECTATE_FRAME_MS)) {
+            return Ok(());
+        }
+        let mut spectators = self.spectators.lock().unwrap_or_else(|e| e.into_inner());
+        if spectators.is_empty() {
+            return Ok(());
+        }
+        self.last_frame = Some(now);
+
+        // Same size as the player's screen, so the layout matches theirs
+        let size = buffer.area;
+        if self.terminal.as_ref().is_none_or(|(_, last_size)| *last_size != size) {
+            let backend = CrosstermBackend::new(Capture(self.output.clone()));
+            let terminal = Terminal::with_options(backend, TerminalOptions { viewport: Viewport::Fixed(size) })?;
+            self.terminal = Some((terminal, size));
+            spectators.iter_mut().for_each(|spectator| spectator.resync = true);
+        }
+        let Some((terminal, _)) = self.terminal.as_mut() else {
+            return Ok(());
+        };
+        if spectators.iter().any(|spectator| spectator.resync) {
+            terminal.clear()?;
+        }
+        terminal.draw(|f| f.buffer_mut().clone_from(buffer))?;
+        let frame: Arc<[u8]> = self.output.take().into();
+
+        // A spectator whose queue is full drops this frame and catches up
+        // with a full redraw later, rather than holding up the game
+        spectators.retain_mut(|spectator| match spectator.frames.try_send(frame.clone()) {
+            Ok(()) => {
+                spectator.resync = false;
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                spectator.resync = true;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                tracing::info!("spectator left");
+                false
+            }
+        });
+        Ok(())
+    }
+}
+
+impl Broadcast {
+    // The player's bell rings for everyone watching too, whatever frame
+    // they're on
+    pub fn bell(&self) {
+        let bell: Arc<[u8]> = Arc::from(&b"\x07"[..]);
+        for spectator in self.spectators.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let _ = spectator.frames.try_send(bell.clone());
+        }
+    }
+}
+
+// Where the spectators' terminal writes its escape codes
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn accept(mut stream: TcpStream, spectators: &Mutex<Vec<Spectator>>) {
+    let peer = stream.peer_addr().ok();
+    let mut spectators = spectators.lock().unwrap_or_else(|e| e.into_inner());
+    if spectators.len() >= SPECTATE_MAX_CLIENTS {
+        tracing::warn!(?peer, "too many spectators");
+        let _ = stream.write_all(b"Too many spectators, try again later\r\n");
+        return;
+    }
+    let _ = stream.set_nodelay(true);
+    let _ = stream.set_write_timeout(Some(Duration::from_millis(SPECTATE_WRITE_TIMEOUT_MS)));
+    let (frames, queue) = sync_channel(SPECTATE_QUEUE_FRAMES);
+    std::thread::spawn(move || send_frames(stream, queue));
+    spectators.push(Spectator { frames, resync: true });
+    tracing::info!(?peer, watching = spectators.len(), "spectator joined");
+}
+
+// One thread per spectator, so a stalled connection only stalls itself. A
+// write that times out drops the spectator.
+fn send_frames(mut stream: TcpStream, queue: Receiver<Arc<[u8]>>) {
+    // Hide the cursor and start from a blank screen
+    if stream.write_all(b"\x1b[?25l\x1b[2J").is_err() {
+        return;
+    }
+    for frame in queue {
+        if stream.write_all(&frame).is_err() {
+            return;
+        }
+    }
+}
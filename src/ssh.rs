@@ -0,0 +1,431 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    cursor::{Hide, Show},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, ClearType, CrosstermBackend, WindowSize},
+    buffer::Cell,
+    layout::{Rect, Size},
+    Terminal,
+};
+use russh::keys::ssh_key::{private::Ed25519Keypair, LineEnding};
+use russh::keys::{PrivateKey, PublicKey};
+use russh::server::{Auth, ChannelOpenHandle, Handle, Msg, Server as _, Session};
+use russh::{Channel, ChannelId, ChannelOpenFailure, MethodKind, MethodSet};
+use tokio::sync::mpsc::{channel as frame_channel, error::TrySendError, Receiver, Sender};
+
+use crate::app::App;
+use crate::constants::{SSH_DEFAULT_SIZE, SSH_IDLE_TIMEOUT_SECS, SSH_MAX_SESSIONS, SSH_OUTPUT_FRAMES, SSH_PORT};
+use crate::error::{Error, Result};
+use crate::input::ansi::parse_keys;
+use crate::{persistence, session};
+
+// `tstris serve-ssh [--port N] [--host-key PATH] [--max-sessions N]`: anyone
+// who connects gets their own game, played as their SSH user name. Any
+// login is accepted; nothing they do touches this machine's saves.
+pub fn serve(args: &[String]) -> Result<()> {
+    let mut port = SSH_PORT;
+    let mut host_key = None;
+    let mut max_sessions = SSH_MAX_SESSIONS;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => port = args.next().ok_or_else(|| Error::usage("missing port"))?.parse()?,
+            "--host-key" => host_key = Some(PathBuf::from(args.next().ok_or_else(|| Error::usage("missing host key path"))?)),
+            "--max-sessions" => max_sessions = args.next().ok_or_else(|| Error::usage("missing session count"))?.parse()?,
+            other => return Err(Error::usage(format!("unknown option '{}'", other))),
+        }
+    }
+
+    let config = russh::server::Config {
+        methods: MethodSet::from(&[MethodKind::None, MethodKind::Password, MethodKind::PublicKey][..]),
+        keys: vec![load_host_key(host_key.as_deref())?],
+        // Covers clients that connect and never open a game; games
+        // time themselves out
+        inactivity_timeout: Some(Duration::from_secs(SSH_IDLE_TIMEOUT_SECS * 2)),
+        auth_rejection_time: Duration::from_secs(1),
+        auth_rejection_time_initial: Some(Duration::ZERO),
+        nodelay: true,
+        ..Default::default()
+    };
+
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    let mut server = Server { sessions: Arc::new(AtomicUsize::new(0)), max_sessions };
+    println!("Serving tstris over SSH on port {} (up to {} games)", port, max_sessions);
+    tracing::info!(port, max_sessions, "ssh server listening");
+    runtime.block_on(server.run_on_address(Arc::new(config), ("0.0.0.0", port)))?;
+    Ok(())
+}
+
+// An explicit key must load; otherwise one is generated on first run and
+// kept in the data directory so clients see the same host key next time
+fn load_host_key(path: Option<&Path>) -> Result<PrivateKey> {
+    if let Some(path) = path {
+        return russh::keys::load_secret_key(path, None).map_err(|e| Error::Ssh(e.into()));
+    }
+    let Some(path) = persistence::data_dir().map(|dir| dir.join("ssh_host_ed25519_key")) else {
+        tracing::warn!("no data directory, using a throwaway host key");
+        return Ok(generate_host_key());
+    };
+    if path.exists() {
+        return russh::keys::load_secret_key(&path, None).map_err(|e| Error::Ssh(e.into()));
+    }
+    let key = generate_host_key();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    key.write_openssh_file(&path, LineEnding::LF).map_err(|e| Error::Ssh(e.into()))?;
+    tracing::info!(path = %path.display(), "generated ssh host key");
+    Ok(key)
+}
+
+fn generate_host_key() -> PrivateKey {
+    PrivateKey::from(Ed25519Keypair::from_seed(&rand::random()))
+}
+
+struct Server {
+    sessions: Arc<AtomicUsize>,
+    max_sessions: usize,
+}
+
+impl russh::server::Server for Server {
+    type Handler = Connection;
+
+    fn new_client(&mut self, peer: Option<std::net::SocketAddr>) -> Connection {
+        tracing::info!(?peer, "ssh connection");
+        Connection {
+            sessions: self.sessions.clone(),
+            max_sessions: self.max_sessions,
+            user: String::new(),
+            game: None,
+        }
+    }
+}
+
+// One SSH connection; it can run a single game
+struct Connection {
+    sessions: Arc<AtomicUsize>,
+    max_sessions: usize,
+    user: String,
+    game: Option<GameChannel>,
+}
+
+struct GameChannel {
+    id: ChannelId,
+    slot: Option<Slot>, // Handed to the game thread once it starts
+    size: (u16, u16),
+    input: Option<mpsc::Sender<Input>>,
+}
+
+// Holds one of the server's game slots until dropped
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn take(sessions: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        sessions
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then_some(n + 1))
+            .ok()
+            .map(|_| Slot(sessions.clone()))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+enum Input {
+    Bytes(Vec<u8>),
+    Resize(u16, u16),
+}
+
+impl Connection {
+    fn game(&mut self, channel: ChannelId) -> Option<&mut GameChannel> {
+        self.game.as_mut().filter(|game| game.id == channel)
+    }
+
+    fn accept(&mut self, user: &str) -> Auth {
+        self.user = user.to_string();
+        Auth::Accept
+    }
+}
+
+impl russh::server::Handler for Connection {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, user: &str) -> Result<Auth, Self::Error> {
+        Ok(self.accept(user))
+    }
+
+    async fn auth_password(&mut self, user: &str, _password: &str) -> Result<Auth, Self::Error> {
+        Ok(self.accept(user))
+    }
+
+    async fn auth_publickey(&mut self, user: &str, _key: &PublicKey) -> Result<Auth, Self::Error> {
+        Ok(self.accept(user))
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        reply: ChannelOpenHandle,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.game.is_some() {
+            reply.reject(ChannelOpenFailure::AdministrativelyProhibited).await;
+            return Ok(());
+        }
+        let Some(slot) = Slot::take(&self.sessions, self.max_sessions) else {
+            tracing::warn!(user = self.user, "ssh server full");
+            reply.reject(ChannelOpenFailure::ResourceShortage).await;
+            return Ok(());
+        };
+        self.game = Some(GameChannel { id: channel.id(), slot: Some(slot), size: SSH_DEFAULT_SIZE, input: None });
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _term: &str,
+        cols: u32,
+        rows: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(russh::Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(game) = self.game(channel) {
+            game.size = (clamp(cols), clamp(rows));
+        }
+        session.channel_success(channel)
+    }
+
+    async fn window_change_request(
+        &mut self,
+        channel: ChannelId,
+        cols: u32,
+        rows: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(input) = self.game(channel).and_then(|game| game.input.as_ref()) {
+            let _ = input.send(Input::Resize(clamp(cols), clamp(rows)));
+        }
+        Ok(())
+    }
+
+    async fn shell_request(&mut self, channel: ChannelId, session: &mut Session) -> Result<(), Self::Error> {
+        let user = self.user.clone();
+        let handle = session.handle();
+        let Some(game) = self.game(channel) else {
+            return session.channel_failure(channel);
+        };
+        let Some(slot) = game.slot.take() else {
+            return session.channel_failure(channel); // Already playing
+        };
+        let (input, input_rx) = mpsc::channel();
+        game.input = Some(input);
+        let size = game.size;
+        session.channel_success(channel)?;
+
+        // Frames go out through the async side; the game itself runs on a
+        // plain thread like the local one does
+        let (output, output_rx) = frame_channel(SSH_OUTPUT_FRAMES);
+        tokio::spawn(send_output(handle, channel, output_rx));
+        std::thread::spawn(move || {
+            let _slot = slot;
+            tracing::info!(user, "ssh game started");
+            match play(&user, size, input_rx, output) {
+                Ok(()) => tracing::info!(user, "ssh game ended"),
+                Err(e) => tracing::info!(user, reason = %e, "ssh game ended"),
+            }
+        });
+        Ok(())
+    }
+
+    async fn data(&mut self, channel: ChannelId, data: &[u8], _session: &mut Session) -> Result<(), Self::Error> {
+        if let Some(input) = self.game(channel).and_then(|game| game.input.as_ref()) {
+            let _ = input.send(Input::Bytes(data.to_vec()));
+        }
+        Ok(())
+    }
+
+    async fn channel_eof(&mut self, channel: ChannelId, _session: &mut Session) -> Result<(), Self::Error> {
+        // Dropping the input ends the game
+        if let Some(game) = self.game(channel) {
+            game.input = None;
+        }
+        Ok(())
+    }
+
+    async fn channel_close(&mut self, channel: ChannelId, _session: &mut Session) -> Result<(), Self::Error> {
+        if self.game(channel).is_some() {
+            self.game = None;
+        }
+        Ok(())
+    }
+}
+
+fn clamp(n: u32) -> u16 {
+    n.clamp(1, u16::MAX as u32) as u16
+}
+
+// Forwards frames to the client until the game thread hangs up, then closes
+// the channel
+async fn send_output(handle: Handle, channel: ChannelId, mut frames: Receiver<Vec<u8>>) {
+    while let Some(bytes) = frames.recv().await {
+        if handle.data(channel, bytes).await.is_err() {
+            return;
+        }
+    }
+    let _ = handle.exit_status_request(channel, 0).await;
+    let _ = handle.close(channel).await;
+}
+
+fn play(user: &str, size: (u16, u16), input: mpsc::Receiver<Input>, output: Sender<Vec<u8>>) -> Result<()> {
+    let size = Arc::new(Mutex::new(size));
+    let backend = RemoteBackend {
+        inner: CrosstermBackend::new(Output { buffer: Vec::new(), sender: output }),
+        size: size.clone(),
+    };
+    let mut terminal = Terminal::new(backend)?;
+    execute!(terminal.backend_mut().inner, EnterAlternateScreen, Hide)?;
+
+    let mut app = App::guest(user);
+    let mut pending = VecDeque::new();
+    let mut last_input = Instant::now();
+    let idle_limit = Duration::from_secs(SSH_IDLE_TIMEOUT_SECS);
+    let mut terminal = session::run_loop(&mut app, terminal, None, |timeout| {
+        if let Some(event) = pending.pop_front() {
+            return Ok(Some(event));
+        }
+        if last_input.elapsed() > idle_limit {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "idle for too long").into());
+        }
+        match input.recv_timeout(timeout) {
+            Ok(Input::Bytes(bytes)) => {
+                last_input = Instant::now();
+                pending.extend(parse_keys(&bytes));
+                Ok(pending.pop_front())
+            }
+            Ok(Input::Resize(cols, rows)) => {
+                // Picked up by the terminal on the next draw
+                *size.lock().unwrap_or_else(|e| e.into_inner()) = (cols, rows);
+                Ok(None)
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected").into())
+            }
+        }
+    })?;
+
+    execute!(terminal.backend_mut().inner, LeaveAlternateScreen, Show)?;
+    Ok(())
+}
+
+// Bytes written by the terminal backend, sent to the client as one SSH
+// packet per flush. A client more than SSH_OUTPUT_FRAMES behind is cut off:
+// each frame only patches the one before it, so none can be skipped.
+struct Output {
+    buffer: Vec<u8>,
+    sender: Sender<Vec<u8>>,
+}
+
+impl Write for Output {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        match self.sender.try_send(std::mem::take(&mut self.buffer)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(io::Error::new(io::ErrorKind::TimedOut, "client fell too far behind")),
+            Err(TrySendError::Closed(_)) => Err(io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected")),
+        }
+    }
+}
+
+// Crossterm's escape codes, but sized by the client's pty instead of
+// asking the server's own terminal
+struct RemoteBackend {
+    inner: CrosstermBackend<Output>,
+    size: Arc<Mutex<(u16, u16)>>, // Set from the session's resize requests
+}
+
+impl Backend for RemoteBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        self.inner.draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    // Only inline viewports ask, and this one is always fullscreen
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        Ok((0, 0))
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.inner.set_cursor(x, y)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        self.inner.clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        let (width, height) = *self.size.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(Rect::new(0, 0, width, height))
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        let (width, height) = *self.size.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(WindowSize { columns_rows: Size { width, height }, pixels: Size::default() })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.inner)
+    }
+}
+
+// Raw escape codes, for images; the client's size in pixels isn't known,
+// so none are sent
+impl Write for RemoteBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Write::flush(&mut self.inner)
+    }
+}
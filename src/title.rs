@@ -0,0 +1,77 @@
+use std::time::Instant;
+
+use crate::game::GameMode;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MenuItem {
+    Play,
+    Modes,
+    Settings,
+    Stats,
+    Replays,
+    Quit,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 6] = [
+        MenuItem::Play,
+        MenuItem::Modes,
+        MenuItem::Settings,
+        MenuItem::Stats,
+        MenuItem::Replays,
+        MenuItem::Quit,
+    ];
+
+    // Locale key under [title]
+    pub fn key(self) -> &'static str {
+        match self {
+            MenuItem::Play => "title.play",
+            MenuItem::Modes => "title.modes",
+            MenuItem::Settings => "title.settings",
+            MenuItem::Stats => "title.stats",
+            MenuItem::Replays => "title.replays",
+            MenuItem::Quit => "title.quit",
+        }
+    }
+}
+
+// The modes on offer, in M's cycle order; puzzles come from the command line
+pub fn menu_modes() -> Vec<GameMode> {
+    std::iter::successors(Some(GameMode::Sprint), |mode| Some(mode.next()).filter(|&next| next != GameMode::Sprint)).collect()
+}
+
+// The title screen's menu, which stands in for the Ready screen. Modes swaps
+// the menu for a list of modes until one is picked.
+#[derive(Clone, Debug)]
+pub struct TitleMenu {
+    pub selected: usize,             // Into MenuItem::ALL
+    pub picking_mode: Option<usize>, // Into menu_modes(), while the list is open
+    pub shown_at: Instant,           // Start of the background animation
+}
+
+impl TitleMenu {
+    pub fn new() -> Self {
+        Self { selected: 0, picking_mode: None, shown_at: Instant::now() }
+    }
+
+    pub fn item(&self) -> MenuItem {
+        MenuItem::ALL[self.selected]
+    }
+
+    // Up and down through whichever list is showing
+    pub fn step(&mut self, delta: isize) {
+        let (choice, count) = match self.picking_mode.as_mut() {
+            Some(mode) => (mode, menu_modes().len()),
+            None => (&mut self.selected, MenuItem::ALL.len()),
+        };
+        *choice = (*choice as isize + delta).rem_euclid(count as isize) as usize;
+    }
+
+    pub fn open_modes(&mut self, current: GameMode) {
+        self.picking_mode = Some(menu_modes().iter().position(|&mode| mode == current).unwrap_or(0));
+    }
+
+    pub fn picked_mode(&self) -> Option<GameMode> {
+        self.picking_mode.map(|index| menu_modes()[index])
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthChar;
+
+// Art from a text file, drawn dim and tiled behind the panels. ANSI color
+// codes in it are kept; other escape sequences are dropped, and anything
+// that isn't a single column wide shows as a space.
+pub struct Backdrop {
+    lines: Vec<Vec<(char, Style)>>,
+    width: usize, // The longest line; shorter ones leave a gap at the end
+}
+
+impl Backdrop {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut style = Style::default();
+        let lines: Vec<Vec<(char, Style)>> = text
+            .lines()
+            .map(|line| {
+                let mut cells = Vec::new();
+                let mut chars = line.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\x1b' => {
+                            if chars.next() != Some('[') {
+                                continue;
+                            }
+                            // Parameters up to the final byte; only colors are kept
+                            let mut params = String::new();
+                            for c in chars.by_ref() {
+                                if ('@'..='~').contains(&c) {
+                                    if c == 'm' {
+                                        style = sgr(style, &params);
+                                    }
+                                    break;
+                                }
+                                params.push(c);
+                            }
+                        }
+                        '\t' => cells.push((' ', style)),
+                        c if c.width() == Some(1) => cells.push((c, style)),
+                        _ => cells.push((' ', style)),
+                    }
+                }
+                cells
+            })
+            .collect();
+        let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+        Self { lines, width }
+    }
+}
+
+// A Select Graphic Rendition sequence's colors applied to `style`
+fn sgr(style: Style, params: &str) -> Style {
+    let mut style = style;
+    let mut codes = params.split(';').map(|code| code.parse::<u8>().unwrap_or(0));
+    while let Some(code) = codes.next() {
+        let mut extended = || match codes.next() {
+            Some(5) => codes.next().map(Color::Indexed),
+            Some(2) => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+            _ => None,
+        };
+        style = match code {
+            0 => Style::default(),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            38 => extended().map_or(style, |color| style.fg(color)),
+            48 => extended().map_or(style, |color| style.bg(color)),
+            39 => style.fg(Color::Reset),
+            49 => style.bg(Color::Reset),
+            _ => style,
+        };
+    }
+    style
+}
+
+impl Widget for &Backdrop {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.width == 0 {
+            return;
+        }
+        for (row, y) in (area.top()..area.bottom()).enumerate() {
+            let line = &self.lines[row % self.lines.len()];
+            for (column, x) in (area.left()..area.right()).enumerate() {
+                let Some(&(c, style)) = line.get(column % self.width) else {
+                    continue;
+                };
+                // Uncolored art goes gray, so it stays behind whatever the
+                // terminal's own text color is
+                let style = Style { fg: style.fg.or(Some(Color::DarkGray)), ..style }.add_modifier(Modifier::DIM);
+                buf.get_mut(x, y).set_char(c).set_style(style);
+            }
+        }
+    }
+}
@@ -0,0 +1,480 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Instant;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+use crate::config::BoardConfig;
+use crate::constants::{BOARD_HEIGHT, BOARD_WIDTH, BUFFER_HEIGHT, SPAWN_ROWS, STACK_VIEW_MARGIN};
+use crate::game::board::{column_heights, hole_positions, Board};
+use crate::game::color::PieceColor;
+use crate::game::piece::Piece;
+use crate::game::state::GameState;
+use crate::game::{Cell, Game, GameMode};
+use crate::ui::graphics::{self, BoardPixels};
+use crate::ui::theme::{bonus_style, palette_level, piece_rgb, BorderStyle, Glyphs, Palette};
+use crate::ui::trail::Trail;
+
+// The playfield, written straight into the frame buffer two columns per
+// cell. Each cell is set directly rather than through a paragraph of spans,
+// so a frame where only the falling piece moved differs from the last one in
+// just those cells and ratatui's buffer diff sends nothing else.
+pub struct BoardWidget<'a> {
+    pub game: &'a Game,
+    pub title: &'a str,
+    pub options: &'a BoardConfig,
+    pub glyphs: &'a Glyphs,
+    pub border: BorderStyle,
+    pub palette: Palette,
+    pub analysis: bool, // Column heights and hole markers
+    pub preview: bool,  // Show the board as if the piece were hard dropped now
+    pub hint: Option<&'a Piece>, // Placement a T-spin hint asks for next
+    pub next_hole: Option<Range<usize>>, // Columns the next garbage row leaves open, marked in the bottom border
+    pub trail: Option<&'a Trail>, // Streak behind the last hard drop
+}
+
+impl Widget for BoardWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = self.border.block().title(self.title);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let cells = self.cells();
+        // Show the visible field plus the buffer rows pieces spawn into; the
+        // rest of the buffer stays hidden
+        let first_row = BUFFER_HEIGHT - SPAWN_ROWS;
+        let rows = (BOARD_HEIGHT - first_row).min(inner.height as usize);
+        let top = self.view_top(first_row, rows);
+        for (row, y) in (top..top + rows).enumerate() {
+            let screen_y = inner.y + row as u16;
+            for x in 0..BOARD_WIDTH {
+                let screen_x = inner.x + 2 * x as u16;
+                if screen_x + 2 > inner.right() {
+                    break;
+                }
+                let (symbol, style) = self.cell_look(&cells, x, y);
+                buf.set_stringn(screen_x, screen_y, &symbol, 2, style);
+            }
+        }
+
+        // Cut short: the whole stack squeezed into a column beside the rows shown
+        let minimap_x = inner.x + 2 * BOARD_WIDTH as u16;
+        if rows < BOARD_HEIGHT - first_row && minimap_x < inner.right() {
+            self.render_minimap(&cells, first_row, top..top + rows, minimap_x, inner, buf);
+        }
+
+        let floor_shown = top + rows == BOARD_HEIGHT;
+        if let Some((lit, style)) = self.lock_meter(inner.width).filter(|_| floor_shown && area.bottom() > inner.bottom()) {
+            for x in inner.left()..inner.right() {
+                let cell = buf.get_mut(x, inner.bottom());
+                if x - inner.left() < lit {
+                    cell.set_symbol("━").set_style(style);
+                } else {
+                    cell.set_fg(Color::DarkGray);
+                }
+            }
+        }
+        if let Some(hole) = self.next_hole.filter(|_| rows > 0 && floor_shown && area.bottom() > inner.bottom()) {
+            for x in hole {
+                let screen_x = inner.x + 2 * x as u16;
+                if screen_x + 2 <= inner.right() {
+                    buf.set_stringn(screen_x, inner.bottom(), &self.glyphs.incoming, 2, Style::default().fg(Color::Yellow));
+                }
+            }
+        }
+    }
+}
+
+// The same field at half height for the compact layout: a column per cell
+// and two cells to a line, as the top and bottom halves of one character
+pub struct CompactBoard<'a>(pub BoardWidget<'a>);
+
+impl Widget for CompactBoard<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let board = &self.0;
+        let cells = board.cells();
+        let first_row = BUFFER_HEIGHT - SPAWN_ROWS;
+        for (line, y) in (first_row..BOARD_HEIGHT).step_by(2).enumerate().take(area.height as usize) {
+            for x in 0..BOARD_WIDTH.min(area.width as usize) {
+                let (top, bottom) = (board.half_color(&cells, x, y), board.half_color(&cells, x, y + 1));
+                buf.get_mut(area.x + x as u16, area.y + line as u16).set_symbol("▀").set_fg(top).set_bg(bottom);
+            }
+        }
+    }
+}
+
+// Another player's field at quarter size, for keeping an eye on it beside
+// your own: braille characters of two cells across and four down, dots for
+// anything settled or falling. A character takes the color of the topmost
+// block in it.
+pub struct BrailleBoard<'a>(pub BoardWidget<'a>);
+
+impl Widget for BrailleBoard<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let board = &self.0;
+        let block = board.border.block().title(board.title);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let cells = board.cells();
+        let first_row = BUFFER_HEIGHT - SPAWN_ROWS;
+        // Lined up with the floor, so the top line is only partly spawn rows
+        let lines = (BOARD_HEIGHT - first_row).div_ceil(4);
+        let top = BOARD_HEIGHT - 4 * lines;
+        for line in 0..lines.min(inner.height as usize) {
+            for column in 0..(BOARD_WIDTH / 2).min(inner.width as usize) {
+                let mut dots = 0u32;
+                let mut color = None;
+                for (dy, dx) in (0..4).flat_map(|dy| (0..2).map(move |dx| (dy, dx))) {
+                    let (x, y) = (2 * column + dx, top + 4 * line + dy);
+                    let fill = match cells.board[y][x] {
+                        _ if y < first_row => None,
+                        Drawn::Block(piece) => Some(board.color(piece)),
+                        Drawn::Garbage => Some(Color::Gray),
+                        Drawn::Empty | Drawn::Ghost(_) => None,
+                    };
+                    if let Some(fill) = fill {
+                        dots |= BRAILLE_DOTS[dy][dx];
+                        color.get_or_insert(fill);
+                    }
+                }
+                let symbol = char::from_u32(0x2800 + dots).unwrap_or(' ');
+                buf.get_mut(inner.x + column as u16, inner.y + line as u16)
+                    .set_char(symbol)
+                    .set_fg(color.unwrap_or(Color::Reset))
+                    .set_bg(Color::Indexed(235));
+            }
+        }
+    }
+}
+
+// Bit for each dot of a braille character, by row and then column
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// The field a quarter turn anticlockwise for the horizontal orientation:
+// rows run left to right with the floor against the right-hand border, and
+// the left wall is along the bottom
+pub struct SidewaysBoard<'a>(pub BoardWidget<'a>);
+
+impl Widget for SidewaysBoard<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let board = &self.0;
+        let block = board.border.block().title(board.title);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let cells = board.cells();
+        let first_row = BUFFER_HEIGHT - SPAWN_ROWS;
+        for (column, y) in (first_row..BOARD_HEIGHT).enumerate() {
+            let screen_x = inner.x + 2 * column as u16;
+            if screen_x + 2 > inner.right() {
+                break;
+            }
+            // Cut short, the columns by the left wall are the ones lost
+            for (line, x) in (0..BOARD_WIDTH).rev().enumerate().take(inner.height as usize) {
+                let (symbol, style) = board.cell_look(&cells, x, y);
+                buf.set_stringn(screen_x, inner.y + line as u16, &symbol, 2, style);
+            }
+        }
+
+        // The lock meter runs down the floor, which is the right-hand border
+        if let Some((lit, style)) = board.lock_meter(inner.height).filter(|_| area.right() > inner.right()) {
+            for y in inner.top()..inner.bottom() {
+                let cell = buf.get_mut(inner.right(), y);
+                if y - inner.top() < lit {
+                    cell.set_symbol("┃").set_style(style);
+                } else {
+                    cell.set_fg(Color::DarkGray);
+                }
+            }
+        }
+    }
+}
+
+// What a cell shows: the engine's board with the ghost and the falling
+// piece drawn over it, which only the renderer keeps track of
+#[derive(Clone, Copy, PartialEq)]
+enum Drawn {
+    Empty,
+    Block(PieceColor),
+    Ghost(PieceColor),
+    Garbage,
+}
+
+impl Drawn {
+    fn from_cell(cell: Cell) -> Self {
+        match cell {
+            Cell::Empty => Drawn::Empty,
+            Cell::Filled(color) => Drawn::Block(color),
+            Cell::Garbage => Drawn::Garbage,
+        }
+    }
+
+    // As the engine would see it, ghosts counted as placed
+    fn settled(self) -> Cell {
+        match self {
+            Drawn::Empty => Cell::Empty,
+            Drawn::Block(color) | Drawn::Ghost(color) => Cell::Filled(color),
+            Drawn::Garbage => Cell::Garbage,
+        }
+    }
+}
+
+type DrawnBoard = [[Drawn; BOARD_WIDTH]; BOARD_HEIGHT];
+
+// Drop full rows and shift the rest down, like clear_full_rows
+fn clear_full_frame_rows(frame: &mut DrawnBoard) {
+    let kept: Vec<[Drawn; BOARD_WIDTH]> = frame.iter().filter(|row| row.contains(&Drawn::Empty)).copied().collect();
+    let mut cleared = [[Drawn::Empty; BOARD_WIDTH]; BOARD_HEIGHT];
+    cleared[BOARD_HEIGHT - kept.len()..].copy_from_slice(&kept);
+    *frame = cleared;
+}
+
+// What each board cell shows once the pieces are drawn in
+struct Cells {
+    board: DrawnBoard,
+    heights: [usize; BOARD_WIDTH],
+    holes: [[bool; BOARD_WIDTH]; BOARD_HEIGHT],
+    drop: [[bool; BOARD_WIDTH]; BOARD_HEIGHT],
+    target: [[bool; BOARD_WIDTH]; BOARD_HEIGHT], // Puzzle cells still to fill
+    bonus: [[bool; BOARD_WIDTH]; BOARD_HEIGHT],  // Cells of the falling piece, if it's a bonus piece
+    hint: [[bool; BOARD_WIDTH]; BOARD_HEIGHT],   // Outline of the hinted placement
+    hint_color: Color,
+    trail: Option<(u32, Color)>, // Fade stage and color of the hard drop trail, while it's up
+}
+
+impl BoardWidget<'_> {
+    fn cells(&self) -> Cells {
+        let game = self.game;
+        let mut board: DrawnBoard = game.board.map(|row| row.map(Drawn::from_cell));
+
+        // Placement preview: the piece see-through where it would land, with
+        // any rows it completes already cleared. Nothing falling is drawn.
+        let previewing = self.preview && game.current_piece.is_some();
+        if let Some(piece) = game.current_piece.as_ref().filter(|_| previewing) {
+            let mut landed = piece.clone();
+            loop {
+                let mut below = landed.clone();
+                below.y += 1;
+                if !game.is_valid_position(&below) {
+                    break;
+                }
+                landed = below;
+            }
+            for (x, y) in landed.get_blocks() {
+                if x >= 0 && x < BOARD_WIDTH as i32 && y >= 0 && y < BOARD_HEIGHT as i32 {
+                    board[y as usize][x as usize] = Drawn::Ghost(piece.color);
+                }
+            }
+            if game.mode != GameMode::Puzzle {
+                clear_full_frame_rows(&mut board);
+            }
+        }
+
+        // Analysis overlay: column heights along the top, covered holes marked
+        let settled: Board = board.map(|row| row.map(Drawn::settled));
+        let heights = column_heights(&settled);
+        let mut holes = [[false; BOARD_WIDTH]; BOARD_HEIGHT];
+        if self.analysis {
+            for (x, y) in hole_positions(&settled) {
+                holes[y][x] = true;
+            }
+        }
+        let target = self.target();
+        let mut hint = [[false; BOARD_WIDTH]; BOARD_HEIGHT];
+        let hint_color = self.hint.map_or(Color::Reset, |piece| self.color(piece.color));
+        for (x, y) in self.hint.map(Piece::get_blocks).unwrap_or_default() {
+            if x >= 0 && x < BOARD_WIDTH as i32 && y >= 0 && y < BOARD_HEIGHT as i32 {
+                hint[y as usize][x as usize] = true;
+            }
+        }
+        let mut drop = [[false; BOARD_WIDTH]; BOARD_HEIGHT];
+        let mut bonus = [[false; BOARD_WIDTH]; BOARD_HEIGHT];
+        if previewing {
+            return Cells { board, heights, holes, drop, target, bonus, hint, hint_color, trail: None };
+        }
+
+        // Columns under the active piece, from just below it down to where it lands
+        if self.options.drop_column {
+            if let (Some(piece), Some(ghost)) = (&game.current_piece, game.get_ghost_piece()) {
+                for ((x, top), (_, bottom)) in piece.get_blocks().into_iter().zip(ghost.get_blocks()) {
+                    for y in top.max(0)..=bottom.min(BOARD_HEIGHT as i32 - 1) {
+                        drop[y as usize][x as usize] = true;
+                    }
+                }
+            }
+        }
+
+        // Ghost piece first so it appears behind the current piece
+        if let Some(ghost) = game.get_ghost_piece() {
+            for (x, y) in ghost.get_blocks() {
+                if x >= 0 && x < BOARD_WIDTH as i32 && y >= 0 && y < BOARD_HEIGHT as i32
+                    && board[y as usize][x as usize] == Drawn::Empty
+                {
+                    board[y as usize][x as usize] = Drawn::Ghost(ghost.color);
+                }
+            }
+        }
+
+        if let Some(piece) = &game.current_piece {
+            for (x, y) in piece.get_blocks() {
+                if x >= 0 && x < BOARD_WIDTH as i32 && y >= 0 && y < BOARD_HEIGHT as i32 {
+                    board[y as usize][x as usize] = Drawn::Block(piece.color);
+                    bonus[y as usize][x as usize] = piece.bonus;
+                }
+            }
+        }
+
+        let trail = self.trail.and_then(|trail| Some((trail.stage(Instant::now())?, self.color(trail.color))));
+        Cells { board, heights, holes, drop, target, bonus, hint, hint_color, trail }
+    }
+
+    // Drawn over the empty cells the last hard drop fell through
+    fn trail_at(&self, cells: &Cells, x: usize, y: usize) -> Option<(u32, Color)> {
+        let covered = cells.board[y][x] == Drawn::Empty && self.trail.is_some_and(|trail| trail.covers(x, y));
+        cells.trail.filter(|_| covered)
+    }
+
+    // The visible field for the pixel board: the stack, the ghost blended
+    // over the background and the falling piece, without the text board's
+    // markers
+    pub fn pixels(&self, area: Rect) -> BoardPixels {
+        let cells = self.cells();
+        let level = palette_level(self.game);
+        let first_row = BUFFER_HEIGHT - SPAWN_ROWS;
+        let pixels = (first_row..BOARD_HEIGHT)
+            .flat_map(|y| (0..BOARD_WIDTH).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let background = graphics::BACKGROUND[(x + y) % 2];
+                let rgb = |color| piece_rgb(self.palette.shifted(color, level));
+                match cells.board[y][x] {
+                    Drawn::Empty if y < BUFFER_HEIGHT => None,
+                    Drawn::Empty => Some(background),
+                    Drawn::Block(color) => Some(rgb(color)),
+                    Drawn::Ghost(color) => Some(graphics::blend(rgb(color), background)),
+                    Drawn::Garbage => Some(graphics::GARBAGE),
+                }
+            })
+            .collect();
+        BoardPixels { area, cells: pixels }
+    }
+
+    // First row shown when the terminal is too short for the whole field:
+    // a few rows above the falling piece, or the floor when nothing's falling
+    fn view_top(&self, first_row: usize, rows: usize) -> usize {
+        let last_top = BOARD_HEIGHT - rows;
+        let piece_top = self.game.current_piece.as_ref().and_then(|piece| piece.get_blocks().into_iter().map(|(_, y)| y).min());
+        match piece_top {
+            Some(y) => (y - STACK_VIEW_MARGIN as i32).clamp(first_row as i32, last_top as i32) as usize,
+            None => last_top,
+        }
+    }
+
+    // One character per band of rows, shaded by how full the band is. The
+    // bands on screen are drawn brighter, and the falling piece's in its
+    // color. Only drawn with fewer rows on screen than the field has, so no
+    // band is empty.
+    fn render_minimap(&self, cells: &Cells, first_row: usize, shown: Range<usize>, x: u16, inner: Rect, buf: &mut Buffer) {
+        let total = BOARD_HEIGHT - first_row;
+        let height = inner.height as usize;
+        let piece = self.game.current_piece.as_ref();
+        for row in 0..height {
+            let band = first_row + row * total / height..first_row + (row + 1) * total / height;
+            let filled = cells.board[band.clone()]
+                .iter()
+                .flatten()
+                .filter(|drawn| matches!(drawn, Drawn::Block(_) | Drawn::Garbage))
+                .count();
+            let shade = self.glyphs.minimap[(filled * (self.glyphs.minimap.len() - 1)).div_ceil(band.len() * BOARD_WIDTH)];
+            let has_piece = piece.is_some_and(|piece| piece.get_blocks().iter().any(|&(_, y)| y >= 0 && band.contains(&(y as usize))));
+            let style = match piece {
+                Some(piece) if has_piece => Style::default().fg(self.color(piece.color)),
+                _ if band.start < shown.end && shown.start < band.end => Style::default().fg(Color::White),
+                _ => Style::default().fg(Color::DarkGray),
+            };
+            let shade = if has_piece && shade == ' ' { self.glyphs.minimap[1] } else { shade };
+            buf.set_stringn(x, inner.y + row as u16, shade.to_string(), 1, style);
+        }
+    }
+
+    // Puzzle cells still to fill
+    fn target(&self) -> [[bool; BOARD_WIDTH]; BOARD_HEIGHT] {
+        let mut target = [[false; BOARD_WIDTH]; BOARD_HEIGHT];
+        if let (GameMode::Puzzle, Some(puzzle)) = (self.game.mode, &self.game.puzzle) {
+            for (x, y) in puzzle.target() {
+                target[y][x] = true;
+            }
+        }
+        target
+    }
+
+    // A piece color as the palette has it at this point in the run
+    // How much of a floor border `length` long the lock meter lights, and
+    // its color, running yellow to red; None unless a piece is grounded
+    fn lock_meter(&self, length: u16) -> Option<(u16, Style)> {
+        let playing = self.game.game_state == GameState::Playing;
+        let left = self.game.lock_left().filter(|_| self.options.lock_meter && playing)?;
+        let color = match left {
+            left if left > 0.5 => Color::Yellow,
+            left if left > 0.25 => Color::LightRed,
+            _ => Color::Red,
+        };
+        Some(((left * length as f64).ceil() as u16, Style::default().fg(color)))
+    }
+
+    fn color(&self, color: PieceColor) -> Color {
+        self.palette.piece(color, palette_level(self.game))
+    }
+
+    // One cell's color for the compact layout, where it's half a character
+    fn half_color(&self, cells: &Cells, x: usize, y: usize) -> Color {
+        if let Some((0, color)) = self.trail_at(cells, x, y) {
+            return color;
+        }
+        match cells.board[y][x] {
+            Drawn::Empty if y < BUFFER_HEIGHT => Color::Reset,
+            Drawn::Empty if cells.target[y][x] => Color::Indexed(58),
+            Drawn::Empty => Color::Indexed(235),
+            Drawn::Block(color) => self.color(color),
+            Drawn::Ghost(_) => Color::DarkGray,
+            Drawn::Garbage => Color::Gray,
+        }
+    }
+
+    fn cell_look(&self, cells: &Cells, x: usize, y: usize) -> (Cow<'_, str>, Style) {
+        let glyphs = self.glyphs;
+        let dim = Style::default().fg(Color::DarkGray);
+        if let Some((stage, color)) = self.trail_at(cells, x, y) {
+            return match stage {
+                0 => (Cow::Borrowed(glyphs.filled.as_str()), Style::default().fg(color)),
+                1 => (Cow::Borrowed(glyphs.ghost.as_str()), Style::default().fg(color)),
+                _ => (Cow::Borrowed(glyphs.ghost.as_str()), Style::default().fg(color).add_modifier(Modifier::DIM)),
+            };
+        }
+        match cells.board[y][x] {
+            Drawn::Empty if self.analysis && y == BUFFER_HEIGHT - SPAWN_ROWS => (Cow::Owned(format!("{:>2}", cells.heights[x])), dim),
+            Drawn::Empty if cells.holes[y][x] => (Cow::Borrowed(glyphs.hole.as_str()), Style::default().fg(Color::Red)),
+            Drawn::Empty if cells.hint[y][x] => (Cow::Borrowed(glyphs.hint.as_str()), Style::default().fg(cells.hint_color)),
+            Drawn::Empty if y < BUFFER_HEIGHT => (Cow::Borrowed("  "), Style::default()),
+            Drawn::Empty if cells.target[y][x] => (Cow::Borrowed("  "), Style::default().bg(Color::Indexed(58))),
+            Drawn::Empty if cells.drop[y][x] => (Cow::Borrowed("  "), Style::default().bg(Color::Indexed(236))),
+            Drawn::Empty if self.options.bottom_row && y == BOARD_HEIGHT - 1 => (Cow::Borrowed(glyphs.floor.as_str()), dim),
+            Drawn::Empty if self.options.column_guides => (Cow::Borrowed(glyphs.guide.as_str()), dim),
+            // Checkerboard background
+            Drawn::Empty if (x + y).is_multiple_of(2) => {
+                let background = self.palette.background(palette_level(self.game));
+                (Cow::Borrowed(glyphs.background.as_str()), Style::default().fg(background))
+            }
+            Drawn::Empty => (Cow::Borrowed("  "), Style::default()),
+            Drawn::Block(color) if cells.bonus[y][x] => (Cow::Borrowed(glyphs.bonus.as_str()), bonus_style(self.color(color))),
+            Drawn::Block(color) => (Cow::Borrowed(glyphs.filled.as_str()), Style::default().fg(self.color(color))),
+            Drawn::Ghost(color) => (Cow::Borrowed(glyphs.ghost.as_str()), Style::default().fg(self.color(color))),
+            Drawn::Garbage => (Cow::Borrowed(glyphs.filled.as_str()), Style::default().fg(Color::Gray)),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::io::{self, Write};
+
+use ratatui::{backend::WindowSize, layout::Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{BOARD_WIDTH, GHOST_ALPHA, KITTY_CHUNK};
+use crate::input::multiplexer::Multiplexer;
+
+// Drawing the board as real pixels, for terminals that can show images.
+// The text board is still drawn underneath, so anything that can't show
+// the image falls back to it.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsMode {
+    #[default]
+    Off,
+    Auto, // Whichever the terminal looks like it speaks
+    Kitty,
+    Sixel,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Protocol {
+    Kitty,
+    Sixel,
+}
+
+impl GraphicsMode {
+    pub fn protocol(self) -> Option<Protocol> {
+        match self {
+            GraphicsMode::Off => None,
+            GraphicsMode::Auto => detect(),
+            GraphicsMode::Kitty => Some(Protocol::Kitty),
+            GraphicsMode::Sixel => Some(Protocol::Sixel),
+        }
+    }
+}
+
+// Goes by what the terminal calls itself; a multiplexer in between would
+// have to pass the images through, which most don't
+fn detect() -> Option<Protocol> {
+    let env = |name: &str| std::env::var(name).unwrap_or_default().to_lowercase();
+    if Multiplexer::detect().is_some() {
+        return None;
+    }
+    let (term, program) = (env("TERM"), env("TERM_PROGRAM"));
+    if term.contains("kitty") || term.contains("ghostty") || ["wezterm", "ghostty"].contains(&program.as_str()) {
+        Some(Protocol::Kitty)
+    } else if term.starts_with("foot") || term.starts_with("mlterm") || program == "mlterm" {
+        Some(Protocol::Sixel)
+    } else {
+        None
+    }
+}
+
+// The board's visible field a color per cell, row by row; None is an empty
+// spawn row, left see-through
+#[derive(Clone, PartialEq, Debug)]
+pub struct BoardPixels {
+    pub area: Rect, // The board inside its border
+    pub cells: Vec<Option<[u8; 3]>>,
+}
+
+impl BoardPixels {
+    fn rows(&self) -> usize {
+        self.cells.len() / BOARD_WIDTH
+    }
+}
+
+// Checkerboard under the pixel board; garbage is plain gray
+pub const BACKGROUND: [[u8; 3]; 2] = [[20, 20, 20], [30, 30, 30]];
+pub const GARBAGE: [u8; 3] = [128, 128, 128];
+
+// A ghost over the board, blended here rather than by the terminal, so
+// the text underneath doesn't show through
+pub fn blend(color: [u8; 3], under: [u8; 3]) -> [u8; 3] {
+    let mix = |top: u8, bottom: u8| (top as f64 * GHOST_ALPHA + bottom as f64 * (1.0 - GHOST_ALPHA)) as u8;
+    [mix(color[0], under[0]), mix(color[1], under[1]), mix(color[2], under[2])]
+}
+
+// What's on screen, so an unchanged board isn't sent again every frame
+#[derive(Default)]
+pub struct Graphics {
+    shown: Option<(Protocol, BoardPixels)>,
+}
+
+impl Graphics {
+    // Puts the board's image up, replaces it, or takes it down. Returns
+    // true when the screen needs drawing again from scratch first.
+    pub fn show(&mut self, out: &mut impl Write, protocol: Option<Protocol>, pixels: Option<BoardPixels>, window: WindowSize) -> io::Result<bool> {
+        let cell = square_cell(&window);
+        let wanted = protocol.zip(pixels).filter(|_| cell > 0);
+        if wanted == self.shown {
+            return Ok(false);
+        }
+        match self.shown.take() {
+            // A sixel image is part of the screen's text once drawn, so
+            // taking it down or moving it means redrawing the lot; it goes
+            // back up on the frame after
+            Some((Protocol::Sixel, shown)) if wanted.as_ref().is_none_or(|(_, pixels)| pixels.area != shown.area) => {
+                return Ok(true);
+            }
+            Some((Protocol::Kitty, _)) if wanted.is_none() => write!(out, "\x1b_Ga=d,d=I,i=1,q=2\x1b\\")?,
+            _ => {}
+        }
+        if let Some((protocol, pixels)) = wanted {
+            // Saved and restored, so the terminal's cursor is where ratatui left it
+            write!(out, "\x1b7\x1b[{};{}H", pixels.area.y + 1, pixels.area.x + 1)?;
+            match protocol {
+                Protocol::Kitty => kitty(out, &pixels, cell)?,
+                Protocol::Sixel => sixel(out, &pixels, cell)?,
+            }
+            write!(out, "\x1b8")?;
+            self.shown = Some((protocol, pixels));
+        }
+        out.flush()?;
+        Ok(false)
+    }
+}
+
+// Pixels per board cell, square and as big as fits the two columns and one
+// row a cell takes as text; 0 when the terminal won't say how big that is
+fn square_cell(window: &WindowSize) -> usize {
+    let (columns, rows) = (window.columns_rows.width as usize, window.columns_rows.height as usize);
+    if columns == 0 || rows == 0 {
+        return 0;
+    }
+    let (width, height) = (window.pixels.width as usize / columns, window.pixels.height as usize / rows);
+    (width * 2).min(height)
+}
+
+// Each board cell scaled up to `cell` pixels square
+fn scaled(pixels: &BoardPixels, cell: usize) -> impl Iterator<Item = Option<[u8; 3]>> + '_ {
+    (0..pixels.rows() * cell).flat_map(move |y| {
+        (0..BOARD_WIDTH * cell).map(move |x| pixels.cells[(y / cell) * BOARD_WIDTH + x / cell])
+    })
+}
+
+// Kitty graphics protocol: RGBA, sent in chunks, under a fixed image and
+// placement id so each new image replaces the last in place
+fn kitty(out: &mut impl Write, pixels: &BoardPixels, cell: usize) -> io::Result<()> {
+    let rgba: Vec<u8> = scaled(pixels, cell)
+        .flat_map(|pixel| match pixel {
+            Some([r, g, b]) => [r, g, b, 255],
+            None => [0, 0, 0, 0],
+        })
+        .collect();
+    let data = base64(&rgba);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            let (width, height) = (BOARD_WIDTH * cell, pixels.rows() * cell);
+            write!(out, "\x1b_Ga=T,f=32,s={},v={},i=1,p=1,q=2,C=1,m={};", width, height, more)?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+// Sixel: a palette of the board's few colors, then the image six pixel
+// rows at a time, one pass per color with runs of the same column pattern
+// squeezed. Pixels left unset stay transparent.
+fn sixel(out: &mut impl Write, pixels: &BoardPixels, cell: usize) -> io::Result<()> {
+    let (width, height) = (BOARD_WIDTH * cell, pixels.rows() * cell);
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    for color in pixels.cells.iter().flatten() {
+        if !palette.contains(color) {
+            palette.push(*color);
+        }
+    }
+    let image: Vec<Option<usize>> = scaled(pixels, cell)
+        .map(|pixel| pixel.and_then(|color| palette.iter().position(|&c| c == color)))
+        .collect();
+
+    write!(out, "\x1bP0;1;0q\"1;1;{};{}", width, height)?;
+    for (i, [r, g, b]) in palette.iter().enumerate() {
+        let percent = |value: u8| value as u32 * 100 / 255;
+        write!(out, "#{};2;{};{};{}", i, percent(*r), percent(*g), percent(*b))?;
+    }
+    for band in (0..height).step_by(6) {
+        for color in 0..palette.len() {
+            let column = |x: usize| {
+                let bits = (0..6)
+                    .filter(|&bit| band + bit < height && image[(band + bit) * width + x] == Some(color))
+                    .fold(0u8, |bits, bit| bits | 1 << bit);
+                (63 + bits) as char
+            };
+            let columns: Vec<char> = (0..width).map(column).collect();
+            if columns.iter().all(|&c| c == '?') {
+                continue;
+            }
+            write!(out, "#{}", color)?;
+            let mut x = 0;
+            while x < width {
+                let run = columns[x..].iter().take_while(|&&c| c == columns[x]).count();
+                if run > 3 {
+                    write!(out, "!{}{}", run, columns[x])?;
+                } else {
+                    write!(out, "{}", columns[x].to_string().repeat(run))?;
+                }
+                x += run;
+            }
+            write!(out, "$")?;
+        }
+        write!(out, "-")?;
+    }
+    write!(out, "\x1b\\")
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            out.push(if i <= chunk.len() { BASE64[(n >> (18 - 6 * i) & 63) as usize] as char } else { '=' });
+        }
+    }
+    out
+}
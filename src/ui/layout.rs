@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutPreset {
+    Minimal, // Board only
+    #[default]
+    Standard,
+    Streamer, // Standard plus a big timer and a key overlay under the board
+}
+
+impl LayoutPreset {
+    pub fn next(self) -> Self {
+        match self {
+            LayoutPreset::Minimal => LayoutPreset::Standard,
+            LayoutPreset::Standard => LayoutPreset::Streamer,
+            LayoutPreset::Streamer => LayoutPreset::Minimal,
+        }
+    }
+}
+
+// When to use the one-panel compact layout
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompactMode {
+    #[default]
+    Auto, // Whenever the whole field won't fit
+    Always,
+    Never, // Keep the columns and scroll the board instead
+}
+
+// Which way up the board is drawn. Sideways turns it a quarter turn
+// anticlockwise, gravity pulling to the right, for wide but short windows.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+// Which panels are on screen. Picking a preset sets every panel; each can
+// then be toggled on its own.
+#[derive(Clone, Copy, Debug)]
+pub struct PanelLayout {
+    pub preset: LayoutPreset,
+    pub hold: bool,
+    pub next: bool,  // Next queue, with the bag and PB ghost under it
+    pub stats: bool, // Run stats, with the session panel under it
+    pub mirrored: bool, // Hold on the right, next on the left
+}
+
+impl PanelLayout {
+    pub fn new(preset: LayoutPreset, mirrored: bool) -> Self {
+        let panels = preset != LayoutPreset::Minimal;
+        Self {
+            preset,
+            hold: panels,
+            next: panels,
+            stats: panels,
+            mirrored,
+        }
+    }
+
+    pub fn cycle_preset(&mut self) {
+        *self = PanelLayout::new(self.preset.next(), self.mirrored);
+    }
+
+    pub fn streamer(&self) -> bool {
+        self.preset == LayoutPreset::Streamer
+    }
+}
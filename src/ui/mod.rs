@@ -1,3 +1,12 @@
+pub mod backdrop;
+pub mod board;
+pub mod graphics;
+pub mod layout;
+pub mod popup;
 pub mod renderer;
+pub mod text;
+pub mod theme;
+pub mod toast;
+pub mod trail;
 
-pub use renderer::ui;
\ No newline at end of file
+pub use renderer::ui;
@@ -0,0 +1,29 @@
+use std::time::{Duration, Instant};
+
+use crate::constants::{POPUP_MS, POPUP_RISE};
+
+// Points a clear scored, floating up from the rows it cleared and fading
+// as it goes
+#[derive(Clone, Debug)]
+pub struct ScorePopup {
+    pub points: u64,
+    row: usize, // Board row it starts on, the middle of the clear
+    at: Instant,
+}
+
+impl ScorePopup {
+    pub fn new(points: u64, row: usize, now: Instant) -> Self {
+        Self { points, row, at: now }
+    }
+
+    // Board row it's on by now, and whether it's fading; None once it's gone
+    pub fn place(&self, now: Instant) -> Option<(usize, bool)> {
+        let elapsed = now.saturating_duration_since(self.at);
+        if elapsed >= Duration::from_millis(POPUP_MS) {
+            return None;
+        }
+        let risen = elapsed.as_millis() as usize * POPUP_RISE / POPUP_MS as usize;
+        let fading = elapsed.as_millis() as u64 * 3 >= POPUP_MS * 2; // The last third
+        Some((self.row.saturating_sub(risen), fading))
+    }
+}
@@ -1,20 +1,308 @@
+use std::time::{Duration, Instant};
+
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::block::{Position, Title},
+    widgets::{Borders, Clear, Paragraph},
     Frame,
 };
 
-use crate::game::{Game, Cell};
-use crate::constants::{BOARD_WIDTH, BOARD_HEIGHT};
+use crate::app::{App, PbStatus};
+use crate::input::diagnostics::{key_name, InputDiagnostics};
+use crate::calibration::Calibration;
+use crate::profiler::{Profiler, Timing};
+use crate::prediction::Prediction;
+use crate::game::{Game, GameMode};
+use crate::game::blind::Blind;
+use crate::game::event::GameEvent;
+use crate::game::piece::{Piece, PieceType};
+use crate::game::playback::Playback;
+use crate::ui::board::{BoardWidget, BrailleBoard, CompactBoard, SidewaysBoard};
+use crate::ui::graphics::BoardPixels;
+use crate::ui::layout::{CompactMode, Orientation};
+use crate::ui::text::{self, fit_lines, pad_left, pad_right, truncate};
+use crate::ui::toast::{ToastKind, Toasts};
+use crate::ui::theme::{accent, big_text, bonus_style, palette_level, piece_color, BorderStyle, Glyphs, Palette};
+use crate::title::{menu_modes, MenuItem};
+use crate::versus::{side_key, MatchSetup, Row, Setting};
+use crate::wizard::{theme_glyphs, KeyTest, Step, Wizard, HANDLING_CHOICES, THEME_CHOICES};
+use crate::game::state::EndReason;
+use crate::i18n::Strings;
+use crate::ai::battle::Handicap;
+use crate::ai::heuristic;
+use crate::ai::race::{CpuRacer, RaceResult};
+use crate::koth::{Koth, TurnResult};
+use crate::netplay::{Netplay, Outcome};
+use crate::config::format_time;
+use crate::constants::{
+    SPAWN_ROWS, BUFFER_HEIGHT, VISIBLE_HEIGHT, MINIMAP_WIDTH, PACE_GRAPH_WIDTH, USAGE_BAR_WIDTH, DIG_PIECE_LIMIT, TARGET_LINES,
+    GO_DISPLAY_MS, KEY_FLASH_MS, LEVEL_UP_FLASH_MS, MARATHON_LINES, ULTRA_SECS, CALIBRATION_TAPS,
+    TITLE_DROP_SPACING, TITLE_DROP_SPEED, TITLE_PANEL_WIDTH, BOARD_WIDTH, COMPACT_WIDTH, COMPACT_HEIGHT, COMPACT_NEXT,
+    MINI_BOARD_WIDTH, MINI_BOARD_HEIGHT, NETPLAY_EMOTE_MS,
+};
+use crate::game::level::gravity_g;
+use crate::game::replay::InputEvent;
+use crate::game::usage::ActionCounts;
+use crate::input::InputDirection;
+
+// Columns across the middle of the screen
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Hold, // Hold, stats, session
+    Board,
+    Next, // Next, bag, PB ghost
+    Cpu,  // The CPU's board in a sprint race
+    Events,
+}
 
-pub fn ui(f: &mut Frame, game: &Game) {
+// Draws the frame, and hands back the board as pixels when it should go
+// over the text board as an image
+pub fn ui(f: &mut Frame, app: &App) -> Option<BoardPixels> {
+    let game = app.shown_game();
     let size = f.size();
+    if let Some(calibration) = &app.calibration {
+        render_calibration(f, app, calibration, size);
+        render_overlays(f, app, size);
+        return None;
+    }
+    let border = app.config.theme.border;
+    // Too small for the columns, which 80x24 just is: one compact panel instead
+    // The compact panel only draws the board upright
+    let compact = match app.config.layout.compact {
+        _ if app.config.layout.orientation == Orientation::Horizontal => false,
+        CompactMode::Always => true,
+        CompactMode::Never => false,
+        CompactMode::Auto => size.height < (VISIBLE_HEIGHT + SPAWN_ROWS + 4) as u16, // Board, borders and a row either side
+    };
+    if let Some(backdrop) = &app.backdrop {
+        f.render_widget(backdrop, size);
+    }
+    let (board_area, center_area) = if compact { render_compact(f, app, size) } else { render_columns(f, app, size) };
+    
+    if let Some(playback) = &app.playback {
+        // Timeline goes just under the board, spanning both side panels
+        let timeline_area = Rect {
+            y: center_area.y + center_area.height,
+            height: 5,
+            ..center_area
+        }
+        .intersection(size);
+        f.render_widget(Clear, timeline_area);
+        render_playback_timeline(f, &app.strings, playback, border, timeline_area);
+        
+        if let crate::game::state::GameState::Countdown(count) = game.game_state {
+            render_countdown_overlay(f, app, &count.to_string(), board_area);
+        } else if showing_go(game) {
+            render_countdown_overlay(f, app, "GO", board_area);
+        }
+        render_overlays(f, app, size);
+        return None;
+    }
+    
+    // Render countdown or game state overlays
+    match game.game_state {
+        _ if app.attract.is_some() => {
+            render_attract_overlay(f, app, board_area);
+        }
+        crate::game::state::GameState::Ready => {
+            render_title(f, app, size);
+        }
+        crate::game::state::GameState::Countdown(count) => {
+            let text = match app.countdown_shown(count) {
+                0 => "GO".to_string(),
+                count => count.to_string(),
+            };
+            render_countdown_overlay(f, app, &text, board_area);
+        }
+        crate::game::state::GameState::Playing if showing_go(game) => {
+            render_countdown_overlay(f, app, "GO", board_area);
+        }
+        crate::game::state::GameState::Paused => {
+            render_paused_overlay(f, &app.strings, border, board_area);
+        }
+        crate::game::state::GameState::Finished => {
+            render_finished_overlay(f, app, board_area);
+        }
+        _ => {}
+    }
+    if let Some(progress) = app.game.input_state.restart_progress(Instant::now()) {
+        render_restart_hold(f, app, progress, board_area);
+    }
+    if app.quit_prompt.is_some() {
+        render_quit_prompt(f, &app.strings, border, board_area);
+    }
+    
+    if let Some(wizard) = &app.wizard {
+        render_wizard(f, app, wizard, size);
+    } else if let Some(name) = &app.name_entry {
+        render_name_entry(f, &app.strings, name, border, board_area);
+    }
+    if let Some(koth) = app.koth.as_ref().filter(|koth| koth.handoff) {
+        render_koth_handoff(f, &app.strings, koth, border, board_area);
+    }
+    
+    if app.handling_menu {
+        render_handling_menu(f, app, size);
+    }
+    if let Some(setup) = &app.match_setup {
+        render_match_setup(f, app, setup, size);
+    }
+    
+    if app.show_stats {
+        render_stats_dashboard(f, app, size);
+    }
+    
+    render_overlays(f, app, size);
+    
+    // Only a board in plain play goes up as an image; anything drawn over
+    // it needs the text cells showing
+    let full_board = !compact
+        && app.config.layout.orientation == Orientation::Vertical
+        && board_area.height >= (VISIBLE_HEIGHT + SPAWN_ROWS + 2) as u16;
+    let covered = showing_go(game)
+        || app.attract.is_some()
+        || app.wizard.is_some()
+        || app.name_entry.is_some()
+        || app.koth.as_ref().is_some_and(|koth| koth.handoff)
+        || app.handling_menu
+        || app.match_setup.is_some()
+        || app.quit_prompt.is_some()
+        || app.show_stats;
+    let playing = game.game_state == crate::game::state::GameState::Playing;
+    app.graphics.filter(|_| full_board && playing && !covered).map(|_| {
+        let glyphs = Glyphs::from_config(&app.config.glyphs);
+        let board = BoardWidget {
+            game,
+            title: "",
+            options: &app.config.board,
+            glyphs: &glyphs,
+            border: app.config.theme.border,
+            palette: app.config.theme.palette,
+            analysis: false,
+            preview: app.previewing(),
+            hint: None,
+            next_hole: None,
+            trail: None,
+        };
+        board.pixels(app.config.theme.border.block().inner(board_area))
+    })
+}
+
+// A box flashing on the beat, with how the taps along to it have landed
+fn render_calibration(f: &mut Frame, app: &App, calibration: &Calibration, area: Rect) {
+    let strings = &app.strings;
+    let now = Instant::now();
+    let popup_area = centered_rect(60, 60, area);
+    f.render_widget(Clear, popup_area);
+    
+    let ms = |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{:+.0}", value));
+    let dim = Style::default().fg(Color::DarkGray);
+    let flash = if calibration.lit(now) && !calibration.done() {
+        Style::default().bg(accent(&app.config, app.game.mode))
+    } else {
+        Style::default().bg(Color::Indexed(236))
+    };
+    let status = if calibration.done() {
+        strings.get("calibrate.done").to_string()
+    } else if calibration.warming_up(now) {
+        strings.get("calibrate.warmup").to_string()
+    } else {
+        strings.fmt("calibrate.taps", &[&calibration.offsets.len(), &CALIBRATION_TAPS])
+    };
+    
+    let mut text = vec![
+        Line::from(vec![Span::raw(strings.get("calibrate.instructions"))]),
+        Line::from(""),
+    ];
+    for _ in 0..3 {
+        text.push(Line::from(vec![Span::styled(" ".repeat(16), flash)]));
+    }
+    text.extend([
+        Line::from(""),
+        Line::from(vec![Span::raw(status)]),
+        Line::from(vec![Span::raw(strings.fmt("calibrate.last", &[&ms(calibration.offsets.last().copied())]))]),
+        Line::from(vec![Span::raw(strings.fmt("calibrate.average", &[&ms(calibration.average())]))]),
+        Line::from(vec![Span::raw(strings.fmt(
+            "calibrate.percentiles",
+            &[&ms(calibration.percentile(0.1)), &ms(calibration.percentile(0.5)), &ms(calibration.percentile(0.9))],
+        ))]),
+        Line::from(vec![Span::styled(
+            strings.fmt("calibrate.frame", &[&format!("{:.1}", app.profiler.last.input.average().as_secs_f64() * 1000.0)]),
+            dim,
+        )]),
+    ]);
+    // Only worth suggesting an offset for taps that land late
+    if let Some(average) = calibration.average().filter(|&average| calibration.done() && average >= 1.0) {
+        text.push(Line::from(""));
+        text.push(Line::from(vec![Span::styled(
+            strings.fmt("calibrate.suggest", &[&format!("{:.0}", average)]),
+            Style::default().fg(Color::Yellow),
+        )]));
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(vec![Span::styled(strings.get("calibrate.help"), dim)]));
+    
+    let widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(app.config.theme.border.block().title(strings.get("calibrate.title")))
+        .alignment(Alignment::Center);
+    f.render_widget(widget, popup_area);
+}
+
+// Upright filling its column, or on its side in a strip across the middle
+// of it, with the rest of the column left for overlays
+fn render_board(f: &mut Frame, board: BoardWidget, sideways: bool, area: Rect) {
+    if sideways {
+        let height = (BOARD_WIDTH + 2) as u16;
+        let area = Rect { y: area.y + area.height.saturating_sub(height) / 2, height: height.min(area.height), ..area };
+        f.render_widget(SidewaysBoard(board), area);
+    } else {
+        f.render_widget(board, area);
+    }
+}
+
+// Points from recent clears over the board, each centered on the row it's
+// drifted up to
+fn render_score_popups(f: &mut Frame, app: &App, inner: Rect) {
+    let now = Instant::now();
+    let first_row = BUFFER_HEIGHT - SPAWN_ROWS;
+    for popup in app.score_popups() {
+        let Some((row, fading)) = popup.place(now) else {
+            continue;
+        };
+        let Some(line) = row.checked_sub(first_row).filter(|&line| line < inner.height as usize) else {
+            continue;
+        };
+        let text = app.strings.fmt("event.scored", &[&popup.points]);
+        let style = Style::default().fg(Color::Yellow).add_modifier(if fading { Modifier::DIM } else { Modifier::BOLD });
+        let area = Rect { y: inner.y + line as u16, height: 1, ..inner };
+        f.render_widget(Paragraph::new(Span::styled(text, style)).alignment(Alignment::Center), area);
+    }
+}
+
+// The CPU's or right hand's board, full size or as a braille mini at the
+// top of its column
+fn render_other_board(f: &mut Frame, board: BoardWidget, mini: bool, sideways: bool, area: Rect) {
+    if mini {
+        let area = Rect { height: MINI_BOARD_HEIGHT.min(area.height), ..area };
+        f.render_widget(BrailleBoard(board), area);
+    } else {
+        render_board(f, board, sideways, area);
+    }
+}
+
+// The board and its panels side by side, with the streamer strip under
+// them. Returns the board's area and the board's with the side panels
+// around it, for what goes over or under them.
+fn render_columns(f: &mut Frame, app: &App, size: Rect) -> (Rect, Rect) {
+    let game = app.shown_game();
+    let layout = &app.layout;
+    let sideways = app.config.layout.orientation == Orientation::Horizontal;
     
     // Calculate center position for the game board
-    let board_height = 22; // 20 rows + 2 borders
-    let board_width = 22;  // 20 cols (2 chars per block) + 2 borders
+    let board_height = (VISIBLE_HEIGHT + SPAWN_ROWS + 2) as u16; // 20 rows + 2 spawn rows + 2 borders
+    let streamer_height = if layout.streamer() { 7 } else { 0 }; // Big timer and keys
     
     // Create a centered layout
     let vertical_chunks = Layout::default()
@@ -22,178 +310,1043 @@ pub fn ui(f: &mut Frame, game: &Game) {
         .constraints([
             Constraint::Min(1),          // Flexible top space
             Constraint::Length(board_height), // Game board height
+            Constraint::Length(streamer_height), // Streamer strip
             Constraint::Min(1),          // Flexible bottom space
         ])
         .split(size);
+    // Too short for the whole field: the board scrolls with the piece and
+    // gets a minimap of the whole stack alongside
+    let stack_view = !sideways && vertical_chunks[1].height < board_height;
+    let board_width = if sideways {
+        (2 * (VISIBLE_HEIGHT + SPAWN_ROWS) + 2) as u16 // A column pair per row + 2 borders
+    } else {
+        22 + if stack_view { MINIMAP_WIDTH } else { 0 } // 20 cols (2 chars per block) + 2 borders
+    };
+    
+    // An online opponent's board, as far as their inputs have got here
+    let opponent = app.netplay.as_ref().and_then(|netplay| netplay.mirror.as_ref()).map(|mirror| &mirror.game);
+    
+    // The other board shrinks to braille when it won't fit at full size
+    let side_width = side_panel_width(&app.strings);
+    let mini = match app.config.layout.mini_boards {
+        CompactMode::Always => true,
+        CompactMode::Never => false,
+        CompactMode::Auto => {
+            let panels = [layout.hold || layout.stats, layout.next].into_iter().filter(|&shown| shown).count() as u16;
+            let events = if app.show_event_log { 22 } else { 0 };
+            2 * board_width + panels * side_width + events + 2 > size.width // And a column of margin either side
+        }
+    };
+    
+    // Left to right; mirrored swaps the hold and next sides
+    let order = if layout.mirrored {
+        [Column::Next, Column::Board, Column::Hold, Column::Cpu, Column::Events]
+    } else {
+        [Column::Hold, Column::Board, Column::Next, Column::Cpu, Column::Events]
+    };
+    let columns: Vec<(Column, u16)> = order
+        .into_iter()
+        .filter_map(|column| match column {
+            Column::Hold => (layout.hold || layout.stats).then_some((column, side_width)),
+            Column::Board => Some((column, board_width)),
+            Column::Next => layout.next.then_some((column, side_width)),
+            Column::Cpu => ((app.cpu.is_some() || app.twin.is_some() || opponent.is_some()) && app.playback.is_none())
+                .then_some((column, if mini { MINI_BOARD_WIDTH } else { board_width })),
+            Column::Events => app.show_event_log.then_some((column, 22)),
+        })
+        .collect();
     
+    // Flexible margins either side keep the columns centered
+    let constraints: Vec<Constraint> = std::iter::once(Constraint::Min(1))
+        .chain(columns.iter().map(|&(_, width)| Constraint::Length(width)))
+        .chain(std::iter::once(Constraint::Min(1)))
+        .collect();
     let horizontal_chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Min(1),          // Left margin
-            Constraint::Length(15),      // Left info panel
-            Constraint::Length(board_width), // Game board
-            Constraint::Length(15),      // Right info panel  
-            Constraint::Min(1),          // Right margin
-        ])
+        .constraints(constraints)
         .split(vertical_chunks[1]);
+    let column_area = |column: Column| {
+        columns.iter().position(|&(c, _)| c == column).map(|i| horizontal_chunks[i + 1])
+    };
     
-    let left_info_area = horizontal_chunks[1];
-    let board_area = horizontal_chunks[2];
-    let right_info_area = horizontal_chunks[3];
+    // Any background art stays outside the columns and the strip under them
+    let strip_width = horizontal_chunks[1..=columns.len()].iter().map(|chunk| chunk.width).sum();
+    let backing = Rect { x: horizontal_chunks[1].x, width: strip_width, ..vertical_chunks[1] };
+    f.render_widget(Clear, backing.union(Rect { y: vertical_chunks[2].y, height: vertical_chunks[2].height, ..backing }));
     
-    // Render components
-    render_board(f, game, board_area);
-    render_left_info(f, game, left_info_area);
-    render_right_info(f, game, right_info_area);
+    let board_area = column_area(Column::Board).unwrap_or_default();
+    // Board plus the side panels, for things drawn under them
+    let center_area = [Column::Hold, Column::Next]
+        .into_iter()
+        .filter_map(column_area)
+        .fold(board_area, |area, panel| area.union(panel));
     
-    // Render countdown or game state overlays
-    match game.game_state {
-        crate::game::state::GameState::Ready => {
-            render_ready_overlay(f, board_area);
-        }
-        crate::game::state::GameState::Countdown(count) => {
-            render_countdown_overlay(f, count, board_area);
-        }
-        crate::game::state::GameState::Finished => {
-            render_finished_overlay(f, game, board_area);
+    // Render components
+    let glyphs = Glyphs::from_config(&app.config.glyphs);
+    let border = app.config.theme.border;
+    let palette = app.config.theme.palette;
+    let hint = app.hint.as_ref().filter(|_| app.playback.is_none());
+    let title = hint.map(|hint| app.strings.fmt("hint.step", &[&(hint.done + 1), &hint.steps.len()]));
+    let title = title.as_deref().unwrap_or(match (&app.versus, &app.twin) {
+        (Some(_), _) => app.strings.get("versus.player_left"),
+        (None, Some(_)) => app.strings.get("twin.left"),
+        (None, None) => "tstris",
+    });
+    render_board(
+        f,
+        BoardWidget {
+            game,
+            title,
+            options: &app.config.board,
+            glyphs: &glyphs,
+            border,
+            palette,
+            analysis: app.show_analysis,
+            preview: app.previewing(),
+            hint: hint.and_then(|hint| hint.next_step()),
+            next_hole: game.next_garbage_hole().filter(|_| app.config.garbage.preview),
+            trail: app.trail(),
+        },
+        sideways,
+        board_area,
+    );
+    if !sideways && !stack_view {
+        render_score_popups(f, app, border.block().inner(board_area));
+    }
+    if let (Some(area), Some(cpu)) = (column_area(Column::Cpu), &app.cpu) {
+        let title = app.strings.get("race.cpu");
+        let board = BoardWidget { game: &cpu.game, title, options: &app.config.board, glyphs: &glyphs, border, palette, analysis: false, preview: false, hint: None, next_hole: None, trail: None };
+        render_other_board(f, board, mini, sideways, area);
+    }
+    if let (Some(area), Some(opponent)) = (column_area(Column::Cpu), opponent) {
+        let netplay = app.netplay.as_ref();
+        let name = netplay.and_then(|netplay| netplay.opponent.clone()).unwrap_or_else(|| app.strings.get("netplay.opponent").to_string());
+        let title = match netplay.and_then(|netplay| netplay.ping) {
+            Some(ping) => app.strings.fmt("netplay.ping", &[&name, &ping.as_millis()]),
+            None => name,
+        };
+        let board = BoardWidget { game: opponent, title: &title, options: &app.config.board, glyphs: &glyphs, border, palette, analysis: false, preview: false, hint: None, next_hole: None, trail: None };
+        render_other_board(f, board, mini, sideways, area);
+    }
+    if let (Some(area), Some(netplay)) = (column_area(Column::Cpu), &app.netplay) {
+        render_emote(f, app, netplay, area);
+        render_chat(f, app, netplay, area, vertical_chunks[3]);
+    }
+    // The right hand's board has no panels of its own, so its next and held
+    // pieces go in the title
+    if let (Some(area), Some(twin)) = (column_area(Column::Cpu), &app.twin) {
+        let letter = |piece: Option<&Piece>| piece.map_or('-', |piece| piece.piece_type.letter());
+        let key = if app.versus.is_some() { "versus.right" } else { "twin.right" };
+        let title = app.strings.fmt(key, &[&letter(twin.next_pieces.first()), &letter(twin.hold_piece.as_ref())]);
+        let board = BoardWidget { game: twin, title: &title, options: &app.config.board, glyphs: &glyphs, border, palette, analysis: false, preview: false, hint: None, next_hole: None, trail: None };
+        render_other_board(f, board, mini, sideways, area);
+    }
+    // Team play: each CPU teammate under its player's board, or over it
+    // when there's no room below, so the four make a grid
+    if let Some(versus) = &app.versus {
+        let boards = [column_area(Column::Board), column_area(Column::Cpu)];
+        for (i, (cpu, above)) in versus.cpus.iter().zip(boards).enumerate() {
+            let Some(above) = above else {
+                continue;
+            };
+            let x = above.x + above.width.saturating_sub(MINI_BOARD_WIDTH) / 2;
+            let y = if vertical_chunks[3].height >= MINI_BOARD_HEIGHT {
+                vertical_chunks[3].y
+            } else if vertical_chunks[0].height >= MINI_BOARD_HEIGHT {
+                vertical_chunks[0].bottom() - MINI_BOARD_HEIGHT
+            } else {
+                continue;
+            };
+            let title = app.strings.fmt("versus.cpu", &[&(i + 1)]);
+            let board = BoardWidget { game: &cpu.game, title: &title, options: &app.config.board, glyphs: &glyphs, border, palette, analysis: false, preview: false, hint: None, next_hole: None, trail: None };
+            render_other_board(f, board, true, sideways, Rect { x, y, width: MINI_BOARD_WIDTH, height: MINI_BOARD_HEIGHT });
         }
-        _ => {}
     }
+    if let Some(area) = column_area(Column::Hold) {
+        render_left_info(f, app, area);
+    }
+    if let Some(area) = column_area(Column::Next) {
+        render_right_info(f, app, area);
+    }
+    if let Some(area) = column_area(Column::Events) {
+        render_event_log(f, app, area);
+    }
+    if layout.streamer() {
+        let strip = Rect { y: vertical_chunks[2].y, height: vertical_chunks[2].height, ..center_area };
+        render_streamer_strip(f, app, strip);
+    }
+    (board_area, center_area)
 }
 
-fn render_board(f: &mut Frame, game: &Game, area: Rect) {
-    let mut render_board = game.board;
+// One panel for terminals too small for the columns: the board at half
+// height, with the mode, time, lines, hold and next in its border. Returns
+// an area the shape of the full board for the overlays, and the panel.
+fn render_compact(f: &mut Frame, app: &App, size: Rect) -> (Rect, Rect) {
+    let game = app.shown_game();
+    let strings = &app.strings;
+    let width = COMPACT_WIDTH.min(size.width);
+    let height = COMPACT_HEIGHT.min(size.height);
+    let panel = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
     
-    // Render ghost piece first (so it appears behind the current piece)
-    if let Some(ghost) = game.get_ghost_piece() {
-        for (x, y) in ghost.get_blocks() {
-            if x >= 0 && x < BOARD_WIDTH as i32 && y >= 0 && y < BOARD_HEIGHT as i32 {
-                if render_board[y as usize][x as usize] == Cell::Empty {
-                    render_board[y as usize][x as usize] = Cell::Ghost(ghost.color);
-                }
-            }
-        }
-    }
+    let time = format!("{:.2}s", game.get_current_time().unwrap_or_default().as_secs_f64());
+    let progress = match game.mode {
+        GameMode::Sprint | GameMode::Mystery => format!("{}/{}", game.lines_cleared, TARGET_LINES),
+        GameMode::Marathon => format!("{}/{}", game.lines_cleared, MARATHON_LINES),
+        _ => strings.fmt("stats.lines", &[&game.lines_cleared]),
+    };
+    let letter = |piece: &Piece| piece.piece_type.letter();
+    let hold = game.hold_piece.as_ref().filter(|_| !game.blind.hold).map_or('-', letter);
+    let next: String = if game.blind.next || app.prediction.is_some() {
+        "-".to_string()
+    } else {
+        game.next_pieces.iter().take(COMPACT_NEXT).map(letter).collect()
+    };
+    let mode = Span::styled(strings.mode_short(game.mode), Style::default().fg(accent(&app.config, game.mode)));
+    let block = app.config.theme.border.block()
+        .title(Title::from(mode))
+        .title(Title::from(time).alignment(Alignment::Right))
+        .title(Title::from(strings.fmt("compact.hold", &[&hold])).position(Position::Bottom))
+        .title(Title::from(progress).position(Position::Bottom).alignment(Alignment::Center))
+        .title(Title::from(strings.fmt("compact.next", &[&next])).position(Position::Bottom).alignment(Alignment::Right));
+    let inner = block.inner(panel);
+    f.render_widget(Clear, panel);
+    f.render_widget(block, panel);
     
-    // Render current piece on top
-    if let Some(piece) = &game.current_piece {
-        for (x, y) in piece.get_blocks() {
-            if x >= 0 && x < BOARD_WIDTH as i32 && y >= 0 && y < BOARD_HEIGHT as i32 {
-                render_board[y as usize][x as usize] = Cell::Filled(piece.color);
-            }
-        }
+    let board_width = (BOARD_WIDTH as u16).min(inner.width);
+    let board_area = Rect { x: inner.x + (inner.width - board_width) / 2, width: board_width, ..inner };
+    let glyphs = Glyphs::from_config(&app.config.glyphs);
+    let hint = app.hint.as_ref().filter(|_| app.playback.is_none());
+    let board = BoardWidget {
+        game,
+        title: "",
+        options: &app.config.board,
+        glyphs: &glyphs,
+        border: app.config.theme.border,
+        palette: app.config.theme.palette,
+        analysis: false,
+        preview: app.previewing(),
+        hint: hint.and_then(|hint| hint.next_step()),
+        next_hole: None,
+        trail: app.trail(),
+    };
+    f.render_widget(CompactBoard(board), board_area);
+    
+    let overlay_width = (BOARD_WIDTH as u16 * 2 + 2).min(size.width);
+    let overlay_area = Rect { x: size.x + (size.width - overlay_width) / 2, width: overlay_width, ..size };
+    (overlay_area, panel)
+}
+
+// Drawn over everything else, in playback too
+fn render_overlays(f: &mut Frame, app: &App, size: Rect) {
+    let border = app.config.theme.border;
+    if app.show_profiler {
+        render_profiler(f, &app.profiler, border, size);
+    }
+    if app.show_input_diagnostics {
+        render_input_diagnostics(f, &app.input_diagnostics, border, size);
     }
+    if app.frame_step {
+        render_frame_step(f, &app.game, border, size);
+    }
+    render_toasts(f, &app.toasts, border, size);
+}
+
+// Last second of main loop timings: average / worst
+fn render_profiler(f: &mut Frame, profiler: &Profiler, border: BorderStyle, area: Rect) {
+    let window = &profiler.last;
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let timing = |label: &str, timing: &Timing| {
+        Line::from(vec![
+            Span::styled(format!("{:<7}", label), Style::default().fg(Color::DarkGray)),
+            Span::raw(format!("{:>6.2} / {:>6.2}ms", ms(timing.average()), ms(timing.max))),
+        ])
+    };
+    let dropped_style = if window.dropped_ticks > 0 {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    };
     
-    let mut board_lines = Vec::new();
+    let text = vec![
+        timing("render", &window.render),
+        timing("update", &window.update),
+        timing("input", &window.input),
+        Line::from(vec![
+            Span::raw(format!("{} fps  ", window.frames)),
+            Span::styled(format!("{} dropped", window.dropped_ticks), dropped_style),
+        ]),
+    ];
     
-    for y in 0..BOARD_HEIGHT {
-        let mut line_spans = Vec::new();
-        for x in 0..BOARD_WIDTH {
-            match render_board[y][x] {
-                Cell::Empty => {
-                    // Restore checkerboard pattern
-                    if (x + y) % 2 == 0 {
-                        line_spans.push(Span::styled("░░", Style::default().fg(Color::DarkGray)));
-                    } else {
-                        line_spans.push(Span::styled("  ", Style::default()));
-                    }
-                }
-                Cell::Filled(color) => {
-                    line_spans.push(Span::styled("██", Style::default().fg(color)));
-                }
-                Cell::Ghost(color) => {
-                    // Ghost piece with dimmed color and outline
-                    line_spans.push(Span::styled("▒▒", Style::default().fg(color)));
-                }
-            }
-        }
-        board_lines.push(Line::from(line_spans));
+    let profiler_area = Rect { x: area.x, y: area.y, width: 28, height: 6 }.intersection(area);
+    let profiler_widget = Paragraph::new(text)
+        .block(border.block().title("F3"));
+    f.render_widget(Clear, profiler_area);
+    f.render_widget(profiler_widget, profiler_area);
+}
+
+// Per-key press statistics along the bottom-left corner; anything that
+// looks like hardware or terminal trouble is red
+fn render_input_diagnostics(f: &mut Frame, diagnostics: &InputDiagnostics, border: BorderStyle, area: Rect) {
+    let ms = |duration: Option<Duration>| duration.map_or("-".to_string(), |d| format!("{:.1}", d.as_secs_f64() * 1000.0));
+    let flagged = |count: u32| {
+        let style = if count > 0 { Style::default().fg(Color::Red) } else { Style::default() };
+        Span::styled(format!("{:>6}", count), style)
+    };
+    
+    let mut text = vec![Line::from(vec![Span::styled(
+        format!("{:<7}{:>6}{:>6}{:>6}{:>8}{:>8}{:>6}{:>6}", "key", "press", "rel", "rep", "hold", "gap", "chat", "miss"),
+        Style::default().fg(Color::DarkGray),
+    )])];
+    for (code, stats) in &diagnostics.keys {
+        text.push(Line::from(vec![
+            Span::raw(format!(
+                "{:<7}{:>6}{:>6}{:>6}{:>8}{:>8}",
+                truncate(&key_name(*code), 6),
+                stats.presses,
+                stats.releases,
+                stats.repeats,
+                ms(stats.average_hold()),
+                ms(stats.shortest_gap),
+            )),
+            flagged(stats.chatter),
+            flagged(stats.missing_releases),
+        ]));
+    }
+    let presses: u32 = diagnostics.keys.iter().map(|(_, stats)| stats.presses).sum();
+    if presses > 0 && !diagnostics.releases_seen {
+        text.push(Line::from(vec![Span::styled(
+            "no key releases from this terminal",
+            Style::default().fg(Color::Yellow),
+        )]));
     }
     
-    let board_widget = Paragraph::new(board_lines)
-        .block(Block::default()
-               .borders(Borders::ALL)
-               .title("tstris"));
+    let height = (text.len() as u16 + 2).min(area.height);
+    let diagnostics_area = Rect { x: area.x, y: area.bottom() - height, width: 55, height }.intersection(area);
+    let diagnostics_widget = Paragraph::new(text)
+        .block(border.block().title("F4 input"));
+    f.render_widget(Clear, diagnostics_area);
+    f.render_widget(diagnostics_widget, diagnostics_area);
+}
+
+// Frame-step timers along the bottom-right corner
+fn render_frame_step(f: &mut Frame, game: &Game, border: BorderStyle, area: Rect) {
+    let text: Vec<Line> = game
+        .timer_report()
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:<8}", label), Style::default().fg(Color::DarkGray)),
+                Span::raw(value),
+            ])
+        })
+        .collect();
     
-    f.render_widget(board_widget, area);
+    let width = 44.min(area.width);
+    let height = (text.len() as u16 + 2).min(area.height);
+    let step_area = Rect { x: area.right() - width, y: area.bottom() - height, width, height };
+    let step_widget = Paragraph::new(text)
+        .block(border.block().title(". step  > x10"));
+    f.render_widget(Clear, step_area);
+    f.render_widget(step_widget, step_area);
+}
+
+// Stacked down the top-right corner, each sized to its text
+fn render_toasts(f: &mut Frame, toasts: &Toasts, border: BorderStyle, area: Rect) {
+    let mut y = area.y;
+    for toast in toasts.visible() {
+        let color = match toast.kind {
+            ToastKind::Info => Color::White,
+            ToastKind::Success => Color::Green,
+            ToastKind::Warning => Color::Yellow,
+            ToastKind::Error => Color::Red,
+        };
+        // A line each, for the few with more to say
+        let lines: Vec<Line> = toast.text.lines().map(|line| Line::from(Span::styled(line.to_string(), Style::default().fg(color)))).collect();
+        let width = (toast.text.lines().map(text::width).max().unwrap_or(0) as u16 + 4).min(area.width);
+        let height = lines.len() as u16 + 2;
+        let toast_area = Rect { x: area.right() - width, y, width, height }.intersection(area);
+        if toast_area.height < height {
+            break;
+        }
+        
+        let toast_widget = Paragraph::new(fit_lines(lines, width))
+            .block(border.block().border_style(Style::default().fg(color)))
+            .alignment(Alignment::Center);
+        f.render_widget(Clear, toast_area);
+        f.render_widget(toast_widget, toast_area);
+        y += height;
+    }
 }
 
-fn render_left_info(f: &mut Frame, game: &Game, area: Rect) {
+// Side panels are 15 columns unless a translated title needs more; CJK
+// characters count double
+fn side_panel_width(strings: &Strings) -> u16 {
+    let widest = ["panel.hold", "panel.next", "panel.bag", "panel.session", "panel.level_up", "panel.pb"]
+        .into_iter()
+        .map(|key| strings.width(key))
+        .max()
+        .unwrap_or(0);
+    (widest as u16 + 4).max(15)
+}
+
+fn render_left_info(f: &mut Frame, app: &App, area: Rect) {
+    let game = app.shown_game();
+    let hold_height = if app.layout.hold { 6 } else { 0 };
+    let (stats_height, session_height) = match (app.layout.stats, app.show_session) {
+        (false, _) => (0, 0),
+        (true, true) => (8, 7),
+        (true, false) => (8, 1),
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(8),  // Hold piece
-            Constraint::Length(8),  // Stats
-            Constraint::Min(1),     // Empty space
+            Constraint::Length(hold_height),  // Hold piece
+            Constraint::Length(stats_height),  // Stats
+            Constraint::Length(session_height), // Session stats
+            Constraint::Min(0),     // Empty space
         ])
         .split(area);
     
-    render_hold_piece(f, game, chunks[0]);
-    render_stats(f, game, chunks[1]);
+    if app.layout.hold {
+        render_hold_piece(f, &app.strings, game, &Glyphs::from_config(&app.config.glyphs), app.config.theme.palette, app.config.theme.border, chunks[0]);
+    }
+    if app.layout.stats {
+        render_stats(f, app, chunks[1]);
+        render_session(f, app, chunks[2]);
+    }
 }
 
-fn render_right_info(f: &mut Frame, game: &Game, area: Rect) {
+fn render_session(f: &mut Frame, app: &App, area: Rect) {
+    // Collapsed panel is just its title on a top border
+    let strings = &app.strings;
+    if !app.show_session {
+        let title = format!("{} +", strings.get("panel.session"));
+        f.render_widget(app.config.theme.border.block().borders(Borders::TOP).title(title), area);
+        return;
+    }
+    
+    let summary = app.session_summary();
+    let format_time = |time: Option<std::time::Duration>| {
+        time.map_or("-".to_string(), |t| format!("{:.2}s", t.as_secs_f64()))
+    };
+    
+    let session_text = vec![
+        Line::from(vec![Span::raw(strings.fmt("session.runs", &[&summary.finished, &summary.attempts]))]),
+        Line::from(vec![Span::raw(strings.fmt("session.best", &[&format_time(summary.best)]))]),
+        Line::from(vec![Span::raw(strings.fmt("session.average", &[&format_time(summary.average)]))]),
+        Line::from(vec![Span::raw(strings.fmt("session.pieces", &[&summary.pieces]))]),
+        Line::from(vec![Span::raw(strings.fmt("session.pps", &[&format!("{:.2}", summary.pps)]))]),
+    ];
+    
+    let session_widget = Paragraph::new(fit_lines(session_text, area.width))
+        .block(app.config.theme.border.block().title(format!("{} -", strings.get("panel.session"))))
+        .alignment(Alignment::Center);
+    
+    f.render_widget(session_widget, area);
+}
+
+fn render_right_info(f: &mut Frame, app: &App, area: Rect) {
+    let border = app.config.theme.border;
+    let game = app.shown_game();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(16), // Next pieces
-            Constraint::Min(1),     // Empty space
+            Constraint::Length(3),  // Bag status
+            Constraint::Length(5),  // PB ghost or level
+            Constraint::Min(0),     // Empty space
         ])
         .split(area);
     
-    render_next_piece(f, game, chunks[0]);
+    let strings = &app.strings;
+    // The drill hides everything that would give the next piece away
+    match app.prediction.as_ref().filter(|_| app.playback.is_none()) {
+        Some(prediction) => render_prediction(f, strings, prediction, border, chunks[0]),
+        None => {
+            render_next_piece(f, strings, game, &Glyphs::from_config(&app.config.glyphs), app.config.theme.palette, border, chunks[0]);
+            if !game.blind.next {
+                render_bag_status(f, strings, game, app.config.theme.palette, border, chunks[1]);
+            }
+        }
+    }
+    if matches!(game.mode, GameMode::Marathon | GameMode::Classic) {
+        render_level(f, strings, game, border, chunks[2]);
+    } else if app.playback.is_none() {
+        if let Some(cpu) = &app.cpu {
+            render_race(f, strings, game, cpu, border, chunks[2]);
+        } else if let Some(ghost) = &app.ghost {
+            render_ghost(f, strings, game, &ghost.game, border, chunks[2]);
+        }
+    }
+}
+
+// Level, gravity, lock delay and lines to the next level; flashes on level up
+fn render_level(f: &mut Frame, strings: &Strings, game: &Game, border: BorderStyle, area: Rect) {
+    let level = game.level();
+    let flashing = match (game.level_up_at, game.get_current_time()) {
+        (Some(at), Some(now)) => now.saturating_sub(at) < Duration::from_millis(LEVEL_UP_FLASH_MS),
+        _ => false,
+    };
+    let style = if flashing {
+        Style::default().fg(Color::Black).bg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    let next = game
+        .lines_to_next_level()
+        .map_or(strings.get("stats.max_level").to_string(), |lines| format!("+{}", lines));
+    
+    let level_text = vec![
+        Line::from(vec![Span::styled(format!("{} {}", strings.fmt("stats.level", &[&format!("{:<2}", level)]), pad_left(&next, 4)), style)]),
+        Line::from(vec![Span::raw(format!("{:.3}G", gravity_g(game.drop_delay())))]),
+        Line::from(vec![Span::styled(strings.fmt("stats.lock_delay", &[&game.lock_delay().as_millis()]), Style::default().fg(Color::DarkGray))]),
+    ];
+    
+    let title = strings.get(if flashing { "panel.level_up" } else { "panel.level" });
+    let title = match &game.curve {
+        Some(curve) => format!("{} {}", title, curve.name),
+        None => title.to_string(),
+    };
+    let level_widget = Paragraph::new(fit_lines(level_text, area.width))
+        .block(border.block().border_style(style).title(title))
+        .alignment(Alignment::Center);
+    
+    f.render_widget(level_widget, area);
+}
+
+// Lines cleared by the PB replay at this point in its run, against the live game
+fn render_ghost(f: &mut Frame, strings: &Strings, game: &Game, ghost: &Game, border: BorderStyle, area: Rect) {
+    const WIDTH: usize = 11;
+    let ghost_lines = ghost.lines_cleared.min(TARGET_LINES);
+    let ghost_filled = ghost_lines as usize * WIDTH / TARGET_LINES as usize;
+    let filled = game.lines_cleared.min(TARGET_LINES) as usize * WIDTH / TARGET_LINES as usize;
+    
+    // Your progress in white, with the ghost's lead (or deficit) shaded behind it
+    let bar = (0..WIDTH)
+        .map(|i| {
+            if i < filled.min(ghost_filled) {
+                Span::raw("█")
+            } else if i < filled {
+                Span::styled("█", Style::default().fg(Color::Green))
+            } else if i < ghost_filled {
+                Span::styled("▒", Style::default().fg(Color::DarkGray))
+            } else {
+                Span::styled("░", Style::default().fg(Color::DarkGray))
+            }
+        })
+        .collect::<Vec<_>>();
+    
+    let diff = game.lines_cleared as i64 - ghost_lines as i64;
+    let diff_color = if diff >= 0 { Color::Green } else { Color::Red };
+    let ghost_text = vec![
+        Line::from(bar),
+        Line::from(vec![
+            Span::styled(format!("{}", ghost_lines), Style::default().fg(Color::DarkGray)),
+            Span::styled(format!(" {:+}", diff), Style::default().fg(diff_color)),
+        ]),
+    ];
+    
+    let ghost_widget = Paragraph::new(ghost_text)
+        .block(border.block().title(strings.get("panel.pb")))
+        .alignment(Alignment::Center);
+    
+    f.render_widget(ghost_widget, area);
 }
 
-fn render_stats(f: &mut Frame, game: &Game, area: Rect) {
+// Both sides' lines in a race against the CPU
+fn render_race(f: &mut Frame, strings: &Strings, game: &Game, cpu: &CpuRacer, border: BorderStyle, area: Rect) {
+    const WIDTH: usize = 8;
+    let bar = |lines: u32, color: Color| {
+        let filled = lines.min(TARGET_LINES) as usize * WIDTH / TARGET_LINES as usize;
+        Line::from(vec![
+            Span::styled("█".repeat(filled), Style::default().fg(color)),
+            Span::styled("░".repeat(WIDTH - filled), Style::default().fg(Color::DarkGray)),
+            Span::raw(format!("{:>3}", lines.min(TARGET_LINES))),
+        ])
+    };
+    let leading = game.lines_cleared >= cpu.game.lines_cleared;
+    let (you, them) = if leading { (Color::Green, Color::White) } else { (Color::White, Color::Red) };
+    let race_text = vec![bar(game.lines_cleared, you), bar(cpu.game.lines_cleared, them)];
+    
+    let title = strings.fmt("panel.race", &[&format!("{:.2}", cpu.pps())]);
+    let race_widget = Paragraph::new(race_text)
+        .block(border.block().title(title))
+        .alignment(Alignment::Center);
+    
+    f.render_widget(race_widget, area);
+}
+
+// Most recent events at the bottom, older ones scrolling off the top
+// Streamer preset: the run time in big digits with the live inputs beside it
+fn render_streamer_strip(f: &mut Frame, app: &App, area: Rect) {
+    let game = app.shown_game();
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(1), Constraint::Length(13)])
+        .split(area);
+    
+    let time = game.get_current_time().unwrap_or_default();
+    let timer_text: Vec<Line> = big_text(&format!("{:.2}", time.as_secs_f64()))
+        .into_iter()
+        .map(|row| Line::from(vec![Span::styled(row, Style::default().fg(accent(&app.config, game.mode)))]))
+        .collect();
+    let timer_widget = Paragraph::new(timer_text)
+        .block(app.config.theme.border.block())
+        .alignment(Alignment::Center);
+    f.render_widget(timer_widget, chunks[0]);
+    
+    render_key_overlay(f, &app.strings, game, app.config.theme.border, chunks[1]);
+}
+
+// Held directions stay lit; one-shot actions light up briefly after each press
+fn render_key_overlay(f: &mut Frame, strings: &Strings, game: &Game, border: BorderStyle, area: Rect) {
+    let now = game.replay_elapsed().as_nanos() as u64;
+    let flash = Duration::from_millis(KEY_FLASH_MS).as_nanos() as u64;
+    let recent = |input: InputEvent| {
+        game.replay
+            .events
+            .iter()
+            .rev()
+            .take_while(|event| event.at_nanos + flash >= now)
+            .any(|event| event.input == input)
+    };
+    let held = |dir: InputDirection| game.input_state.is_pressed(dir);
+    
+    let key = |label: &str, lit: bool| {
+        let style = if lit {
+            Style::default().fg(Color::Black).bg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Span::styled(label.to_string(), style)
+    };
+    
+    let keys_text = vec![
+        Line::from(vec![
+            key("↺", recent(InputEvent::RotateCcw)),
+            Span::raw(" "),
+            key("↻", recent(InputEvent::RotateCw)),
+            Span::raw(" "),
+            key("180", recent(InputEvent::Rotate180)),
+        ]),
+        Line::from(vec![
+            key("←", held(InputDirection::Left)),
+            Span::raw(" "),
+            key("↓", held(InputDirection::Down)),
+            Span::raw(" "),
+            key("→", held(InputDirection::Right)),
+        ]),
+        Line::from(vec![
+            key(strings.get("keys.hold"), recent(InputEvent::Hold)),
+            Span::raw(" "),
+            key(strings.get("keys.drop"), recent(InputEvent::HardDrop)),
+        ]),
+    ];
+    
+    let keys_widget = Paragraph::new(fit_lines(keys_text, area.width))
+        .block(border.block())
+        .alignment(Alignment::Center);
+    f.render_widget(keys_widget, area);
+}
+
+// Their latest emote, across the middle of their board for a moment
+fn render_emote(f: &mut Frame, app: &App, netplay: &Netplay, area: Rect) {
+    let Some((text, at)) = netplay.emote.as_ref().filter(|(_, at)| at.elapsed() < Duration::from_millis(NETPLAY_EMOTE_MS)) else {
+        return;
+    };
+    let height = 3.min(area.height);
+    let area = Rect { y: area.y + (area.height - height) / 2, height, ..area };
+    let fading = at.elapsed().as_millis() as u64 * 3 >= NETPLAY_EMOTE_MS * 2; // The last third
+    let style = Style::default().fg(Color::Yellow).add_modifier(if fading { Modifier::DIM } else { Modifier::BOLD });
+    let widget = Paragraph::new(fit_lines(vec![Line::styled(text.as_str(), style)], area.width))
+        .block(app.config.theme.border.block())
+        .alignment(Alignment::Center);
+    f.render_widget(Clear, area);
+    f.render_widget(widget, area);
+}
+
+// Under the opponent's board if there's room below it, over its bottom
+// rows if not
+fn render_chat(f: &mut Frame, app: &App, netplay: &Netplay, column: Rect, below: Rect) {
+    if netplay.chat.is_empty() && app.chat_entry.is_none() {
+        return;
+    }
+    let max = column.width.saturating_sub(2) as usize;
+    let opponent = netplay.opponent.as_deref().unwrap_or_else(|| app.strings.get("netplay.opponent"));
+    let mut lines: Vec<Line> = netplay
+        .chat
+        .iter()
+        .map(|line| {
+            let (name, color) = if line.ours { (app.game.player.as_str(), Color::DarkGray) } else { (opponent, Color::Cyan) };
+            Line::from(vec![Span::styled(format!("{}: ", name), Style::default().fg(color)), Span::raw(line.text.as_str())])
+        })
+        .collect();
+    if let Some(entry) = &app.chat_entry {
+        // The end of a long message, where the typing is
+        let mut shown = entry.as_str();
+        while text::width(shown) + 3 > max {
+            let mut chars = shown.chars();
+            chars.next();
+            shown = chars.as_str();
+        }
+        lines.push(Line::styled(format!("> {}_", shown), Style::default().fg(Color::Yellow)));
+    }
+    let height = (lines.len() as u16 + 2).min(column.height);
+    let area = if below.height >= height {
+        Rect { x: column.x, y: below.y, width: column.width, height }
+    } else {
+        Rect { y: column.bottom() - height, height, ..column }
+    };
+    let widget = Paragraph::new(fit_lines(lines, area.width))
+        .block(app.config.theme.border.block().title(app.strings.get("netplay.chat")));
+    f.render_widget(Clear, area);
+    f.render_widget(widget, area);
+}
+
+fn render_event_log(f: &mut Frame, app: &App, area: Rect) {
+    let rows = area.height.saturating_sub(2) as usize;
+    let skip = app.event_log.len().saturating_sub(rows);
+    
+    let log_text: Vec<Line> = app
+        .event_log
+        .iter()
+        .skip(skip)
+        .map(|entry| {
+            let color = match entry.event {
+                GameEvent::LinesCleared(4) | GameEvent::PerfectClear | GameEvent::BonusClear => Color::Yellow,
+                GameEvent::Misdrop | GameEvent::Finished(EndReason::ToppedOut | EndReason::PuzzleFailed) => Color::Red,
+                _ => Color::White,
+            };
+            Line::from(vec![
+                Span::styled(format!("{:>6.2} ", entry.at.as_secs_f64()), Style::default().fg(Color::DarkGray)),
+                Span::styled(event_label(&app.strings, entry.event), Style::default().fg(color)),
+            ])
+        })
+        .collect();
+    
+    let log_widget = Paragraph::new(fit_lines(log_text, area.width))
+        .block(app.config.theme.border.block().title(app.strings.get("panel.events")));
+    
+    f.render_widget(log_widget, area);
+}
+
+fn event_label(strings: &Strings, event: GameEvent) -> String {
+    match event {
+        GameEvent::LinesCleared(1) => strings.get("event.single").to_string(),
+        GameEvent::LinesCleared(2) => strings.get("event.double").to_string(),
+        GameEvent::LinesCleared(3) => strings.get("event.triple").to_string(),
+        GameEvent::LinesCleared(4) => strings.get("event.quad").to_string(),
+        GameEvent::LinesCleared(lines) => strings.fmt("event.lines", &[&lines]),
+        GameEvent::PerfectClear => strings.get("event.perfect_clear").to_string(),
+        GameEvent::BonusClear => strings.get("event.bonus").to_string(),
+        GameEvent::Hold(piece_type) => strings.fmt("event.hold", &[&piece_type.letter()]),
+        GameEvent::HardDrop(rows) => strings.fmt("event.hard_drop", &[&rows]),
+        GameEvent::Scored(points, _) => strings.fmt("event.scored", &[&points]),
+        GameEvent::Misdrop => strings.get("event.misdrop").to_string(),
+        GameEvent::BoardReset => strings.get("event.board_reset").to_string(),
+        GameEvent::LevelUp(level) => strings.fmt("event.level_up", &[&level]),
+        GameEvent::Mystery(mystery) => strings.get(&format!("mystery.{}", mystery.key())).to_string(),
+        GameEvent::Finished(EndReason::Completed) => strings.get("event.finished").to_string(),
+        GameEvent::Finished(EndReason::ToppedOut) => strings.get("event.topped_out").to_string(),
+        GameEvent::Finished(EndReason::DrillFailed) => strings.get("event.dig_stalled").to_string(),
+        GameEvent::Finished(EndReason::PuzzleFailed) => strings.get("event.puzzle_failed").to_string(),
+    }
+}
+
+fn render_bag_status(f: &mut Frame, strings: &Strings, game: &Game, palette: Palette, border: BorderStyle, area: Rect) {
+    let remaining = game.bag_remaining();
+    let mut spans = Vec::new();
+    
+    // Pieces already dealt from the current bag are dimmed
+    for (i, piece_type) in PieceType::ALL.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" "));
+        }
+        let color = if remaining.contains(piece_type) { palette.piece(piece_type.color(), palette_level(game)) } else { Color::DarkGray };
+        spans.push(Span::styled(piece_type.letter().to_string(), Style::default().fg(color)));
+    }
+    
+    let bag_widget = Paragraph::new(vec![Line::from(spans)])
+        .block(border.block().title(strings.get("panel.bag")))
+        .alignment(Alignment::Center);
+    
+    f.render_widget(bag_widget, area);
+}
+
+fn render_stats(f: &mut Frame, app: &App, area: Rect) {
+    let game = app.shown_game();
+    let strings = &app.strings;
+    let goal = app.config.goal_time(TARGET_LINES);
+    let title_style = Style::default().fg(accent(&app.config, game.mode));
     let time_text = if let Some(duration) = game.get_current_time() {
         format!("{:.2}s", duration.as_secs_f64())
     } else {
         "0.00s".to_string()
     };
     
-    let stats_text = vec![
-        Line::from(vec![Span::styled("40L", Style::default().fg(Color::Cyan))]),
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw(time_text)]),
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw(format!("{}/40", game.lines_cleared))]),
-    ];
+    let mut stats_text = match game.mode {
+        GameMode::Sprint => {
+            let elapsed = game.get_current_time().unwrap_or_default();
+            let (time_style, pace_line) = match goal {
+                Some(goal) => {
+                    let ahead = game.lines_cleared as f64 >= goal_pace_lines(elapsed, goal);
+                    let color = if ahead { Color::Green } else { Color::Red };
+                    (Style::default().fg(color), goal_progress_bar(game, elapsed, goal, color))
+                }
+                None => (Style::default(), Line::from(vec![Span::raw("")])),
+            };
+            
+            vec![
+                Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+                Line::from(vec![Span::raw("")]),
+                Line::from(vec![Span::styled(time_text, time_style)]),
+                pace_line,
+                Line::from(vec![Span::raw(format!("{}/40", game.lines_cleared))]),
+                Line::from(vec![Span::raw(strings.fmt("stats.misdrops", &[&game.misdrops]))]),
+            ]
+        }
+        GameMode::Marathon => vec![
+            Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+            Line::from(vec![Span::raw("")]),
+            Line::from(vec![Span::raw(time_text)]),
+            Line::from(vec![Span::raw(format!("{}/{}", game.lines_cleared, MARATHON_LINES))]),
+            Line::from(vec![Span::raw(strings.fmt("stats.misdrops", &[&game.misdrops]))]),
+        ],
+        GameMode::Classic => vec![
+            Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+            Line::from(vec![Span::raw("")]),
+            Line::from(vec![Span::raw(time_text)]),
+            Line::from(vec![Span::raw(strings.fmt("stats.lines", &[&game.lines_cleared]))]),
+            Line::from(vec![Span::raw(strings.fmt("stats.misdrops", &[&game.misdrops]))]),
+        ],
+        GameMode::Dig => {
+            // Warn when the next placement has to clear garbage
+            let stall_color = if game.pieces_since_dig + 1 >= DIG_PIECE_LIMIT {
+                Color::Red
+            } else {
+                Color::White
+            };
+            
+            vec![
+                Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+                Line::from(vec![Span::raw(time_text)]),
+                Line::from(vec![Span::raw(strings.fmt("stats.left", &[&game.garbage_remaining]))]),
+                Line::from(vec![Span::styled(
+                    strings.fmt("stats.pieces", &[&game.pieces_since_dig, &DIG_PIECE_LIMIT]),
+                    Style::default().fg(stall_color),
+                )]),
+                Line::from(vec![Span::raw(format!("{:.2} L/P", game.dig_efficiency()))]),
+                Line::from(vec![Span::raw(strings.fmt("stats.misdrops", &[&game.misdrops]))]),
+            ]
+        }
+        GameMode::Survival => vec![
+            Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+            Line::from(vec![Span::raw("")]),
+            Line::from(vec![Span::raw(time_text)]),
+            Line::from(vec![Span::styled(
+                strings.fmt("stats.garbage_every", &[&format!("{:.1}", game.garbage_interval().as_secs_f64())]),
+                Style::default().fg(Color::LightRed),
+            )]),
+            Line::from(vec![Span::raw(strings.fmt("stats.lines", &[&game.lines_cleared]))]),
+            Line::from(vec![Span::raw(strings.fmt("stats.misdrops", &[&game.misdrops]))]),
+        ],
+        GameMode::Mystery => {
+            // The lasting event and its time left, or the wait for the next one
+            let elapsed = game.get_current_time().unwrap_or_default();
+            let event_line = match game.mystery {
+                Some(mystery) => {
+                    let name = strings.get(&format!("mystery.{}", mystery.key())).to_string();
+                    let left = game.mystery_until.saturating_sub(elapsed).as_secs() + 1;
+                    Line::from(vec![Span::styled(strings.fmt("stats.event_left", &[&name, &left]), Style::default().fg(Color::LightBlue))])
+                }
+                None => Line::from(vec![Span::styled(
+                    strings.fmt("stats.next_event", &[&(game.next_mystery_at.saturating_sub(elapsed).as_secs() + 1)]),
+                    Style::default().fg(Color::DarkGray),
+                )]),
+            };
+            vec![
+                Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+                Line::from(vec![Span::raw("")]),
+                Line::from(vec![Span::raw(time_text)]),
+                event_line,
+                Line::from(vec![Span::raw(format!("{}/40", game.lines_cleared))]),
+                Line::from(vec![Span::raw(strings.fmt("stats.misdrops", &[&game.misdrops]))]),
+            ]
+        }
+        GameMode::Ultra => {
+            // Time left rather than time taken, red for the last ten seconds
+            let left = Duration::from_secs(ULTRA_SECS).saturating_sub(game.get_current_time().unwrap_or_default());
+            let color = if left.as_secs() < 10 { Color::Red } else { Color::White };
+            vec![
+                Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+                Line::from(vec![Span::raw("")]),
+                Line::from(vec![Span::styled(format!("{:.2}s", left.as_secs_f64()), Style::default().fg(color))]),
+                Line::from(vec![Span::raw(strings.fmt("stats.lines", &[&game.lines_cleared]))]),
+                Line::from(vec![Span::styled(strings.fmt("stats.bonus", &[&game.scoring.bonus_clears]), Style::default().fg(Color::Yellow))]),
+            ]
+        }
+        GameMode::Puzzle => {
+            // The puzzle this run is on; the next one is queued up once it's solved
+            let puzzle = game.replay.puzzle.as_ref().or(game.puzzle.as_ref());
+            let (name, pieces) = puzzle.map_or(("", 0), |puzzle| (puzzle.name.as_str(), puzzle.queue.len() as u32));
+            vec![
+                Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+                Line::from(vec![Span::raw("")]),
+                Line::from(vec![Span::raw(name.to_string())]),
+                Line::from(vec![Span::raw(strings.fmt("stats.left", &[&pieces.saturating_sub(game.pieces_placed)]))]),
+            ]
+        }
+        GameMode::Zen => vec![
+            Line::from(vec![Span::styled(strings.mode_short(game.mode), title_style)]),
+            Line::from(vec![Span::raw("")]),
+            Line::from(vec![Span::raw(strings.fmt("stats.lines", &[&game.lines_cleared]))]),
+            Line::from(vec![Span::raw(strings.fmt("stats.zen_pieces", &[&game.pieces_placed]))]),
+            Line::from(vec![Span::styled(
+                strings.fmt("stats.total", &[&app.zen_lifetime_lines]),
+                Style::default().fg(Color::DarkGray),
+            )]),
+            Line::from(vec![Span::raw(strings.fmt("stats.misdrops", &[&game.misdrops]))]),
+        ],
+    };
+    
+    // Adaptive Zen: where gravity has got to
+    if game.mode == GameMode::Zen && game.adaptive.is_some() {
+        let gravity = format!("{:.2}", game.drop_delay().as_secs_f64());
+        stats_text.push(Line::from(vec![Span::raw(strings.fmt("stats.gravity", &[&gravity]))]));
+    }
+    
+    // A custom mode's own finish line, since the base mode's no longer applies
+    if let Some(goal) = &game.goal {
+        stats_text.push(Line::from(vec![Span::styled(strings.goal(goal), Style::default().fg(Color::DarkGray))]));
+    }
     
-    let stats_widget = Paragraph::new(stats_text)
-        .block(Block::default().borders(Borders::ALL))
+    if game.is_scored() {
+        // Drop meter: cells dropped by the falling piece, or the last one once it locks
+        let drop_cells = match game.scoring.piece_drop_cells {
+            0 => game.scoring.last_drop_cells,
+            cells => cells,
+        };
+        stats_text.push(Line::from(vec![
+            Span::raw(format!("{}", game.scoring.points)),
+            Span::styled(format!(" ↓{}", drop_cells), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+    
+    let stats_widget = Paragraph::new(fit_lines(stats_text, area.width))
+        .block(app.config.theme.border.block())
         .alignment(Alignment::Center);
     
     f.render_widget(stats_widget, area);
 }
 
-fn render_next_piece(f: &mut Frame, game: &Game, area: Rect) {
-    let mut next_lines = Vec::new();
+// Pieces per second across the run as up to `width` bars, each a slice of
+// the run's time scaled against the fastest, so the slow patches stand out.
+// Returns the graph and the fastest slice's pace.
+fn pace_graph(game: &Game, glyphs: &Glyphs, width: usize) -> Option<(String, f64)> {
+    let duration = game.final_time?.as_secs_f64();
+    let columns = game.lock_times.len().min(width);
+    if columns < 2 || duration <= 0.0 {
+        return None;
+    }
+    let slice = duration / columns as f64;
+    let mut counts = vec![0u32; columns];
+    for at in &game.lock_times {
+        counts[((at.as_secs_f64() / slice) as usize).min(columns - 1)] += 1;
+    }
+    let peak = counts.iter().copied().max().unwrap_or(0) as f64 / slice;
+    let top = glyphs.bars.len() - 1;
+    let graph = counts
+        .iter()
+        .map(|&count| match count {
+            0 => ' ',
+            _ => glyphs.bars[(count as f64 / slice / peak * top as f64).round() as usize],
+        })
+        .collect();
+    Some((graph, peak))
+}
+
+// Lines the player should have cleared by now to finish exactly on the goal
+fn goal_pace_lines(elapsed: Duration, goal: Duration) -> f64 {
+    if goal.is_zero() {
+        return TARGET_LINES as f64;
+    }
+    (elapsed.as_secs_f64() / goal.as_secs_f64()).min(1.0) * TARGET_LINES as f64
+}
+
+// Lines cleared as a bar, with a marker where goal pace currently is
+fn goal_progress_bar(game: &Game, elapsed: Duration, goal: Duration, color: Color) -> Line<'static> {
+    const WIDTH: usize = 11;
+    let filled = game.lines_cleared as usize * WIDTH / TARGET_LINES as usize;
+    let pace = (goal_pace_lines(elapsed, goal) * WIDTH as f64 / TARGET_LINES as f64) as usize;
     
-    // Show 5 next pieces compactly
-    for (piece_idx, piece) in game.next_pieces.iter().take(5).enumerate() {
-        // Find the bounding box of the piece shape
-        let mut min_row = piece.shape.len();
-        let mut max_row = 0;
-        
-        for (i, row) in piece.shape.iter().enumerate() {
-            if row.iter().any(|&cell| cell) {
-                min_row = min_row.min(i);
-                max_row = max_row.max(i);
+    let spans = (0..WIDTH)
+        .map(|i| {
+            if i == pace.min(WIDTH - 1) {
+                Span::styled("│", Style::default().fg(Color::Yellow))
+            } else if i < filled {
+                Span::styled("█", Style::default().fg(color))
+            } else {
+                Span::styled("░", Style::default().fg(Color::DarkGray))
             }
-        }
-        
-        // Render only the rows that contain blocks
-        for i in min_row..=max_row {
-            let mut line_spans = Vec::new();
-            for j in 0..4 {
-                if j < piece.shape[i].len() && piece.shape[i][j] {
-                    line_spans.push(Span::styled("██", Style::default().fg(piece.color)));
+        })
+        .collect::<Vec<_>>();
+    Line::from(spans)
+}
+
+// A piece trimmed to its bounding box and centered vertically in a slot
+// `slot_height` rows tall; the panel centers it horizontally
+// Big pieces are drawn at normal size with a ×2 tag, so they fit the slot
+fn preview_lines(piece: &Piece, color: Color, glyphs: &Glyphs, slot_height: usize) -> Vec<Line<'static>> {
+    let shape = if piece.big { Piece::new(piece.piece_type).shape } else { piece.shape.clone() };
+    let blocks: Vec<(usize, usize)> = shape
+        .iter()
+        .enumerate()
+        .flat_map(|(i, row)| row.iter().enumerate().filter(|(_, &cell)| cell).map(move |(j, _)| (i, j)))
+        .collect();
+    let (Some(min_row), Some(max_row)) = (blocks.iter().map(|b| b.0).min(), blocks.iter().map(|b| b.0).max()) else {
+        return vec![Line::from(""); slot_height];
+    };
+    let min_col = blocks.iter().map(|b| b.1).min().unwrap_or(0);
+    let max_col = blocks.iter().map(|b| b.1).max().unwrap_or(0);
+    
+    let height = max_row - min_row + 1;
+    let top = slot_height.saturating_sub(height) / 2;
+    let mut lines = vec![Line::from(""); top];
+    for (i, row) in shape.iter().enumerate().take(max_row + 1).skip(min_row) {
+        let mut spans = (min_col..=max_col)
+            .map(|j| {
+                if row[j] && piece.bonus {
+                    Span::styled(glyphs.bonus.clone(), bonus_style(color))
+                } else if row[j] {
+                    Span::styled(glyphs.filled.clone(), Style::default().fg(color))
                 } else {
-                    line_spans.push(Span::raw("  "));
+                    Span::raw("  ")
                 }
-            }
-            next_lines.push(Line::from(line_spans));
+            })
+            .collect::<Vec<_>>();
+        // Every row gets the extra width so the rows stay lined up
+        if piece.big {
+            let tag = if i == min_row { "×2" } else { "  " };
+            spans.push(Span::styled(tag, Style::default().fg(color).add_modifier(Modifier::BOLD)));
         }
+        lines.push(Line::from(spans));
+    }
+    lines.resize(slot_height.max(lines.len()), Line::from(""));
+    lines
+}
+
+fn render_next_piece(f: &mut Frame, strings: &Strings, game: &Game, glyphs: &Glyphs, palette: Palette, border: BorderStyle, area: Rect) {
+    let mut next_lines = Vec::new();
+    let shown = if game.blind.next { 0 } else { 5 };
+    
+    // Show 5 next pieces compactly, two rows each
+    for (piece_idx, piece) in game.next_pieces.iter().take(shown).enumerate() {
+        next_lines.extend(preview_lines(piece, palette.piece(piece.color, palette_level(game)), glyphs, 2));
         
         // Add spacing between pieces
         if piece_idx < 4 {
@@ -202,49 +1355,54 @@ fn render_next_piece(f: &mut Frame, game: &Game, area: Rect) {
     }
     
     let next_widget = Paragraph::new(next_lines)
-        .block(Block::default().borders(Borders::ALL).title("Next"))
+        .block(border.block().title(strings.get("panel.next")))
         .alignment(Alignment::Center);
     
     f.render_widget(next_widget, area);
 }
 
-fn render_hold_piece(f: &mut Frame, game: &Game, area: Rect) {
-    let mut hold_lines = Vec::new();
+// Stands in for the Next panel in the prediction drill: the call so far for
+// the piece after the one in play, how the last call went and the tally
+fn render_prediction(f: &mut Frame, strings: &Strings, prediction: &Prediction, border: BorderStyle, area: Rect) {
+    let guess = prediction.guess.map_or("-".to_string(), |piece_type| piece_type.letter().to_string());
+    let last = match prediction.last {
+        Some((piece_type, true)) => Span::styled(format!("✓ {}", piece_type.letter()), Style::default().fg(Color::Green)),
+        Some((piece_type, false)) => Span::styled(format!("✗ {}", piece_type.letter()), Style::default().fg(Color::Red)),
+        None => Span::raw(""),
+    };
+    let accuracy = prediction.accuracy().map_or(String::new(), |accuracy| format!("{:.0}%", accuracy * 100.0));
     
-    hold_lines.push(Line::from(vec![Span::raw("")])); // Padding
+    let lines = vec![
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(strings.fmt("predict.guess", &[&guess]), Style::default().fg(Color::Yellow))]),
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![last]),
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::raw(strings.fmt("predict.score", &[&prediction.correct, &prediction.total]))]),
+        Line::from(vec![Span::raw(accuracy)]),
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(strings.get("predict.keys"), Style::default().fg(Color::DarkGray))]),
+    ];
     
-    if let Some(ref hold_piece) = game.hold_piece {
-        // Find the bounding box of the piece shape
-        let mut min_row = hold_piece.shape.len();
-        let mut max_row = 0;
-        
-        for (i, row) in hold_piece.shape.iter().enumerate() {
-            if row.iter().any(|&cell| cell) {
-                min_row = min_row.min(i);
-                max_row = max_row.max(i);
-            }
-        }
-        
-        // Render only the rows that contain blocks
-        for i in min_row..=max_row {
-            let mut line_spans = Vec::new();
-            for j in 0..4 {
-                if j < hold_piece.shape[i].len() && hold_piece.shape[i][j] {
-                    let color = if game.can_hold { hold_piece.color } else { Color::DarkGray };
-                    line_spans.push(Span::styled("██", Style::default().fg(color)));
-                } else {
-                    line_spans.push(Span::raw("  "));
-                }
-            }
-            hold_lines.push(Line::from(line_spans));
+    let widget = Paragraph::new(fit_lines(lines, area.width))
+        .block(border.block().title(strings.get("panel.predict")))
+        .alignment(Alignment::Center);
+    
+    f.render_widget(widget, area);
+}
+
+fn render_hold_piece(f: &mut Frame, strings: &Strings, game: &Game, glyphs: &Glyphs, palette: Palette, border: BorderStyle, area: Rect) {
+    let slot_height = area.height.saturating_sub(2) as usize;
+    let hold_lines = match game.hold_piece.as_ref().filter(|_| !game.blind.hold) {
+        Some(hold_piece) => {
+            let color = if game.can_hold { palette.piece(hold_piece.color, palette_level(game)) } else { Color::DarkGray };
+            preview_lines(hold_piece, color, glyphs, slot_height)
         }
-    } else {
-        hold_lines.push(Line::from(vec![Span::raw("        ")]));
-        hold_lines.push(Line::from(vec![Span::raw("        ")]));
-    }
+        None => Vec::new(),
+    };
     
     let hold_widget = Paragraph::new(hold_lines)
-        .block(Block::default().borders(Borders::ALL).title("Hold"))
+        .block(border.block().title(strings.get("panel.hold")))
         .alignment(Alignment::Center);
     
     f.render_widget(hold_widget, area);
@@ -273,77 +1431,775 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-fn render_ready_overlay(f: &mut Frame, area: Rect) {
-    let popup_area = centered_rect(60, 35, area);
+// The Ready screen: logo and menu on a panel, with pieces falling behind it
+fn render_title(f: &mut Frame, app: &App, area: Rect) {
+    let game = &app.game;
+    let strings = &app.strings;
+    let menu = &app.title;
+    f.render_widget(Clear, area);
+    render_title_drops(f, app, area);
+    
+    let mut text = vec![Line::from("")];
+    // One color per letter, taken from the piece of that name where there is one
+    let letters: Vec<(Vec<String>, Color)> = "TSTRIS"
+        .chars()
+        .map(|letter| {
+            let piece = PieceType::from_letter(letter).unwrap_or(PieceType::L);
+            (big_text(&letter.to_string()), piece_color(piece.color()))
+        })
+        .collect();
+    for row in 0..5 {
+        let spans: Vec<Span> = letters
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (rows, color))| {
+                let gap = Span::raw(if i == 0 { "" } else { "  " });
+                [gap, Span::styled(rows[row].clone(), Style::default().fg(*color))]
+            })
+            .collect();
+        text.push(Line::from(spans));
+    }
+    text.push(Line::from(""));
+    
+    let goal_line = match (game.mode, app.config.goal_time(TARGET_LINES)) {
+        (GameMode::Sprint, Some(goal)) => strings.fmt("ready.goal", &[&format_time(goal)]),
+        _ => String::new(),
+    };
+    let mode_style = Style::default().fg(accent(&app.config, game.mode)).add_modifier(Modifier::BOLD);
+    text.push(Line::from(Span::styled(strings.mode_title(game.mode), mode_style)));
+    text.push(Line::from(goal_line));
+    
+    // Padded to one width so the centered rows line up
+    let choice = |selected: bool, label: &str| {
+        let style = if selected { Style::default().fg(Color::Yellow) } else { Style::default() };
+        Line::from(Span::styled(format!("{} {}", if selected { ">" } else { " " }, pad_right(label, 16)), style))
+    };
+    match menu.picking_mode {
+        Some(picked) => {
+            for (i, &mode) in menu_modes().iter().enumerate() {
+                text.push(choice(i == picked, strings.mode_title(mode)));
+            }
+        }
+        None => {
+            for (i, item) in MenuItem::ALL.iter().enumerate() {
+                text.push(choice(i == menu.selected, strings.get(item.key())));
+            }
+        }
+    }
+    text.push(Line::from(""));
+    let help = if menu.picking_mode.is_some() { "title.help_modes" } else { "title.help" };
+    text.push(Line::from(Span::styled(strings.get(help), Style::default().fg(Color::DarkGray))));
+    text.push(Line::from(Span::styled(strings.fmt("ready.handling", &[&app.preset_name()]), Style::default().fg(Color::DarkGray))));
+    
+    let width = TITLE_PANEL_WIDTH.min(area.width);
+    let height = (text.len() as u16 + 2).min(area.height);
+    let panel = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, panel);
+    let title_widget = Paragraph::new(fit_lines(text, panel.width))
+        .block(app.config.theme.border.block())
+        .alignment(Alignment::Center);
+    f.render_widget(title_widget, panel);
+}
+
+// Pieces falling down the screen in lanes, each lane its own piece and
+// speed, worked out from the time alone
+fn render_title_drops(f: &mut Frame, app: &App, area: Rect) {
+    let glyphs = Glyphs::from_config(&app.config.glyphs);
+    let elapsed = app.title.shown_at.elapsed().as_secs_f64();
+    let cycle = area.height as f64 + 4.0; // Off the bottom, then back in at the top
+    let buffer = f.buffer_mut();
+    for lane in 0..area.width / TITLE_DROP_SPACING {
+        let lane = lane as usize;
+        let piece_type = PieceType::ALL[lane * 3 % PieceType::ALL.len()];
+        let speed = TITLE_DROP_SPEED * (1.0 + (lane % 3) as f64 * 0.5);
+        let offset = (lane * 7 % 11) as f64 * 3.0;
+        let top = ((elapsed * speed + offset) % cycle) as i32 - 4;
+        let left = area.x + lane as u16 * TITLE_DROP_SPACING + (lane % 3) as u16 * 2;
+        let style = Style::default().fg(piece_color(piece_type.color())).add_modifier(Modifier::DIM);
+        for (i, row) in Piece::new(piece_type).shape.iter().enumerate() {
+            for (j, _) in row.iter().enumerate().filter(|(_, &cell)| cell) {
+                let (x, y) = (left + j as u16 * 2, top + i as i32);
+                if y >= 0 && (y as u16) < area.height && x + 1 < area.x + area.width {
+                    buffer.set_string(x, area.y + y as u16, &glyphs.filled, style);
+                }
+            }
+        }
+    }
+}
+
+// Title over the demo, until a key brings the Ready screen back
+fn render_attract_overlay(f: &mut Frame, app: &App, area: Rect) {
+    let strings = &app.strings;
+    let popup_area = centered_rect(90, 25, area);
     f.render_widget(Clear, popup_area);
     
-    let ready_text = vec![
+    let text = vec![
         Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::styled("40L SPRINT", Style::default().fg(Color::Cyan))]),
+        Line::from(vec![Span::styled("TSTRIS", Style::default().fg(accent(&app.config, app.game.mode)).add_modifier(Modifier::BOLD))]),
+        Line::from(vec![Span::styled(strings.get("attract.demo"), Style::default().fg(Color::DarkGray))]),
+        Line::from(vec![Span::raw(strings.get("attract.press_key"))]),
+    ];
+    
+    let attract_widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(app.config.theme.border.block())
+        .alignment(Alignment::Center);
+        
+    f.render_widget(attract_widget, popup_area);
+}
+
+// Countdown number, or GO once the run has started, drawn large in the mode's accent
+fn render_countdown_overlay(f: &mut Frame, app: &App, text: &str, area: Rect) {
+    let game = app.shown_game();
+    let rows = big_text(text);
+    let width = rows.first().map_or(0, |row| row.chars().count()) as u16 + 4;
+    let popup_area = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(7) / 2,
+        width: width.min(area.width),
+        height: 7.min(area.height),
+    };
+    f.render_widget(Clear, popup_area);
+    
+    let color = accent(&app.config, game.mode);
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|row| Line::from(vec![Span::styled(row, Style::default().fg(color))]))
+        .collect();
+    
+    let countdown_widget = Paragraph::new(lines)
+        .block(app.config.theme.border.block().border_style(Style::default().fg(color)))
+        .alignment(Alignment::Center);
+        
+    f.render_widget(countdown_widget, popup_area);
+}
+
+// GO stays up briefly after the countdown ends
+fn showing_go(game: &Game) -> bool {
+    game.game_state == crate::game::state::GameState::Playing
+        && game.get_current_time().is_some_and(|time| time < Duration::from_millis(GO_DISPLAY_MS))
+}
+
+fn render_paused_overlay(f: &mut Frame, strings: &Strings, border: BorderStyle, area: Rect) {
+    let popup_area = centered_rect(90, 30, area);
+    f.render_widget(Clear, popup_area);
+    
+    let text = vec![
         Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw("Press SPACE to start")]),
+        Line::from(vec![Span::styled(strings.get("paused.title"), Style::default().fg(Color::Yellow))]),
+        Line::from(vec![Span::raw(strings.get("paused.resume"))]),
+    ];
+    
+    let paused_widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(border.block())
+        .alignment(Alignment::Center);
+        
+    f.render_widget(paused_widget, popup_area);
+}
+
+fn render_quit_prompt(f: &mut Frame, strings: &Strings, border: BorderStyle, area: Rect) {
+    let popup_area = centered_rect(90, 30, area);
+    f.render_widget(Clear, popup_area);
+    
+    let text = vec![
         Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(strings.get("quit.title"), Style::default().fg(Color::Yellow))]),
+        Line::from(vec![Span::raw(strings.get("quit.confirm"))]),
+        Line::from(vec![Span::raw(strings.get("quit.cancel"))]),
     ];
     
-    let ready_widget = Paragraph::new(ready_text)
-        .block(Block::default().borders(Borders::ALL).title("Ready"))
+    let quit_widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(border.block())
         .alignment(Alignment::Center);
         
-    f.render_widget(ready_widget, popup_area);
+    f.render_widget(quit_widget, popup_area);
 }
 
-fn render_countdown_overlay(f: &mut Frame, count: u32, area: Rect) {
-    let popup_area = centered_rect(40, 20, area);
+// A bar filling up while the restart key is held, across the middle of the board
+fn render_restart_hold(f: &mut Frame, app: &App, progress: f64, area: Rect) {
+    let popup_area = Rect {
+        x: area.x + 2,
+        y: area.y + area.height.saturating_sub(4) / 2,
+        width: area.width.saturating_sub(4),
+        height: 4,
+    }
+    .intersection(area);
     f.render_widget(Clear, popup_area);
     
-    let countdown_text = if count == 1 {
-        "GO!"
-    } else {
-        match count {
-            2 => "Ready", 
-            _ => "",
+    let width = popup_area.width.saturating_sub(4) as usize;
+    let filled = (progress * width as f64).round() as usize;
+    let color = accent(&app.config, app.game.mode);
+    let text = vec![
+        Line::from(Span::styled(app.strings.get("restart.hold"), Style::default().fg(Color::Yellow))),
+        Line::from(vec![
+            Span::styled("█".repeat(filled), Style::default().fg(color)),
+            Span::styled("░".repeat(width - filled), Style::default().fg(Color::DarkGray)),
+        ]),
+    ];
+    let widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(app.config.theme.border.block())
+        .alignment(Alignment::Center);
+    f.render_widget(widget, popup_area);
+}
+
+// Handling presets by number key, the one in use highlighted
+fn render_handling_menu(f: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(70, 50, area);
+    f.render_widget(Clear, popup_area);
+    
+    let strings = &app.strings;
+    let presets = app.config.handling.presets();
+    let entries = std::iter::once((None, strings.get("presets.config").to_string(), app.config.handling.timings()))
+        .chain(presets.into_iter().enumerate().map(|(i, (name, timings))| (Some(i), name, timings)))
+        .take(9);
+    
+    let mut text = vec![Line::from("")];
+    for (number, (preset, name, timings)) in entries.enumerate() {
+        let style = if preset == app.preset { Style::default().fg(Color::Yellow) } else { Style::default() };
+        let detail = strings.fmt(
+            "presets.timings",
+            &[&timings.das_ms, &timings.arr_ms, &timings.soft_drop_ms, &timings.lock_delay_ms],
+        );
+        text.push(Line::from(vec![
+            Span::styled(format!("{}  {} ", number + 1, pad_right(&truncate(&name, 18), 18)), style),
+            Span::styled(pad_right(&detail, 36), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(strings.get("presets.help"), Style::default().fg(Color::DarkGray))));
+    
+    let menu_widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(app.config.theme.border.block().title(strings.get("presets.title")))
+        .alignment(Alignment::Center);
+        
+    f.render_widget(menu_widget, popup_area);
+}
+
+// Versus handicaps, a block of rows per player. Online, the opponent's come
+// last and can't be picked.
+fn render_match_setup(f: &mut Frame, app: &App, setup: &MatchSetup, area: Rect) {
+    let popup_area = centered_rect(70, 80, area);
+    f.render_widget(Clear, popup_area);
+    
+    let strings = &app.strings;
+    let value = |setting: Setting, handicap: &Handicap| match setting {
+        Setting::Gravity => format!("×{:.2}", handicap.gravity),
+        Setting::Hold => strings.get(if handicap.no_hold { "setup.off" } else { "setup.on" }).to_string(),
+        Setting::Garbage => handicap.garbage_rows.to_string(),
+        Setting::Attack => format!("×{:.2}", handicap.attack),
+    };
+    let mut players = vec![(strings.get("versus.player_left").to_string(), app.game.handicap)];
+    if let Some(twin) = app.twin.as_ref().filter(|_| app.versus.is_some()) {
+        players.push((strings.get("versus.player_right").to_string(), twin.handicap));
+    }
+    if let Some(netplay) = &app.netplay {
+        players[0].0 = strings.get("setup.you").to_string();
+        let name = netplay.opponent.clone().unwrap_or_else(|| strings.get("netplay.opponent").to_string());
+        players.push((name, netplay.their_handicap));
+    }
+    
+    let mut text = vec![Line::from("")];
+    for (player, (name, handicap)) in players.iter().enumerate() {
+        text.push(Line::from(Span::styled(pad_right(&truncate(name, 28), 28), Style::default().fg(Color::Cyan))));
+        for (i, &setting) in Setting::ALL.iter().enumerate() {
+            let selected = player < setup.players && setup.row == player * Setting::ALL.len() + i;
+            let style = match (selected, player < setup.players) {
+                (true, _) => Style::default().fg(Color::Yellow),
+                (false, true) => Style::default(),
+                (false, false) => Style::default().fg(Color::DarkGray),
+            };
+            let label = pad_right(strings.get(&format!("setup.{}", setting.key())), 16);
+            text.push(Line::from(Span::styled(
+                format!("{} {} {}", if selected { ">" } else { " " }, label, pad_left(&value(setting, handicap), 9)),
+                style,
+            )));
         }
+        text.push(Line::from(""));
+    }
+    if let Some(versus) = app.versus.as_ref().filter(|_| setup.teams) {
+        let selected = setup.selected() == Row::Targeting;
+        let style = if selected { Style::default().fg(Color::Yellow) } else { Style::default() };
+        let label = pad_right(strings.get("setup.targeting"), 16);
+        let value = strings.get(&format!("setup.{}", versus.targeting().name())).to_string();
+        text.push(Line::from(Span::styled(format!("{} {} {}", if selected { ">" } else { " " }, label, pad_left(&value, 9)), style)));
+        text.push(Line::from(""));
+    }
+    text.push(Line::from(Span::styled(strings.get("setup.help"), Style::default().fg(Color::DarkGray))));
+    
+    let setup_widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(app.config.theme.border.block().title(strings.get("setup.title")))
+        .alignment(Alignment::Center);
+        
+    f.render_widget(setup_widget, popup_area);
+}
+
+// First-run setup, one step at a time, over the whole screen
+fn render_wizard(f: &mut Frame, app: &App, wizard: &Wizard, area: Rect) {
+    let popup_area = centered_rect(70, 40, area);
+    f.render_widget(Clear, popup_area);
+    
+    let strings = &app.strings;
+    // Padded to one width so the centered rows line up
+    let choice = |selected: bool, label: String, detail: String| {
+        let style = if selected { Style::default().fg(Color::Yellow) } else { Style::default() };
+        Line::from(vec![
+            Span::styled(format!("{} {} ", if selected { ">" } else { " " }, pad_right(&label, 12)), style),
+            Span::styled(pad_right(&detail, 36), Style::default().fg(Color::DarkGray)),
+        ])
     };
     
-    let color = if count == 1 { Color::Green } else { Color::Yellow };
+    let mut text = vec![Line::from("")];
+    let help = match wizard.step {
+        Step::Handling => {
+            text.push(Line::from(strings.get("wizard.handling")));
+            text.push(Line::from(""));
+            for (i, (key, timings)) in HANDLING_CHOICES.iter().enumerate() {
+                let detail = strings.fmt("wizard.timings", &[&timings.das_ms, &timings.arr_ms, &timings.soft_drop_ms]);
+                text.push(choice(i == wizard.handling, strings.get(&format!("wizard.{}", key)).to_string(), detail));
+            }
+            "wizard.help"
+        }
+        Step::Theme => {
+            text.push(Line::from(strings.get("wizard.theme")));
+            text.push(Line::from(""));
+            for (i, theme) in THEME_CHOICES.iter().enumerate() {
+                let glyphs = Glyphs::from_config(&theme_glyphs(theme));
+                let sample = format!("{}{}{}", glyphs.filled.repeat(3), glyphs.ghost.repeat(2), glyphs.background.repeat(2));
+                text.push(choice(i == wizard.theme, strings.get(&format!("wizard.{}", theme)).to_string(), sample));
+            }
+            "wizard.help"
+        }
+        Step::KeyRelease => {
+            text.push(Line::from(strings.get("wizard.keys")));
+            text.push(Line::from(""));
+            let (key, color) = if !app.game.input_state.keyboard_enhancement_active {
+                ("wizard.keys_missing", Color::Red)
+            } else {
+                match wizard.key_test {
+                    KeyTest::Waiting => ("wizard.keys_hold", Color::White),
+                    KeyTest::Held => ("wizard.keys_let_go", Color::Yellow),
+                    KeyTest::Passed => ("wizard.keys_ok", Color::Green),
+                }
+            };
+            text.push(Line::from(Span::styled(strings.get(key), Style::default().fg(color))));
+            "wizard.help_last"
+        }
+    };
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(strings.get(help), Style::default().fg(Color::DarkGray))));
+    
+    let title = strings.fmt("wizard.title", &[&wizard.step_number(), &3]);
+    let wizard_widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(app.config.theme.border.block().title(title))
+        .alignment(Alignment::Center);
+        
+    f.render_widget(wizard_widget, popup_area);
+}
+
+// First-run prompt for the name shown on records and replays
+fn render_name_entry(f: &mut Frame, strings: &Strings, name: &str, border: BorderStyle, area: Rect) {
+    let popup_area = centered_rect(90, 35, area);
+    f.render_widget(Clear, popup_area);
     
     let text = vec![
         Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::styled(countdown_text, Style::default().fg(color))]),
+        Line::from(vec![Span::raw(strings.get("name.prompt"))]),
+        Line::from(vec![
+            Span::styled(name.to_string(), Style::default().fg(Color::Yellow)),
+            Span::styled("_", Style::default().fg(Color::DarkGray)),
+        ]),
         Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(strings.get("name.help"), Style::default().fg(Color::DarkGray))]),
     ];
     
-    let countdown_widget = Paragraph::new(text)
-        .block(Block::default().borders(Borders::ALL))
+    let name_widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(border.block().title(strings.get("name.title")))
         .alignment(Alignment::Center);
         
-    f.render_widget(countdown_widget, popup_area);
+    f.render_widget(name_widget, popup_area);
 }
 
-fn render_finished_overlay(f: &mut Frame, game: &Game, area: Rect) {
-    let popup_area = centered_rect(50, 40, area);
+// Between turns: who plays next, what they have to beat and the scoreboard
+fn render_koth_handoff(f: &mut Frame, strings: &Strings, koth: &Koth, border: BorderStyle, area: Rect) {
+    let popup_area = centered_rect(90, 70, area);
+    f.render_widget(Clear, popup_area);
+    
+    let mut text = vec![Line::from("")];
+    if let Some((winner, loser)) = &koth.last_match {
+        text.push(Line::from(Span::styled(strings.fmt("koth.beat", &[winner, loser]), Style::default().fg(Color::Green))));
+    }
+    text.push(Line::from(Span::styled(
+        strings.fmt("koth.pass", &[&koth.up_next()]),
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+    )));
+    if let (Some(mode), Some(result)) = (koth.mode, &koth.king_result) {
+        text.push(Line::from(strings.fmt("koth.to_beat", &[&strings.mode_title(mode), &turn_summary(strings, mode, result)])));
+    }
+    
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(strings.get("koth.scoreboard"), Style::default().fg(Color::Cyan))));
+    let name_width = koth.players.iter().map(|name| text::width(name)).max().unwrap_or(0);
+    for (i, name) in koth.players.iter().enumerate() {
+        let wins = koth.wins.iter().find(|(player, _)| player == name).map_or(0, |(_, wins)| *wins);
+        // The king is on top, in gold
+        let style = if i == 0 { Style::default().fg(Color::Yellow) } else { Style::default() };
+        text.push(Line::from(Span::styled(format!("{}  {:>3}", pad_right(name, name_width), wins), style)));
+    }
+    
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(strings.get("koth.help"), Style::default().fg(Color::DarkGray))));
+    
+    let widget = Paragraph::new(fit_lines(text, popup_area.width))
+        .block(border.block().title(strings.get("koth.title")))
+        .alignment(Alignment::Center);
+    f.render_widget(widget, popup_area);
+}
+
+fn turn_summary(strings: &Strings, mode: GameMode, result: &TurnResult) -> String {
+    match result.time {
+        Some(time) if result.completed && mode.is_time_ranked() => format_time(time),
+        _ if mode.is_scored() => strings.fmt("koth.points", &[&result.points]),
+        _ => strings.fmt("koth.lines", &[&result.lines]),
+    }
+}
+
+fn render_finished_overlay(f: &mut Frame, app: &App, area: Rect) {
+    let game = &app.game;
+    let strings = &app.strings;
+    let popup_area = centered_rect(90, 95, area);
     f.render_widget(Clear, popup_area);
     
     let time_text = if let Some(duration) = game.final_time {
         format!("{:.3}s", duration.as_secs_f64())
     } else {
-        "N/A".to_string()
+        strings.get("finished.no_time").to_string()
     };
     
-    let finished_text = vec![
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::styled("40L COMPLETE!", Style::default().fg(Color::Green))]),
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw(format!("Final Time: {}", time_text))]),
-        Line::from(vec![Span::raw(format!("Lines Cleared: {}", game.lines_cleared))]),
+    // A versus match's result comes before how the run itself ended
+    let outcome = app.netplay.as_ref().and_then(|netplay| netplay.outcome);
+    let local = app.versus.as_ref().and_then(|versus| Some((versus.teams, versus.winner?)));
+    let (headline, color) = match (outcome, game.end_reason) {
+        (None, _) if local.is_some() => {
+            let (teams, winner) = local.unwrap_or_default();
+            (strings.fmt("versus.won", &[&strings.get(side_key(teams, winner))]), Color::Green)
+        }
+        (Some(Outcome::Won), _) => (strings.get("netplay.won").to_string(), Color::Green),
+        (Some(Outcome::Lost), _) => {
+            let name = app.netplay.as_ref().and_then(|netplay| netplay.opponent.clone()).unwrap_or_default();
+            (strings.fmt("netplay.lost", &[&name]), Color::Red)
+        }
+        (None, Some(EndReason::Completed)) if game.mode == GameMode::Survival => {
+            (strings.fmt("finished.survived", &[&time_text]), Color::Yellow)
+        }
+        (None, Some(EndReason::Completed)) if game.mode == GameMode::Ultra => (strings.get("finished.time_up").to_string(), Color::Green),
+        (None, Some(EndReason::Completed)) => (strings.fmt("finished.complete", &[&strings.mode_short(game.mode)]), Color::Green),
+        (None, Some(EndReason::DrillFailed)) => (strings.get("finished.dig_stalled").to_string(), Color::Red),
+        (None, Some(EndReason::PuzzleFailed)) => (strings.get("finished.puzzle_failed").to_string(), Color::Red),
+        _ => (strings.get("finished.topped_out").to_string(), Color::Red),
+    };
+    
+    let result_line = match game.mode {
+        GameMode::Sprint => strings.fmt("finished.lines", &[&game.lines_cleared]),
+        GameMode::Dig => strings.fmt("finished.efficiency", &[&format!("{:.2}", game.dig_efficiency())]),
+        GameMode::Marathon | GameMode::Classic => strings.fmt("finished.score", &[&game.scoring.points, &game.level()]),
+        GameMode::Ultra => strings.fmt("finished.ultra", &[&game.scoring.points, &game.scoring.bonus_clears]),
+        GameMode::Mystery | GameMode::Survival | GameMode::Zen => strings.fmt("finished.lines", &[&game.lines_cleared]),
+        GameMode::Puzzle => strings.fmt("finished.puzzle", &[&game.replay.puzzle.as_ref().map_or("", |puzzle| puzzle.name.as_str())]),
+    };
+    
+    let mut finished_text = vec![
+        Line::from(vec![Span::styled(headline, Style::default().fg(color))]),
+        Line::from(vec![Span::raw(strings.fmt("finished.final_time", &[&time_text]))]),
+        Line::from(vec![Span::raw(result_line)]),
+        Line::from(vec![Span::raw(strings.fmt(
+            "finished.misdrops",
+            &[&game.misdrops, &format!("{:.1}", game.misdrop_rate() * 100.0)],
+        ))]),
         Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw("Press R to restart")]),
-        Line::from(vec![Span::raw("Press Q to quit")]),
     ];
+    if let Some(accuracy) = game.accuracy() {
+        finished_text.insert(4, Line::from(vec![Span::raw(strings.fmt("finished.accuracy", &[&format!("{:.1}", accuracy)]))]));
+    }
+    if !game.blind.is_none() {
+        let line = Line::from(vec![Span::raw(strings.fmt("finished.blind", &[&strings.blind(game.blind)]))]);
+        finished_text.insert(finished_text.len() - 1, line);
+    }
+    let glyphs = Glyphs::from_config(&app.config.glyphs);
+    let graph_width = (popup_area.width.saturating_sub(2) as usize).min(PACE_GRAPH_WIDTH);
+    if let Some((graph, peak)) = pace_graph(game, &glyphs, graph_width) {
+        let label = Line::from(vec![Span::raw(strings.fmt("finished.pace", &[&format!("{:.2}", peak)]))]);
+        let graph = Line::from(vec![Span::styled(graph, Style::default().fg(Color::Cyan))]);
+        finished_text.splice(finished_text.len() - 1..finished_text.len() - 1, [label, graph]);
+    }
     
-    let finished_widget = Paragraph::new(finished_text)
-        .block(Block::default().borders(Borders::ALL).title("Finished"))
+    if let Some(result) = app.cpu.as_ref().and_then(|cpu| cpu.result(game)) {
+        let (text, color) = match result {
+            RaceResult::Won(Some(margin)) => (strings.fmt("race.won_by", &[&format!("{:.3}", margin.as_secs_f64())]), Color::Green),
+            RaceResult::Won(None) => (strings.get("race.won").to_string(), Color::Green),
+            RaceResult::Lost(Some(margin)) => (strings.fmt("race.lost_by", &[&format!("{:.3}", margin.as_secs_f64())]), Color::Red),
+            RaceResult::Lost(None) => (strings.get("race.lost").to_string(), Color::Red),
+        };
+        finished_text.insert(1, Line::from(vec![Span::styled(text, Style::default().fg(color))]));
+    }
+    
+    finished_text.extend(run_history_lines(app, game.mode));
+    
+    // Solving a puzzle lines up the next one for the restart
+    let help = if game.mode == GameMode::Puzzle && game.puzzle != game.replay.puzzle {
+        "finished.help_next_puzzle"
+    } else {
+        "finished.help"
+    };
+    finished_text.push(Line::from(vec![Span::raw(strings.get(help))]));
+    
+    let title = strings.get(match app.pb_status {
+        Some(PbStatus::New) => "finished.new_pb",
+        Some(PbStatus::Rejected) => "finished.pb_rejected",
+        None => "finished.title",
+    });
+    
+    let finished_widget = Paragraph::new(fit_lines(finished_text, popup_area.width))
+        .block(app.config.theme.border.block().title(title))
         .alignment(Alignment::Center);
         
     f.render_widget(finished_widget, popup_area);
+}
+
+// Last 10 attempts in this mode with a trend arrow against the previous
+// finished attempt, followed by the session best and average
+fn run_history_lines(app: &App, mode: GameMode) -> Vec<Line<'static>> {
+    let strings = &app.strings;
+    let runs: Vec<_> = app.history.iter().filter(|run| run.mode == mode).collect();
+    let recent = &runs[runs.len().saturating_sub(10)..];
+    
+    let mut lines = Vec::new();
+    let mut previous_time = runs[..runs.len() - recent.len()]
+        .iter()
+        .rev()
+        .find(|run| run.completed)
+        .map(|run| run.time());
+    
+    for run in recent {
+        if !run.completed {
+            lines.push(Line::from(vec![Span::styled(
+                format!("{}  ", pad_left(strings.get("finished.dnf"), 8)),
+                Style::default().fg(Color::DarkGray),
+            )]));
+            continue;
+        }
+        
+        let time = run.time();
+        // Arrows follow the time; green when it's an improvement
+        let trend = match previous_time {
+            Some(previous) if time != previous => {
+                let arrow = if time < previous { " ▼" } else { " ▲" };
+                let color = if mode.compare_times(time, previous).is_gt() { Color::Green } else { Color::Red };
+                Span::styled(arrow, Style::default().fg(color))
+            }
+            _ => Span::raw("  "),
+        };
+        previous_time = Some(time);
+        
+        lines.push(Line::from(vec![
+            Span::raw(format!("{:>8.3}s", time.as_secs_f64())),
+            trend,
+            blind_tag(&run.blind),
+        ]));
+    }
+    
+    let format_time = |time: Option<std::time::Duration>| {
+        time.map_or("-".to_string(), |t| format!("{:.3}s", t.as_secs_f64()))
+    };
+    lines.push(Line::from(vec![Span::styled(
+        strings.fmt("finished.best", &[&format_time(app.session_best(mode))]),
+        Style::default().fg(Color::Cyan),
+    )]));
+    lines.push(Line::from(vec![Span::raw(strings.fmt(
+        "finished.average",
+        &[&format_time(app.session_average(mode))],
+    ))]));
+    
+    lines
+}
+
+// N, H and G after a result for whatever the run was played blind to
+fn blind_tag(blind: &Blind) -> Span<'static> {
+    if blind.is_none() {
+        Span::raw("")
+    } else {
+        Span::styled(format!(" {}", blind.tag()), Style::default().fg(Color::DarkGray))
+    }
+}
+
+fn render_playback_timeline(f: &mut Frame, strings: &Strings, playback: &Playback, border: BorderStyle, area: Rect) {
+    let duration = playback.duration();
+    let position = playback.position();
+    let width = area.width.saturating_sub(2) as usize;
+    let played = if duration.is_zero() {
+        width
+    } else {
+        (position.as_secs_f64() / duration.as_secs_f64() * width as f64) as usize
+    };
+    
+    let bar = Line::from(vec![
+        Span::styled("━".repeat(played.min(width)), Style::default().fg(Color::Cyan)),
+        Span::styled("─".repeat(width.saturating_sub(played)), Style::default().fg(Color::DarkGray)),
+    ]);
+    let status = strings.get(if playback.paused { "playback.paused" } else { "playback.playing" });
+    let info = Line::from(vec![Span::raw(format!(
+        "{} / {}  {}x  {}",
+        format_time(position),
+        format_time(duration),
+        playback.speed(),
+        status
+    ))]);
+    let help = Line::from(vec![Span::styled(
+        strings.get("playback.help"),
+        Style::default().fg(Color::DarkGray),
+    )]);
+    
+    let title = match playback.game.player.as_str() {
+        "" => strings.get("panel.replay").to_string(),
+        player => strings.fmt("panel.replay_by", &[&player]),
+    };
+    let graph = accuracy_graph(strings, playback, width);
+    let timeline_widget = Paragraph::new(fit_lines(vec![bar, graph, info, help], area.width))
+        .block(border.block().borders(Borders::TOP).title(title))
+        .alignment(Alignment::Center);
+    
+    f.render_widget(Clear, area);
+    f.render_widget(timeline_widget, area);
+}
+
+// Every graded placement of the run as a bar, averaged down to fit. Pieces
+// already played back are colored by grade; the rest are dimmed.
+fn accuracy_graph(strings: &Strings, playback: &Playback, width: usize) -> Line<'static> {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let grades = &playback.grades;
+    let Some(accuracy) = heuristic::accuracy(grades) else {
+        return Line::from("");
+    };
+    let label = strings.fmt("playback.accuracy", &[&format!("{:.1}", accuracy)]);
+    let columns = width.saturating_sub(text::width(&label) + 1).min(grades.len());
+    let played = playback.game.grades.len();
+    
+    let mut spans = vec![Span::raw(format!("{} ", label))];
+    for column in 0..columns {
+        let range = column * grades.len() / columns..(column + 1) * grades.len() / columns;
+        let grade = grades[range.clone()].iter().sum::<f64>() / range.len() as f64;
+        let color = if range.start >= played {
+            Color::DarkGray
+        } else if grade >= 0.9 {
+            Color::Green
+        } else if grade >= 0.6 {
+            Color::Yellow
+        } else {
+            Color::Red
+        };
+        let bar = BARS[(grade * (BARS.len() - 1) as f64).round() as usize];
+        spans.push(Span::styled(bar.to_string(), Style::default().fg(color)));
+    }
+    Line::from(spans)
+}
+
+fn render_stats_dashboard(f: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(60, 80, area);
+    f.render_widget(Clear, popup_area);
+    
+    let strings = &app.strings;
+    let history = &app.history;
+    let finished = history.iter().filter(|run| run.completed).count();
+    let pieces: u32 = history.iter().map(|run| run.pieces).sum();
+    let misdrops: u32 = history.iter().map(|run| run.misdrops).sum();
+    let rate = |misdrops: u32, pieces: u32| {
+        if pieces == 0 { 0.0 } else { misdrops as f64 / pieces as f64 * 100.0 }
+    };
+    
+    let recent: Vec<_> = history.iter().rev().take(10).collect();
+    let recent_pieces: u32 = recent.iter().map(|run| run.pieces).sum();
+    let recent_misdrops: u32 = recent.iter().map(|run| run.misdrops).sum();
+    
+    let mut lines = vec![
+        Line::from(vec![Span::styled(strings.get("dashboard.lifetime"), Style::default().fg(Color::Cyan))]),
+        Line::from(vec![Span::raw(strings.fmt("dashboard.runs", &[&history.len(), &finished]))]),
+        Line::from(vec![Span::raw(strings.fmt(
+            "dashboard.misdrops",
+            &[&misdrops, &pieces, &format!("{:.1}", rate(misdrops, pieces))],
+        ))]),
+        Line::from(vec![Span::raw(strings.fmt(
+            "dashboard.recent_rate",
+            &[&recent.len(), &format!("{:.1}", rate(recent_misdrops, recent_pieces))],
+        ))]),
+        Line::from(vec![Span::raw("")]),
+    ];
+    
+    // Share of each action across every run, beside the last run's share
+    let mut usage = ActionCounts::default();
+    for run in history {
+        usage.add(&run.actions);
+    }
+    if !usage.is_empty() {
+        let glyphs = Glyphs::from_config(&app.config.glyphs);
+        let last = history.last().map(|run| run.actions).unwrap_or_default();
+        let share = |count: u32, total: u32| count as f64 / total as f64;
+        lines.push(Line::from(vec![Span::styled(strings.get("usage.title"), Style::default().fg(Color::Cyan))]));
+        for ((key, count), (_, last_count)) in usage.entries().into_iter().zip(last.entries()) {
+            let width = (share(count, usage.total()) * USAGE_BAR_WIDTH as f64).round() as usize;
+            let last_share = if last.is_empty() {
+                "-".to_string()
+            } else {
+                format!("{:.0}%", share(last_count, last.total()) * 100.0)
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("{} ", pad_right(&truncate(strings.get(key), 10), 10))),
+                Span::styled(
+                    pad_right(&glyphs.bars[glyphs.bars.len() - 1].to_string().repeat(width), USAGE_BAR_WIDTH),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(format!(" {:>4} {:>4}", format!("{:.0}%", share(count, usage.total()) * 100.0), last_share)),
+            ]));
+        }
+        lines.push(Line::from(vec![Span::raw("")]));
+    }
+    
+    lines.push(Line::from(vec![Span::styled(strings.get("dashboard.recent"), Style::default().fg(Color::Cyan))]));
+    
+    // Player column only once some run has a name
+    let named = recent.iter().any(|run| !run.player.is_empty());
+    for run in &recent {
+        let player = if named { format!("{} ", pad_right(&truncate(&run.player, 8), 8)) } else { String::new() };
+        let result = if run.completed {
+            format!("{:.3}s", run.time().as_secs_f64())
+        } else {
+            strings.get("dashboard.dnf").to_string()
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!(
+                "{}{} {}  {} {:>5.1}%",
+                player,
+                pad_right(strings.mode_short(run.mode), 4),
+                pad_left(&result, 9),
+                strings.fmt("stats.misdrops", &[&format!("{:>3}", run.misdrops)]),
+                run.misdrop_rate() * 100.0
+            )),
+            blind_tag(&run.blind),
+        ]));
+    }
+    
+    if recent.is_empty() {
+        lines.push(Line::from(vec![Span::raw(strings.get("dashboard.no_runs"))]));
+    }
+    
+    lines.push(Line::from(vec![Span::raw("")]));
+    lines.push(Line::from(vec![Span::raw(strings.get("dashboard.close"))]));
+    
+    let dashboard_widget = Paragraph::new(fit_lines(lines, popup_area.width))
+        .block(app.config.theme.border.block().title(strings.get("panel.stats")))
+        .alignment(Alignment::Center);
+        
+    f.render_widget(dashboard_widget, popup_area);
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+use ratatui::text::{Line, Span};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+// Terminal columns a string takes up: CJK and most emoji take two, combining
+// marks none
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+// Cuts text to at most `max` columns, ending in "…" when anything was dropped
+pub fn truncate(text: &str, max: usize) -> String {
+    if width(text) <= max {
+        return text.to_string();
+    }
+    let mut out = take_columns(text, max.saturating_sub(1));
+    if max > 0 {
+        out.push('…');
+    }
+    out
+}
+
+// Leading chars of text filling at most `max` columns; a wide char that
+// would straddle the limit is left out
+fn take_columns(text: &str, max: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= max
+        })
+        .collect()
+}
+
+// Column-aware replacements for `{:<N}` and `{:>N}`, which count chars
+pub fn pad_right(text: &str, columns: usize) -> String {
+    format!("{}{}", text, " ".repeat(columns.saturating_sub(width(text))))
+}
+
+pub fn pad_left(text: &str, columns: usize) -> String {
+    format!("{}{}", " ".repeat(columns.saturating_sub(width(text))), text)
+}
+
+// Truncates a styled line to `max` columns, keeping each span's style
+pub fn fit_line(line: Line<'_>, max: usize) -> Line<'_> {
+    if line.width() <= max || max == 0 {
+        return line;
+    }
+    let Line { spans: all, style, alignment } = line;
+    let mut spans = Vec::new();
+    let mut remaining = max - 1; // Room for the ellipsis
+    for span in all {
+        let span_width = span.width();
+        if span_width <= remaining {
+            remaining -= span_width;
+            spans.push(span);
+        } else {
+            let ellipsis = Span::styled("…", span.style);
+            spans.push(Span::styled(take_columns(&span.content, remaining), span.style));
+            spans.push(ellipsis);
+            break;
+        }
+    }
+    Line { spans, style, alignment }
+}
+
+// Lines for a bordered panel `outer_width` columns wide
+pub fn fit_lines(lines: Vec<Line<'_>>, outer_width: u16) -> Vec<Line<'_>> {
+    let max = outer_width.saturating_sub(2) as usize;
+    lines.into_iter().map(|line| fit_line(line, max)).collect()
+}
@@ -0,0 +1,230 @@
+use std::str::FromStr;
+
+use ratatui::{
+    style::{Color, Style},
+    symbols::border,
+    widgets::{Block, BorderType, Borders},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, GlyphConfig};
+use crate::constants::{LINES_PER_LEVEL, PALETTE_SECTION_LEVELS};
+use crate::game::color::PieceColor;
+use crate::game::{Game, GameMode};
+
+// Mode accent color, used for the countdown and mode titles
+pub fn accent(config: &Config, mode: GameMode) -> Color {
+    config
+        .theme
+        .accents
+        .get(&mode.short_name().to_lowercase())
+        .and_then(|color| Color::from_str(color).ok())
+        .unwrap_or(match mode {
+            GameMode::Sprint => Color::Cyan,
+            GameMode::Marathon => Color::LightGreen,
+            GameMode::Dig => Color::LightRed,
+            GameMode::Survival => Color::LightYellow,
+            GameMode::Mystery => Color::LightBlue,
+            GameMode::Ultra => Color::Yellow,
+            GameMode::Puzzle => Color::LightCyan,
+            GameMode::Zen => Color::LightMagenta,
+            GameMode::Classic => Color::White,
+        })
+}
+
+// Terminal color for each piece color. Orange has no ANSI color of its own,
+// so L pieces borrow light yellow.
+pub fn piece_color(color: PieceColor) -> Color {
+    match color {
+        PieceColor::Cyan => Color::Cyan,
+        PieceColor::Yellow => Color::Yellow,
+        PieceColor::Purple => Color::Magenta,
+        PieceColor::Green => Color::Green,
+        PieceColor::Red => Color::Red,
+        PieceColor::Blue => Color::Blue,
+        PieceColor::Orange => Color::LightYellow,
+    }
+}
+
+// The same in true color for the pixel board, where orange can be orange
+pub fn piece_rgb(color: PieceColor) -> [u8; 3] {
+    match color {
+        PieceColor::Cyan => [0, 240, 240],
+        PieceColor::Yellow => [240, 240, 0],
+        PieceColor::Purple => [160, 0, 240],
+        PieceColor::Green => [0, 240, 0],
+        PieceColor::Red => [240, 0, 0],
+        PieceColor::Blue => [0, 80, 240],
+        PieceColor::Orange => [240, 160, 0],
+    }
+}
+
+// Line set for the borders of the playfield and every panel. None still
+// leaves the border's row and column empty, so nothing moves and titles show.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderStyle {
+    #[default]
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+    None,
+}
+
+const BLANK_BORDER: border::Set = border::Set {
+    top_left: " ",
+    top_right: " ",
+    bottom_left: " ",
+    bottom_right: " ",
+    vertical_left: " ",
+    vertical_right: " ",
+    horizontal_top: " ",
+    horizontal_bottom: " ",
+};
+
+impl BorderStyle {
+    // A bordered block in this style, for any widget
+    pub fn block<'a>(self) -> Block<'a> {
+        let block = Block::default().borders(Borders::ALL);
+        match self {
+            BorderStyle::Plain => block.border_type(BorderType::Plain),
+            BorderStyle::Rounded => block.border_type(BorderType::Rounded),
+            BorderStyle::Double => block.border_type(BorderType::Double),
+            BorderStyle::Thick => block.border_type(BorderType::Thick),
+            BorderStyle::None => block.border_set(BLANK_BORDER),
+        }
+    }
+}
+
+// How piece colors and the board's checkerboard move on as a run goes up
+// levels, to give long runs some visual progression
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    #[default]
+    Fixed,    // Guideline colors throughout
+    Levels,   // New colors every level
+    Sections, // Every PALETTE_SECTION_LEVELS levels, like TGM's section colors
+}
+
+// Checkerboard colors, one per shift, round and round
+const SHIFT_BACKGROUNDS: [Color; 4] = [Color::DarkGray, Color::Indexed(24), Color::Indexed(65), Color::Indexed(96)];
+
+impl Palette {
+    // How many times the colors have moved on by this level
+    fn shift(self, level: u32) -> usize {
+        let level = level.saturating_sub(1);
+        match self {
+            Palette::Fixed => 0,
+            Palette::Levels => level as usize,
+            Palette::Sections => (level / PALETTE_SECTION_LEVELS) as usize,
+        }
+    }
+
+    // Whose color a piece wears at a level. Each shift hands every piece
+    // the guideline color of the next one along.
+    pub fn shifted(self, color: PieceColor, level: u32) -> PieceColor {
+        let index = PieceColor::ALL.iter().position(|&c| c == color).unwrap_or(0);
+        PieceColor::ALL[(index + self.shift(level)) % PieceColor::ALL.len()]
+    }
+
+    pub fn piece(self, color: PieceColor, level: u32) -> Color {
+        piece_color(self.shifted(color, level))
+    }
+
+    pub fn background(self, level: u32) -> Color {
+        SHIFT_BACKGROUNDS[self.shift(level) % SHIFT_BACKGROUNDS.len()]
+    }
+}
+
+// The level palettes go by: the run's own in modes with levels, otherwise
+// one per LINES_PER_LEVEL lines, so Zen and the rest move on too
+pub fn palette_level(game: &Game) -> u32 {
+    match game.mode {
+        GameMode::Marathon | GameMode::Classic => game.level(),
+        _ => game.lines_cleared / LINES_PER_LEVEL + 1,
+    }
+}
+
+// Bonus pieces glow gold through their own color
+pub fn bonus_style(color: Color) -> Style {
+    Style::default().fg(color).bg(Color::Yellow)
+}
+
+// Two-column strings used to draw cells on the board and in the previews
+pub struct Glyphs {
+    pub filled: String,
+    pub ghost: String,
+    pub background: String, // Every other empty cell, making the checkerboard
+    pub hole: String,       // Analysis overlay
+    pub floor: String,      // Empty cells in the bottom row, when marked
+    pub guide: String,      // Empty cells, when column guides are on
+    pub bonus: String,      // Cells of a bonus piece
+    pub hint: String,       // Outline of where a T-spin hint wants the next piece
+    pub incoming: String,   // Under the board, where the next garbage row's hole will be
+    pub minimap: [char; 5], // Stack view minimap, emptiest band to fullest
+    pub bars: [char; 8],    // Sparkline bars, lowest to highest
+}
+
+impl Glyphs {
+    pub fn from_config(config: &GlyphConfig) -> Self {
+        let glyph = |custom: &Option<String>, unicode: &str, ascii: &str| {
+            custom
+                .clone()
+                .filter(|glyph| glyph.chars().count() == 2)
+                .unwrap_or_else(|| if config.ascii { ascii } else { unicode }.to_string())
+        };
+        Self {
+            filled: glyph(&config.filled, "██", "[]"),
+            ghost: glyph(&config.ghost, "▒▒", "::"),
+            background: glyph(&config.background, "░░", " ."),
+            hole: glyph(&None, "××", "xx"),
+            floor: glyph(&None, "▁▁", "__"),
+            guide: glyph(&None, "▏ ", "| "),
+            bonus: glyph(&None, "▓▓", "$$"),
+            hint: glyph(&None, "[]", "<>"),
+            incoming: glyph(&None, "▲▲", "^^"),
+            bars: if config.ascii { ['_', '.', '-', ':', '=', '+', '*', '#'] } else { ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'] },
+            minimap: if config.ascii { [' ', '.', ':', '+', '#'] } else { [' ', '░', '▒', '▓', '█'] },
+        }
+    }
+}
+
+// 3x5 pixel glyphs for the countdown, big timer and title logo, '#' for a
+// lit pixel
+fn glyph(ch: char) -> [&'static str; 5] {
+    match ch {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", " ##", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => [" ##", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", " # ", " # ", " # "],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        '.' => ["   ", "   ", "   ", "   ", " # "],
+        ':' => ["   ", " # ", "   ", " # ", "   "],
+        'G' => [" ##", "#  ", "# #", "# #", " ##"],
+        'O' => [" # ", "# #", "# #", "# #", " # "],
+        'T' => ["###", " # ", " # ", " # ", " # "],
+        'S' => [" ##", "#  ", " # ", "  #", "## "],
+        'R' => ["## ", "# #", "## ", "# #", "# #"],
+        'I' => ["###", " # ", " # ", " # ", "###"],
+        _ => ["   ", "   ", "   ", "   ", "   "],
+    }
+}
+
+// Text drawn five rows tall, two columns per pixel so it looks square
+pub fn big_text(text: &str) -> Vec<String> {
+    (0..5)
+        .map(|row| {
+            text.chars()
+                .map(|ch| glyph(ch)[row].replace('#', "██").replace(' ', "  "))
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .collect()
+}
@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::constants::{TOAST_MAX_VISIBLE, TOAST_MS};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub text: String,
+    duration: Duration,
+    shown_at: Option<Instant>, // None while still queued
+}
+
+// Short notifications drawn in a corner. A few are up at a time, oldest on
+// top; the rest wait until one expires.
+#[derive(Clone, Debug, Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, kind: ToastKind, text: impl Into<String>) {
+        self.push_for(kind, text, Duration::from_millis(TOAST_MS));
+    }
+
+    pub fn push_for(&mut self, kind: ToastKind, text: impl Into<String>, duration: Duration) {
+        self.queue.push_back(Toast { kind, text: text.into(), duration, shown_at: None });
+    }
+
+    // Drop expired toasts and start the clock on newly visible ones
+    pub fn update(&mut self, now: Instant) {
+        self.queue.retain(|toast| toast.shown_at.is_none_or(|at| now.duration_since(at) < toast.duration));
+        for toast in self.queue.iter_mut().take(TOAST_MAX_VISIBLE) {
+            toast.shown_at.get_or_insert(now);
+        }
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &Toast> {
+        self.queue.iter().take_while(|toast| toast.shown_at.is_some())
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Instant;
+
+use crate::constants::{BOARD_HEIGHT, BOARD_WIDTH, TRAIL_MS, TRAIL_STAGES};
+use crate::game::color::PieceColor;
+use crate::game::piece::Piece;
+
+// The streak a hard-dropped piece leaves over the rows it fell through,
+// fading out over a few frames
+#[derive(Clone, Debug)]
+pub struct Trail {
+    pub color: PieceColor,
+    cells: [[bool; BOARD_WIDTH]; BOARD_HEIGHT],
+    at: Instant,
+}
+
+impl Trail {
+    // From the piece as it landed and how many rows it fell
+    pub fn new(piece: &Piece, rows: u32, now: Instant) -> Self {
+        let mut cells = [[false; BOARD_WIDTH]; BOARD_HEIGHT];
+        for (x, y) in piece.get_blocks() {
+            for above in (y - rows as i32).max(0)..y {
+                if x >= 0 && x < BOARD_WIDTH as i32 && above < BOARD_HEIGHT as i32 {
+                    cells[above as usize][x as usize] = true;
+                }
+            }
+        }
+        Self { color: piece.color, cells, at: now }
+    }
+
+    pub fn covers(&self, x: usize, y: usize) -> bool {
+        self.cells[y][x]
+    }
+
+    // 0 while fresh, counting up as it fades; None once it's gone
+    pub fn stage(&self, now: Instant) -> Option<u32> {
+        let age = now.saturating_duration_since(self.at).as_millis() as u64;
+        let stage = (age * TRAIL_STAGES as u64 / TRAIL_MS) as u32;
+        (stage < TRAIL_STAGES).then_some(stage)
+    }
+}
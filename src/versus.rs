@@ -0,0 +1,304 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::ai::battle::{attack, Handicap, Targeter, Targeting};
+use crate::ai::race::CpuRacer;
+use crate::ai::AiConfig;
+use crate::constants::{VERSUS_CPU_PPS, VERSUS_GARBAGE_MS, VERSUS_MAX_ATTACK, VERSUS_MAX_GARBAGE_ROWS, VERSUS_MAX_GRAVITY, VERSUS_STEP};
+use crate::game::event::{GameEvent, TimedEvent};
+use crate::game::replay::InputEvent;
+use crate::game::state::{EndReason, GameState};
+use crate::game::Game;
+
+// Lines a person's clears send, worked out from the reports their game
+// gives, under the same rules as AI battles
+pub struct Attacks {
+    pieces_seen: u32,
+    combo: Option<u32>, // Consecutive clearing placements, None when the last one cleared nothing
+    back_to_back: bool,
+}
+
+impl Attacks {
+    pub fn new(game: &Game) -> Self {
+        Self { pieces_seen: game.pieces_placed, combo: None, back_to_back: false }
+    }
+
+    // Each clear's attack since the last call, with the player's handicap
+    // on it
+    pub fn sent(&mut self, game: &Game, events: &[TimedEvent]) -> Vec<u32> {
+        let placed = game.pieces_placed.saturating_sub(self.pieces_seen);
+        self.pieces_seen = game.pieces_placed;
+        let clears: Vec<u32> = events
+            .iter()
+            .filter_map(|event| match event.event {
+                GameEvent::LinesCleared(lines) => Some(lines),
+                _ => None,
+            })
+            .collect();
+        if placed as usize > clears.len() {
+            self.combo = None;
+        }
+        let perfect_clear = events.iter().any(|event| event.event == GameEvent::PerfectClear);
+        clears
+            .into_iter()
+            .map(|lines| {
+                let combo = self.combo.map_or(0, |combo| combo + 1);
+                self.combo = Some(combo);
+                let rows = attack(lines, combo, self.back_to_back, perfect_clear);
+                self.back_to_back = lines == 4;
+                (rows as f64 * game.handicap.attack).round() as u32
+            })
+            .collect()
+    }
+}
+
+// Garbage on its way in, due at a run time of the board it's headed for
+pub struct Incoming {
+    pub rows: u32,
+    pub due: Duration,
+}
+
+// An attack first cancels garbage still on its way in, oldest first. What's
+// left of it goes on to the other side.
+pub fn cancel(incoming: &mut VecDeque<Incoming>, mut rows: u32) -> u32 {
+    while rows > 0 {
+        let Some(next) = incoming.front_mut() else {
+            break;
+        };
+        let cancelled = rows.min(next.rows);
+        rows -= cancelled;
+        next.rows -= cancelled;
+        if next.rows == 0 {
+            incoming.pop_front();
+        }
+    }
+    rows
+}
+
+// One board's side of a local match
+struct Side {
+    attacks: Attacks,
+    incoming: VecDeque<Incoming>,
+}
+
+// Locale key naming a side of a match by who plays it
+pub fn side_key(teams: bool, side: usize) -> &'static str {
+    match (teams, side) {
+        (false, 0) => "versus.player_left",
+        (false, _) => "versus.player_right",
+        (true, 0) => "versus.team_left",
+        (true, _) => "versus.team_right",
+    }
+}
+
+// Side of board `board`: 0 for the main board and its CPU teammate, 1 for
+// the twin and its teammate
+fn team(board: usize) -> usize {
+    board % 2
+}
+
+// Two people on one keyboard, one on the main board and the other on the
+// twin, sending each other garbage. In team play each has a CPU teammate,
+// and each attack is shared out among the other side's boards still in by
+// the match's targeting. Garbage lands VERSUS_GARBAGE_MS after it was sent,
+// so there's a moment to cancel it with a clear. Every board starts with
+// the main one. A completed run wins the match for its side; a side with
+// every run topped out loses it.
+pub struct LocalVersus {
+    sides: Vec<Side>,        // By board: the main one, the twin, then the CPUs
+    pub cpus: Vec<CpuRacer>, // Team play: the main board's teammate, then the twin's
+    pub teams: bool,
+    targeter: Targeter,
+    run: u32,                  // The main board's run the boards are on
+    pub winner: Option<usize>, // 0 for the main board's side, 1 for the twin's
+}
+
+impl LocalVersus {
+    pub fn new(teams: bool) -> Self {
+        let boards = if teams { 4 } else { 2 };
+        Self {
+            sides: Vec::new(),
+            cpus: Vec::new(),
+            teams,
+            targeter: Targeter::new(Targeting::Even, boards, rand::random()),
+            run: 0,
+            winner: None,
+        }
+    }
+
+    // ← and → on the setup screen's targeting row
+    pub fn cycle_targeting(&mut self, delta: isize) {
+        let i = Targeting::ALL.iter().position(|&targeting| targeting == self.targeter.targeting).unwrap_or(0);
+        self.targeter.targeting = Targeting::ALL[(i as isize + delta).rem_euclid(Targeting::ALL.len() as isize) as usize];
+    }
+
+    pub fn targeting(&self) -> Targeting {
+        self.targeter.targeting
+    }
+
+    // Once per update, after both people's boards have moved, with the
+    // reports each gave. The side that just won, once one has.
+    pub fn update(&mut self, game: &mut Game, twin: &mut Game, events: &[TimedEvent], twin_events: &[TimedEvent]) -> Option<usize> {
+        let mut cpus = std::mem::take(&mut self.cpus);
+        if self.run != game.runs {
+            self.run = game.runs;
+            self.winner = None;
+            if self.teams {
+                let weights = AiConfig::PRESETS[0].weights;
+                cpus = (0..2).map(|_| CpuRacer::steady(game.seed, game.mode, VERSUS_CPU_PPS, weights)).collect();
+            }
+            self.sides.clear();
+        }
+        if self.winner.is_none() {
+            let elapsed = game.get_current_time().unwrap_or_default();
+            cpus.iter_mut().for_each(|cpu| cpu.advance(elapsed));
+        }
+        let cpu_events: Vec<Vec<TimedEvent>> = cpus.iter_mut().map(|cpu| cpu.game.drain_events()).collect();
+        let reports: Vec<&[TimedEvent]> = [events, twin_events].into_iter().chain(cpu_events.iter().map(Vec::as_slice)).collect();
+        let now = game.replay_elapsed();
+        let mut boards: Vec<&mut Game> = vec![game, twin];
+        boards.extend(cpus.iter_mut().map(|cpu| &mut cpu.game));
+        if self.sides.len() != boards.len() {
+            self.sides = boards.iter().map(|board| Side { attacks: Attacks::new(board), incoming: VecDeque::new() }).collect();
+        }
+
+        let decided = self.winner.is_none().then(|| {
+            self.attack(&boards, &reports, now);
+            self.land_garbage(&mut boards, now);
+            self.decide(&mut boards)
+        });
+        self.cpus = cpus;
+        decided.flatten()
+    }
+
+    // Each board's clears first cancel garbage on its way to it, and the
+    // rest goes to the other side
+    fn attack(&mut self, boards: &[&mut Game], reports: &[&[TimedEvent]], now: Duration) {
+        for from in 0..boards.len() {
+            let sent = self.sides[from].attacks.sent(boards[from], reports[from]);
+            if boards[from].game_state != GameState::Playing {
+                continue;
+            }
+            for rows in sent {
+                let rows = cancel(&mut self.sides[from].incoming, rows);
+                if rows == 0 {
+                    continue;
+                }
+                let opponents: Vec<usize> = (0..boards.len())
+                    .filter(|&i| team(i) != team(from) && boards[i].game_state == GameState::Playing)
+                    .collect();
+                for (target, share) in self.targeter.split(from, rows, &opponents) {
+                    let due = now + Duration::from_millis(VERSUS_GARBAGE_MS);
+                    self.sides[target].incoming.push_back(Incoming { rows: share, due });
+                }
+            }
+        }
+    }
+
+    // Garbage that's come due rises now
+    fn land_garbage(&mut self, boards: &mut [&mut Game], now: Duration) {
+        for (board, side) in boards.iter_mut().zip(&mut self.sides) {
+            if board.game_state != GameState::Playing {
+                continue;
+            }
+            while let Some(rows) = side.incoming.front().filter(|next| next.due <= now).map(|next| next.rows) {
+                side.incoming.pop_front();
+                board.apply_input(InputEvent::Garbage(rows));
+            }
+        }
+    }
+
+    // Runs still going once the match is settled end there
+    fn decide(&mut self, boards: &mut [&mut Game]) -> Option<usize> {
+        let standing = |team_index: usize| {
+            (0..boards.len()).any(|i| team(i) == team_index && boards[i].game_state != GameState::Finished)
+        };
+        let completed = (0..boards.len()).find(|&i| boards[i].end_reason == Some(EndReason::Completed));
+        let winner = match (completed, standing(0), standing(1)) {
+            (Some(board), _, _) => team(board),
+            (None, true, true) => return None,
+            (None, true, false) => 0,
+            (None, false, true) => 1,
+            // Both people topped out at once: the later one holds on
+            (None, false, false) => usize::from(boards[1].final_time >= boards[0].final_time),
+        };
+        for (i, board) in boards.iter_mut().enumerate() {
+            board.end_match(team(i) == winner);
+        }
+        tracing::info!(winner, "local match decided");
+        self.winner = Some(winner);
+        Some(winner)
+    }
+}
+
+// What the match setup screen can change about a player's handicap
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Setting {
+    Gravity,
+    Hold,
+    Garbage,
+    Attack,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 4] = [Setting::Gravity, Setting::Hold, Setting::Garbage, Setting::Attack];
+
+    // Locale key under [setup]
+    pub fn key(self) -> &'static str {
+        match self {
+            Setting::Gravity => "gravity",
+            Setting::Hold => "hold",
+            Setting::Garbage => "garbage",
+            Setting::Attack => "attack",
+        }
+    }
+
+    // One press of ← (-1) or → (1)
+    pub fn step(self, handicap: &mut Handicap, delta: i32) {
+        let step = VERSUS_STEP * delta as f64;
+        match self {
+            Setting::Gravity => handicap.gravity = (handicap.gravity + step).clamp(VERSUS_STEP, VERSUS_MAX_GRAVITY),
+            Setting::Hold => handicap.no_hold = !handicap.no_hold,
+            Setting::Garbage => {
+                handicap.garbage_rows = handicap.garbage_rows.saturating_add_signed(delta).min(VERSUS_MAX_GARBAGE_ROWS);
+            }
+            Setting::Attack => handicap.attack = (handicap.attack + step).clamp(0.0, VERSUS_MAX_ATTACK),
+        }
+    }
+}
+
+// A row of the match setup screen
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Row {
+    Handicap(usize, Setting), // For the main board's player or the twin's
+    Targeting,                // Team play: who attacks go to
+}
+
+// Handicaps for a versus match, a row per setting per player, and in team
+// play the targeting after them. Locally both players' rows can be changed;
+// online only our own, with theirs shown under them as they set it.
+#[derive(Clone, Copy, Debug)]
+pub struct MatchSetup {
+    pub row: usize,
+    pub players: usize, // Players whose rows can be changed here
+    pub teams: bool,
+}
+
+impl MatchSetup {
+    pub fn new(players: usize, teams: bool) -> Self {
+        Self { row: 0, players, teams }
+    }
+
+    // Up and down through the rows
+    pub fn select(&mut self, delta: isize) {
+        let rows = self.players * Setting::ALL.len() + usize::from(self.teams);
+        self.row = (self.row as isize + delta).rem_euclid(rows as isize) as usize;
+    }
+
+    pub fn selected(&self) -> Row {
+        match self.row / Setting::ALL.len() {
+            player if player < self.players => Row::Handicap(player, Setting::ALL[self.row % Setting::ALL.len()]),
+            _ => Row::Targeting,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::config::GlyphConfig;
+use crate::constants::GROUND_TIME;
+use crate::input::repeat::Timings;
+
+// Handling to start from, gentlest first: locale key under [wizard] and timings
+pub const HANDLING_CHOICES: [(&str, Timings); 3] = [
+    ("beginner", Timings { das_ms: 170, arr_ms: 50, soft_drop_ms: 50, lock_delay_ms: GROUND_TIME }),
+    ("standard", Timings { das_ms: 120, arr_ms: 25, soft_drop_ms: 20, lock_delay_ms: GROUND_TIME }),
+    ("pro", Timings { das_ms: 70, arr_ms: 10, soft_drop_ms: 0, lock_delay_ms: GROUND_TIME }),
+];
+
+pub const THEME_CHOICES: [&str; 3] = ["blocks", "shaded", "ascii"];
+
+pub fn theme_glyphs(theme: &str) -> GlyphConfig {
+    match theme {
+        "shaded" => GlyphConfig {
+            filled: Some("▓▓".to_string()),
+            ghost: Some("░░".to_string()),
+            background: Some("  ".to_string()),
+            ..GlyphConfig::default()
+        },
+        "ascii" => GlyphConfig { ascii: true, ..GlyphConfig::default() },
+        _ => GlyphConfig::default(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Step {
+    Handling,
+    Theme,
+    KeyRelease,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeyTest {
+    Waiting,
+    Held,   // Space is down; letting go proves releases come through
+    Passed,
+}
+
+// First-run setup: handling, look and a key release check, before the name
+// prompt. Shown only when there's no config file yet.
+#[derive(Clone, Debug)]
+pub struct Wizard {
+    pub step: Step,
+    pub handling: usize, // Into HANDLING_CHOICES
+    pub theme: usize,    // Into THEME_CHOICES
+    pub key_test: KeyTest,
+}
+
+impl Wizard {
+    pub fn new() -> Self {
+        Self { step: Step::Handling, handling: 1, theme: 0, key_test: KeyTest::Waiting }
+    }
+
+    pub fn step_number(&self) -> usize {
+        match self.step {
+            Step::Handling => 1,
+            Step::Theme => 2,
+            Step::KeyRelease => 3,
+        }
+    }
+
+    // Up and down through the choices on this step
+    pub fn choose(&mut self, delta: isize) {
+        let (choice, count) = match self.step {
+            Step::Handling => (&mut self.handling, HANDLING_CHOICES.len()),
+            Step::Theme => (&mut self.theme, THEME_CHOICES.len()),
+            Step::KeyRelease => return,
+        };
+        *choice = (*choice as isize + delta).rem_euclid(count as isize) as usize;
+    }
+
+    pub fn space(&mut self, pressed: bool) {
+        if self.step != Step::KeyRelease {
+            return;
+        }
+        self.key_test = match (self.key_test, pressed) {
+            (KeyTest::Waiting, true) => KeyTest::Held,
+            (KeyTest::Held, false) => KeyTest::Passed,
+            (test, _) => test,
+        };
+    }
+
+    // On to the next step; false once there are none left
+    pub fn advance(&mut self) -> bool {
+        self.step = match self.step {
+            Step::Handling => Step::Theme,
+            Step::Theme => Step::KeyRelease,
+            Step::KeyRelease => return false,
+        };
+        true
+    }
+
+    pub fn timings(&self) -> Timings {
+        HANDLING_CHOICES[self.handling].1
+    }
+
+    pub fn glyphs(&self) -> GlyphConfig {
+        theme_glyphs(THEME_CHOICES[self.theme])
+    }
+}